@@ -0,0 +1,985 @@
+use crate::cascade::Cascade;
+use crate::counters::{format_counter, marker_text, CounterState};
+use crate::css::{
+    AttributeSelector, Combinator, ContentItem, LengthContext, Nth, PseudoClass, PseudoElement,
+    RelativeSelector, Selector, SimpleSelector, Stylesheet, Value,
+};
+use crate::dom::{ElementData, Node, NodeType};
+use crate::properties::apply_defaults;
+use crate::style::{compute_values, ComputedStyle, Display};
+use crate::variables::resolve_variables;
+use std::collections::HashMap;
+
+pub type PropertyMap = HashMap<String, Value>;
+
+// a node of the DOM with the css values that apply to it
+#[derive(Debug)]
+pub struct StyledNode<'a> {
+    pub node: &'a Node,
+    // the computed values as css values, including custom properties
+    pub computed_values: PropertyMap,
+    pub style: ComputedStyle,
+    pub children: Vec<StyledNode<'a>>,
+    // set on the anonymous boxes generated for `::before`, `::after` and
+    // `::marker`, whose `node` is the originating element
+    pub pseudo_element: Option<PseudoElement>,
+    // the resolved `content` text of a generated box
+    pub generated_content: Option<String>,
+}
+
+impl StyledNode<'_> {
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.computed_values.get(name)
+    }
+}
+
+// a node together with its position in the tree, so that selectors can
+// look at its ancestors and siblings
+#[derive(Debug, Clone, Copy)]
+pub struct ElementRef<'a> {
+    pub node: &'a Node,
+    parent: Option<&'a ElementRef<'a>>,
+    index: usize,
+}
+
+impl<'a> ElementRef<'a> {
+    pub fn root(node: &'a Node) -> ElementRef<'a> {
+        ElementRef {
+            node,
+            parent: None,
+            index: 0,
+        }
+    }
+
+    pub fn child(&'a self, index: usize) -> ElementRef<'a> {
+        ElementRef {
+            node: &self.node.children[index],
+            parent: Some(self),
+            index,
+        }
+    }
+
+    pub fn element(&self) -> Option<&'a ElementData> {
+        match self.node.node_type {
+            NodeType::Element(ref elem) => Some(elem),
+            _ => None,
+        }
+    }
+
+    pub fn parent(&self) -> Option<&'a ElementRef<'a>> {
+        self.parent
+    }
+
+    fn is_same(&self, other: &ElementRef) -> bool {
+        std::ptr::eq(self.node, other.node)
+    }
+
+    // the element children, skipping text and comments
+    pub fn children(&'a self) -> Vec<ElementRef<'a>> {
+        (0..self.node.children.len())
+            .map(|index| self.child(index))
+            .filter(|child| child.element().is_some())
+            .collect()
+    }
+
+    // all the element siblings, including this element
+    fn siblings(&self) -> Vec<ElementRef<'a>> {
+        match self.parent {
+            Some(parent) => parent.children(),
+            None => vec![*self],
+        }
+    }
+
+    fn previous_siblings(&self) -> Vec<ElementRef<'a>> {
+        let mut siblings = self.siblings();
+        siblings.retain(|sibling| sibling.index < self.index);
+        siblings
+    }
+
+    fn next_siblings(&self) -> Vec<ElementRef<'a>> {
+        let mut siblings = self.siblings();
+        siblings.retain(|sibling| sibling.index > self.index);
+        siblings
+    }
+}
+
+fn matches_attribute(elem: &ElementData, selector: &AttributeSelector) -> bool {
+    match elem.attributes.get(&selector.name) {
+        Some(value) => selector.matches(value),
+        None => false,
+    }
+}
+
+fn matches_any(element: &ElementRef, selectors: &[Selector]) -> bool {
+    selectors
+        .iter()
+        .any(|selector| matches_selector(element, selector))
+}
+
+// 1-based position of the element among its siblings passing the filter,
+// counted from the end when `from_end` is set
+fn sibling_position<F>(element: &ElementRef, from_end: bool, filter: F) -> i32
+where
+    F: Fn(&ElementRef) -> bool,
+{
+    let siblings = if from_end {
+        element.next_siblings()
+    } else {
+        element.previous_siblings()
+    };
+    siblings.iter().filter(|sibling| filter(sibling)).count() as i32 + 1
+}
+
+fn matches_nth_child(element: &ElementRef, nth: &Nth, of: &[Selector], from_end: bool) -> bool {
+    if !of.is_empty() && !matches_any(element, of) {
+        return false;
+    }
+    let position = sibling_position(element, from_end, |sibling| {
+        of.is_empty() || matches_any(sibling, of)
+    });
+    nth.matches(position)
+}
+
+fn matches_nth_of_type(element: &ElementRef, nth: &Nth, from_end: bool) -> bool {
+    let tag_name = &element.element().unwrap().tag_name;
+    let position = sibling_position(element, from_end, |sibling| {
+        sibling.element().unwrap().tag_name == *tag_name
+    });
+    nth.matches(position)
+}
+
+fn is_empty(node: &Node) -> bool {
+    node.children.iter().all(|child| match &child.node_type {
+        NodeType::Element(_) => false,
+        NodeType::Text(text) => text.is_empty(),
+        NodeType::Comment(_) => true,
+    })
+}
+
+// whether `element` is reached from `anchor` through the combinator
+fn is_related(anchor: &ElementRef, element: &ElementRef, combinator: Combinator) -> bool {
+    match combinator {
+        Combinator::Child => element
+            .parent()
+            .is_some_and(|parent| parent.is_same(anchor)),
+        Combinator::Descendant => {
+            let mut ancestor = element.parent();
+            while let Some(current) = ancestor {
+                if current.is_same(anchor) {
+                    return true;
+                }
+                ancestor = current.parent();
+            }
+            false
+        }
+        Combinator::NextSibling => element
+            .previous_siblings()
+            .last()
+            .is_some_and(|sibling| sibling.is_same(anchor)),
+        Combinator::SubsequentSibling => element
+            .previous_siblings()
+            .iter()
+            .any(|sibling| sibling.is_same(anchor)),
+    }
+}
+
+fn matches_has(anchor: &ElementRef, relative: &RelativeSelector) -> bool {
+    // try every element of the subtree as the subject of the relative selector
+    fn search(element: &ElementRef, anchor: &ElementRef, relative: &RelativeSelector) -> bool {
+        let last = relative.selector.compounds.len() - 1;
+        if matches_complex(
+            element,
+            &relative.selector,
+            last,
+            Some((anchor, relative.combinator)),
+        ) {
+            return true;
+        }
+        element
+            .children()
+            .iter()
+            .any(|child| search(child, anchor, relative))
+    }
+
+    // sibling combinators look at the following siblings and their subtrees
+    let roots = match relative.combinator {
+        Combinator::Descendant | Combinator::Child => anchor.children(),
+        Combinator::NextSibling | Combinator::SubsequentSibling => anchor.next_siblings(),
+    };
+    roots.iter().any(|root| search(root, anchor, relative))
+}
+
+fn matches_pseudo_class(element: &ElementRef, pseudo_class: &PseudoClass) -> bool {
+    let elem = element.element().unwrap();
+    let first = Nth { a: 0, b: 1 };
+    match pseudo_class {
+        PseudoClass::Root => element.parent().is_none(),
+        PseudoClass::Empty => is_empty(element.node),
+        PseudoClass::FirstChild => element.previous_siblings().is_empty(),
+        PseudoClass::LastChild => element.next_siblings().is_empty(),
+        PseudoClass::OnlyChild => element.siblings().len() == 1,
+        PseudoClass::FirstOfType => matches_nth_of_type(element, &first, false),
+        PseudoClass::LastOfType => matches_nth_of_type(element, &first, true),
+        PseudoClass::OnlyOfType => {
+            matches_nth_of_type(element, &first, false)
+                && matches_nth_of_type(element, &first, true)
+        }
+        PseudoClass::NthChild(nth, of) => matches_nth_child(element, nth, of, false),
+        PseudoClass::NthLastChild(nth, of) => matches_nth_child(element, nth, of, true),
+        PseudoClass::NthOfType(nth) => matches_nth_of_type(element, nth, false),
+        PseudoClass::NthLastOfType(nth) => matches_nth_of_type(element, nth, true),
+        PseudoClass::Not(selectors) => !matches_any(element, selectors),
+        PseudoClass::Is(selectors) | PseudoClass::Where(selectors) => {
+            matches_any(element, selectors)
+        }
+        PseudoClass::Has(selectors) => selectors
+            .iter()
+            .any(|relative| matches_has(element, relative)),
+        PseudoClass::Checked => {
+            elem.attributes.contains_key("checked") || elem.attributes.contains_key("selected")
+        }
+        PseudoClass::Disabled => elem.attributes.contains_key("disabled"),
+        PseudoClass::Enabled => {
+            matches!(
+                elem.tag_name.as_str(),
+                "button" | "input" | "select" | "textarea" | "option"
+            ) && !elem.attributes.contains_key("disabled")
+        }
+        PseudoClass::AnyLink => {
+            matches!(elem.tag_name.as_str(), "a" | "area") && elem.attributes.contains_key("href")
+        }
+        PseudoClass::UserAction(_) => false,
+    }
+}
+
+pub fn matches_simple_selector(element: &ElementRef, selector: &SimpleSelector) -> bool {
+    let elem = match element.element() {
+        Some(elem) => elem,
+        None => return false,
+    };
+
+    if selector
+        .tag_name
+        .iter()
+        .any(|name| !elem.tag_name.eq_ignore_ascii_case(name))
+    {
+        return false;
+    }
+
+    if selector.id.iter().any(|id| elem.id() != Some(id)) {
+        return false;
+    }
+
+    let elem_classes = elem.classes();
+    if selector
+        .class
+        .iter()
+        .any(|class| !elem_classes.contains(class.as_str()))
+    {
+        return false;
+    }
+
+    if !selector
+        .attributes
+        .iter()
+        .all(|attr| matches_attribute(elem, attr))
+    {
+        return false;
+    }
+
+    selector
+        .pseudo_classes
+        .iter()
+        .all(|pseudo_class| matches_pseudo_class(element, pseudo_class))
+}
+
+// match the compounds of the selector from right to left, starting at `index`;
+// relative selectors also check that the leftmost compound is related to the anchor
+fn matches_complex(
+    element: &ElementRef,
+    selector: &Selector,
+    index: usize,
+    anchor: Option<(&ElementRef, Combinator)>,
+) -> bool {
+    if !matches_simple_selector(element, &selector.compounds[index]) {
+        return false;
+    }
+
+    if index == 0 {
+        return match anchor {
+            Some((anchor, combinator)) => is_related(anchor, element, combinator),
+            None => true,
+        };
+    }
+
+    let next = |candidate: &ElementRef| matches_complex(candidate, selector, index - 1, anchor);
+    match selector.combinators[index - 1] {
+        Combinator::Child => element.parent().is_some_and(next),
+        Combinator::Descendant => {
+            let mut ancestor = element.parent();
+            while let Some(current) = ancestor {
+                if next(current) {
+                    return true;
+                }
+                ancestor = current.parent();
+            }
+            false
+        }
+        Combinator::NextSibling => element.previous_siblings().last().is_some_and(next),
+        Combinator::SubsequentSibling => element.previous_siblings().iter().any(next),
+    }
+}
+
+pub fn matches_selector(element: &ElementRef, selector: &Selector) -> bool {
+    matches_complex(element, selector, selector.compounds.len() - 1, None)
+}
+
+fn keyword<'v>(values: &'v PropertyMap, name: &str) -> Option<&'v str> {
+    match values.get(name) {
+        Some(Value::Keyword(keyword)) => Some(keyword),
+        _ => None,
+    }
+}
+
+// an integer attribute of an element, like the `start` of a list
+fn integer_attribute(elem: &ElementData, name: &str) -> Option<i32> {
+    elem.attributes.get(name)?.trim().parse().ok()
+}
+
+// whether an element is an ordered list counting down
+fn is_reversed_list(elem: &ElementData) -> bool {
+    elem.tag_name == "ol" && elem.attributes.contains_key("reversed")
+}
+
+fn update_counters(
+    values: &PropertyMap,
+    display: Display,
+    element: &ElementRef,
+    counters: &mut CounterState,
+) {
+    let counter_list = |name: &str| match values.get(name) {
+        Some(Value::CounterList(list)) => list.clone(),
+        _ => Vec::new(),
+    };
+    let elem = element.element().unwrap();
+
+    // an ordered list starts its items at its `start`, or when reversed at
+    // the number of its items
+    let mut resets = counter_list("counter-reset");
+    if elem.tag_name == "ol" {
+        if let Some(reset) = resets.iter_mut().find(|(name, _)| name == "list-item") {
+            let start = integer_attribute(elem, "start");
+            reset.1 = match is_reversed_list(elem) {
+                true => {
+                    let items = element.node.children.iter().filter(|child| {
+                        matches!(&child.node_type, NodeType::Element(child) if child.tag_name == "li")
+                    });
+                    let count = i32::try_from(items.count()).unwrap_or(i32::MAX);
+                    start.unwrap_or(count).saturating_add(1)
+                }
+                false => start.unwrap_or(1).saturating_sub(1),
+            };
+        }
+    }
+    for (name, value) in resets {
+        counters.reset(&name, value);
+    }
+    let increments = counter_list("counter-increment");
+    for (name, value) in &increments {
+        counters.increment(name, *value);
+    }
+    // list items implicitly increment the `list-item` counter, and the
+    // `value` of an item sets it
+    let mut sets = counter_list("counter-set");
+    if display == Display::ListItem {
+        if !increments.iter().any(|(name, _)| name == "list-item") {
+            let reversed = element
+                .parent()
+                .and_then(ElementRef::element)
+                .is_some_and(is_reversed_list);
+            counters.increment("list-item", if reversed { -1 } else { 1 });
+        }
+        if let Some(value) = integer_attribute(elem, "value") {
+            sets.insert(0, (String::from("list-item"), value));
+        }
+    }
+    for (name, value) in sets {
+        counters.set(&name, value);
+    }
+}
+
+fn resolve_content(items: &[ContentItem], elem: &ElementData, counters: &CounterState) -> String {
+    items
+        .iter()
+        .map(|item| match item {
+            ContentItem::String(text) => text.clone(),
+            ContentItem::Attr(name) => elem.attributes.get(name).cloned().unwrap_or_default(),
+            ContentItem::Counter(name, style) => format_counter(counters.value(name), style),
+            ContentItem::Counters(name, separator, style) => counters
+                .values(name)
+                .iter()
+                .map(|value| format_counter(*value, style))
+                .collect::<Vec<String>>()
+                .join(separator),
+            // without a `quotes` property, quotes are the english ones and don't nest
+            ContentItem::OpenQuote => "\u{201c}".to_string(),
+            ContentItem::CloseQuote => "\u{201d}".to_string(),
+            // the others only change the nesting of quotes, and images aren't
+            // generated as text
+            ContentItem::NoOpenQuote | ContentItem::NoCloseQuote | ContentItem::Url(_) => {
+                String::new()
+            }
+        })
+        .collect()
+}
+
+// the anonymous box of a `::before`, `::after` or `::marker` pseudo-element,
+// if its `content` generates one
+fn generated_node<'a>(
+    node: &'a Node,
+    element: &ElementRef,
+    cascade: &Cascade,
+    pseudo_element: PseudoElement,
+    element_values: &PropertyMap,
+    context: &LengthContext,
+    counters: &CounterState,
+) -> Option<StyledNode<'a>> {
+    let elem = element.element().unwrap();
+    let mut values = resolve_variables(
+        cascade.cascaded_values(element, Some(pseudo_element)),
+        element_values,
+    );
+    apply_defaults(&mut values, element_values);
+    compute_values(&mut values, element_values, context);
+
+    let content = match values.get("content") {
+        Some(Value::Content(items)) => resolve_content(items, elem, counters),
+        // with `content: normal`, markers show the list item counter, or
+        // the string of the list style
+        Some(Value::Keyword(content))
+            if content == "normal" && pseudo_element == PseudoElement::Marker =>
+        {
+            match element_values.get("list-style-type") {
+                Some(Value::String(text)) => text.clone(),
+                _ => {
+                    let style = keyword(element_values, "list-style-type").unwrap_or("disc");
+                    marker_text(counters.value("list-item"), style)
+                }
+            }
+        }
+        _ => return None,
+    };
+
+    Some(StyledNode {
+        node,
+        style: ComputedStyle::from_values(&values),
+        computed_values: values,
+        children: Vec::new(),
+        pseudo_element: Some(pseudo_element),
+        generated_content: Some(content),
+    })
+}
+
+fn styled_node<'a>(
+    node: &'a Node,
+    element: &ElementRef,
+    cascade: &Cascade,
+    parent_values: &PropertyMap,
+    context: &LengthContext,
+    counters: &mut CounterState,
+) -> StyledNode<'a> {
+    if element.element().is_none() {
+        // text inherits from its parent element
+        let mut computed_values = PropertyMap::new();
+        apply_defaults(&mut computed_values, parent_values);
+        compute_values(&mut computed_values, parent_values, context);
+        return StyledNode {
+            node,
+            style: ComputedStyle::from_values(&computed_values),
+            computed_values,
+            children: Vec::new(),
+            pseudo_element: None,
+            generated_content: None,
+        };
+    }
+
+    let mut computed_values =
+        resolve_variables(cascade.cascaded_values(element, None), parent_values);
+    apply_defaults(&mut computed_values, parent_values);
+    compute_values(&mut computed_values, parent_values, context);
+    let style = ComputedStyle::from_values(&computed_values);
+    update_counters(&computed_values, style.display, element, counters);
+
+    // `rem` lengths are relative to the font size of the root element
+    let root_context;
+    let context = match element.parent() {
+        Some(_) => context,
+        None => {
+            root_context = LengthContext {
+                root_font_size: style.font_size,
+                ..*context
+            };
+            &root_context
+        }
+    };
+
+    let mut children = Vec::new();
+    counters.enter();
+    if style.display == Display::ListItem {
+        children.extend(generated_node(
+            node,
+            element,
+            cascade,
+            PseudoElement::Marker,
+            &computed_values,
+            context,
+            counters,
+        ));
+    }
+    children.extend(generated_node(
+        node,
+        element,
+        cascade,
+        PseudoElement::Before,
+        &computed_values,
+        context,
+        counters,
+    ));
+    for (index, child) in node.children.iter().enumerate() {
+        children.push(styled_node(
+            child,
+            &element.child(index),
+            cascade,
+            &computed_values,
+            context,
+            counters,
+        ));
+    }
+    children.extend(generated_node(
+        node,
+        element,
+        cascade,
+        PseudoElement::After,
+        &computed_values,
+        context,
+        counters,
+    ));
+    counters.leave();
+
+    StyledNode {
+        node,
+        computed_values,
+        style,
+        children,
+        pseudo_element: None,
+        generated_content: None,
+    }
+}
+
+pub fn cascade_style_tree<'a>(root: &'a Node, cascade: &Cascade) -> StyledNode<'a> {
+    let mut counters = CounterState::new();
+    let context = LengthContext {
+        viewport_width: cascade.media().width,
+        viewport_height: cascade.media().height,
+        ..LengthContext::default()
+    };
+    styled_node(
+        root,
+        &ElementRef::root(root),
+        cascade,
+        &PropertyMap::new(),
+        &context,
+        &mut counters,
+    )
+}
+
+// style a tree with the default styles of html elements and an author stylesheet
+pub fn style_tree<'a>(root: &'a Node, stylesheet: &'a Stylesheet) -> StyledNode<'a> {
+    let mut cascade = Cascade::new();
+    cascade.add_stylesheet(stylesheet);
+    cascade_style_tree(root, &cascade)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::CSSParser;
+    use crate::html::HTMLParser;
+    use crate::properties::initial_value;
+
+    fn element(tag_name: &str, attributes: &[(&str, &str)]) -> Node {
+        Node::element(
+            tag_name.to_string(),
+            attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            Vec::new(),
+        )
+    }
+
+    fn parse_selector(selector: &str) -> Selector {
+        let mut css_parser = CSSParser::new(selector.to_string());
+        css_parser.parse_selectors().unwrap().remove(0)
+    }
+
+    fn matches(node: &Node, selector: &str) -> bool {
+        matches_selector(&ElementRef::root(node), &parse_selector(selector))
+    }
+
+    // ids of the elements of the document matching the selector, in tree order
+    fn select(html: &str, selector: &str) -> Vec<String> {
+        fn walk(element: &ElementRef, selector: &Selector, ids: &mut Vec<String>) {
+            if let Some(elem) = element.element() {
+                if matches_selector(element, selector) {
+                    ids.push(elem.id().cloned().unwrap_or(elem.tag_name.clone()));
+                }
+            }
+            for index in 0..element.node.children.len() {
+                walk(&element.child(index), selector, ids);
+            }
+        }
+
+        let document = HTMLParser::new(html.to_string()).parse_document();
+        let mut ids = Vec::new();
+        walk(
+            &ElementRef::root(document.root()),
+            &parse_selector(selector),
+            &mut ids,
+        );
+        ids
+    }
+
+    const LIST: &str = "<html><body>
+        <ul id='list'>
+            <li id='a' class='item'>A</li>
+            <li id='b'>B</li>
+            <p id='c' class='item'></p>
+            <li id='d' class='item'><img id='img' src='x.png'></img></li>
+            <li id='e' class='item'>E</li>
+        </ul>
+    </body></html>";
+
+    #[test]
+    fn test_match_attribute_selectors() {
+        let checkbox = element("input", &[("type", "checkbox"), ("class", "big")]);
+        assert!(matches(&checkbox, "[type=checkbox]"));
+        assert!(matches(&checkbox, "input[type].big"));
+        assert!(!matches(&checkbox, "[type=radio]"));
+        assert!(!matches(&checkbox, "[checked]"));
+
+        let link = element("a", &[("href", "https://example.com/DOC.PDF")]);
+        assert!(matches(&link, "a[href^=\"https\"]"));
+        assert!(matches(&link, "[href$=\".pdf\" i]"));
+        assert!(!matches(&link, "[href$=\".pdf\"]"));
+        // attribute names are case-insensitive, not their values
+        assert!(matches(&link, "a[HREF]"));
+        assert!(matches(&link, "[Href*=DOC]"));
+
+        let panel = element("div", &[("data-state", "visible open"), ("lang", "en-GB")]);
+        assert!(matches(&panel, "[data-state~=open]"));
+        assert!(matches(&panel, "[lang|=en]"));
+    }
+
+    #[test]
+    fn test_match_combinators() {
+        assert_eq!(select(LIST, "ul > li + li"), vec!["b", "e"]);
+        assert_eq!(select(LIST, "#a ~ .item"), vec!["c", "d", "e"]);
+        assert_eq!(select(LIST, "body li img"), vec!["img"]);
+        assert!(select(LIST, "body > li").is_empty());
+    }
+
+    #[test]
+    fn test_match_structural_pseudo_classes() {
+        assert_eq!(select(LIST, "li:first-child"), vec!["a"]);
+        assert_eq!(select(LIST, "li:last-child"), vec!["e"]);
+        assert_eq!(
+            select(LIST, ":only-child"),
+            vec!["html", "body", "list", "img"]
+        );
+        assert_eq!(select(LIST, ":root"), vec!["html"]);
+        assert_eq!(select(LIST, ":empty"), vec!["c", "img"]);
+        assert_eq!(select(LIST, "li:nth-child(odd)"), vec!["a", "e"]);
+        assert_eq!(select(LIST, "li:nth-last-child(2)"), vec!["d"]);
+        assert_eq!(select(LIST, "li:nth-of-type(3)"), vec!["d"]);
+        assert_eq!(select(LIST, "li:first-of-type"), vec!["a"]);
+        assert_eq!(select(LIST, "li:last-of-type"), vec!["e"]);
+        assert_eq!(select(LIST, "p:only-of-type"), vec!["c"]);
+        assert_eq!(select(LIST, ":nth-child(2 of .item)"), vec!["c"]);
+        assert_eq!(
+            select(LIST, ":nth-last-child(-n+2 of .item)"),
+            vec!["d", "e"]
+        );
+    }
+
+    #[test]
+    fn test_match_logical_pseudo_classes() {
+        assert_eq!(select(LIST, "li:not(.item)"), vec!["b"]);
+        assert_eq!(select(LIST, "li:not(:first-child, #e)"), vec!["b", "d"]);
+        assert_eq!(select(LIST, ":is(p, #b).item"), vec!["c"]);
+        assert_eq!(select(LIST, ":where(ul) :is(#a, #e)"), vec!["a", "e"]);
+        assert_eq!(select(LIST, "li:has(img)"), vec!["d"]);
+        assert_eq!(select(LIST, "ul:has(> li > img)"), vec!["list"]);
+        assert!(select(LIST, "ul:has(> img)").is_empty());
+        assert_eq!(select(LIST, "li:has(+ p)"), vec!["b"]);
+        assert_eq!(select(LIST, "p:has(~ #e)"), vec!["c"]);
+    }
+
+    #[test]
+    fn test_style_tree_attribute_rules() {
+        let root = Node::element(
+            "html".to_string(),
+            HashMap::new(),
+            vec![Box::new(element("input", &[("type", "checkbox")]))],
+        );
+        let mut css_parser = CSSParser::new(
+            "[type=checkbox] { margin-top: 4px; } input { margin-top: 1px; }".to_string(),
+        );
+        let stylesheet = css_parser.parse_stylesheet();
+
+        let styled = style_tree(&root, &stylesheet);
+        assert_eq!(styled.value("margin-top"), initial_value("margin-top"));
+        assert_eq!(
+            styled.children[0].value("margin-top"),
+            Some(&Value::Length(4.0, crate::css::Unit::Px))
+        );
+    }
+
+    #[test]
+    fn test_style_tree_skips_invalid_selectors() {
+        styled(
+            "<html><p>text</p></html>",
+            "p:unknown { margin-top: 1px; } p { margin-top: 2px; } p:lang(en) { margin-top: 3px; }",
+            |root| {
+                assert_eq!(
+                    root.children[0].value("margin-top"),
+                    Some(&Value::Length(2.0, crate::css::Unit::Px))
+                );
+            },
+        );
+    }
+
+    fn styled(html: &str, css: &str, f: impl Fn(&StyledNode)) {
+        styled_with(false, html, css, f);
+    }
+
+    // with the default styles of html elements
+    fn user_agent_styled(html: &str, css: &str, f: impl Fn(&StyledNode)) {
+        styled_with(true, html, css, f);
+    }
+
+    fn styled_with(user_agent: bool, html: &str, css: &str, f: impl Fn(&StyledNode)) {
+        let document = HTMLParser::new(html.to_string()).parse_document();
+        let stylesheet = CSSParser::new(css.to_string()).parse_stylesheet();
+        let mut cascade = Cascade::new();
+        if !user_agent {
+            cascade = cascade.without_user_agent_stylesheet();
+        }
+        cascade.add_stylesheet(&stylesheet);
+        f(&cascade_style_tree(document.root(), &cascade));
+    }
+
+    // the text of the generated boxes of the tree, in order
+    fn generated(root: &StyledNode) -> Vec<String> {
+        let mut texts: Vec<String> = root.generated_content.iter().cloned().collect();
+        for child in &root.children {
+            texts.extend(generated(child));
+        }
+        texts
+    }
+
+    #[test]
+    fn test_before_and_after_content() {
+        styled(
+            "<html><a href='/docs' title='Docs'>link</a></html>",
+            "a::before { content: \"[\" attr(title) \"] \"; color: #cc0000; }
+            a:after { content: \" \u{2192}\"; }
+            a::marker { content: \"never\"; }",
+            |root| {
+                let link = &root.children[0];
+                assert_eq!(link.children.len(), 3);
+                assert_eq!(link.children[0].pseudo_element, Some(PseudoElement::Before));
+                assert_eq!(
+                    link.children[0].generated_content.as_deref(),
+                    Some("[Docs] ")
+                );
+                assert!(link.children[0].value("color").is_some());
+                assert_eq!(link.value("color"), initial_value("color"));
+                assert_eq!(link.children[2].pseudo_element, Some(PseudoElement::After));
+                assert_eq!(generated(root), vec!["[Docs] ", " \u{2192}"]);
+            },
+        );
+    }
+
+    #[test]
+    fn test_no_generated_box_without_content() {
+        styled(
+            "<html><p>text</p></html>",
+            "p::before { color: #cc0000; } p::after { content: none; }",
+            |root| assert_eq!(root.children[0].children.len(), 1),
+        );
+    }
+
+    #[test]
+    fn test_nested_counters() {
+        styled(
+            "<html><ol><li>a<ol><li>b</li><li>c</li></ol></li><li>d</li></ol></html>",
+            "ol { counter-reset: item; }
+            li { counter-increment: item; }
+            li::before { content: counters(item, \".\") \" \"; }",
+            |root| assert_eq!(generated(root), vec!["1 ", "1.1 ", "1.2 ", "2 "]),
+        );
+    }
+
+    #[test]
+    fn test_list_item_markers() {
+        styled(
+            "<html><ul><li>a</li><li>b</li></ul><ol><li>c</li><li>d</li></ol></html>",
+            "li { display: list-item; }
+            ol li { list-style-type: decimal; }
+            ol li:last-child::marker { content: counter(list-item) \") \"; }",
+            |root| {
+                let first_item = &root.children[0].children[0];
+                assert_eq!(
+                    first_item.children[0].pseudo_element,
+                    Some(PseudoElement::Marker)
+                );
+                assert_eq!(
+                    generated(root),
+                    vec!["\u{2022} ", "\u{2022} ", "1. ", "2) "]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_list_attributes() {
+        user_agent_styled(
+            "<html><ol start='3'><li>a</li><li value='10'>b</li><li>c</li></ol>\
+             <ol reversed><li>d</li><li>e</li><li>f</li></ol>\
+             <ol reversed start='5'><li>g</li><li>h</li></ol></html>",
+            "ol { list-style-type: upper-roman; }",
+            |root| {
+                assert_eq!(
+                    generated(root),
+                    vec!["III. ", "X. ", "XI. ", "III. ", "II. ", "I. ", "V. ", "IV. "]
+                );
+            },
+        );
+        // nested lists count their own items, and a string is the marker
+        user_agent_styled(
+            "<html><ul><li>a<ul><li>b</li></ul></li><li>c</li></ul></html>",
+            "ul { list-style-type: decimal; } ul ul { list-style-type: \"- \"; }",
+            |root| assert_eq!(generated(root), vec!["1. ", "- ", "2. "]),
+        );
+        // counting stops at the largest and smallest integers
+        user_agent_styled(
+            "<html><ol start='2147483647'><li>a</li><li>b</li></ol>\
+             <ol reversed start='-2147483648'><li>c</li><li>d</li></ol></html>",
+            "",
+            |root| {
+                assert_eq!(
+                    generated(root),
+                    vec![
+                        "2147483647. ",
+                        "2147483647. ",
+                        "-2147483648. ",
+                        "-2147483648. "
+                    ]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_user_agent_stylesheet() {
+        let html = "<html><head><title>t</title></head><body><h1>a</h1><p>b</p></body></html>";
+        let document = HTMLParser::new(html.to_string()).parse_document();
+        let author = CSSParser::new("p { display: inline; }".to_string()).parse_stylesheet();
+
+        let mut cascade = Cascade::new();
+        cascade.add_stylesheet(&author);
+        let root = cascade_style_tree(document.root(), &cascade);
+        let (head, body) = (&root.children[0], &root.children[1]);
+        assert_eq!(head.style.display, Display::None);
+        assert_eq!(body.style.display, Display::Block);
+        let heading = &body.children[0];
+        assert_eq!(heading.style.font_size, 32.0);
+        assert_eq!(heading.style.font_weight, 700.0);
+        // author styles win over the default ones
+        assert_eq!(body.children[1].style.display, Display::Inline);
+
+        let mut cascade = Cascade::new().without_user_agent_stylesheet();
+        cascade.add_stylesheet(&author);
+        let root = cascade_style_tree(document.root(), &cascade);
+        assert_eq!(root.children[0].style.display, Display::Inline);
+        assert_eq!(root.children[1].children[0].style.font_size, 16.0);
+    }
+
+    #[test]
+    fn test_user_agent_default_values() {
+        user_agent_styled(
+            "<html><body><div><a href='/docs'>link</a><a>anchor</a></div>\
+             <ul><li>a</li></ul></body></html>",
+            "",
+            |root| {
+                let body = &root.children[0];
+                let div = &body.children[0];
+                assert_eq!(div.style.display, Display::Block);
+                assert_eq!(
+                    div.children[0].value("color"),
+                    Some(&Value::ColorValue(crate::css::Color::rgb(0, 0, 255)))
+                );
+                // only links are colored
+                assert_eq!(div.children[1].value("color"), initial_value("color"));
+                let list = &body.children[1];
+                assert_eq!(list.style.display, Display::Block);
+                assert_eq!(list.children[0].style.display, Display::ListItem);
+                assert_eq!(generated(list), vec!["\u{2022} "]);
+            },
+        );
+    }
+
+    #[test]
+    fn test_custom_properties_inheritance() {
+        styled(
+            "<html><div><p>a</p><p class='x'>b</p></div></html>",
+            ":root { --brand: #cc0000; --label: \"!\"; }
+            div { --gap: 2px; }
+            p { color: var(--brand, #000000); margin-top: var(--gap); }
+            .x { --brand: var(--gap); }
+            p::after { content: var(--label); }",
+            |root| {
+                let div = &root.children[0];
+                assert!(div.value("--brand").is_some());
+                let first = &div.children[0];
+                assert_eq!(
+                    first.value("color"),
+                    Some(&Value::ColorValue(crate::css::Color::rgb(204, 0, 0)))
+                );
+                assert_eq!(
+                    first.value("margin-top"),
+                    Some(&Value::Length(2.0, crate::css::Unit::Px))
+                );
+                // a length isn't a valid color, so the color is inherited
+                assert_eq!(div.children[1].value("color"), div.value("color"));
+                assert_eq!(generated(root), vec!["!", "!"]);
+            },
+        );
+    }
+
+    #[test]
+    fn test_first_line_and_first_letter_rules() {
+        // they don't apply to the element itself
+        styled(
+            "<html><p>text</p></html>",
+            "p::first-line { color: #cc0000; } p::first-letter { font-size: 2em; }",
+            |root| {
+                let paragraph = &root.children[0];
+                assert_eq!(paragraph.value("color"), initial_value("color"));
+                assert_eq!(paragraph.style.font_size, 16.0);
+                assert!(generated(root).is_empty());
+            },
+        );
+    }
+}
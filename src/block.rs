@@ -0,0 +1,680 @@
+use crate::floats::FloatContext;
+use crate::layout::{BoxType, ContainingBlock, Dimensions, EdgeSizes, LayoutBox, LayoutContext};
+use crate::style::{BoxSizing, Direction, Size};
+
+// the adjoining vertical margins of a block formatting context, collapsing
+// into the largest positive margin plus the most negative one
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MarginStrut {
+    positive: f32,
+    negative: f32,
+    // the margins collapse with the top margin of an ancestor, which was
+    // already placed below them
+    applied: bool,
+}
+
+impl MarginStrut {
+    pub fn adjoin(&mut self, margin: f32) {
+        self.positive = self.positive.max(margin);
+        self.negative = self.negative.min(margin);
+    }
+
+    pub fn collapsed(&self) -> f32 {
+        self.positive + self.negative
+    }
+
+    // the space left above the next box
+    fn offset(&self) -> f32 {
+        match self.applied {
+            true => 0.0,
+            false => self.collapsed(),
+        }
+    }
+}
+
+// CSS 2.1 §10.3.3: the widths of the margins and the content of a block
+// fill the width `available` to them, auto margins taking up the rest;
+// an over-constrained end margin is ignored
+fn solve_widths(
+    width: Option<f32>,
+    margin_left: Option<f32>,
+    margin_right: Option<f32>,
+    available: f32,
+    rtl: bool,
+) -> (f32, f32, f32) {
+    let Some(width) = width else {
+        let (left, right) = (margin_left.unwrap_or(0.0), margin_right.unwrap_or(0.0));
+        return match available - left - right {
+            width if width >= 0.0 => (width, left, right),
+            _ if rtl => (0.0, available - right, right),
+            _ => (0.0, left, available - left),
+        };
+    };
+
+    let (mut margin_left, mut margin_right) = (margin_left, margin_right);
+    if width + margin_left.unwrap_or(0.0) + margin_right.unwrap_or(0.0) > available {
+        margin_left = margin_left.or(Some(0.0));
+        margin_right = margin_right.or(Some(0.0));
+    }
+    let rest = available - width;
+    match (margin_left, margin_right) {
+        (Some(_), Some(right)) if rtl => (width, rest - right, right),
+        (Some(left), _) => (width, left, rest - left),
+        (None, Some(right)) => (width, rest - right, right),
+        (None, None) => (width, rest / 2.0, rest / 2.0),
+    }
+}
+
+impl<'a> LayoutBox<'a> {
+    // the padding and border widths of the box, percentages resolving against
+    // the width of its containing block
+    pub(crate) fn padding_and_border(&self, containing_width: f32) -> (EdgeSizes, EdgeSizes) {
+        if let Some(border) = self.collapsed_table_borders() {
+            return (EdgeSizes::default(), border);
+        }
+        let style = self.style();
+        let padding = EdgeSizes {
+            left: style.padding.left.resolve(containing_width),
+            right: style.padding.right.resolve(containing_width),
+            top: style.padding.top.resolve(containing_width),
+            bottom: style.padding.bottom.resolve(containing_width),
+        };
+        let border = EdgeSizes {
+            left: style.border_width.left,
+            right: style.border_width.right,
+            top: style.border_width.top,
+            bottom: style.border_width.bottom,
+        };
+        (padding, border)
+    }
+
+    // the horizontal dimensions of a block-level box, and the widths of its
+    // vertical margins, padding and borders
+    fn block_widths(&self, context: &LayoutContext, containing_width: f32) -> Dimensions {
+        let style = self.style();
+        let (padding, border) = self.padding_and_border(containing_width);
+        let margin = |size: &Size| size.resolve(Some(containing_width));
+        let edges = padding.left + padding.right + border.left + border.right;
+        let content_width = |size: &Size| {
+            size.resolve(Some(containing_width))
+                .map(|width| match style.box_sizing {
+                    BoxSizing::ContentBox => width,
+                    BoxSizing::BorderBox => (width - edges).max(0.0),
+                })
+        };
+
+        let (margin_left, margin_right) = (margin(&style.margin.left), margin(&style.margin.right));
+        // inline-blocks and floats shrink to fit their content, and ignore
+        // auto margins
+        let shrink_to_fit = |width: Option<f32>| {
+            let (left, right) = (margin_left.unwrap_or(0.0), margin_right.unwrap_or(0.0));
+            let width = width.unwrap_or_else(|| {
+                let (min_content, max_content) = self.intrinsic_widths(context);
+                let available = containing_width - edges - left - right;
+                max_content.min(available).max(min_content)
+            });
+            (width, left, right)
+        };
+        // a table is at least as wide as its columns need, and otherwise
+        // shrinks to fit them
+        let table_width = |width: Option<f32>| {
+            let (min_content, max_content) = self.intrinsic_widths(context);
+            let margins = margin_left.unwrap_or(0.0) + margin_right.unwrap_or(0.0);
+            let available = containing_width - edges - margins;
+            width.unwrap_or(max_content.min(available)).max(min_content)
+        };
+        let solve = |width: Option<f32>| {
+            let width = match self.is_table() {
+                true => Some(table_width(width)),
+                false => width,
+            };
+            match self.box_type {
+                BoxType::InlineBlock(_) => shrink_to_fit(width),
+                _ if self.is_floated() => shrink_to_fit(width),
+                _ => solve_widths(
+                    width,
+                    margin_left,
+                    margin_right,
+                    containing_width - edges,
+                    style.direction == Direction::Rtl,
+                ),
+            }
+        };
+        let mut widths = solve(content_width(&style.width));
+        if let Some(max_width) = content_width(&style.max_width) {
+            if widths.0 > max_width {
+                widths = solve(Some(max_width));
+            }
+        }
+        if let Some(min_width) = content_width(&style.min_width) {
+            if widths.0 < min_width {
+                widths = solve(Some(min_width));
+            }
+        }
+        let (width, margin_left, margin_right) = widths;
+
+        let mut dimensions = Dimensions {
+            padding,
+            border,
+            margin: EdgeSizes {
+                left: margin_left,
+                right: margin_right,
+                top: margin(&style.margin.top).unwrap_or(0.0),
+                bottom: margin(&style.margin.bottom).unwrap_or(0.0),
+            },
+            ..Dimensions::default()
+        };
+        dimensions.content.width = width;
+        dimensions
+    }
+
+    // a content height, `None` when auto or a percentage of an indefinite
+    // height
+    pub(crate) fn content_height(
+        &self,
+        size: &Size,
+        containing_height: Option<f32>,
+    ) -> Option<f32> {
+        let dimensions = self.dimensions;
+        size.resolve(containing_height)
+            .map(|height| match self.style().box_sizing {
+                BoxSizing::ContentBox => height,
+                BoxSizing::BorderBox => {
+                    let edges = dimensions.padding.top
+                        + dimensions.padding.bottom
+                        + dimensions.border.top
+                        + dimensions.border.bottom;
+                    (height - edges).max(0.0)
+                }
+            })
+    }
+
+    // whether the box has inline content that generates line boxes
+    fn has_inline_content(&self) -> bool {
+        match &self.box_type {
+            BoxType::Text(..) => !self.is_collapsible_whitespace(),
+            BoxType::InlineBlock(_) => true,
+            BoxType::Inline(_) => {
+                let style = self.style();
+                style.border_width.left + style.border_width.right > 0.0
+                    || style.padding.left.resolve(1.0) != 0.0
+                    || style.padding.right.resolve(1.0) != 0.0
+                    || self.children.iter().any(LayoutBox::has_inline_content)
+            }
+            _ => false,
+        }
+    }
+
+    // whether the top and bottom margins of the box collapse together: an
+    // empty block with no height that isn't a formatting context root
+    fn collapses_through(&self) -> bool {
+        if !self.is_block_level() || self.establishes_formatting_context() {
+            return false;
+        }
+        let style = self.style();
+        let is_zero = |size: &Size| size.resolve(Some(1.0)).is_none_or(|size| size == 0.0);
+        let mut in_flow = self.children.iter().filter(|child| !child.is_out_of_flow());
+        let empty = match self.has_inline_children() {
+            true => !in_flow.any(LayoutBox::has_inline_content),
+            false => in_flow.all(LayoutBox::collapses_through),
+        };
+        empty
+            && is_zero(&style.height)
+            && is_zero(&style.min_height)
+            && style.padding.top.resolve(1.0) == 0.0
+            && style.padding.bottom.resolve(1.0) == 0.0
+            && style.border_width.top == 0.0
+            && style.border_width.bottom == 0.0
+    }
+
+    // adjoins the margins that collapse with the top margin of the box: the
+    // margins of its first children, down to the first one with content
+    fn adjoin_leading_margins(&self, context: &LayoutContext, strut: &mut MarginStrut, width: f32) {
+        let in_flow = |child: &&LayoutBox| child.is_block_level() && !child.is_out_of_flow();
+        for child in self.children.iter().filter(in_flow) {
+            let dimensions = child.block_widths(context, width);
+            let independent = child.establishes_formatting_context();
+            strut.adjoin(dimensions.margin.top);
+            if child.collapses_through() {
+                child.adjoin_leading_margins(context, strut, dimensions.content.width);
+                strut.adjoin(dimensions.margin.bottom);
+                continue;
+            }
+            if !independent && dimensions.border.top == 0.0 && dimensions.padding.top == 0.0 {
+                child.adjoin_leading_margins(context, strut, dimensions.content.width);
+            }
+            return;
+        }
+    }
+
+    // lays out a block-level box below `y`, after the margins of `strut`, in
+    // a formatting context with the `floats`; returns the margins collapsing
+    // below the box. The margins of an `independent` box don't collapse with
+    // its children, and it contains its own floats
+    pub(crate) fn layout_block(
+        &mut self,
+        context: &LayoutContext,
+        containing: &ContainingBlock,
+        y: f32,
+        mut strut: MarginStrut,
+        independent: bool,
+        floats: &mut FloatContext,
+    ) -> MarginStrut {
+        let mut own_floats = FloatContext::new();
+        let floats = match independent {
+            true => &mut own_floats,
+            false => floats,
+        };
+        self.dimensions = self.block_widths(context, containing.width);
+        let dimensions = self.dimensions;
+        self.dimensions.content.x = containing.x
+            + dimensions.margin.left
+            + dimensions.border.left
+            + dimensions.padding.left;
+        strut.adjoin(dimensions.margin.top);
+
+        let style = self.style();
+        let height = self.content_height(&style.height, containing.height);
+        let min_height = self
+            .content_height(&style.min_height, containing.height)
+            .unwrap_or(0.0);
+        let max_height = self.content_height(&style.max_height, containing.height);
+
+        // an empty box is placed after the margins, which all collapse with
+        // the ones below it
+        if !independent && self.collapses_through() {
+            self.dimensions.content.y = y + strut.offset();
+            let containing = self.children_containing_block(height);
+            let applied = MarginStrut {
+                applied: true,
+                ..MarginStrut::default()
+            };
+            self.layout_block_children(context, &containing, applied, floats);
+            self.layout_outside_marker(context);
+            self.dimensions.content.height = 0.0;
+            self.adjoin_leading_margins(context, &mut strut, dimensions.content.width);
+            strut.adjoin(dimensions.margin.bottom);
+            return strut;
+        }
+
+        // the top margin collapses with the margins of the first children,
+        // and the box is placed after all of them
+        let top_collapses =
+            !independent && dimensions.border.top == 0.0 && dimensions.padding.top == 0.0;
+        let children_strut = match top_collapses {
+            true => {
+                self.adjoin_leading_margins(context, &mut strut, dimensions.content.width);
+                self.dimensions.content.y = y + strut.offset();
+                MarginStrut {
+                    applied: true,
+                    ..MarginStrut::default()
+                }
+            }
+            false => {
+                self.dimensions.content.y =
+                    y + strut.offset() + dimensions.border.top + dimensions.padding.top;
+                MarginStrut::default()
+            }
+        };
+
+        let containing = self.children_containing_block(height);
+        let (bottom, trailing) =
+            self.layout_block_children(context, &containing, children_strut, floats);
+        self.layout_outside_marker(context);
+
+        // likewise the bottom margin collapses with the margins of the last
+        // children when the box takes their height
+        let bottom_collapses = !independent
+            && height.is_none()
+            && min_height == 0.0
+            && dimensions.border.bottom == 0.0
+            && dimensions.padding.bottom == 0.0;
+        let content_y = self.dimensions.content.y;
+        let mut auto_height = match bottom_collapses {
+            true => bottom - content_y,
+            false => bottom + trailing.offset() - content_y,
+        };
+        if let Some(floats_bottom) = floats.bottom().filter(|_| independent) {
+            auto_height = auto_height.max(floats_bottom - content_y);
+        }
+        let mut content_height = height.unwrap_or(auto_height);
+        if let Some(max_height) = max_height {
+            content_height = content_height.min(max_height);
+        }
+        // a table is at least as tall as its rows
+        if self.is_table() {
+            content_height = content_height.max(auto_height);
+        }
+        self.dimensions.content.height = content_height.max(min_height);
+
+        let mut strut = match bottom_collapses {
+            true => trailing,
+            false => MarginStrut::default(),
+        };
+        strut.adjoin(dimensions.margin.bottom);
+        strut
+    }
+
+    // lays out an independent box sized by its formatting context, like a
+    // flex item: `dimensions` holds its margins, padding, borders and content
+    // width, and a definite `height` replaces its height properties. The
+    // margin box is placed at the origin
+    pub(crate) fn layout_sized(
+        &mut self,
+        context: &LayoutContext,
+        dimensions: Dimensions,
+        containing_height: Option<f32>,
+        height: Option<f32>,
+    ) {
+        self.dimensions = dimensions;
+        self.dimensions.content.x =
+            dimensions.margin.left + dimensions.border.left + dimensions.padding.left;
+        self.dimensions.content.y =
+            dimensions.margin.top + dimensions.border.top + dimensions.padding.top;
+
+        let style = self.style();
+        let specified = height.or_else(|| self.content_height(&style.height, containing_height));
+        let min_height = self
+            .content_height(&style.min_height, containing_height)
+            .unwrap_or(0.0);
+        let max_height = self.content_height(&style.max_height, containing_height);
+
+        let containing = self.children_containing_block(specified);
+        let mut floats = FloatContext::new();
+        let (bottom, trailing) =
+            self.layout_block_children(context, &containing, MarginStrut::default(), &mut floats);
+        self.layout_outside_marker(context);
+        self.dimensions.content.height = match height {
+            Some(height) => height,
+            None => {
+                let bottom = floats.bottom().map_or(bottom, |floats| bottom.max(floats));
+                let auto_height = bottom + trailing.offset() - containing.y;
+                let mut content_height = specified.unwrap_or(auto_height);
+                if let Some(max_height) = max_height {
+                    content_height = content_height.min(max_height);
+                }
+                if self.is_table() {
+                    content_height = content_height.max(auto_height);
+                }
+                content_height.max(min_height)
+            }
+        };
+    }
+
+    // lays out the outside marker of a list item before the start of its
+    // first line, on its baseline, or at the top of its content without
+    // lines
+    fn layout_outside_marker(&mut self, context: &LayoutContext) {
+        let Some(index) = self.children.iter().position(LayoutBox::is_outside_marker) else {
+            return;
+        };
+        let content = self.dimensions.content;
+        let baseline = self.first_baseline();
+        let rtl = self.style().direction == Direction::Rtl;
+        let marker = &mut self.children[index];
+        let mut dimensions = Dimensions::default();
+        dimensions.content.width = marker.intrinsic_widths(context).1;
+        marker.layout_sized(context, dimensions, None, None);
+        let margin_box = marker.dimensions.margin_box();
+        let marker_baseline = marker.first_baseline().unwrap_or(margin_box.height);
+        let x = match rtl {
+            true => content.x + content.width,
+            false => content.x - margin_box.width,
+        };
+        let y = baseline.map_or(content.y, |baseline| baseline - marker_baseline);
+        marker.translate(x - margin_box.x, y - margin_box.y);
+    }
+
+    fn children_containing_block(&self, height: Option<f32>) -> ContainingBlock {
+        let content = self.dimensions.content;
+        ContainingBlock {
+            x: content.x,
+            y: content.y,
+            width: content.width,
+            height,
+        }
+    }
+
+    // lays out the children of a block container from the top of its
+    // content; returns the bottom of the last child with content, and the
+    // margins collapsing below it
+    fn layout_block_children(
+        &mut self,
+        context: &LayoutContext,
+        containing: &ContainingBlock,
+        mut strut: MarginStrut,
+        floats: &mut FloatContext,
+    ) -> (f32, MarginStrut) {
+        let items_height = if self.is_flex_container() {
+            Some(self.layout_flex_items(context, containing))
+        } else if self.is_grid_container() {
+            Some(self.layout_grid_items(context, containing))
+        } else if self.is_table() {
+            Some(self.layout_table(context, containing))
+        } else {
+            None
+        };
+        if let Some(height) = items_height {
+            // out-of-flow children are placed at the start of the content
+            for child in self
+                .children
+                .iter_mut()
+                .filter(|child| child.is_absolutely_positioned())
+            {
+                child.set_static_position(containing.x, containing.y);
+            }
+            return (containing.y + height, MarginStrut::default());
+        }
+        if self.has_inline_children() {
+            let height = self.layout_inline_content(context, floats);
+            return (containing.y + height, MarginStrut::default());
+        }
+
+        let mut bottom = containing.y;
+        for child in &mut self.children {
+            if child.is_absolutely_positioned() {
+                child.set_static_position(containing.x, bottom + strut.offset());
+                continue;
+            }
+            if child.is_floated() {
+                child.layout_float(context, containing, bottom + strut.offset(), floats);
+                continue;
+            }
+            if child.is_outside_marker() {
+                continue;
+            }
+
+            // clearance puts the border box of the box below the floats it
+            // clears, past the margins above it
+            if let Some(clearance) = floats.clearance(child.style()) {
+                let margin_top = child.block_widths(context, containing.width).margin.top;
+                let mut collapsed = strut;
+                collapsed.adjoin(margin_top);
+                if bottom + collapsed.offset() < clearance {
+                    bottom = clearance - margin_top;
+                    strut = MarginStrut::default();
+                }
+            }
+            // and a formatting context root goes beside the floats
+            let independent = child.establishes_formatting_context();
+            let mut child_containing = *containing;
+            if independent {
+                let right = containing.x + containing.width;
+                let (left, right) =
+                    floats.available(bottom + strut.offset(), 0.0, containing.x, right);
+                child_containing.x = left;
+                child_containing.width = right - left;
+            }
+            strut = child.layout_block(
+                context,
+                &child_containing,
+                bottom,
+                strut,
+                independent,
+                floats,
+            );
+            if independent || !child.collapses_through() {
+                let border_box = child.dimensions.border_box();
+                bottom = border_box.y + border_box.height;
+            }
+        }
+        (bottom, strut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::test_util::{border_box, find, with_layout};
+
+    #[test]
+    fn test_block_widths() {
+        with_layout(
+            "<html><body><div id='a'></div><div id='b'></div><div id='c'></div></body></html>",
+            "div { height: 10px; }
+            #a { width: 200px; padding: 10px; border: 5px solid; margin: 0 auto; }
+            #b { box-sizing: border-box; width: 50%; padding: 0 10px; border: 1px solid; }
+            #c { margin-left: auto; width: 100px; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (277.0 + 8.0, 8.0, 230.0, 40.0));
+                assert_eq!(border_box(root, "b"), (8.0, 48.0, 392.0, 10.0));
+                assert_eq!(border_box(root, "c"), (692.0, 58.0, 100.0, 10.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_min_max_widths() {
+        with_layout(
+            "<html><body><div id='a'></div><div id='b'></div><div id='c'></div><div id='d'></div></body></html>",
+            "div { height: 10px; }
+            #a { max-width: 100px; margin: 0 auto; }
+            #b { width: 100px; min-width: 50%; }
+            #c { width: 1000px; margin: 0 auto; }
+            #d { width: 1000px; direction: rtl; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (350.0, 8.0, 100.0, 10.0));
+                assert_eq!(border_box(root, "b"), (8.0, 18.0, 392.0, 10.0));
+                // over-constrained: the end margin is ignored
+                assert_eq!(border_box(root, "c"), (8.0, 28.0, 1000.0, 10.0));
+                assert_eq!(border_box(root, "d"), (-208.0, 38.0, 1000.0, 10.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_sibling_margins_collapse() {
+        with_layout(
+            "<html><body><div id='a'></div><div id='b'></div><div id='c'></div></body></html>",
+            "div { height: 50px; }
+            #a { margin-bottom: 20px; }
+            #b { margin-top: 30px; }
+            #c { margin-top: -10px; }",
+            |root| {
+                assert_eq!(border_box(root, "a").1, 8.0);
+                assert_eq!(border_box(root, "b").1, 88.0);
+                assert_eq!(border_box(root, "c").1, 128.0);
+                // the root contains the margins of the body
+                assert_eq!(root.dimensions.margin_box().height, 186.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_parent_child_margins_collapse() {
+        let html = "<html><body><section id='s'><div id='a'></div></section></body></html>";
+        let css = "#a { margin-top: 40px; height: 10px; }";
+        with_layout(
+            "<html><body id='body'><section id='s'><div id='a'></div></section></body></html>",
+            css,
+            |root| {
+                assert_eq!(border_box(root, "body"), (8.0, 40.0, 784.0, 10.0));
+                assert_eq!(border_box(root, "s"), (8.0, 40.0, 784.0, 10.0));
+                assert_eq!(border_box(root, "a"), (8.0, 40.0, 784.0, 10.0));
+                assert_eq!(root.dimensions.content.height, 58.0);
+            },
+        );
+        // padding and formatting context roots separate the margins
+        with_layout(
+            html,
+            "#a { margin-top: 40px; height: 10px; } #s { padding-top: 1px; }",
+            |root| {
+                assert_eq!(border_box(root, "s"), (8.0, 8.0, 784.0, 51.0));
+                assert_eq!(border_box(root, "a").1, 49.0);
+            },
+        );
+        with_layout(
+            html,
+            "#a { margin-top: 40px; height: 10px; } #s { overflow: hidden; }",
+            |root| {
+                assert_eq!(border_box(root, "s"), (8.0, 8.0, 784.0, 50.0));
+                assert_eq!(border_box(root, "a").1, 48.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_empty_block_margins_collapse() {
+        with_layout(
+            "<html><body><div id='e'></div><div id='a'></div><div id='f'></div></body></html>",
+            "#e { margin: 10px 0; } #a { margin: 20px 0 5px; height: 10px; }
+            #f { margin: 30px 0 -5px; }",
+            |root| {
+                assert_eq!(border_box(root, "e").1, 20.0);
+                assert_eq!(border_box(root, "a").1, 20.0);
+                assert_eq!(border_box(root, "f").1, 60.0);
+                // the margins below the body collapse to 30 - 5
+                assert_eq!(root.dimensions.content.height, 55.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_block_heights() {
+        with_layout(
+            "<html><body><div id='a'></div><div id='b'></div><div id='c'></div></body></html>",
+            "html, body { height: 100%; } body { margin: 0; }
+            #a { height: 50%; }
+            #b { height: 100px; max-height: 20px; padding: 5px; }
+            #c { min-height: 30px; box-sizing: border-box; padding: 10px; }",
+            |root| {
+                assert_eq!(root.dimensions.content.height, 600.0);
+                assert_eq!(border_box(root, "a"), (0.0, 0.0, 800.0, 300.0));
+                assert_eq!(border_box(root, "b"), (0.0, 300.0, 800.0, 30.0));
+                assert_eq!(border_box(root, "c"), (0.0, 330.0, 800.0, 30.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_list_markers() {
+        with_layout(
+            "<html><body><ul><li id='a'>item</li><li id='b' class='inside'>item</li></ul>\
+             <ol><li id='c'><p>para</p></li><li id='d'></li></ol></body></html>",
+            "body { margin: 0; font-size: 10px; line-height: 10px; }
+            ul, ol, p { margin: 0; } .inside { list-style-position: inside; }",
+            |root| {
+                let marker = |id: &str| {
+                    let item = find(root, id);
+                    let marker = item.children.iter().find(|child| child.is_outside_marker());
+                    marker.map(|marker| {
+                        let rect = marker.dimensions.border_box();
+                        (rect.x, rect.y, rect.width, rect.height)
+                    })
+                };
+                // outside markers go before the first line, and keep their
+                // trailing space
+                assert_eq!(marker("a"), Some((28.0, 0.0, 12.0, 10.0)));
+                assert_eq!(border_box(root, "a"), (40.0, 0.0, 760.0, 10.0));
+                // inside markers are part of the first line
+                assert_eq!(marker("b"), None);
+                let line = &find(root, "b").lines[0];
+                let last = line.fragments.last().unwrap().rect;
+                assert_eq!(last.x + last.width, 76.0);
+                // beside the first line of a block inside the item, or at the
+                // top of an empty item
+                assert_eq!(marker("c"), Some((22.0, 20.0, 18.0, 10.0)));
+                assert_eq!(marker("d"), Some((22.0, 30.0, 18.0, 10.0)));
+            },
+        );
+    }
+}
@@ -0,0 +1,435 @@
+use crate::css::{LengthContext, Unit};
+
+// the expression tree of a math function: calc(), min(), max() or clamp()
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcNode {
+    Number(f32),
+    Percentage(f32),
+    Dimension(f32, Unit),
+    Sum(Vec<CalcNode>),
+    Product(Vec<CalcNode>),
+    // the reciprocal of a divisor
+    Invert(Box<CalcNode>),
+    Min(Vec<CalcNode>),
+    Max(Vec<CalcNode>),
+    // clamp(min, value, max)
+    Clamp(Box<CalcNode>, Box<CalcNode>, Box<CalcNode>),
+}
+
+// the largest magnitude of a used calc() result, to which infinities clamp:
+// the largest integer an f32 represents exactly
+const MAX_RESULT: f32 = 16_777_216.0;
+
+// a calc() result that layout can use: infinities clamp to the largest
+// value, and NaN is 0
+pub fn finite(value: f32) -> f32 {
+    match value {
+        value if value.is_nan() => 0.0,
+        value if value.is_infinite() => MAX_RESULT.copysign(value),
+        value => value,
+    }
+}
+
+// a calc() result in an integer context, rounded half up and clamped to the
+// integer range
+pub fn integer(value: f32) -> f32 {
+    match value.is_nan() {
+        true => 0.0,
+        false => (value + 0.5)
+            .floor()
+            .clamp(i32::MIN as f32, i32::MAX as f32),
+    }
+}
+
+// the type of a math expression; percentages are assumed to resolve against
+// lengths when mixed with them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalcType {
+    Number,
+    Length,
+    Percentage,
+    LengthPercentage,
+}
+
+impl CalcType {
+    fn add(self, other: CalcType) -> Option<CalcType> {
+        match (self, other) {
+            (a, b) if a == b => Some(a),
+            (CalcType::Number, _) | (_, CalcType::Number) => None,
+            _ => Some(CalcType::LengthPercentage),
+        }
+    }
+}
+
+impl CalcNode {
+    // the type of the expression, `None` if it mixes incompatible types, like
+    // `1px + 2` or `1px * 1px`
+    pub fn calc_type(&self) -> Option<CalcType> {
+        match self {
+            CalcNode::Number(_) => Some(CalcType::Number),
+            CalcNode::Percentage(_) => Some(CalcType::Percentage),
+            CalcNode::Dimension(..) => Some(CalcType::Length),
+            CalcNode::Sum(nodes) | CalcNode::Min(nodes) | CalcNode::Max(nodes) => {
+                CalcNode::common_type(nodes.iter())
+            }
+            CalcNode::Clamp(min, value, max) => {
+                CalcNode::common_type([&**min, &**value, &**max].into_iter())
+            }
+            CalcNode::Product(nodes) => {
+                let mut product = CalcType::Number;
+                for node in nodes {
+                    match (product, node.calc_type()?) {
+                        (product_type, CalcType::Number) => product = product_type,
+                        (CalcType::Number, node_type) => product = node_type,
+                        _ => return None,
+                    }
+                }
+                Some(product)
+            }
+            // only numbers can be divisors
+            CalcNode::Invert(node) => match node.calc_type()? {
+                CalcType::Number => Some(CalcType::Number),
+                _ => None,
+            },
+        }
+    }
+
+    fn common_type<'n, I: Iterator<Item = &'n CalcNode>>(mut nodes: I) -> Option<CalcType> {
+        let first = nodes.next()?.calc_type()?;
+        nodes.try_fold(first, |common, node| common.add(node.calc_type()?))
+    }
+
+    fn value(&self) -> Option<f32> {
+        match self {
+            CalcNode::Number(value)
+            | CalcNode::Percentage(value)
+            | CalcNode::Dimension(value, _) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn with_value(&self, value: f32) -> CalcNode {
+        match self {
+            CalcNode::Number(_) => CalcNode::Number(value),
+            CalcNode::Percentage(_) => CalcNode::Percentage(value),
+            CalcNode::Dimension(_, unit) => CalcNode::Dimension(value, *unit),
+            _ => panic!("Only numeric values have a value"),
+        }
+    }
+
+    // whether both nodes are numeric values of the same unit
+    fn same_unit(&self, other: &CalcNode) -> bool {
+        match (self, other) {
+            (CalcNode::Number(_), CalcNode::Number(_)) => true,
+            (CalcNode::Percentage(_), CalcNode::Percentage(_)) => true,
+            (CalcNode::Dimension(_, a), CalcNode::Dimension(_, b)) => a == b,
+            _ => false,
+        }
+    }
+
+    // the values of the nodes, if they all have the same unit as the first one
+    fn same_unit_values(nodes: &[CalcNode]) -> Option<Vec<f32>> {
+        let first = nodes.first()?;
+        nodes
+            .iter()
+            .map(|node| match first.same_unit(node) {
+                true => node.value(),
+                false => None,
+            })
+            .collect()
+    }
+
+    fn scale(&self, factor: f32) -> Option<CalcNode> {
+        match self {
+            CalcNode::Sum(nodes) => nodes
+                .iter()
+                .map(|node| node.scale(factor))
+                .collect::<Option<Vec<CalcNode>>>()
+                .map(CalcNode::Sum),
+            node => node.value().map(|value| node.with_value(value * factor)),
+        }
+    }
+
+    // combine the terms of the same unit and fold the constant operations, as
+    // far as possible without knowing what relative units resolve to
+    pub fn simplify(self) -> CalcNode {
+        match self {
+            CalcNode::Dimension(value, unit) => match unit.absolute_factor() {
+                Some(factor) => CalcNode::Dimension(value * factor, Unit::Px),
+                None => CalcNode::Dimension(value, unit),
+            },
+            CalcNode::Sum(nodes) => {
+                let mut terms: Vec<CalcNode> = Vec::new();
+                let mut pending: Vec<CalcNode> = nodes.into_iter().rev().collect();
+                while let Some(node) = pending.pop() {
+                    match node.simplify() {
+                        CalcNode::Sum(nested) => pending.extend(nested.into_iter().rev()),
+                        term => match terms.iter_mut().find(|other| other.same_unit(&term)) {
+                            Some(other) => {
+                                *other =
+                                    other.with_value(other.value().unwrap() + term.value().unwrap())
+                            }
+                            None => terms.push(term),
+                        },
+                    }
+                }
+                match terms.len() {
+                    1 => terms.pop().unwrap(),
+                    _ => CalcNode::Sum(terms),
+                }
+            }
+            CalcNode::Product(nodes) => {
+                let mut factor = 1.0;
+                let mut factors: Vec<CalcNode> = Vec::new();
+                let mut pending = nodes;
+                while let Some(node) = pending.pop() {
+                    match node.simplify() {
+                        CalcNode::Number(value) => factor *= value,
+                        CalcNode::Product(nested) => pending.extend(nested),
+                        node => factors.insert(0, node),
+                    }
+                }
+                if factors.is_empty() {
+                    return CalcNode::Number(factor);
+                }
+                if factors.len() == 1 {
+                    if let Some(scaled) = factors[0].scale(factor) {
+                        return scaled;
+                    }
+                }
+                if factor != 1.0 {
+                    factors.insert(0, CalcNode::Number(factor));
+                }
+                CalcNode::Product(factors)
+            }
+            CalcNode::Invert(node) => match node.simplify() {
+                CalcNode::Number(value) => CalcNode::Number(1.0 / value),
+                node => CalcNode::Invert(Box::new(node)),
+            },
+            CalcNode::Min(nodes) | CalcNode::Max(nodes) if nodes.len() == 1 => {
+                nodes.into_iter().next().unwrap().simplify()
+            }
+            CalcNode::Min(nodes) => {
+                let nodes: Vec<CalcNode> = nodes.into_iter().map(CalcNode::simplify).collect();
+                match CalcNode::same_unit_values(&nodes) {
+                    Some(values) => {
+                        nodes[0].with_value(values.into_iter().fold(f32::INFINITY, f32::min))
+                    }
+                    None => CalcNode::Min(nodes),
+                }
+            }
+            CalcNode::Max(nodes) => {
+                let nodes: Vec<CalcNode> = nodes.into_iter().map(CalcNode::simplify).collect();
+                match CalcNode::same_unit_values(&nodes) {
+                    Some(values) => {
+                        nodes[0].with_value(values.into_iter().fold(f32::NEG_INFINITY, f32::max))
+                    }
+                    None => CalcNode::Max(nodes),
+                }
+            }
+            CalcNode::Clamp(min, value, max) => {
+                let nodes = [min.simplify(), value.simplify(), max.simplify()];
+                match CalcNode::same_unit_values(&nodes) {
+                    Some(values) => nodes[0].with_value(values[1].min(values[2]).max(values[0])),
+                    None => {
+                        let [min, value, max] = nodes;
+                        CalcNode::Clamp(Box::new(min), Box::new(value), Box::new(max))
+                    }
+                }
+            }
+            node => node,
+        }
+    }
+
+    fn absolute_lengths(&self, context: &LengthContext) -> CalcNode {
+        let map = |nodes: &[CalcNode]| {
+            nodes
+                .iter()
+                .map(|node| node.absolute_lengths(context))
+                .collect()
+        };
+        let boxed = |node: &CalcNode| Box::new(node.absolute_lengths(context));
+        match self {
+            CalcNode::Dimension(value, unit) => {
+                CalcNode::Dimension(unit.to_px(*value, context), Unit::Px)
+            }
+            CalcNode::Sum(nodes) => CalcNode::Sum(map(nodes)),
+            CalcNode::Product(nodes) => CalcNode::Product(map(nodes)),
+            CalcNode::Invert(node) => CalcNode::Invert(boxed(node)),
+            CalcNode::Min(nodes) => CalcNode::Min(map(nodes)),
+            CalcNode::Max(nodes) => CalcNode::Max(map(nodes)),
+            CalcNode::Clamp(min, value, max) => {
+                CalcNode::Clamp(boxed(min), boxed(value), boxed(max))
+            }
+            node => node.clone(),
+        }
+    }
+
+    // the expression with its lengths in px, leaving only the percentages to
+    // resolve at used-value time
+    pub fn to_absolute(&self, context: &LengthContext) -> CalcNode {
+        self.absolute_lengths(context).simplify()
+    }
+
+    // the value of the expression in px, or as a plain number, clamped to a
+    // finite value
+    pub fn resolve(&self, context: &LengthContext) -> f32 {
+        finite(self.evaluate(context))
+    }
+
+    fn evaluate(&self, context: &LengthContext) -> f32 {
+        match self {
+            CalcNode::Number(value) => *value,
+            CalcNode::Percentage(value) => value / 100.0 * context.percentage_basis,
+            CalcNode::Dimension(value, unit) => unit.to_px(*value, context),
+            CalcNode::Sum(nodes) => nodes.iter().map(|node| node.evaluate(context)).sum(),
+            CalcNode::Product(nodes) => nodes.iter().map(|node| node.evaluate(context)).product(),
+            CalcNode::Invert(node) => 1.0 / node.evaluate(context),
+            CalcNode::Min(nodes) => nodes
+                .iter()
+                .map(|node| node.evaluate(context))
+                .fold(f32::INFINITY, f32::min),
+            CalcNode::Max(nodes) => nodes
+                .iter()
+                .map(|node| node.evaluate(context))
+                .fold(f32::NEG_INFINITY, f32::max),
+            CalcNode::Clamp(min, value, max) => value
+                .evaluate(context)
+                .min(max.evaluate(context))
+                .max(min.evaluate(context)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::CSSParser;
+
+    fn parse(css: &str) -> Option<CalcNode> {
+        CSSParser::new(css.to_string()).parse_math_function()
+    }
+
+    fn simplified(css: &str) -> CalcNode {
+        parse(css).unwrap().simplify()
+    }
+
+    #[test]
+    fn test_parse_math_functions() {
+        assert_eq!(
+            parse("calc(100% - 2 * 16px)"),
+            Some(CalcNode::Sum(vec![
+                CalcNode::Percentage(100.0),
+                CalcNode::Product(vec![
+                    CalcNode::Number(-1.0),
+                    CalcNode::Product(vec![
+                        CalcNode::Number(2.0),
+                        CalcNode::Dimension(16.0, Unit::Px)
+                    ]),
+                ]),
+            ]))
+        );
+        assert_eq!(
+            parse("clamp(1rem, 2vw, 2rem)"),
+            Some(CalcNode::Clamp(
+                Box::new(CalcNode::Dimension(1.0, Unit::Rem)),
+                Box::new(CalcNode::Dimension(2.0, Unit::Vw)),
+                Box::new(CalcNode::Dimension(2.0, Unit::Rem)),
+            ))
+        );
+        assert!(parse("min(1px, max(2px, (3px + 4%) / 2))").is_some());
+        // `+` and `-` must be surrounded by whitespace
+        assert!(parse("calc(1px -2px)").is_none());
+        assert!(parse("calc(1px+2px)").is_none());
+        assert!(parse("clamp(1px, 2px)").is_none());
+        assert!(parse("calc(2px * 3foo)").is_none());
+    }
+
+    #[test]
+    fn test_type_checking() {
+        let calc_type = |css: &str| parse(css).unwrap().calc_type();
+        assert_eq!(calc_type("calc(1px + 2em)"), Some(CalcType::Length));
+        assert_eq!(
+            calc_type("calc(50% - 1em)"),
+            Some(CalcType::LengthPercentage)
+        );
+        assert_eq!(calc_type("calc(3 / 4 * 2)"), Some(CalcType::Number));
+        assert_eq!(calc_type("min(10%, 20%)"), Some(CalcType::Percentage));
+        assert_eq!(calc_type("calc(1px + 2)"), None);
+        assert_eq!(calc_type("calc(1px * 1px)"), None);
+        assert_eq!(calc_type("calc(1px / 1px)"), None);
+        assert_eq!(calc_type("max(1px, 2)"), None);
+    }
+
+    #[test]
+    fn test_simplification() {
+        assert_eq!(
+            simplified("calc(1in + 2px * 3 - (4px))"),
+            CalcNode::Dimension(98.0, Unit::Px)
+        );
+        assert_eq!(
+            simplified("calc(100% - 2 * 16px + 10%)"),
+            CalcNode::Sum(vec![
+                CalcNode::Percentage(110.0),
+                CalcNode::Dimension(-32.0, Unit::Px),
+            ])
+        );
+        assert_eq!(
+            simplified("calc((1em + 2px) / 2)"),
+            CalcNode::Sum(vec![
+                CalcNode::Dimension(0.5, Unit::Em),
+                CalcNode::Dimension(1.0, Unit::Px),
+            ])
+        );
+        assert_eq!(
+            simplified("max(1px, 3px, 2px)"),
+            CalcNode::Dimension(3.0, Unit::Px)
+        );
+        assert_eq!(
+            simplified("clamp(1px, 5px, 3px)"),
+            CalcNode::Dimension(3.0, Unit::Px)
+        );
+        assert_eq!(
+            simplified("min(1em, 2px)"),
+            CalcNode::Min(vec![
+                CalcNode::Dimension(1.0, Unit::Em),
+                CalcNode::Dimension(2.0, Unit::Px),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_resolution() {
+        let context = LengthContext {
+            percentage_basis: 200.0,
+            font_size: 20.0,
+            viewport_width: 1000.0,
+            ..LengthContext::default()
+        };
+        let resolve = |css: &str| simplified(css).resolve(&context);
+        assert_eq!(resolve("calc(100% - 2 * 16px)"), 168.0);
+        assert_eq!(resolve("clamp(1rem, 2vw, 2rem)"), 20.0);
+        assert_eq!(resolve("clamp(1rem, 1vw, 2rem)"), 16.0);
+        assert_eq!(resolve("min(50%, 1em + 5px)"), 25.0);
+        // infinite and NaN results are clamped
+        assert_eq!(resolve("calc(1px / 0)"), MAX_RESULT);
+        assert_eq!(resolve("calc(100% * -infinity)"), -MAX_RESULT);
+        assert_eq!(resolve("calc(1em * NaN)"), 0.0);
+        assert_eq!(
+            simplified("calc(50% + 1em + 2px)").to_absolute(&context),
+            CalcNode::Sum(vec![
+                CalcNode::Percentage(50.0),
+                CalcNode::Dimension(22.0, Unit::Px),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(integer(1.5), 2.0);
+        assert_eq!(integer(-1.5), -1.0);
+        assert_eq!(integer(f32::INFINITY), i32::MAX as f32);
+        assert_eq!(integer(f32::NEG_INFINITY), i32::MIN as f32);
+        assert_eq!(integer(f32::NAN), 0.0);
+    }
+}
@@ -0,0 +1,701 @@
+use crate::assign::{matches_selector, ElementRef, PropertyMap};
+use crate::css::{
+    CSSParser, CssRule, Declaration, FontFaceRule, KeyframesRule, Origin, PageRule, PseudoElement,
+    Rule, Specificity, Stylesheet, Value,
+};
+use crate::media::MediaEnvironment;
+use crate::user_agent::user_agent_stylesheet;
+use std::collections::HashMap;
+
+// the stylesheets taking part in the cascade, in order of appearance
+#[derive(Debug, Default)]
+pub struct Cascade<'a> {
+    media: MediaEnvironment,
+    // the style rules applying to the media
+    rules: Vec<(RuleContext<'a>, &'a Rule)>,
+    pages: Vec<(RuleContext<'a>, &'a PageRule)>,
+    keyframes: Vec<&'a KeyframesRule>,
+    // with the url of their stylesheet, that sources are relative to
+    font_faces: Vec<(Option<&'a str>, &'a FontFaceRule)>,
+    // the full dotted names of the cascade layers of each origin, and their
+    // ids in increasing order of precedence for normal declarations
+    layers: Vec<(Origin, String)>,
+    layer_order: Vec<usize>,
+    stylesheet_count: usize,
+}
+
+// where a rule comes from: its stylesheet index, origin and cascade layer,
+// the url of the stylesheet or import defining it, and whether it is one of
+// the default styles of html elements
+#[derive(Debug, Clone, Copy)]
+struct RuleContext<'a> {
+    sheet_index: usize,
+    user_agent_sheet: bool,
+    origin: Origin,
+    layer: Option<usize>,
+    url: Option<&'a str>,
+}
+
+// the precedence of a declaration: when several declarations set the same
+// property on an element, the one with the greatest key wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct CascadeKey {
+    origin: u8,
+    inline: bool,
+    layer: usize,
+    specificity: Specificity,
+    // stylesheet, rule and declaration indices
+    order: (usize, usize, usize),
+}
+
+// normal declarations follow the origin order, important ones reverse it
+fn origin_rank(origin: Origin, important: bool) -> u8 {
+    match (important, origin) {
+        (false, Origin::UserAgent) => 0,
+        (false, Origin::User) => 1,
+        (false, Origin::Author) => 2,
+        (true, Origin::Author) => 3,
+        (true, Origin::User) => 4,
+        (true, Origin::UserAgent) => 5,
+    }
+}
+
+// the origin of a rank, whether the declaration is important or not
+fn origin_level(rank: u8) -> u8 {
+    rank.min(5 - rank)
+}
+
+fn is_revert(value: &Value) -> bool {
+    matches!(value, Value::Keyword(keyword) if keyword.eq_ignore_ascii_case("revert"))
+}
+
+impl<'a> Cascade<'a> {
+    pub fn new() -> Cascade<'a> {
+        Cascade::with_media(MediaEnvironment::default())
+    }
+
+    // a cascade starting with the user agent stylesheet
+    pub fn with_media(media: MediaEnvironment) -> Cascade<'a> {
+        let mut cascade = Cascade {
+            media,
+            ..Cascade::default()
+        };
+        cascade.add_rules_of(user_agent_stylesheet(), true);
+        cascade
+    }
+
+    // leave out the default styles of html elements
+    pub fn without_user_agent_stylesheet(mut self) -> Cascade<'a> {
+        self.rules.retain(|(context, _)| !context.user_agent_sheet);
+        self.pages.retain(|(context, _)| !context.user_agent_sheet);
+        self
+    }
+
+    pub fn media(&self) -> &MediaEnvironment {
+        &self.media
+    }
+
+    pub fn add_stylesheet(&mut self, stylesheet: &'a Stylesheet) {
+        self.add_rules_of(stylesheet, false);
+    }
+
+    fn add_rules_of(&mut self, stylesheet: &'a Stylesheet, user_agent_sheet: bool) {
+        let context = RuleContext {
+            sheet_index: self.stylesheet_count,
+            user_agent_sheet,
+            origin: stylesheet.origin(),
+            layer: None,
+            url: stylesheet.url(),
+        };
+        self.add_rules(&stylesheet.rules, context);
+        self.stylesheet_count += 1;
+    }
+
+    // the id of a layer, declared after the existing layers of its parent
+    // when it is new
+    fn layer_id(&mut self, origin: Origin, parent: Option<usize>, name: &str) -> usize {
+        let mut id = parent;
+        for part in name.split('.') {
+            let full_name = match id {
+                Some(id) => format!("{}.{}", self.layers[id].1, part),
+                None => part.to_string(),
+            };
+            let existing = self
+                .layers
+                .iter()
+                .position(|layer| layer.0 == origin && layer.1 == full_name);
+            id = Some(match existing {
+                Some(existing) => existing,
+                None => {
+                    let new = self.layers.len();
+                    self.layers.push((origin, full_name));
+                    // the rules of a layer outside of its sublayers come last
+                    let position = match id {
+                        Some(parent) => self.layer_position(parent),
+                        None => self.layer_order.len(),
+                    };
+                    self.layer_order.insert(position, new);
+                    new
+                }
+            });
+        }
+        id.unwrap()
+    }
+
+    // a new layer without a name, which can't be added to later
+    fn anonymous_layer_id(&mut self, origin: Origin, parent: Option<usize>) -> usize {
+        let anonymous = format!("<anonymous-{}>", self.layers.len());
+        self.layer_id(origin, parent, &anonymous)
+    }
+
+    fn layer_position(&self, layer: usize) -> usize {
+        self.layer_order.iter().position(|id| *id == layer).unwrap()
+    }
+
+    // the rules of the list applying to the media, with the layers they
+    // belong to
+    fn add_rules(&mut self, rules: &'a [CssRule], context: RuleContext<'a>) {
+        for rule in rules {
+            match rule {
+                CssRule::Style(rule) => self.rules.push((context, rule)),
+                CssRule::Media(media_rule) => {
+                    if media_rule.queries.matches(&self.media) {
+                        self.add_rules(&media_rule.rules, context);
+                    }
+                }
+                // imports that failed to load, or were skipped as cycles, have no
+                // stylesheet but still declare their layer
+                CssRule::Import(import) => {
+                    let layer = match &import.layer {
+                        Some(Some(name)) => {
+                            Some(self.layer_id(context.origin, context.layer, name))
+                        }
+                        Some(None) => Some(self.anonymous_layer_id(context.origin, context.layer)),
+                        None => context.layer,
+                    };
+                    if let Some(stylesheet) = &import.stylesheet {
+                        if import.queries.matches(&self.media) {
+                            let context = RuleContext {
+                                url: stylesheet.url(),
+                                layer,
+                                ..context
+                            };
+                            self.add_rules(&stylesheet.rules, context);
+                        }
+                    }
+                }
+                CssRule::Supports(supports) => {
+                    if supports.condition.matches() {
+                        self.add_rules(&supports.rules, context);
+                    }
+                }
+                CssRule::Layer(layer) => {
+                    let ids: Vec<usize> = layer
+                        .names
+                        .iter()
+                        .map(|name| self.layer_id(context.origin, context.layer, name))
+                        .collect();
+                    if let Some(rules) = &layer.rules {
+                        let id = match ids.first() {
+                            Some(id) => *id,
+                            None => self.anonymous_layer_id(context.origin, context.layer),
+                        };
+                        let context = RuleContext {
+                            layer: Some(id),
+                            ..context
+                        };
+                        self.add_rules(rules, context);
+                    }
+                }
+                CssRule::Keyframes(keyframes) => self.keyframes.push(keyframes),
+                CssRule::FontFace(font_face) => self.font_faces.push((context.url, font_face)),
+                CssRule::Page(page) => self.pages.push((context, page)),
+                CssRule::Unknown(_) => {}
+            }
+        }
+    }
+
+    // the animation of the name, the last one defined winning
+    pub fn keyframes(&self, name: &str) -> Option<&'a KeyframesRule> {
+        self.keyframes
+            .iter()
+            .rev()
+            .find(|keyframes| keyframes.name == name)
+            .copied()
+    }
+
+    pub fn font_faces(&self) -> &[(Option<&'a str>, &'a FontFaceRule)] {
+        &self.font_faces
+    }
+
+    // unlayered declarations beat layered ones, unless they are important
+    fn layer_rank(&self, layer: Option<usize>, important: bool) -> usize {
+        let rank = match layer {
+            Some(layer) => self.layer_position(layer),
+            None => self.layer_order.len(),
+        };
+        match important {
+            true => self.layer_order.len() - rank,
+            false => rank,
+        }
+    }
+
+    fn key(
+        &self,
+        context: &RuleContext,
+        important: bool,
+        specificity: Specificity,
+        order: (usize, usize, usize),
+    ) -> CascadeKey {
+        CascadeKey {
+            origin: origin_rank(context.origin, important),
+            inline: false,
+            layer: self.layer_rank(context.layer, important),
+            specificity,
+            order,
+        }
+    }
+
+    // every declaration applying to the element, or to one of its pseudo-elements
+    fn matching_declarations(
+        &self,
+        element: &ElementRef,
+        pseudo_element: Option<PseudoElement>,
+    ) -> Vec<(CascadeKey, Declaration)> {
+        let mut declarations = Vec::new();
+
+        for (rule_index, (context, rule)) in self.rules.iter().enumerate() {
+            // the most specific matching selector determines the rule specificity
+            let specificity = rule
+                .selectors
+                .iter()
+                .filter(|selector| selector.pseudo_element() == pseudo_element)
+                .filter(|selector| matches_selector(element, selector))
+                .map(|selector| selector.specificity())
+                .max();
+            let specificity = match specificity {
+                Some(specificity) => specificity,
+                None => continue,
+            };
+
+            for (index, declaration) in rule.declarations.iter().enumerate() {
+                let order = (context.sheet_index, rule_index, index);
+                let key = self.key(context, declaration.important, specificity, order);
+                declarations.push((key, declaration.clone()));
+            }
+        }
+
+        // the `style` attribute belongs to the author origin and beats any selector
+        if pseudo_element.is_none() {
+            if let Some(style) = element
+                .element()
+                .and_then(|elem| elem.attributes.get("style"))
+            {
+                let mut css_parser = CSSParser::new(style.clone());
+                for (index, declaration) in css_parser.parse_declarations().into_iter().enumerate()
+                {
+                    let key = CascadeKey {
+                        origin: origin_rank(Origin::Author, declaration.important),
+                        inline: true,
+                        layer: self.layer_rank(None, declaration.important),
+                        specificity: (0, 0, 0),
+                        order: (self.stylesheet_count, 0, index),
+                    };
+                    declarations.push((key, declaration));
+                }
+            }
+        }
+
+        declarations
+    }
+
+    // the winning declaration of every property; a winning `revert` rolls the
+    // cascade back to the declarations of the previous origins
+    pub(crate) fn cascaded_declarations(
+        &self,
+        element: &ElementRef,
+        pseudo_element: Option<PseudoElement>,
+    ) -> HashMap<String, Declaration> {
+        let declarations = self.matching_declarations(element, pseudo_element);
+        Cascade::winning_declarations(declarations)
+    }
+
+    fn winning_declarations(
+        mut declarations: Vec<(CascadeKey, Declaration)>,
+    ) -> HashMap<String, Declaration> {
+        declarations.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let mut winners: HashMap<String, (CascadeKey, Declaration)> = HashMap::new();
+        for (key, declaration) in declarations {
+            match winners.get(&declaration.name) {
+                Some((winning_key, winner))
+                    if is_revert(&winner.value)
+                        && origin_level(key.origin) < origin_level(winning_key.origin) => {}
+                Some(_) => continue,
+                None => {}
+            }
+            winners.insert(declaration.name.clone(), (key, declaration));
+        }
+        winners
+            .into_iter()
+            .map(|(name, (_, declaration))| (name, declaration))
+            .collect()
+    }
+
+    pub fn cascaded_values(
+        &self,
+        element: &ElementRef,
+        pseudo_element: Option<PseudoElement>,
+    ) -> PropertyMap {
+        self.cascaded_declarations(element, pseudo_element)
+            .into_iter()
+            .map(|(name, declaration)| (name, declaration.value))
+            .collect()
+    }
+
+    // the values of the `@page` rules matching the page, numbered from 1, or
+    // of one of its margin boxes
+    pub fn page_values(&self, page_number: usize, margin_box: Option<&str>) -> PropertyMap {
+        let mut declarations = Vec::new();
+        for (rule_index, (context, page)) in self.pages.iter().enumerate() {
+            let specificity = match page.selectors.is_empty() {
+                true => Some((0, 0, 0)),
+                false => page
+                    .selectors
+                    .iter()
+                    .filter(|selector| selector.matches(page_number))
+                    .map(|selector| selector.specificity())
+                    .max(),
+            };
+            let specificity = match specificity {
+                Some(specificity) => specificity,
+                None => continue,
+            };
+            let page_declarations = match margin_box {
+                Some(name) => page
+                    .margin_boxes
+                    .iter()
+                    .filter(|(margin_box, _)| margin_box == name)
+                    .flat_map(|(_, declarations)| declarations)
+                    .collect::<Vec<_>>(),
+                None => page.declarations.iter().collect(),
+            };
+            for (index, declaration) in page_declarations.into_iter().enumerate() {
+                let order = (context.sheet_index, rule_index, index);
+                let key = self.key(context, declaration.important, specificity, order);
+                declarations.push((key, declaration.clone()));
+            }
+        }
+        Cascade::winning_declarations(declarations)
+            .into_iter()
+            .map(|(name, declaration)| (name, declaration.value))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::Color;
+    use crate::html::HTMLParser;
+
+    fn stylesheet(css: &str, origin: Origin) -> Stylesheet {
+        CSSParser::new(css.to_string())
+            .parse_stylesheet()
+            .with_origin(origin)
+    }
+
+    // the cascaded `color` of the first child of the root
+    fn cascaded_color(html: &str, stylesheets: &[&Stylesheet]) -> Option<Value> {
+        cascaded_color_for(html, stylesheets, MediaEnvironment::default())
+    }
+
+    fn cascaded_color_for(
+        html: &str,
+        stylesheets: &[&Stylesheet],
+        media: MediaEnvironment,
+    ) -> Option<Value> {
+        let document = HTMLParser::new(html.to_string()).parse_document();
+        let root = ElementRef::root(document.root());
+        let mut cascade = Cascade::with_media(media);
+        for stylesheet in stylesheets {
+            cascade.add_stylesheet(stylesheet);
+        }
+        cascade
+            .cascaded_values(&root.child(0), None)
+            .remove("color")
+    }
+
+    fn keyword(keyword: &str) -> Option<Value> {
+        Some(Value::Keyword(keyword.to_string()))
+    }
+
+    fn color(r: u8, g: u8, b: u8) -> Option<Value> {
+        Some(Value::ColorValue(Color::rgb(r, g, b)))
+    }
+
+    #[test]
+    fn test_specificity_and_source_order() {
+        let html = "<html><p id='verylongid' class='b'></p></html>";
+        let author = stylesheet(
+            "#verylongid { color: #000001; } p#verylongid.b { color: #000002; }",
+            Origin::Author,
+        );
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 2));
+
+        let author = stylesheet(
+            ".b { color: #000001; } p { color: #000003; } .b { color: #000002; }",
+            Origin::Author,
+        );
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 2));
+    }
+
+    #[test]
+    fn test_origins() {
+        let html = "<html><p id='a'></p></html>";
+        let user_agent = stylesheet("#a { color: #000001; }", Origin::UserAgent);
+        let user = stylesheet("#a { color: #000002; }", Origin::User);
+        let author = stylesheet("p { color: #000003; }", Origin::Author);
+
+        // author beats user beats user agent, regardless of specificity
+        assert_eq!(
+            cascaded_color(html, &[&author, &user, &user_agent]),
+            color(0, 0, 3)
+        );
+        assert_eq!(cascaded_color(html, &[&user, &user_agent]), color(0, 0, 2));
+    }
+
+    #[test]
+    fn test_without_user_agent_stylesheet() {
+        let html = "<html><a href='/'></a><a href='/'></a></html>";
+        let document = HTMLParser::new(html.to_string()).parse_document();
+        let root = ElementRef::root(document.root());
+        let user_agent = stylesheet("a + a { color: #000001; }", Origin::UserAgent);
+        let color_of = |cascade: &Cascade, index| {
+            cascade
+                .cascaded_values(&root.child(index), None)
+                .remove("color")
+        };
+
+        let mut cascade = Cascade::new();
+        cascade.add_stylesheet(&user_agent);
+        assert_eq!(color_of(&cascade, 0), color(0, 0, 255));
+        // only the default styles are left out, not other user agent stylesheets
+        let cascade = cascade.without_user_agent_stylesheet();
+        assert_eq!(color_of(&cascade, 0), None);
+        assert_eq!(color_of(&cascade, 1), color(0, 0, 1));
+    }
+
+    #[test]
+    fn test_important_reverses_origins() {
+        let html = "<html><p id='a'></p></html>";
+        let user_agent = stylesheet("p { color: #000001 !important; }", Origin::UserAgent);
+        let user = stylesheet("p { color: #000002 !important; }", Origin::User);
+        let author = stylesheet(
+            "#a { color: #000003; } p { color: #000004 !important; }",
+            Origin::Author,
+        );
+
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 4));
+        assert_eq!(cascaded_color(html, &[&author, &user]), color(0, 0, 2));
+        assert_eq!(
+            cascaded_color(html, &[&author, &user, &user_agent]),
+            color(0, 0, 1)
+        );
+    }
+
+    #[test]
+    fn test_revert() {
+        let html = "<html><p id='a'></p></html>";
+        let user_agent = stylesheet("p { color: #000001; }", Origin::UserAgent);
+        let user = stylesheet("p { color: #000002; } #a { color: revert; }", Origin::User);
+        let author = stylesheet(
+            "p { color: #000003; } #a { color: revert; }",
+            Origin::Author,
+        );
+
+        assert_eq!(
+            cascaded_color(html, &[&user_agent, &user, &author]),
+            color(0, 0, 1)
+        );
+        assert_eq!(cascaded_color(html, &[&author]), keyword("revert"));
+    }
+
+    #[test]
+    fn test_inline_style() {
+        let html = "<html><p id='a' style='color: #000009; margin: 1px'></p></html>";
+        let author = stylesheet("html p#a { color: #000001; }", Origin::Author);
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 9));
+
+        let author = stylesheet("#a { color: #000001 !important; }", Origin::Author);
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 1));
+
+        let html = "<html><p style='color: #000009 !important'></p></html>";
+        let author = stylesheet("p { color: #000001 !important; }", Origin::Author);
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 9));
+    }
+
+    #[test]
+    fn test_media_rules() {
+        let html = "<html><p></p></html>";
+        let author = stylesheet(
+            "p { color: #000001; }
+            @media (max-width: 600px) { p { color: #000002; } }
+            @media print { p { color: #000003; } }",
+            Origin::Author,
+        );
+        let mobile = MediaEnvironment::new(375.0, 667.0);
+        let desktop = MediaEnvironment::new(1280.0, 800.0);
+        let mut print = MediaEnvironment::new(1280.0, 800.0);
+        print.media_type = String::from("print");
+
+        assert_eq!(cascaded_color_for(html, &[&author], mobile), color(0, 0, 2));
+        assert_eq!(
+            cascaded_color_for(html, &[&author], desktop),
+            color(0, 0, 1)
+        );
+        assert_eq!(cascaded_color_for(html, &[&author], print), color(0, 0, 3));
+    }
+
+    #[test]
+    fn test_cascade_layers() {
+        let html = "<html><p id='a'></p></html>";
+        // unlayered declarations beat layered ones, later layers beat earlier ones
+        let author = stylesheet(
+            "@layer base, theme;
+            p { color: #000001; }
+            @layer theme { #a { color: #000002; } }
+            @layer base { #a { color: #000003; } }",
+            Origin::Author,
+        );
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 1));
+
+        let author = stylesheet(
+            "@layer base, theme;
+            @layer base { #a#a { color: #000003; } }
+            @layer theme { p { color: #000002; } }",
+            Origin::Author,
+        );
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 2));
+
+        // the rules of a layer come after the ones of its sublayers
+        let author = stylesheet(
+            "@layer theme { p { color: #000001; } @layer dark { #a { color: #000002; } } }",
+            Origin::Author,
+        );
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 1));
+        let author = stylesheet(
+            "@layer a.b { p { color: #000001; } }
+            @layer a.c { p { color: #000002; } }
+            @layer a.b { p { color: #000003; } }
+            @layer { p { color: #000004; } }",
+            Origin::Author,
+        );
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 4));
+
+        // imported rules belong to the layer of the import
+        let imported = |css: &str| {
+            let mut author = stylesheet(css, Origin::Author);
+            for rule in &mut author.rules {
+                if let CssRule::Import(import) = rule {
+                    let rules = format!("#a {{ color: #00000{}; }}", import.url);
+                    import.stylesheet = Some(Box::new(stylesheet(&rules, Origin::Author)));
+                }
+            }
+            author
+        };
+        let author = imported("@import '1' layer(base); @import '2' layer; p { color: #000003; }");
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 3));
+        let author = imported("@import '1' layer(base); @import '2' layer;");
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 2));
+        let author =
+            imported("@import '1' layer(theme.dark); @layer theme { p { color: #000003; } }");
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 3));
+        let author =
+            imported("@layer base, theme; @import '1' layer(theme); @import '2' layer(base);");
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 1));
+
+        // important declarations reverse the layer order
+        let author = stylesheet(
+            "@layer base, theme;
+            p { color: #000001 !important; }
+            @layer theme { p { color: #000002 !important; } }
+            @layer base { p { color: #000003 !important; } }",
+            Origin::Author,
+        );
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 3));
+
+        // layers do not cross origins, and the style attribute is unlayered
+        let user = stylesheet("@layer base { p { color: #000005; } }", Origin::User);
+        let author = stylesheet("@layer base { p { color: #000006; } }", Origin::Author);
+        assert_eq!(cascaded_color(html, &[&author, &user]), color(0, 0, 6));
+        let html = "<html><p style='color: #000007'></p></html>";
+        let author = stylesheet(
+            "@layer { p { color: #000008 !important; } }",
+            Origin::Author,
+        );
+        assert_eq!(cascaded_color(html, &[&author]), color(0, 0, 8));
+    }
+
+    #[test]
+    fn test_supports_rules() {
+        let html = "<html><p></p></html>";
+        let author = stylesheet(
+            "@supports (display: flex) { p { color: #000001; } }
+            @supports (display: nope) { p { color: #000002; } }
+            @supports not selector(p:nope) { @supports (color: red) { p { margin: 1px; } } }",
+            Origin::Author,
+        );
+        let document = HTMLParser::new(html.to_string()).parse_document();
+        let mut cascade = Cascade::new();
+        cascade.add_stylesheet(&author);
+        let values = cascade.cascaded_values(&ElementRef::root(document.root()).child(0), None);
+        assert_eq!(values.get("color").cloned(), color(0, 0, 1));
+        assert!(values.contains_key("margin-top"));
+    }
+
+    #[test]
+    fn test_keyframes_and_font_faces() {
+        let author = stylesheet(
+            "@keyframes spin { to { opacity: 0; } }
+            @media print { @keyframes fade { to { opacity: 0; } } }
+            @font-face { font-family: Serif; src: url(serif.woff); }
+            @keyframes spin { from { opacity: 0; } to { opacity: 1; } }",
+            Origin::Author,
+        );
+        let mut cascade = Cascade::new();
+        cascade.add_stylesheet(&author);
+        assert_eq!(cascade.keyframes("spin").unwrap().keyframes.len(), 2);
+        assert!(cascade.keyframes("fade").is_none());
+        assert_eq!(cascade.font_faces().len(), 1);
+        assert_eq!(
+            cascade.font_faces()[0].1.family(),
+            Some(String::from("Serif"))
+        );
+    }
+
+    #[test]
+    fn test_page_rules() {
+        let author = stylesheet(
+            "@page { margin-top: 1px; margin-left: 1px; }
+            @page :left { margin-left: 2px; }
+            @page :first { margin-top: 3px; @top-center { content: 'title'; } }
+            @page chapter { margin-top: 4px; }",
+            Origin::Author,
+        );
+        let mut cascade = Cascade::new();
+        cascade.add_stylesheet(&author);
+        let length = |value| Some(Value::Length(value, crate::css::Unit::Px));
+
+        let first = cascade.page_values(1, None);
+        assert_eq!(first.get("margin-top").cloned(), length(3.0));
+        assert_eq!(first.get("margin-left").cloned(), length(1.0));
+        let second = cascade.page_values(2, None);
+        assert_eq!(second.get("margin-top").cloned(), length(1.0));
+        assert_eq!(second.get("margin-left").cloned(), length(2.0));
+        assert!(cascade
+            .page_values(1, Some("top-center"))
+            .contains_key("content"));
+        assert!(cascade.page_values(3, Some("top-center")).is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+// css counters, scoped like in css lists 3: a counter created by an element is
+// visible to the element, its following siblings and all their descendants
+#[derive(Debug, Default)]
+pub struct CounterState {
+    // nested instances of every counter, innermost last
+    values: HashMap<String, Vec<i32>>,
+    // names of the counters created at each sibling level, innermost last
+    scopes: Vec<Vec<String>>,
+}
+
+impl CounterState {
+    pub fn new() -> CounterState {
+        CounterState {
+            values: HashMap::new(),
+            scopes: vec![Vec::new()],
+        }
+    }
+
+    // start the children of an element
+    pub fn enter(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    // leave the children of an element, dropping the counters they created
+    pub fn leave(&mut self) {
+        for name in self.scopes.pop().unwrap_or_default() {
+            if let Some(instances) = self.values.get_mut(&name) {
+                instances.pop();
+            }
+        }
+    }
+
+    pub fn reset(&mut self, name: &str, value: i32) {
+        let scope = self.scopes.last_mut().unwrap();
+        let instances = self.values.entry(name.to_string()).or_default();
+
+        // a sibling already created this counter: the new one replaces it
+        if scope.iter().any(|created| created == name) {
+            *instances.last_mut().unwrap() = value;
+        } else {
+            scope.push(name.to_string());
+            instances.push(value);
+        }
+    }
+
+    pub fn increment(&mut self, name: &str, by: i32) {
+        if !self.exists(name) {
+            self.reset(name, 0);
+        }
+        // counters stop at the bounds of their integers instead of overflowing
+        let value = self.values.get_mut(name).unwrap().last_mut().unwrap();
+        *value = value.saturating_add(by);
+    }
+
+    pub fn set(&mut self, name: &str, value: i32) {
+        if !self.exists(name) {
+            self.reset(name, value);
+        }
+        *self.values.get_mut(name).unwrap().last_mut().unwrap() = value;
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.values
+            .get(name)
+            .is_some_and(|instances| !instances.is_empty())
+    }
+
+    // value of the innermost counter, as used by `counter()`
+    pub fn value(&self, name: &str) -> i32 {
+        self.values
+            .get(name)
+            .and_then(|instances| instances.last().copied())
+            .unwrap_or(0)
+    }
+
+    // values of all the nested counters, outermost first, as used by `counters()`
+    pub fn values(&self, name: &str) -> Vec<i32> {
+        match self.values.get(name) {
+            Some(instances) if !instances.is_empty() => instances.clone(),
+            _ => vec![0],
+        }
+    }
+}
+
+// the symbols of the alphabetic counter styles
+const LATIN: &str = "abcdefghijklmnopqrstuvwxyz";
+const GREEK: &str = "αβγδεζηθικλμνξοπρστυφχψω";
+
+// the values and symbols of the roman numerals, largest first
+const ROMAN: [(i32, &str); 13] = [
+    (1000, "m"),
+    (900, "cm"),
+    (500, "d"),
+    (400, "cd"),
+    (100, "c"),
+    (90, "xc"),
+    (50, "l"),
+    (40, "xl"),
+    (10, "x"),
+    (9, "ix"),
+    (5, "v"),
+    (4, "iv"),
+    (1, "i"),
+];
+
+// css counter styles 3 §6.1: counting with the letters of an alphabet, from
+// `a` for 1, `z` for 26 and `aa` for 27 onwards
+fn alphabetic(value: i32, alphabet: &str) -> Option<String> {
+    let symbols: Vec<char> = alphabet.chars().collect();
+    let mut value = usize::try_from(value).ok().filter(|&value| value > 0)?;
+    let mut letters = Vec::new();
+    while value > 0 {
+        value -= 1;
+        letters.push(symbols[value % symbols.len()]);
+        value /= symbols.len();
+    }
+    Some(letters.into_iter().rev().collect())
+}
+
+// the roman numeral of a value from 1 to 3999
+fn roman(value: i32) -> Option<String> {
+    if !(1..4000).contains(&value) {
+        return None;
+    }
+    let mut value = value;
+    let mut numeral = String::new();
+    for (amount, symbol) in ROMAN {
+        while value >= amount {
+            numeral.push_str(symbol);
+            value -= amount;
+        }
+    }
+    Some(numeral)
+}
+
+// the text representation of a counter value in the given `list-style-type`;
+// values out of the range of a style, and unknown styles, are decimal
+pub fn format_counter(value: i32, style: &str) -> String {
+    let formatted = match style {
+        "none" => Some(String::new()),
+        "disc" => Some(String::from("\u{2022}")),
+        "circle" => Some(String::from("\u{25e6}")),
+        "square" => Some(String::from("\u{25aa}")),
+        "disclosure-open" => Some(String::from("\u{25be}")),
+        "disclosure-closed" => Some(String::from("\u{25b8}")),
+        "decimal-leading-zero" if (0..10).contains(&value) => Some(format!("0{}", value)),
+        "decimal-leading-zero" if (-9..0).contains(&value) => Some(format!("-0{}", -value)),
+        "lower-roman" => roman(value),
+        "upper-roman" => roman(value).map(|numeral| numeral.to_uppercase()),
+        "lower-alpha" | "lower-latin" => alphabetic(value, LATIN),
+        "upper-alpha" | "upper-latin" => {
+            alphabetic(value, LATIN).map(|letters| letters.to_uppercase())
+        }
+        "lower-greek" => alphabetic(value, GREEK),
+        _ => None,
+    };
+    formatted.unwrap_or_else(|| value.to_string())
+}
+
+// the text of a `::marker` for a list item: the symbol of a bullet, or the
+// counter followed by a period, then a space
+pub fn marker_text(value: i32, style: &str) -> String {
+    match style {
+        "none" => String::new(),
+        "disc" | "circle" | "square" | "disclosure-open" | "disclosure-closed" => {
+            format!("{} ", format_counter(value, style))
+        }
+        _ => format!("{}. ", format_counter(value, style)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_scopes() {
+        let mut counters = CounterState::new();
+        counters.reset("item", 0);
+        counters.increment("item", 1);
+        counters.enter();
+        // a nested list creates its own counter
+        counters.reset("item", 0);
+        counters.increment("item", 2);
+        assert_eq!(counters.value("item"), 2);
+        assert_eq!(counters.values("item"), vec![1, 2]);
+        // a sibling reset replaces the counter instead of nesting it
+        counters.reset("item", 5);
+        assert_eq!(counters.values("item"), vec![1, 5]);
+        counters.leave();
+        assert_eq!(counters.value("item"), 1);
+    }
+
+    #[test]
+    fn test_counter_instantiated_on_use() {
+        let mut counters = CounterState::new();
+        assert!(!counters.exists("section"));
+        assert_eq!(counters.value("section"), 0);
+        counters.increment("section", 1);
+        counters.increment("section", 1);
+        assert_eq!(counters.value("section"), 2);
+        counters.set("figure", 7);
+        assert_eq!(counters.value("figure"), 7);
+    }
+
+    #[test]
+    fn test_counter_bounds() {
+        let mut counters = CounterState::new();
+        counters.reset("item", i32::MAX);
+        counters.increment("item", 1);
+        assert_eq!(counters.value("item"), i32::MAX);
+        counters.reset("item", i32::MIN);
+        counters.increment("item", -1);
+        assert_eq!(counters.value("item"), i32::MIN);
+    }
+
+    #[test]
+    fn test_format_counter() {
+        assert_eq!(format_counter(3, "decimal"), "3");
+        assert_eq!(format_counter(3, "decimal-leading-zero"), "03");
+        assert_eq!(format_counter(-3, "decimal-leading-zero"), "-03");
+        assert_eq!(format_counter(1994, "upper-roman"), "MCMXCIV");
+        assert_eq!(format_counter(4, "lower-roman"), "iv");
+        assert_eq!(format_counter(28, "lower-alpha"), "ab");
+        assert_eq!(format_counter(26, "upper-latin"), "Z");
+        assert_eq!(format_counter(2, "lower-greek"), "β");
+        // out of range, and unknown styles, fall back to decimal
+        assert_eq!(format_counter(0, "lower-roman"), "0");
+        assert_eq!(format_counter(-1, "lower-alpha"), "-1");
+        assert_eq!(format_counter(5, "thumbs"), "5");
+        assert_eq!(marker_text(1, "disc"), "\u{2022} ");
+        assert_eq!(marker_text(4, "decimal"), "4. ");
+        assert_eq!(marker_text(3, "upper-alpha"), "C. ");
+        assert_eq!(marker_text(1, "disclosure-closed"), "\u{25b8} ");
+        assert_eq!(marker_text(4, "none"), "");
+    }
+}
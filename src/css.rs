@@ -1,246 +1,2535 @@
+use crate::calc::{integer, CalcNode};
+use crate::media::MediaQueryList;
 use crate::parser::TextParser;
-use std::{f32, u8};
+use crate::properties::{is_valid, takes_integers, Component};
+use crate::shorthands::{expand, is_shorthand, longhands};
+use crate::supports::SupportsCondition;
+use crate::tokenizer::{
+    consume_numeric, consume_string, quote_string, serialize, starts_number, tokenize, Token,
+};
+use crate::variables::{contains_var, is_custom_property};
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct Stylesheet {
-    rules: Vec<Rule>,
+    pub(crate) rules: Vec<CssRule>,
+    pub(crate) origin: Origin,
+    // where the stylesheet was loaded from, to resolve relative urls
+    pub(crate) url: Option<String>,
 }
 
 #[derive(Debug, Clone)]
-struct Rule {
-    selectors: Vec<SimpleSelector>,
-    declarations: Vec<Declaration>,
+pub(crate) enum CssRule {
+    Style(Rule),
+    Media(MediaRule),
+    Import(ImportRule),
+    Supports(SupportsRule),
+    Layer(LayerRule),
+    Keyframes(KeyframesRule),
+    FontFace(FontFaceRule),
+    Page(PageRule),
+    Unknown(AtRule),
 }
 
+// `@import url(<url>) [layer | layer(<name>)] <queries>;`, whose stylesheet is
+// filled in by the loader. `layer` alone imports into an anonymous layer
 #[derive(Debug, Clone)]
+pub(crate) struct ImportRule {
+    pub(crate) url: String,
+    pub(crate) layer: Option<Option<String>>,
+    pub(crate) queries: MediaQueryList,
+    pub(crate) stylesheet: Option<Box<Stylesheet>>,
+}
+
+// `@media <queries> { <rules> }`
+#[derive(Debug, Clone)]
+pub(crate) struct MediaRule {
+    pub(crate) queries: MediaQueryList,
+    pub(crate) rules: Vec<CssRule>,
+}
+
+// `@supports <condition> { <rules> }`
+#[derive(Debug, Clone)]
+pub(crate) struct SupportsRule {
+    pub(crate) condition: SupportsCondition,
+    pub(crate) rules: Vec<CssRule>,
+}
+
+// `@layer a, b;` declares the order of layers, `@layer a { <rules> }` adds
+// rules to a layer, anonymous when it has no name
+#[derive(Debug, Clone)]
+pub(crate) struct LayerRule {
+    pub(crate) names: Vec<String>,
+    pub(crate) rules: Option<Vec<CssRule>>,
+}
+
+// `@keyframes <name> { from { ... } 50% { ... } to { ... } }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyframesRule {
+    pub name: String,
+    pub keyframes: Vec<Keyframe>,
+}
+
+// the declarations of one or more offsets of an animation, between 0 and 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    pub offsets: Vec<f32>,
+    pub declarations: Vec<(String, Value)>,
+}
+
+// `@font-face { font-family: ...; src: ...; }`, whose descriptors are kept
+// as written
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontFaceRule {
+    pub descriptors: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontSource {
+    // `url(<url>) format(<format>)`
+    Url(String, Option<String>),
+    // `local(<name>)`
+    Local(String),
+}
+
+// `@page <selectors> { <declarations> @top-center { ... } }`
+#[derive(Debug, Clone)]
+pub(crate) struct PageRule {
+    pub(crate) selectors: Vec<PageSelector>,
+    pub(crate) declarations: Vec<Declaration>,
+    pub(crate) margin_boxes: Vec<(String, Vec<Declaration>)>,
+}
+
+// `name:first:left`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PageSelector {
+    pub(crate) name: Option<String>,
+    pub(crate) pseudo_classes: Vec<String>,
+}
+
+// any other at-rule, kept as written
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: String,
+    pub block: Option<String>,
+}
+
+// where a stylesheet comes from, in increasing order of precedence
+// for normal declarations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    UserAgent,
+    User,
+    Author,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Rule {
+    pub(crate) selectors: Vec<Selector>,
+    pub(crate) declarations: Vec<Declaration>,
+}
+
+// a complex selector: compound selectors joined by combinators,
+// `combinators[i]` sits between `compounds[i]` and `compounds[i + 1]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    pub compounds: Vec<SimpleSelector>,
+    pub combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Combinator {
+    // a b
+    Descendant,
+    // a > b
+    Child,
+    // a + b
+    NextSibling,
+    // a ~ b
+    SubsequentSibling,
+}
+
+// a selector starting with a combinator, as used in `:has()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelativeSelector {
+    pub combinator: Combinator,
+    pub selector: Selector,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct SimpleSelector {
     pub tag_name: Option<String>,
     pub id: Option<String>,
     pub class: Vec<String>,
+    pub attributes: Vec<AttributeSelector>,
+    pub pseudo_classes: Vec<PseudoClass>,
+    // only allowed on the last compound of a selector
+    pub pseudo_element: Option<PseudoElement>,
 }
 
-#[derive(Debug, Clone)]
-struct Declaration {
-    name: String,
-    value: Value,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PseudoElement {
+    Before,
+    After,
+    Marker,
+    FirstLine,
+    FirstLetter,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PseudoClass {
+    Root,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    NthChild(Nth, Vec<Selector>),
+    NthLastChild(Nth, Vec<Selector>),
+    NthOfType(Nth),
+    NthLastOfType(Nth),
+    Not(Vec<Selector>),
+    Is(Vec<Selector>),
+    Where(Vec<Selector>),
+    Has(Vec<RelativeSelector>),
+    Checked,
+    Disabled,
+    Enabled,
+    AnyLink,
+    // user action states (`:hover`, `:focus`, ...) never match a static document
+    UserAction(String),
+}
+
+// the `an+b` argument of the `:nth-*` pseudo-classes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Nth {
+    pub a: i32,
+    pub b: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeSelector {
+    pub name: String,
+    pub operator: AttributeOperator,
+    pub value: String,
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttributeOperator {
+    // [attr]
+    Exists,
+    // [attr=value]
+    Equals,
+    // [attr~=value]
+    Includes,
+    // [attr|=value]
+    DashMatch,
+    // [attr^=value]
+    Prefix,
+    // [attr$=value]
+    Suffix,
+    // [attr*=value]
+    Substring,
 }
 
 #[derive(Debug, Clone)]
+pub(crate) struct Declaration {
+    pub(crate) name: String,
+    pub(crate) value: Value,
+    pub(crate) important: bool,
+    // the shorthand name and value a longhand was expanded from, shared by all
+    // the longhands of the shorthand declaration
+    pub(crate) shorthand: Option<Arc<(String, Value)>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Keyword(String),
     Length(f32, Unit),
+    // a dimension that is not a length, like a time or an angle, with its
+    // lowercase unit
+    Dimension(f32, String),
+    Percentage(f32),
+    Number(f32),
+    String(String),
+    Url(String),
     ColorValue(Color),
+    // a math function that could not be simplified to a single value
+    Calc(CalcNode),
+    // space-separated components
+    List(Vec<Value>),
+    // comma-separated components
+    CommaList(Vec<Value>),
+    // the items of the `content` property
+    Content(Vec<ContentItem>),
+    // `counter-reset`, `counter-increment` and `counter-set` pairs
+    CounterList(Vec<(String, i32)>),
+    // the tracks of `grid-template-*` and `grid-auto-*`
+    TrackList(Vec<TrackListItem>),
+    // the value of a custom property, or a value referencing custom properties
+    // with `var()`, substituted at computed-value time
+    Tokens(Vec<Token>),
+    // a longhand set by a shorthand referencing custom properties: the
+    // shorthand is substituted and expanded at computed-value time
+    PendingShorthand(String, Vec<Token>),
+    // a declaration this engine doesn't support, kept as written by parsers
+    // not validating declarations
+    Unsupported(Vec<Token>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentItem {
+    String(String),
+    // attr(name)
+    Attr(String),
+    // counter(name, style)
+    Counter(String, String),
+    // counters(name, separator, style)
+    Counters(String, String, String),
+    OpenQuote,
+    CloseQuote,
+    NoOpenQuote,
+    NoCloseQuote,
+    // url(image)
+    Url(String),
+}
+
+// the size of a grid track, or one of its bounds
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackBreadth {
+    // a length or a percentage
+    Length(Value),
+    // a share of the free space, in `fr`
+    Flex(f32),
+    MinContent,
+    MaxContent,
+    Auto,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackSize {
+    Breadth(TrackBreadth),
+    // minmax(min, max)
+    MinMax(TrackBreadth, TrackBreadth),
+    // fit-content(limit)
+    FitContent(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepeatCount {
+    Count(usize),
+    AutoFill,
+    AutoFit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackListItem {
+    // [name...]
+    LineNames(Vec<String>),
+    Track(TrackSize),
+    // repeat(count, tracks)
+    Repeat(RepeatCount, Vec<TrackListItem>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Unit {
     Px,
+    Cm,
+    Mm,
+    Q,
+    In,
+    Pt,
+    Pc,
+    Em,
+    Rem,
+    Ex,
+    Ch,
+    Vw,
+    Vh,
+    Vmin,
+    Vmax,
 }
 
-#[derive(Debug, Clone)]
+// what relative lengths and percentages resolve against
+#[derive(Debug, Clone, Copy)]
+pub struct LengthContext {
+    pub percentage_basis: f32,
+    pub font_size: f32,
+    pub root_font_size: f32,
+    pub viewport_width: f32,
+    pub viewport_height: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Color {
-    r: u8,
-    g: u8,
-    b: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    // the opacity, from 0 (transparent) to 255 (opaque)
+    pub a: u8,
 }
 
 pub struct CSSParser {
     text_parser: TextParser,
+    // whether declarations this engine doesn't support are dropped, or kept
+    // as written
+    validate: bool,
+}
+
+impl Stylesheet {
+    pub fn origin(&self) -> Origin {
+        self.origin
+    }
+
+    pub fn with_origin(mut self, origin: Origin) -> Stylesheet {
+        self.origin = origin;
+        self
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn with_url(mut self, url: &str) -> Stylesheet {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    // restrict the rules to the media matching `queries`, like the `media`
+    // attribute of `<style>` and `<link>`
+    // the top-level at-rules of the name that are not otherwise supported
+    pub fn at_rules<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a AtRule> + 'a {
+        self.rules.iter().filter_map(move |rule| match rule {
+            CssRule::Unknown(at_rule) if at_rule.name == name => Some(at_rule),
+            _ => None,
+        })
+    }
+
+    pub fn with_media(mut self, queries: MediaQueryList) -> Stylesheet {
+        let rules = std::mem::take(&mut self.rules);
+        self.rules = vec![CssRule::Media(MediaRule { queries, rules })];
+        self
+    }
+}
+
+// remove `/* ... */` comments, leaving strings untouched
+fn strip_comments(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                if c == '\\' {
+                    result.push(c);
+                    result.extend(chars.next());
+                    continue;
+                }
+                // a newline ends a bad string
+                if c == q || matches!(c, '\n' | '\r' | '\x0c') {
+                    quote = None;
+                }
+                result.push(c);
+            }
+            None if c == '/' && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                // a comment still separates tokens
+                result.push(' ');
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                result.push(c);
+            }
+        }
+    }
+    result
 }
 
+pub(crate) const NAMED_COLORS: &[(&str, (u8, u8, u8))] = &[
+    ("black", (0, 0, 0)),
+    ("silver", (192, 192, 192)),
+    ("gray", (128, 128, 128)),
+    ("grey", (128, 128, 128)),
+    ("white", (255, 255, 255)),
+    ("maroon", (128, 0, 0)),
+    ("red", (255, 0, 0)),
+    ("purple", (128, 0, 128)),
+    ("fuchsia", (255, 0, 255)),
+    ("magenta", (255, 0, 255)),
+    ("green", (0, 128, 0)),
+    ("lime", (0, 255, 0)),
+    ("olive", (128, 128, 0)),
+    ("yellow", (255, 255, 0)),
+    ("navy", (0, 0, 128)),
+    ("blue", (0, 0, 255)),
+    ("teal", (0, 128, 128)),
+    ("aqua", (0, 255, 255)),
+    ("cyan", (0, 255, 255)),
+    ("orange", (255, 165, 0)),
+    ("pink", (255, 192, 203)),
+    ("brown", (165, 42, 42)),
+    ("gold", (255, 215, 0)),
+    ("indigo", (75, 0, 130)),
+    ("violet", (238, 130, 238)),
+    ("darkgray", (169, 169, 169)),
+    ("darkgrey", (169, 169, 169)),
+    ("lightgray", (211, 211, 211)),
+    ("lightgrey", (211, 211, 211)),
+    ("rebeccapurple", (102, 51, 153)),
+];
+
 impl Color {
-    fn from_hex_code(code: String) -> Color {
-        // transform code string to color
-        let convert_to_u8 = |s| u8::from_str_radix(s, 16).unwrap();
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 255 }
+    }
+
+    // #rgb, #rgba, #rrggbb or #rrggbbaa
+    fn from_hex_code(code: &str) -> Option<Color> {
+        let digits = code.strip_prefix('#')?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |index: usize| match digits.len() {
+            3 | 4 => u8::from_str_radix(&digits[index..index + 1], 16).map(|c| c * 17),
+            _ => u8::from_str_radix(&digits[2 * index..2 * index + 2], 16),
+        };
+        match digits.len() {
+            3 | 6 => Some(Color::rgb(
+                channel(0).ok()?,
+                channel(1).ok()?,
+                channel(2).ok()?,
+            )),
+            4 | 8 => Some(Color {
+                r: channel(0).ok()?,
+                g: channel(1).ok()?,
+                b: channel(2).ok()?,
+                a: channel(3).ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    // rgb() and rgba(), with comma or space separated channels and an optional
+    // alpha
+    fn from_rgb_function(arguments: &[Value]) -> Option<Color> {
+        let channel = |value: &Value| match value {
+            Value::Number(value) => Some(value.round().clamp(0.0, 255.0) as u8),
+            Value::Percentage(value) => Some((value * 2.55).round().clamp(0.0, 255.0) as u8),
+            _ => None,
+        };
+        // the alpha is a number between 0 and 1, or a percentage
+        let alpha = |value: &Value| match value {
+            Value::Number(value) => Some((value * 255.0).round().clamp(0.0, 255.0) as u8),
+            Value::Percentage(value) => Some((value * 2.55).round().clamp(0.0, 255.0) as u8),
+            _ => None,
+        };
+        match arguments {
+            [r, g, b] => Some(Color::rgb(channel(r)?, channel(g)?, channel(b)?)),
+            [r, g, b, a] => Some(Color {
+                r: channel(r)?,
+                g: channel(g)?,
+                b: channel(b)?,
+                a: alpha(a)?,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Color> {
+        let name = name.to_lowercase();
+        NAMED_COLORS
+            .iter()
+            .find(|(color, _)| *color == name)
+            .map(|(_, (r, g, b))| Color::rgb(*r, *g, *b))
+    }
+}
+
+pub type Specificity = (usize, usize, usize);
+
+impl Default for LengthContext {
+    fn default() -> Self {
+        LengthContext {
+            percentage_basis: 0.0,
+            font_size: 16.0,
+            root_font_size: 16.0,
+            viewport_width: 1024.0,
+            viewport_height: 768.0,
+        }
+    }
+}
+
+impl Unit {
+    pub fn parse(unit: &str) -> Option<Unit> {
+        let unit = match unit.to_lowercase().as_str() {
+            "px" => Unit::Px,
+            "cm" => Unit::Cm,
+            "mm" => Unit::Mm,
+            "q" => Unit::Q,
+            "in" => Unit::In,
+            "pt" => Unit::Pt,
+            "pc" => Unit::Pc,
+            "em" => Unit::Em,
+            "rem" => Unit::Rem,
+            "ex" => Unit::Ex,
+            "ch" => Unit::Ch,
+            "vw" => Unit::Vw,
+            "vh" => Unit::Vh,
+            "vmin" => Unit::Vmin,
+            "vmax" => Unit::Vmax,
+            _ => return None,
+        };
+        Some(unit)
+    }
+
+    // the number of px in one unit, for absolute units
+    pub fn absolute_factor(&self) -> Option<f32> {
+        match self {
+            Unit::Px => Some(1.0),
+            Unit::Cm => Some(96.0 / 2.54),
+            Unit::Mm => Some(96.0 / 25.4),
+            Unit::Q => Some(96.0 / 101.6),
+            Unit::In => Some(96.0),
+            Unit::Pt => Some(96.0 / 72.0),
+            Unit::Pc => Some(16.0),
+            _ => None,
+        }
+    }
+
+    pub fn to_px(&self, value: f32, context: &LengthContext) -> f32 {
+        if let Some(factor) = self.absolute_factor() {
+            return value * factor;
+        }
+        let viewport = (context.viewport_width, context.viewport_height);
+        match self {
+            Unit::Em => value * context.font_size,
+            Unit::Rem => value * context.root_font_size,
+            // without font metrics, the x-height and the advance of `0` are half an em
+            Unit::Ex | Unit::Ch => value * context.font_size / 2.0,
+            Unit::Vw => value * viewport.0 / 100.0,
+            Unit::Vh => value * viewport.1 / 100.0,
+            Unit::Vmin => value * viewport.0.min(viewport.1) / 100.0,
+            Unit::Vmax => value * viewport.0.max(viewport.1) / 100.0,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Value {
+    // the numeric value of a number, percentage or dimension
+    fn from_numeric(number: &str, unit: &str) -> Option<Value> {
+        let number = number.parse::<f32>().ok()?;
+        match unit {
+            "" => Some(Value::Number(number)),
+            "%" => Some(Value::Percentage(number)),
+            unit => Some(match Unit::parse(unit) {
+                Some(unit) => Value::Length(number, unit),
+                None => Value::Dimension(number, unit.to_lowercase()),
+            }),
+        }
+    }
+
+    // the used length in px of a length, percentage or math function
+    pub fn to_px(&self, context: &LengthContext) -> Option<f32> {
+        match self {
+            Value::Length(value, unit) => Some(unit.to_px(*value, context)),
+            Value::Percentage(value) => Some(value / 100.0 * context.percentage_basis),
+            Value::Calc(node) => Some(node.resolve(context)),
+            // unitless zero is a valid length
+            Value::Number(value) if *value == 0.0 => Some(0.0),
+            _ => None,
+        }
+    }
+}
+
+fn add_specificity(a: Specificity, b: Specificity) -> Specificity {
+    (a.0 + b.0, a.1 + b.1, a.2 + b.2)
+}
+
+fn max_specificity<'a, I>(selectors: I) -> Specificity
+where
+    I: Iterator<Item = &'a Selector>,
+{
+    selectors
+        .map(|selector| selector.specificity())
+        .max()
+        .unwrap_or((0, 0, 0))
+}
 
-        let r = convert_to_u8(&code[1..3]);
-        let g = convert_to_u8(&code[3..5]);
-        let b = convert_to_u8(&code[5..7]);
+impl Selector {
+    pub fn specificity(&self) -> Specificity {
+        self.compounds.iter().fold((0, 0, 0), |acc, compound| {
+            add_specificity(acc, compound.specificity())
+        })
+    }
 
-        Color { r, g, b }
+    pub fn pseudo_element(&self) -> Option<PseudoElement> {
+        self.compounds
+            .last()
+            .and_then(|compound| compound.pseudo_element)
     }
 }
 
 impl SimpleSelector {
-    pub fn specificity(&self) -> (usize, usize, usize) {
+    pub fn specificity(&self) -> Specificity {
         let ids = match &self.id {
-            Some(ident) => ident.len(),
-            None => 0,
-        };
-        let classes = self.class.len();
-        let type_selector = match &self.tag_name {
             Some(_) => 1,
             None => 0,
         };
-        (ids, classes, type_selector)
+        // attribute selectors weigh as much as class selectors
+        let classes = self.class.len() + self.attributes.len();
+        // pseudo-elements weigh as much as type selectors
+        let type_selector = self.tag_name.iter().count() + self.pseudo_element.iter().count();
+        self.pseudo_classes
+            .iter()
+            .fold((ids, classes, type_selector), |acc, pseudo_class| {
+                add_specificity(acc, pseudo_class.specificity())
+            })
+    }
+}
+
+impl PseudoClass {
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            // :where() never adds specificity
+            PseudoClass::Where(_) => (0, 0, 0),
+            // the logical pseudo-classes take the specificity of their most specific argument
+            PseudoClass::Is(selectors) | PseudoClass::Not(selectors) => {
+                max_specificity(selectors.iter())
+            }
+            PseudoClass::Has(selectors) => {
+                max_specificity(selectors.iter().map(|relative| &relative.selector))
+            }
+            // :nth-child(an+b of S) counts as a pseudo-class plus the most specific S
+            PseudoClass::NthChild(_, selectors) | PseudoClass::NthLastChild(_, selectors) => {
+                add_specificity((0, 1, 0), max_specificity(selectors.iter()))
+            }
+            _ => (0, 1, 0),
+        }
+    }
+}
+
+impl Nth {
+    // whether the 1-based `position` is `an+b` for some non-negative n
+    pub fn matches(&self, position: i32) -> bool {
+        if self.a == 0 {
+            return position == self.b;
+        }
+        let offset = position - self.b;
+        offset % self.a == 0 && offset / self.a >= 0
+    }
+}
+
+impl AttributeSelector {
+    pub fn matches(&self, value: &str) -> bool {
+        let (value, expected) = if self.case_insensitive {
+            (value.to_lowercase(), self.value.to_lowercase())
+        } else {
+            (value.to_string(), self.value.clone())
+        };
+
+        match self.operator {
+            AttributeOperator::Exists => true,
+            AttributeOperator::Equals => value == expected,
+            AttributeOperator::Includes => {
+                !expected.is_empty()
+                    && !expected.contains(char::is_whitespace)
+                    && value.split_whitespace().any(|word| word == expected)
+            }
+            AttributeOperator::DashMatch => {
+                value == expected || value.starts_with(&format!("{}-", expected))
+            }
+            AttributeOperator::Prefix => !expected.is_empty() && value.starts_with(&expected),
+            AttributeOperator::Suffix => !expected.is_empty() && value.ends_with(&expected),
+            AttributeOperator::Substring => !expected.is_empty() && value.contains(&expected),
+        }
+    }
+}
+
+impl PageSelector {
+    // `:first` is the first page, right pages are odd and left pages even
+    pub(crate) fn matches(&self, page_number: usize) -> bool {
+        // pages have no name without the `page` property
+        self.name.is_none()
+            && self
+                .pseudo_classes
+                .iter()
+                .all(|pseudo| match pseudo.as_str() {
+                    "first" => page_number == 1,
+                    "left" => page_number.is_multiple_of(2),
+                    "right" => !page_number.is_multiple_of(2),
+                    _ => false,
+                })
+    }
+
+    pub(crate) fn specificity(&self) -> Specificity {
+        let count = |names: &[&str]| {
+            self.pseudo_classes
+                .iter()
+                .filter(|pseudo| names.contains(&pseudo.as_str()))
+                .count()
+        };
+        (
+            self.name.is_some() as usize,
+            count(&["first", "blank"]),
+            count(&["left", "right"]),
+        )
+    }
+}
+
+impl FontFaceRule {
+    pub fn descriptor(&self, name: &str) -> Option<&str> {
+        self.descriptors
+            .iter()
+            .rev()
+            .find(|(descriptor, _)| descriptor == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn family(&self) -> Option<String> {
+        let family = self.descriptor("font-family")?;
+        match tokenize(family).as_slice() {
+            [Token::String(family)] => Some(family.clone()),
+            _ => Some(family.to_string()),
+        }
+    }
+
+    // the sources of the `src` descriptor, in order of preference
+    pub fn sources(&self) -> Vec<FontSource> {
+        let src = match self.descriptor("src") {
+            Some(src) => src,
+            None => return Vec::new(),
+        };
+        split_components(&tokenize(src))
+            .into_iter()
+            .filter_map(|source| {
+                let mut parts = source.into_iter().map(Value::parse_component);
+                match parts.next()?? {
+                    Value::Url(url) => {
+                        let format = parts.next().flatten().and_then(|format| match format {
+                            Value::Keyword(format) => format
+                                .strip_prefix("format(")?
+                                .strip_suffix(')')
+                                .map(|format| format.trim_matches(['"', '\'']).to_string()),
+                            _ => None,
+                        });
+                        Some(FontSource::Url(url, format))
+                    }
+                    Value::Keyword(local) => {
+                        let name = local.strip_prefix("local(")?.strip_suffix(')')?;
+                        Some(FontSource::Local(
+                            name.trim().trim_matches(['"', '\'']).to_string(),
+                        ))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+const PSEUDO_CLASSES: &[&str] = &[
+    "root",
+    "empty",
+    "first-child",
+    "last-child",
+    "only-child",
+    "first-of-type",
+    "last-of-type",
+    "only-of-type",
+    "nth-child",
+    "nth-last-child",
+    "nth-of-type",
+    "nth-last-of-type",
+    "not",
+    "is",
+    "matches",
+    "where",
+    "has",
+    "checked",
+    "disabled",
+    "enabled",
+    "link",
+    "any-link",
+    "hover",
+    "active",
+    "focus",
+    "focus-within",
+    "focus-visible",
+    "visited",
+    "target",
+];
+
+const PSEUDO_ELEMENTS: &[&str] = &["before", "after", "marker", "first-line", "first-letter"];
+
+// whether the selector list is made of selectors the parser knows, for
+// `@supports selector(...)`
+pub(crate) fn is_supported_selector(selectors: &str) -> bool {
+    let mut text_parser = TextParser::new(selectors.to_string());
+    let mut depth = 0;
+    let mut empty = true;
+    while !text_parser.eol() {
+        match text_parser.consume_char() {
+            ':' => {
+                let element = text_parser.starts_with(":");
+                text_parser.consume_chars_while(|c| c == ':');
+                let name = text_parser
+                    .consume_chars_while(valid_identifier_char)
+                    .to_lowercase();
+                let known = match element {
+                    true => PSEUDO_ELEMENTS.contains(&name.as_str()),
+                    false => {
+                        PSEUDO_CLASSES.contains(&name.as_str())
+                            || PSEUDO_ELEMENTS.contains(&name.as_str())
+                    }
+                };
+                if !known {
+                    return false;
+                }
+            }
+            '(' => depth += 1,
+            ')' if depth == 0 => return false,
+            ')' => depth -= 1,
+            quote @ ('"' | '\'') => {
+                text_parser.consume_chars_while(|c| c != quote);
+                if text_parser.eol() {
+                    return false;
+                }
+                text_parser.consume_char();
+            }
+            '#' | '.' | '[' | ']' | '=' | '~' | '|' | '^' | '$' | '*' | ',' | '>' | '+' => {}
+            c if c.is_whitespace() => continue,
+            c if valid_identifier_char(c) => {}
+            _ => return false,
+        }
+        empty = false;
+    }
+    depth == 0 && !empty
+}
+
+pub(crate) fn valid_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+fn is_math_function(name: &str) -> bool {
+    ["calc", "min", "max", "clamp"]
+        .iter()
+        .any(|function| name.eq_ignore_ascii_case(function))
+}
+
+pub(crate) fn is_css_wide_keyword(value: &Value) -> bool {
+    matches!(value, Value::Keyword(keyword)
+        if ["inherit", "initial", "unset", "revert"].contains(&keyword.to_lowercase().as_str()))
+}
+
+// split a value into its comma-separated groups of space-separated components;
+// a `/` is a component of its own
+fn split_components(tokens: &[Token]) -> Vec<Vec<String>> {
+    let mut groups = vec![Vec::new()];
+    let mut component: Vec<Token> = Vec::new();
+    let mut depth = 0;
+    for token in tokens {
+        match token {
+            Token::Function(_) | Token::OpenParen => depth += 1,
+            Token::CloseParen => depth -= 1,
+            Token::Whitespace | Token::Comma | Token::Delim('/') if depth == 0 => {
+                if !component.is_empty() {
+                    groups.last_mut().unwrap().push(serialize(&component));
+                    component.clear();
+                }
+                match token {
+                    Token::Comma => groups.push(Vec::new()),
+                    Token::Delim('/') => groups.last_mut().unwrap().push(String::from("/")),
+                    _ => {}
+                }
+                continue;
+            }
+            _ => {}
+        }
+        component.push(token.clone());
+    }
+    if !component.is_empty() {
+        groups.last_mut().unwrap().push(serialize(&component));
+    }
+    groups
+}
+
+// the number of tokens of the component starting `tokens`: a single token, or
+// a bracketed block or a function up to its closing token
+fn component_length(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Function(_) | Token::OpenParen | Token::OpenBracket => depth += 1,
+            Token::CloseParen | Token::CloseBracket => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+fn trim_whitespace(mut tokens: &[Token]) -> &[Token] {
+    while let [Token::Whitespace, rest @ ..] = tokens {
+        tokens = rest;
+    }
+    while let [rest @ .., Token::Whitespace] = tokens {
+        tokens = rest;
+    }
+    tokens
+}
+
+// the comma-separated arguments of a function component
+fn function_arguments(component: &[Token]) -> Vec<&[Token]> {
+    let inner = &component[1..component.len() - 1];
+    let mut arguments = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (index, token) in inner.iter().enumerate() {
+        match token {
+            Token::Function(_) | Token::OpenParen | Token::OpenBracket => depth += 1,
+            Token::CloseParen | Token::CloseBracket => depth -= 1,
+            Token::Comma if depth == 0 => {
+                arguments.push(trim_whitespace(&inner[start..index]));
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(trim_whitespace(&inner[start..]));
+    arguments
+}
+
+fn parse_track_breadth(tokens: &[Token]) -> Option<TrackBreadth> {
+    match tokens {
+        [Token::Ident(keyword)] => match keyword.to_lowercase().as_str() {
+            "auto" => Some(TrackBreadth::Auto),
+            "min-content" => Some(TrackBreadth::MinContent),
+            "max-content" => Some(TrackBreadth::MaxContent),
+            _ => None,
+        },
+        [Token::Number(number)] if number.to_lowercase().ends_with("fr") => number
+            [..number.len() - 2]
+            .parse::<f32>()
+            .ok()
+            .filter(|flex| *flex >= 0.0)
+            .map(TrackBreadth::Flex),
+        tokens => {
+            let value = Value::parse_component(serialize(tokens))?;
+            match Component::NonNegativeLengthPercentage.matches(&value) {
+                true => Some(TrackBreadth::Length(value)),
+                false => None,
+            }
+        }
+    }
+}
+
+fn parse_track_size(component: &[Token]) -> Option<TrackSize> {
+    match component.first()? {
+        Token::Function(name) if name.eq_ignore_ascii_case("minmax") => {
+            let [min, max] = function_arguments(component).try_into().ok()?;
+            match parse_track_breadth(min)? {
+                TrackBreadth::Flex(_) => None,
+                min => Some(TrackSize::MinMax(min, parse_track_breadth(max)?)),
+            }
+        }
+        Token::Function(name) if name.eq_ignore_ascii_case("fit-content") => {
+            let [limit] = function_arguments(component).try_into().ok()?;
+            match parse_track_breadth(limit)? {
+                TrackBreadth::Length(limit) => Some(TrackSize::FitContent(limit)),
+                _ => None,
+            }
+        }
+        _ => parse_track_breadth(component).map(TrackSize::Breadth),
+    }
+}
+
+// the tracks of a track list; only `template` lists have line names and
+// `repeat()`, with at most one automatic repetition
+fn parse_track_list(tokens: &[Token], template: bool) -> Option<Vec<TrackListItem>> {
+    let mut items = Vec::new();
+    let mut rest = tokens;
+    while !rest.is_empty() {
+        let (component, next) = rest.split_at(component_length(rest)?);
+        rest = next;
+        match &component[0] {
+            Token::Whitespace => {}
+            Token::OpenBracket if template => {
+                let names = component[1..component.len() - 1]
+                    .iter()
+                    .filter(|token| **token != Token::Whitespace)
+                    .map(|token| match token {
+                        Token::Ident(name) => Some(name.clone()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<String>>>()?;
+                items.push(TrackListItem::LineNames(names));
+            }
+            Token::Function(name) if template && name.eq_ignore_ascii_case("repeat") => {
+                let [count, tracks] = function_arguments(component).try_into().ok()?;
+                let count = match count {
+                    [Token::Ident(keyword)] if keyword.eq_ignore_ascii_case("auto-fill") => {
+                        RepeatCount::AutoFill
+                    }
+                    [Token::Ident(keyword)] if keyword.eq_ignore_ascii_case("auto-fit") => {
+                        RepeatCount::AutoFit
+                    }
+                    [Token::Number(count)] => {
+                        RepeatCount::Count(count.parse().ok().filter(|count| *count > 0)?)
+                    }
+                    _ => return None,
+                };
+                let tracks = parse_track_list(tracks, true)?;
+                if tracks
+                    .iter()
+                    .any(|item| matches!(item, TrackListItem::Repeat(..)))
+                {
+                    return None;
+                }
+                items.push(TrackListItem::Repeat(count, tracks));
+            }
+            _ => items.push(TrackListItem::Track(parse_track_size(component)?)),
+        }
+    }
+    let has_tracks = items
+        .iter()
+        .any(|item| !matches!(item, TrackListItem::LineNames(_)));
+    let auto_repeats = items
+        .iter()
+        .filter(|item| {
+            matches!(
+                item,
+                TrackListItem::Repeat(RepeatCount::AutoFill | RepeatCount::AutoFit, _)
+            )
+        })
+        .count();
+    Some(items).filter(|_| has_tracks && auto_repeats <= 1)
+}
+
+impl TrackBreadth {
+    fn map_length(&self, map: &dyn Fn(&Value) -> Value) -> TrackBreadth {
+        match self {
+            TrackBreadth::Length(length) => TrackBreadth::Length(map(length)),
+            breadth => breadth.clone(),
+        }
+    }
+}
+
+impl TrackListItem {
+    // the item with `map` applied to its lengths
+    pub fn map_lengths(&self, map: &dyn Fn(&Value) -> Value) -> TrackListItem {
+        match self {
+            TrackListItem::LineNames(names) => TrackListItem::LineNames(names.clone()),
+            TrackListItem::Track(size) => TrackListItem::Track(match size {
+                TrackSize::Breadth(breadth) => TrackSize::Breadth(breadth.map_length(map)),
+                TrackSize::MinMax(min, max) => {
+                    TrackSize::MinMax(min.map_length(map), max.map_length(map))
+                }
+                TrackSize::FitContent(limit) => TrackSize::FitContent(map(limit)),
+            }),
+            TrackListItem::Repeat(count, items) => TrackListItem::Repeat(
+                *count,
+                items.iter().map(|item| item.map_lengths(map)).collect(),
+            ),
+        }
+    }
+}
+
+impl Value {
+    // a single component value
+    fn parse_component(component: String) -> Option<Value> {
+        let tokens = tokenize(&component);
+        match tokens.first()? {
+            Token::Hash(_) => Color::from_hex_code(&component).map(Value::ColorValue),
+            Token::String(text) if tokens.len() == 1 => Some(Value::String(text.clone())),
+            Token::Number(_) => {
+                let mut text_parser = TextParser::new(component);
+                let (number, unit) = consume_numeric(&mut text_parser);
+                match text_parser.eol() {
+                    true => Value::from_numeric(&number, &unit),
+                    false => None,
+                }
+            }
+            Token::Function(name) if is_math_function(name) => {
+                let mut value_parser = CSSParser::new(component);
+                let node = value_parser.parse_math_function()?;
+                if !value_parser.text_parser.eol() {
+                    return None;
+                }
+                node.calc_type()?;
+                Some(match node.simplify() {
+                    CalcNode::Number(value) => Value::Number(value),
+                    CalcNode::Percentage(value) => Value::Percentage(value),
+                    CalcNode::Dimension(value, unit) => Value::Length(value, unit),
+                    node => Value::Calc(node),
+                })
+            }
+            Token::Function(name) if name.eq_ignore_ascii_case("url") => {
+                let url = component[4..].strip_suffix(')')?.trim();
+                let url = match tokenize(url).as_slice() {
+                    [Token::String(url)] => url.clone(),
+                    _ => url.to_string(),
+                };
+                Some(Value::Url(url))
+            }
+            Token::Function(name)
+                if name.eq_ignore_ascii_case("rgb") || name.eq_ignore_ascii_case("rgba") =>
+            {
+                let arguments = component[name.len() + 1..].strip_suffix(')')?;
+                let arguments: Vec<Value> = split_components(&tokenize(arguments))
+                    .into_iter()
+                    .flatten()
+                    .filter(|component| component != "/")
+                    .map(Value::parse_component)
+                    .collect::<Option<Vec<Value>>>()?;
+                Color::from_rgb_function(&arguments).map(Value::ColorValue)
+            }
+            Token::Ident(name) if tokens.len() == 1 => match Color::from_name(name) {
+                Some(color) => Some(Value::ColorValue(color)),
+                None => Some(Value::Keyword(component)),
+            },
+            _ => Some(Value::Keyword(component)),
+        }
+    }
+}
+
+impl Declaration {
+    // the declarations of the longhands of `name`, empty if the value is
+    // invalid, or the unsupported declaration when not validating
+    fn new(name: String, value: String, validate: bool) -> Vec<Declaration> {
+        // `!important` is a flag of the declaration, not part of its value
+        let (value, important) = match value.rfind('!') {
+            Some(index) if value[index + 1..].trim().eq_ignore_ascii_case("important") => {
+                (value[..index].trim_end().to_string(), true)
+            }
+            _ => (value, false),
+        };
+
+        let longhands = Declaration::parse_longhands(&name, value.clone()).unwrap_or_default();
+        if longhands.is_empty() && !validate {
+            let tokens = tokenize(&value);
+            if !tokens.contains(&Token::BadString) {
+                return vec![Declaration {
+                    name,
+                    value: Value::Unsupported(tokens),
+                    important,
+                    shorthand: None,
+                }];
+            }
+        }
+        // shorthands are kept to be serialized as they were declared
+        let shorthand = match is_shorthand(&name) && !longhands.is_empty() {
+            true => Declaration::parse_value(&name, value).map(|value| Arc::new((name, value))),
+            false => None,
+        };
+        longhands
+            .into_iter()
+            .map(|(name, value)| Declaration {
+                name,
+                value,
+                important,
+                shorthand: shorthand.clone(),
+            })
+            .collect()
+    }
+
+    // shorthands are expanded into their longhands, other properties are
+    // returned as they are
+    pub(crate) fn parse_longhands(name: &str, value: String) -> Option<Vec<(String, Value)>> {
+        if !is_shorthand(name) {
+            return Some(vec![(
+                name.to_string(),
+                Declaration::parse_value(name, value)?,
+            )]);
+        }
+
+        let tokens = tokenize(&value);
+        if contains_var(&tokens) {
+            return Some(
+                longhands(name)
+                    .iter()
+                    .map(|longhand| {
+                        let value = Value::PendingShorthand(name.to_string(), tokens.clone());
+                        (longhand.to_string(), value)
+                    })
+                    .collect(),
+            );
+        }
+        let value = Declaration::parse_value(name, value)?;
+        if is_css_wide_keyword(&value) {
+            return Some(
+                longhands(name)
+                    .iter()
+                    .map(|longhand| (longhand.to_string(), value.clone()))
+                    .collect(),
+            );
+        }
+        // the longhands must be valid too
+        expand(name, &value).filter(|longhands| {
+            longhands
+                .iter()
+                .all(|(longhand, value)| is_valid(longhand, value))
+        })
+    }
+
+    pub(crate) fn parse_value(name: &str, value: String) -> Option<Value> {
+        let tokens = tokenize(&value);
+        if tokens.contains(&Token::BadString) {
+            return None;
+        }
+        if is_custom_property(name) {
+            return Some(Value::Tokens(tokens));
+        }
+        if contains_var(&tokens) {
+            return Some(Value::Tokens(tokens));
+        }
+
+        // keywords are parsed as any other value
+        let keyword = value.trim().to_lowercase();
+        let keyword = keyword == "none"
+            || keyword == "normal"
+            || is_css_wide_keyword(&Value::Keyword(keyword.clone()));
+        match (name, value.as_str()) {
+            ("content", _) if keyword => {}
+            ("content", _) => {
+                let mut value_parser = CSSParser::new(value);
+                return value_parser.parse_content().map(Value::Content);
+            }
+            (
+                "grid-template-columns"
+                | "grid-template-rows"
+                | "grid-auto-columns"
+                | "grid-auto-rows",
+                text,
+            ) if !text.eq_ignore_ascii_case("none")
+                && !is_css_wide_keyword(&Value::Keyword(text.to_string())) =>
+            {
+                let template = name.starts_with("grid-template");
+                return parse_track_list(&tokens, template).map(Value::TrackList);
+            }
+            ("counter-reset" | "counter-increment" | "counter-set", _) if keyword => {}
+            ("counter-reset" | "counter-increment" | "counter-set", _) => {
+                // a counter without value is reset/set to 0, or incremented by 1
+                let default = if name == "counter-increment" { 1 } else { 0 };
+                let mut value_parser = CSSParser::new(value);
+                return value_parser
+                    .parse_counter_list(default)
+                    .map(Value::CounterList);
+            }
+            _ => {}
+        }
+
+        // math functions resolving to numbers are rounded where integers are
+        // expected
+        let integers = takes_integers(name);
+        let mut groups = Vec::new();
+        for group in split_components(&tokens) {
+            let mut components = group
+                .into_iter()
+                .map(|component| {
+                    let math = matches!(
+                        tokenize(&component).first(),
+                        Some(Token::Function(function)) if is_math_function(function)
+                    );
+                    match Value::parse_component(component)? {
+                        Value::Number(number) if math && integers => {
+                            Some(Value::Number(integer(number)))
+                        }
+                        value => Some(value),
+                    }
+                })
+                .collect::<Option<Vec<Value>>>()?;
+            groups.push(match components.len() {
+                0 => return None,
+                1 => components.pop().unwrap(),
+                _ => Value::List(components),
+            });
+        }
+        let value = match groups.len() {
+            1 => groups.pop()?,
+            _ => Value::CommaList(groups),
+        };
+        // values not matching the grammar of the property are dropped
+        Some(value).filter(|value| is_valid(name, value))
+    }
+}
+
+impl CSSParser {
+    pub fn new(input: String) -> CSSParser {
+        let text_parser = TextParser::new(strip_comments(&input));
+        CSSParser {
+            text_parser,
+            validate: true,
+        }
+    }
+
+    // a parser keeping unsupported declarations, to rewrite stylesheets
+    // without changing their meaning for other user agents
+    pub fn with_validation(mut self, validate: bool) -> CSSParser {
+        self.validate = validate;
+        self
+    }
+
+    // the raw text of a declaration value, up to the next `;` or `}` outside of
+    // strings and blocks
+    fn parse_raw_value(&mut self) -> String {
+        let mut value = String::from("");
+        let mut depth = 0;
+        while !self.text_parser.eol() {
+            match self.text_parser.get_current_char() {
+                ';' | '}' if depth == 0 => break,
+                '"' | '\'' => {
+                    // keep the string quoted and escaped, so it can be parsed again,
+                    // and bad strings broken by a newline that trimming keeps
+                    match self.parse_string() {
+                        Some(string) => value.push_str(&quote_string(&string)),
+                        None => value.push_str("\"\n\""),
+                    }
+                    continue;
+                }
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth > 0 => depth -= 1,
+                _ => {}
+            }
+            value.push(self.text_parser.consume_char());
+        }
+        value.trim().to_string()
+    }
+
+    pub(crate) fn parse_declarations(&mut self) -> Vec<Declaration> {
+        let mut declarations: Vec<Declaration> = Vec::new();
+
+        loop {
+            self.text_parser.remove_whitespaces();
+            if self.text_parser.eol() {
+                break;
+            }
+            match self.text_parser.get_current_char() {
+                '}' => break,
+                ';' => {
+                    self.text_parser.consume_char();
+                }
+                _ => {
+                    let (name, value) = self.parse_raw_declaration();
+                    declarations.extend(Declaration::new(name, value, self.validate));
+                }
+            }
+        }
+        declarations
+    }
+
+    // the name and the raw value of a declaration
+    fn parse_raw_declaration(&mut self) -> (String, String) {
+        let name = self
+            .text_parser
+            .consume_sequence(|c| c != ':', |c| c.is_whitespace(), true);
+        (name, self.parse_raw_value())
+    }
+
+    // `None` when an item is unknown or has invalid arguments
+    pub(crate) fn parse_content(&mut self) -> Option<Vec<ContentItem>> {
+        let mut items = Vec::new();
+        loop {
+            self.text_parser.remove_whitespaces();
+            let c = match self.next_char() {
+                Some(c) => c,
+                None => break,
+            };
+            if let '"' | '\'' = c {
+                items.push(ContentItem::String(self.parse_string()?));
+                continue;
+            }
+            let state = self.text_parser._state();
+            if state
+                .get(..4)
+                .is_some_and(|url| url.eq_ignore_ascii_case("url("))
+            {
+                items.push(ContentItem::Url(self.parse_url()?));
+                continue;
+            }
+
+            let name = self.parse_identifier().to_lowercase();
+            if name.is_empty() {
+                return None;
+            }
+            if self.next_char() != Some('(') {
+                items.push(match name.as_str() {
+                    "open-quote" => ContentItem::OpenQuote,
+                    "close-quote" => ContentItem::CloseQuote,
+                    "no-open-quote" => ContentItem::NoOpenQuote,
+                    "no-close-quote" => ContentItem::NoCloseQuote,
+                    _ => return None,
+                });
+                continue;
+            }
+            self.text_parser.consume_char();
+
+            // identifiers and strings, the latter being `None`
+            let mut args: Vec<(Option<String>, String)> = Vec::new();
+            loop {
+                self.text_parser.remove_whitespaces();
+                match self.next_char()? {
+                    ')' => break,
+                    ',' => {
+                        self.text_parser.consume_char();
+                    }
+                    '"' | '\'' => args.push((None, self.parse_string()?)),
+                    _ => {
+                        let identifier = self.parse_identifier();
+                        if identifier.is_empty() {
+                            return None;
+                        }
+                        args.push((Some(identifier.clone()), identifier));
+                    }
+                }
+            }
+            self.text_parser.consume_char();
+
+            let identifier = |index: usize| args.get(index).and_then(|arg| arg.0.clone());
+            let string = |index: usize| match args.get(index) {
+                Some((None, string)) => Some(string.clone()),
+                _ => None,
+            };
+            let style = |index: usize| match args.get(index) {
+                Some(_) => identifier(index),
+                None => Some("decimal".to_string()),
+            };
+            items.push(match (name.as_str(), args.len()) {
+                ("attr", 1) => ContentItem::Attr(identifier(0)?),
+                ("counter", 1 | 2) => ContentItem::Counter(identifier(0)?, style(1)?),
+                ("counters", 2 | 3) => ContentItem::Counters(identifier(0)?, string(1)?, style(2)?),
+                _ => return None,
+            });
+        }
+        match items.is_empty() {
+            true => None,
+            false => Some(items),
+        }
+    }
+
+    // `name [integer]?` pairs, as used by the counter properties; `None` when
+    // another token is found
+    fn parse_counter_list(&mut self, default: i32) -> Option<Vec<(String, i32)>> {
+        let mut counters: Vec<(String, i32)> = Vec::new();
+        loop {
+            self.text_parser.remove_whitespaces();
+            let c = match self.next_char() {
+                Some(c) => c,
+                None => break,
+            };
+            let signed = (c == '-' || c == '+') && self.starts_with_number();
+            if c.is_ascii_digit() || signed {
+                let sign = match c {
+                    '-' => -1,
+                    _ => 1,
+                };
+                if signed {
+                    self.text_parser.consume_char();
+                }
+                let value = self.parse_integer()?;
+                // the integer must end there, `1.5` and `1px` aren't integers
+                if self.next_char().is_some_and(|c| !c.is_whitespace()) {
+                    return None;
+                }
+                counters.last_mut()?.1 = sign * value;
+            } else {
+                let name = self.parse_identifier();
+                if name.is_empty() {
+                    return None;
+                }
+                counters.push((name, default));
+            }
+        }
+        Some(counters)
+    }
+
+    fn starts_with_number(&self) -> bool {
+        let rest = self.text_parser._state();
+        let mut chars = rest.chars();
+        chars.next();
+        chars.next().is_some_and(|c| c.is_ascii_digit())
+    }
+
+    fn next_char(&self) -> Option<char> {
+        self.text_parser._state().chars().next()
+    }
+
+    // a numeric value, a parenthesized sum, a nested math function or a constant
+    fn parse_calc_value(&mut self) -> Option<CalcNode> {
+        if starts_number(self.text_parser._state()) {
+            let (number, unit) = consume_numeric(&mut self.text_parser);
+            return match Value::from_numeric(&number, &unit)? {
+                Value::Number(value) => Some(CalcNode::Number(value)),
+                Value::Percentage(value) => Some(CalcNode::Percentage(value)),
+                Value::Length(value, unit) => Some(CalcNode::Dimension(value, unit)),
+                _ => None,
+            };
+        }
+        if self.next_char()? == '(' {
+            self.text_parser.consume_char();
+            self.text_parser.remove_whitespaces();
+            let sum = self.parse_calc_sum()?;
+            self.text_parser.remove_whitespaces();
+            return match self.next_char()? {
+                ')' => {
+                    self.text_parser.consume_char();
+                    Some(sum)
+                }
+                _ => None,
+            };
+        }
+
+        let rest = self.text_parser._state();
+        let name_length = rest
+            .find(|c| !valid_identifier_char(c))
+            .unwrap_or(rest.len());
+        if rest[name_length..].starts_with('(') {
+            return self.parse_math_function();
+        }
+        let constant = match self.parse_identifier().to_lowercase().as_str() {
+            "e" => std::f32::consts::E,
+            "pi" => std::f32::consts::PI,
+            "infinity" => f32::INFINITY,
+            "-infinity" => f32::NEG_INFINITY,
+            "nan" => f32::NAN,
+            _ => return None,
+        };
+        Some(CalcNode::Number(constant))
+    }
+
+    fn parse_calc_product(&mut self) -> Option<CalcNode> {
+        let mut factors = vec![self.parse_calc_value()?];
+        loop {
+            let operator = self.text_parser._state().trim_start().chars().next();
+            if operator != Some('*') && operator != Some('/') {
+                break;
+            }
+            self.text_parser.remove_whitespaces();
+            self.text_parser.consume_char();
+            self.text_parser.remove_whitespaces();
+            let factor = self.parse_calc_value()?;
+            factors.push(match operator {
+                Some('/') => CalcNode::Invert(Box::new(factor)),
+                _ => factor,
+            });
+        }
+        Some(match factors.len() {
+            1 => factors.pop().unwrap(),
+            _ => CalcNode::Product(factors),
+        })
+    }
+
+    fn parse_calc_sum(&mut self) -> Option<CalcNode> {
+        let mut terms = vec![self.parse_calc_product()?];
+        loop {
+            let whitespace = self.text_parser.consume_chars_while(|c| c.is_whitespace());
+            let operator = match self.next_char() {
+                None | Some(')') | Some(',') => break,
+                Some(operator @ ('+' | '-')) if !whitespace.is_empty() => operator,
+                Some(_) => return None,
+            };
+            self.text_parser.consume_char();
+            // `+` and `-` need whitespace on both sides, to not be part of a number
+            if !self.next_char()?.is_whitespace() {
+                return None;
+            }
+            self.text_parser.remove_whitespaces();
+            let term = self.parse_calc_product()?;
+            terms.push(match operator {
+                '-' => CalcNode::Product(vec![CalcNode::Number(-1.0), term]),
+                _ => term,
+            });
+        }
+        Some(match terms.len() {
+            1 => terms.pop().unwrap(),
+            _ => CalcNode::Sum(terms),
+        })
+    }
+
+    // calc(), min(), max() or clamp(); `None` if the expression is invalid
+    pub(crate) fn parse_math_function(&mut self) -> Option<CalcNode> {
+        let name = self.parse_identifier().to_lowercase();
+        if self.next_char()? != '(' {
+            return None;
+        }
+        self.text_parser.consume_char();
+
+        let mut arguments = Vec::new();
+        loop {
+            self.text_parser.remove_whitespaces();
+            arguments.push(self.parse_calc_sum()?);
+            self.text_parser.remove_whitespaces();
+            match self.next_char()? {
+                ',' => self.text_parser.consume_char(),
+                ')' => break,
+                _ => return None,
+            };
+        }
+        self.text_parser.consume_char();
+
+        match (name.as_str(), arguments.len()) {
+            ("calc", 1) => arguments.pop(),
+            ("min", _) => Some(CalcNode::Min(arguments)),
+            ("max", _) => Some(CalcNode::Max(arguments)),
+            ("clamp", 3) => {
+                let max = arguments.pop().unwrap();
+                let value = arguments.pop().unwrap();
+                let min = arguments.pop().unwrap();
+                Some(CalcNode::Clamp(
+                    Box::new(min),
+                    Box::new(value),
+                    Box::new(max),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_identifier(&mut self) -> String {
+        self.text_parser.consume_chars_while(valid_identifier_char)
+    }
+
+    // `None` for a bad string, see `consume_string`
+    fn parse_string(&mut self) -> Option<String> {
+        consume_string(&mut self.text_parser)
+    }
+
+    // `None` for unknown operators and flags, and namespaced attributes
+    fn parse_attribute_selector(&mut self) -> Option<AttributeSelector> {
+        assert!(self.text_parser.consume_char() == '[');
+        self.text_parser.remove_whitespaces();
+        // attribute names are case-insensitive in html
+        let name = self.parse_identifier().to_lowercase();
+        if name.is_empty() {
+            return None;
+        }
+        self.text_parser.remove_whitespaces();
+
+        let operator = match self.next_char()? {
+            ']' => {
+                self.text_parser.consume_char();
+                return Some(AttributeSelector {
+                    name,
+                    operator: AttributeOperator::Exists,
+                    value: String::new(),
+                    case_insensitive: false,
+                });
+            }
+            '=' => AttributeOperator::Equals,
+            '~' => AttributeOperator::Includes,
+            '|' => AttributeOperator::DashMatch,
+            '^' => AttributeOperator::Prefix,
+            '$' => AttributeOperator::Suffix,
+            '*' => AttributeOperator::Substring,
+            _ => return None,
+        };
+        self.text_parser.consume_char();
+        if operator != AttributeOperator::Equals {
+            if self.next_char()? != '=' {
+                return None;
+            }
+            self.text_parser.consume_char();
+        }
+
+        self.text_parser.remove_whitespaces();
+        let value = match self.next_char()? {
+            '"' | '\'' => self.parse_string()?,
+            _ => Some(self.parse_identifier()).filter(|value| !value.is_empty())?,
+        };
+        self.text_parser.remove_whitespaces();
+
+        // optional case-sensitivity flag
+        let mut case_insensitive = false;
+        if self.next_char()? != ']' {
+            let flag = self.parse_identifier().to_lowercase();
+            case_insensitive = match flag.as_str() {
+                "i" => true,
+                "s" => false,
+                _ => return None,
+            };
+            self.text_parser.remove_whitespaces();
+        }
+        if self.next_char()? != ']' {
+            return None;
+        }
+        self.text_parser.consume_char();
+
+        Some(AttributeSelector {
+            name,
+            operator,
+            value,
+            case_insensitive,
+        })
+    }
+
+    fn parse_integer(&mut self) -> Option<i32> {
+        let digits = self.text_parser.consume_chars_while(|c| c.is_ascii_digit());
+        digits.parse::<i32>().ok()
+    }
+
+    // `None` when the expression is invalid
+    fn parse_nth(&mut self) -> Option<Nth> {
+        self.text_parser.remove_whitespaces();
+        let keyword = self.text_parser.consume_chars_while(|c| c.is_alphabetic());
+        match keyword.to_lowercase().as_str() {
+            "odd" => return Some(Nth { a: 2, b: 1 }),
+            "even" => return Some(Nth { a: 2, b: 0 }),
+            _ => {}
+        }
+
+        // otherwise the raw `an+b` form, where the keyword can only be `n`
+        let mut sign = 1;
+        let mut a_part = keyword;
+        if a_part.is_empty() {
+            match self.next_char()? {
+                '-' => {
+                    sign = -1;
+                    self.text_parser.consume_char();
+                }
+                '+' => {
+                    self.text_parser.consume_char();
+                }
+                _ => {}
+            }
+            if self.next_char()?.is_alphabetic() {
+                a_part = self.text_parser.consume_chars_while(|c| c.is_alphabetic());
+            }
+        }
+
+        let mut nth = Nth { a: 0, b: 0 };
+        if a_part.is_empty() {
+            let value = sign * self.parse_integer()?;
+            if !self.next_char()?.eq_ignore_ascii_case(&'n') {
+                nth.b = value;
+                return Some(nth);
+            }
+            self.text_parser.consume_char();
+            nth.a = value;
+        } else if a_part.eq_ignore_ascii_case("n") {
+            nth.a = sign;
+        } else {
+            return None;
+        }
+
+        // optional `+ b` / `- b`
+        self.text_parser.remove_whitespaces();
+        let sign = match self.next_char()? {
+            '+' => 1,
+            '-' => -1,
+            _ => return Some(nth),
+        };
+        self.text_parser.consume_char();
+        self.text_parser.remove_whitespaces();
+        nth.b = sign * self.parse_integer()?;
+        Some(nth)
+    }
+
+    // the `of S` part of `:nth-child()` and `:nth-last-child()`
+    fn parse_nth_of_selectors(&mut self) -> Option<Vec<Selector>> {
+        self.text_parser.remove_whitespaces();
+        if !self.text_parser.starts_with("of") {
+            return Some(Vec::new());
+        }
+        self.text_parser.consume_pattern(String::from("of"));
+        self.parse_selector_list()
+    }
+
+    // `None` for unknown pseudo-classes
+    fn parse_pseudo_class(&mut self) -> Option<PseudoClass> {
+        assert!(self.text_parser.consume_char() == ':');
+        let name = self.parse_identifier().to_lowercase();
+
+        if !self.text_parser.eol() && self.text_parser.get_current_char() == '(' {
+            self.text_parser.consume_char();
+            let pseudo_class = match name.as_str() {
+                "nth-child" => {
+                    let nth = self.parse_nth()?;
+                    PseudoClass::NthChild(nth, self.parse_nth_of_selectors()?)
+                }
+                "nth-last-child" => {
+                    let nth = self.parse_nth()?;
+                    PseudoClass::NthLastChild(nth, self.parse_nth_of_selectors()?)
+                }
+                "nth-of-type" => PseudoClass::NthOfType(self.parse_nth()?),
+                "nth-last-of-type" => PseudoClass::NthLastOfType(self.parse_nth()?),
+                "not" => PseudoClass::Not(self.parse_selector_list()?),
+                "is" | "matches" => PseudoClass::Is(self.parse_selector_list()?),
+                "where" => PseudoClass::Where(self.parse_selector_list()?),
+                "has" => PseudoClass::Has(self.parse_relative_selector_list()?),
+                _ => return None,
+            };
+            self.text_parser.remove_whitespaces();
+            if self.next_char()? != ')' {
+                return None;
+            }
+            self.text_parser.consume_char();
+            return Some(pseudo_class);
+        }
+
+        Some(match name.as_str() {
+            "root" => PseudoClass::Root,
+            "empty" => PseudoClass::Empty,
+            "first-child" => PseudoClass::FirstChild,
+            "last-child" => PseudoClass::LastChild,
+            "only-child" => PseudoClass::OnlyChild,
+            "first-of-type" => PseudoClass::FirstOfType,
+            "last-of-type" => PseudoClass::LastOfType,
+            "only-of-type" => PseudoClass::OnlyOfType,
+            "checked" => PseudoClass::Checked,
+            "disabled" => PseudoClass::Disabled,
+            "enabled" => PseudoClass::Enabled,
+            "link" | "any-link" => PseudoClass::AnyLink,
+            "hover" | "active" | "focus" | "focus-within" | "focus-visible" | "visited"
+            | "target" => PseudoClass::UserAction(name),
+            _ => return None,
+        })
+    }
+
+    fn starts_with_pseudo_element(&self) -> bool {
+        // css2 pseudo-elements may still be written with a single colon
+        self.text_parser.starts_with("::")
+            || [":before", ":after", ":first-line", ":first-letter"]
+                .iter()
+                .any(|legacy| self.text_parser.starts_with(legacy))
+    }
+
+    // `None` for unknown pseudo-elements
+    fn parse_pseudo_element(&mut self) -> Option<PseudoElement> {
+        self.text_parser.consume_chars_while(|c| c == ':');
+        let name = self.parse_identifier().to_lowercase();
+        Some(match name.as_str() {
+            "before" => PseudoElement::Before,
+            "after" => PseudoElement::After,
+            "marker" => PseudoElement::Marker,
+            "first-line" => PseudoElement::FirstLine,
+            "first-letter" => PseudoElement::FirstLetter,
+            _ => return None,
+        })
+    }
+
+    fn parse_simple_selector(&mut self) -> Option<SimpleSelector> {
+        let mut selector = SimpleSelector {
+            tag_name: None,
+            id: None,
+            class: Vec::new(),
+            attributes: Vec::new(),
+            pseudo_classes: Vec::new(),
+            pseudo_element: None,
+        };
+
+        while !self.text_parser.eol() {
+            match self.text_parser.get_current_char() {
+                '#' => {
+                    // id
+                    self.text_parser.consume_char();
+                    selector.id = Some(self.parse_identifier());
+                }
+                '.' => {
+                    // class
+                    self.text_parser.consume_char();
+                    selector.class.push(self.parse_identifier());
+                }
+                '[' => {
+                    // attribute
+                    selector.attributes.push(self.parse_attribute_selector()?);
+                }
+                ':' if self.starts_with_pseudo_element() => {
+                    // pseudo-element
+                    selector.pseudo_element = Some(self.parse_pseudo_element()?);
+                }
+                ':' => {
+                    // pseudo-class
+                    selector.pseudo_classes.push(self.parse_pseudo_class()?);
+                }
+                '*' => {
+                    // universal selector
+                    self.text_parser.consume_char();
+                }
+                c if c.is_whitespace() => {
+                    // whitespace ends the compound selector
+                    break;
+                }
+                '{' | ',' | ')' | '>' | '+' | '~' => {
+                    // start the declaration, or a combinator
+                    break;
+                }
+                c if valid_identifier_char(c) => {
+                    // otherwise tag name
+                    selector.tag_name = Some(self.parse_identifier().to_lowercase());
+                }
+                _ => return None,
+            }
+        }
+
+        Some(selector)
+    }
+
+    fn parse_combinator(&mut self) -> Option<Combinator> {
+        let combinator = match self.text_parser.get_current_char() {
+            '>' => Combinator::Child,
+            '+' => Combinator::NextSibling,
+            '~' => Combinator::SubsequentSibling,
+            _ => return None,
+        };
+        self.text_parser.consume_char();
+        self.text_parser.remove_whitespaces();
+        Some(combinator)
+    }
+
+    fn parse_selector(&mut self) -> Option<Selector> {
+        let mut selector = Selector {
+            compounds: vec![self.parse_simple_selector()?],
+            combinators: Vec::new(),
+        };
+
+        loop {
+            let had_whitespace = !self
+                .text_parser
+                .consume_chars_while(|c| c.is_whitespace())
+                .is_empty();
+            if self.text_parser.eol() {
+                break;
+            }
+            let combinator = match self.parse_combinator() {
+                Some(combinator) => combinator,
+                None => match self.text_parser.get_current_char() {
+                    '{' | ',' | ')' => break,
+                    _ if had_whitespace => Combinator::Descendant,
+                    _ => return None,
+                },
+            };
+            selector.combinators.push(combinator);
+            selector.compounds.push(self.parse_simple_selector()?);
+        }
+
+        Some(selector)
+    }
+
+    fn parse_relative_selector(&mut self) -> Option<RelativeSelector> {
+        self.text_parser.remove_whitespaces();
+        let combinator = self.parse_combinator().unwrap_or(Combinator::Descendant);
+        Some(RelativeSelector {
+            combinator,
+            selector: self.parse_selector()?,
+        })
+    }
+
+    fn parse_relative_selector_list(&mut self) -> Option<Vec<RelativeSelector>> {
+        let mut selectors = vec![self.parse_relative_selector()?];
+        while self.next_char() == Some(',') {
+            self.text_parser.consume_char();
+            selectors.push(self.parse_relative_selector()?);
+        }
+        Some(selectors)
+    }
+
+    // a comma separated list of selectors, ending before a `{` or a `)`;
+    // `None` when any of them is invalid
+    fn parse_selector_list(&mut self) -> Option<Vec<Selector>> {
+        let mut selectors: Vec<Selector> = Vec::new();
+
+        while !self.text_parser.eol() {
+            self.text_parser.remove_whitespaces();
+            match self.text_parser.get_current_char() {
+                ',' => {
+                    self.text_parser.consume_char();
+                }
+                '{' | ')' => break,
+                _ => {
+                    selectors.push(self.parse_selector()?);
+                }
+            }
+        }
+
+        Some(selectors)
+    }
+
+    pub(crate) fn parse_selectors(&mut self) -> Option<Vec<Selector>> {
+        self.parse_selector_list()
+    }
+
+    // a style rule with an invalid selector is dropped as a whole
+    fn parse_rule(&mut self) -> Option<Rule> {
+        let selectors = self.parse_selectors();
+        self.text_parser.remove_whitespaces();
+        let selectors = match selectors {
+            Some(selectors) if self.next_char() == Some('{') => selectors,
+            _ => {
+                self.skip_rule();
+                return None;
+            }
+        };
+        self.text_parser.consume_char();
+        let declarations: Vec<Declaration> = self.parse_declarations();
+        if !self.text_parser.eol() {
+            assert!(self.text_parser.consume_char() == '}');
+        }
+
+        Some(Rule {
+            selectors,
+            declarations,
+        })
+    }
+
+    // the rest of an invalid style rule, up to the end of its block or of the
+    // enclosing one
+    fn skip_rule(&mut self) {
+        let mut depth = 0;
+        while let Some(c) = self.next_char() {
+            match c {
+                '"' | '\'' => {
+                    consume_string(&mut self.text_parser);
+                    continue;
+                }
+                '{' => depth += 1,
+                '}' if depth == 0 => return,
+                '}' if depth == 1 => {
+                    self.text_parser.consume_char();
+                    return;
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+            self.text_parser.consume_char();
+        }
+    }
+
+    // the raw text up to the next `;`, `{` or `}` outside of strings and
+    // parentheses, or up to the `}` closing the current block with `block`
+    fn parse_raw_text(&mut self, block: bool) -> String {
+        let mut text = String::new();
+        let mut depth = 0;
+        while !self.text_parser.eol() {
+            let c = self.text_parser.get_current_char();
+            match c {
+                '"' | '\'' => {
+                    text.push(self.text_parser.consume_char());
+                    // up to the closing quote, or the newline ending a bad string
+                    while let Some(next) = self
+                        .next_char()
+                        .filter(|next| !matches!(next, '\n' | '\r' | '\x0c'))
+                    {
+                        text.push(self.text_parser.consume_char());
+                        match next {
+                            '\\' if !self.text_parser.eol() => {
+                                text.push(self.text_parser.consume_char())
+                            }
+                            next if next == c => break,
+                            _ => {}
+                        }
+                    }
+                    continue;
+                }
+                ';' | '{' if depth == 0 && !block => break,
+                '}' if depth == 0 => break,
+                '(' | '{' => depth += 1,
+                ')' | '}' => depth -= 1,
+                _ => {}
+            }
+            text.push(self.text_parser.consume_char());
+        }
+        text.trim().to_string()
+    }
+
+    // an unsupported at-rule, up to its closing `;` or the end of its block
+    fn parse_at_rule(&mut self, name: String) -> AtRule {
+        let prelude = self.parse_raw_text(false);
+        let block = match self.text_parser.eol() {
+            true => None,
+            false => match self.text_parser.get_current_char() {
+                '{' => {
+                    self.text_parser.consume_char();
+                    let block = self.parse_raw_text(true);
+                    if !self.text_parser.eol() {
+                        self.text_parser.consume_char();
+                    }
+                    Some(block)
+                }
+                ';' => {
+                    self.text_parser.consume_char();
+                    None
+                }
+                _ => None,
+            },
+        };
+        AtRule {
+            name,
+            prelude,
+            block,
+        }
+    }
+
+    fn parse_media_rule(&mut self) -> Option<MediaRule> {
+        let prelude = self.parse_block_prelude()?;
+        Some(MediaRule {
+            queries: MediaQueryList::parse(&prelude),
+            rules: self.parse_block_rules(),
+        })
+    }
+
+    // `url(...)` or a string
+    fn parse_url(&mut self) -> Option<String> {
+        self.text_parser.remove_whitespaces();
+        if self.text_parser.eol() {
+            return None;
+        }
+        if let '"' | '\'' = self.text_parser.get_current_char() {
+            return self.parse_string();
+        }
+
+        let function = self.parse_identifier();
+        if !function.eq_ignore_ascii_case("url")
+            || self.text_parser.eol()
+            || self.text_parser.get_current_char() != '('
+        {
+            return None;
+        }
+        self.text_parser.consume_char();
+        self.text_parser.remove_whitespaces();
+        let url = match !self.text_parser.eol() && self.text_parser.get_current_char() != ')' {
+            true => match self.text_parser.get_current_char() {
+                '"' | '\'' => self.parse_string()?,
+                _ => self
+                    .text_parser
+                    .consume_chars_while(|c| c != ')' && !c.is_whitespace()),
+            },
+            false => String::new(),
+        };
+        self.text_parser.remove_whitespaces();
+        match !self.text_parser.eol() && self.text_parser.consume_char() == ')' {
+            true => Some(url),
+            false => None,
+        }
     }
-}
-
-impl Declaration {
-    fn new(name: String, value: String) -> Declaration {
-        let first_char = value.chars().next().unwrap();
 
-        // test first character to see which type of value we'll return
-        if first_char == '#' {
-            Declaration {
-                name,
-                value: Value::ColorValue(Color::from_hex_code(value)),
+    // a malformed import is dropped, up to the end of its at-rule
+    fn parse_import_rule(&mut self) -> Option<ImportRule> {
+        let url = match self.parse_url() {
+            Some(url) => url,
+            None => {
+                self.parse_at_rule("import".to_string());
+                return None;
             }
-        } else if first_char.is_digit(10) {
-            let mut qty = String::from("");
-            // let mut split_idx = 0;
+        };
+        let prelude = self.parse_raw_value();
+        if !self.text_parser.eol() && self.text_parser.get_current_char() == ';' {
+            self.text_parser.consume_char();
+        }
 
-            for c in value.chars() {
-                if c.is_digit(10) {
-                    qty.push(c);
-                    // split_idx += 1;
-                } else {
-                    break;
-                }
+        let prelude = prelude.trim();
+        let lowercase = prelude.to_lowercase();
+        let (layer, queries) = if lowercase.starts_with("layer(") {
+            let end = prelude.find(')')?;
+            let name = prelude["layer(".len()..end]
+                .split('.')
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join(".");
+            // the name is a dotted list of identifiers
+            let identifiers = name.split('.').all(|part| {
+                !part.is_empty() && !part.contains(|c: char| c.is_whitespace() || c == ',')
+            });
+            if !identifiers {
+                return None;
             }
+            (Some(Some(name)), &prelude[end + 1..])
+        } else if lowercase
+            .strip_prefix("layer")
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            (Some(None), &prelude["layer".len()..])
+        } else {
+            (None, prelude)
+        };
 
-            // TODO: support multiple units
-            // let unit = &value[split_idx..];
+        Some(ImportRule {
+            url,
+            layer,
+            queries: MediaQueryList::parse(queries),
+            stylesheet: None,
+        })
+    }
 
-            Declaration {
-                name,
-                value: Value::Length(qty.parse::<f32>().unwrap(), Unit::Px),
+    // the prelude of an at-rule with a block, and the opening `{` of the block;
+    // `None` when the at-rule ends without one, which makes it invalid
+    fn parse_block_prelude(&mut self) -> Option<String> {
+        let prelude = self.parse_raw_text(false);
+        match self.next_char() {
+            Some('{') => {
+                self.text_parser.consume_char();
+                Some(prelude)
             }
-        } else {
-            Declaration {
-                name,
-                value: Value::Keyword(value),
+            Some(';') => {
+                self.text_parser.consume_char();
+                None
             }
+            _ => None,
         }
     }
-}
 
-impl CSSParser {
-    pub fn new(input: String) -> CSSParser {
-        let text_parser = TextParser::new(input);
-        CSSParser { text_parser }
+    // the closing `}` of a block, which the end of the input closes too
+    fn close_block(&mut self) {
+        if self.next_char() == Some('}') {
+            self.text_parser.consume_char();
+        }
     }
 
-    fn parse_declarations(&mut self) -> Vec<Declaration> {
-        // assume that the input has been sanitized before
-        let mut declarations: Vec<Declaration> = Vec::new();
+    // the rules of a block, after its opening `{`
+    fn parse_block_rules(&mut self) -> Vec<CssRule> {
+        let rules = self.parse_rule_list(false);
+        self.close_block();
+        rules
+    }
 
-        while !self.text_parser.eol() {
-            match self.text_parser.get_current_char() {
-                '}' => break,
-                ' ' => {
+    fn parse_supports_rule(&mut self) -> Option<SupportsRule> {
+        let prelude = self.parse_block_prelude()?;
+        Some(SupportsRule {
+            condition: SupportsCondition::parse(&prelude),
+            rules: self.parse_block_rules(),
+        })
+    }
+
+    fn parse_layer_rule(&mut self) -> LayerRule {
+        let prelude = self.parse_raw_text(false);
+        let names = prelude
+            .split(',')
+            .map(|name| name.split('.').map(str::trim).collect::<Vec<_>>().join("."))
+            .filter(|name| !name.is_empty())
+            .collect();
+        let rules = match self.text_parser.eol() {
+            true => None,
+            false => match self.text_parser.get_current_char() {
+                '{' => {
                     self.text_parser.consume_char();
+                    Some(self.parse_block_rules())
                 }
-                _ => {
-                    let name = self
-                        .text_parser
-                        .consume_sequence(|c| c != ':', |c| c == ' ', true);
-
-                    let value = self
-                        .text_parser
-                        .consume_sequence(|c| c != ';', |c| c == ' ', true);
+                ';' => {
+                    self.text_parser.consume_char();
+                    None
+                }
+                _ => None,
+            },
+        };
+        LayerRule { names, rules }
+    }
 
-                    declarations.push(Declaration::new(name, value));
+    // the offsets of a keyframe selector, `None` if one of them is invalid
+    fn parse_keyframe_offsets(selector: &str) -> Option<Vec<f32>> {
+        selector
+            .split(',')
+            .map(|offset| match offset.trim().to_lowercase().as_str() {
+                "from" => Some(0.0),
+                "to" => Some(1.0),
+                offset => {
+                    let percentage: f32 = offset.strip_suffix('%')?.parse().ok()?;
+                    (0.0..=100.0)
+                        .contains(&percentage)
+                        .then_some(percentage / 100.0)
                 }
+            })
+            .collect()
+    }
+
+    fn parse_keyframes_rule(&mut self) -> Option<KeyframesRule> {
+        let mut prelude = CSSParser::new(self.parse_block_prelude()?);
+        let name = match prelude.next_char() {
+            Some('"' | '\'') => prelude.parse_string(),
+            _ => Some(prelude.parse_identifier()),
+        };
+
+        let mut keyframes = Vec::new();
+        loop {
+            self.text_parser.remove_whitespaces();
+            if self.text_parser.eol() || self.text_parser.get_current_char() == '}' {
+                break;
+            }
+            let selector = match self.parse_block_prelude() {
+                Some(selector) => selector,
+                None => continue,
+            };
+            let declarations = self.parse_declarations();
+            self.close_block();
+
+            // important declarations are ignored in keyframes
+            if let Some(offsets) = CSSParser::parse_keyframe_offsets(&selector) {
+                keyframes.push(Keyframe {
+                    offsets,
+                    declarations: declarations
+                        .into_iter()
+                        .filter(|declaration| !declaration.important)
+                        .map(|declaration| (declaration.name, declaration.value))
+                        .collect(),
+                });
             }
         }
-        declarations
+        self.close_block();
+
+        Some(KeyframesRule {
+            name: name?,
+            keyframes,
+        })
     }
 
-    fn parse_simple_selector(&mut self) -> SimpleSelector {
-        let mut selector = SimpleSelector {
-            tag_name: None,
-            id: None,
-            class: Vec::new(),
-        };
+    fn parse_font_face_rule(&mut self) -> Option<FontFaceRule> {
+        self.parse_block_prelude()?;
 
-        while !self.text_parser.eol() {
+        let mut descriptors = Vec::new();
+        loop {
+            self.text_parser.remove_whitespaces();
+            if self.text_parser.eol() {
+                break;
+            }
             match self.text_parser.get_current_char() {
-                '#' => {
-                    // id
+                '}' => {
                     self.text_parser.consume_char();
-                    selector.id = Some(self.text_parser.consume_sequence(
-                        |c| (c != ',' && c != '.' && c != '{'),
-                        |c| c == ' ',
-                        false,
-                    ));
+                    break;
                 }
-                '.' => {
-                    // class
+                ';' => {
                     self.text_parser.consume_char();
-                    selector.class.push(self.text_parser.consume_sequence(
-                        |c| c != ',' && c != '{',
-                        |c| c == ' ',
-                        false,
-                    ));
-                }
-                ' ' => {
-                    // space separation
-                    self.text_parser.consume_char();
-                }
-                '{' | ',' => {
-                    // start the declaration
-                    break;
                 }
                 _ => {
-                    // otherwise tag name
-                    selector.tag_name = Some(
-                        self.text_parser
-                            .consume_chars_while(|c| c.is_alphanumeric()),
-                    );
+                    let (name, value) = self.parse_raw_declaration();
+                    descriptors.push((name.to_lowercase(), value));
                 }
             }
         }
-
-        selector
+        Some(FontFaceRule { descriptors })
     }
 
-    fn parse_selectors(&mut self) -> Vec<SimpleSelector> {
-        let mut selectors: Vec<SimpleSelector> = Vec::new();
+    fn parse_page_rule(&mut self) -> Option<PageRule> {
+        let prelude = self.parse_block_prelude()?;
+        let selectors = prelude
+            .split(',')
+            .map(str::trim)
+            .filter(|selector| !selector.is_empty())
+            .map(|selector| {
+                let mut parts = selector.split(':').map(|part| part.trim().to_lowercase());
+                PageSelector {
+                    name: parts.next().filter(|name| !name.is_empty()),
+                    pseudo_classes: parts.collect(),
+                }
+            })
+            .collect();
 
-        while !self.text_parser.eol() {
+        let mut declarations = Vec::new();
+        let mut margin_boxes = Vec::new();
+        loop {
+            self.text_parser.remove_whitespaces();
+            if self.text_parser.eol() {
+                break;
+            }
             match self.text_parser.get_current_char() {
-                ',' => {
+                '}' => {
+                    self.text_parser.consume_char();
+                    break;
+                }
+                ';' => {
+                    self.text_parser.consume_char();
+                }
+                // margin boxes, `@top-center { content: "title" }`
+                '@' => {
                     self.text_parser.consume_char();
-                    self.text_parser.remove_whitespaces();
+                    let name = self.parse_identifier().to_lowercase();
+                    if self.parse_block_prelude().is_some() {
+                        margin_boxes.push((name, self.parse_declarations()));
+                        self.close_block();
+                    }
                 }
-                '{' => break,
                 _ => {
-                    selectors.push(self.parse_simple_selector());
+                    let (name, value) = self.parse_raw_declaration();
+                    declarations.extend(Declaration::new(name, value, self.validate));
                 }
             }
         }
 
-        selectors
-    }
-
-    fn parse_rule(&mut self) -> Rule {
-        let selectors: Vec<SimpleSelector> = self.parse_selectors();
-        self.text_parser.remove_whitespaces();
-        assert!(self.text_parser.consume_char() == '{');
-        let declarations: Vec<Declaration> = self.parse_declarations();
-        assert!(self.text_parser.consume_char() == '}');
-
-        Rule {
+        Some(PageRule {
             selectors,
             declarations,
-        }
+            margin_boxes,
+        })
     }
 
-    pub fn parse_stylesheet(&mut self) -> Stylesheet {
-        let mut rules: Vec<Rule> = Vec::new();
-        while !self.text_parser.eol() {
-            match self.text_parser.get_current_char() {
-                '\n' => {
-                    self.text_parser.consume_char();
+    // rules up to the end of the input or of the enclosing block
+    fn parse_rule_list(&mut self, top_level: bool) -> Vec<CssRule> {
+        let mut rules: Vec<CssRule> = Vec::new();
+        // `@import` is only valid before any other rule of the stylesheet
+        let mut imports_allowed = top_level;
+        loop {
+            self.text_parser.remove_whitespaces();
+            if self.text_parser.eol() || self.text_parser.get_current_char() == '}' {
+                break;
+            }
+            if self.text_parser.get_current_char() != '@' {
+                imports_allowed = false;
+                rules.extend(self.parse_rule().map(CssRule::Style));
+                continue;
+            }
+
+            self.text_parser.consume_char();
+            let name = self.parse_identifier().to_lowercase();
+            match name.as_str() {
+                "import" if imports_allowed => {
+                    rules.extend(self.parse_import_rule().map(CssRule::Import))
+                }
+                // misplaced imports are invalid
+                "charset" | "import" => {
+                    self.parse_at_rule(name);
+                }
+                "media" => {
+                    imports_allowed = false;
+                    rules.extend(self.parse_media_rule().map(CssRule::Media));
+                }
+                "supports" => {
+                    imports_allowed = false;
+                    rules.extend(self.parse_supports_rule().map(CssRule::Supports));
+                }
+                "layer" => {
+                    // layer statements may come before imports
+                    let layer = self.parse_layer_rule();
+                    imports_allowed &= layer.rules.is_none();
+                    rules.push(CssRule::Layer(layer));
+                }
+                "keyframes" | "-webkit-keyframes" => {
+                    imports_allowed = false;
+                    rules.extend(self.parse_keyframes_rule().map(CssRule::Keyframes));
+                }
+                "font-face" => {
+                    imports_allowed = false;
+                    rules.extend(self.parse_font_face_rule().map(CssRule::FontFace));
+                }
+                "page" => {
+                    imports_allowed = false;
+                    rules.extend(self.parse_page_rule().map(CssRule::Page));
                 }
                 _ => {
-                    self.text_parser.remove_whitespaces();
-                    rules.push(self.parse_rule());
+                    imports_allowed = false;
+                    rules.push(CssRule::Unknown(self.parse_at_rule(name)));
                 }
             }
         }
-        Stylesheet { rules }
+        rules
+    }
+
+    pub fn parse_stylesheet(&mut self) -> Stylesheet {
+        Stylesheet {
+            rules: self.parse_rule_list(true),
+            origin: Origin::Author,
+            url: None,
+        }
     }
 }
 
@@ -254,23 +2543,629 @@ mod tests {
         let mut css_parser = CSSParser::new(test_input.to_string());
         let test_declarations = css_parser.parse_declarations();
         let decl1 = Declaration {
-            name: "margin".to_string(),
+            name: "margin-top".to_string(),
             value: Value::Keyword("auto".to_string()),
+            important: false,
+            shorthand: None,
         };
 
         let decl2 = Declaration {
             name: "titi".to_string(),
             value: Value::Keyword("toto".to_string()),
+            important: false,
+            shorthand: None,
+        };
+        assert!(test_declarations.first().unwrap().name == decl1.name);
+        // the shorthand is expanded into its four longhands, and the unknown
+        // property is dropped
+        assert_eq!(test_declarations.len(), 4);
+        assert!(test_declarations
+            .iter()
+            .all(|declaration| declaration.name != decl2.name));
+    }
+
+    #[test]
+    fn test_custom_property_declarations() {
+        let test_input = "--Main-Color: #c00 !important; --empty:; color: var(--Main-Color);";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let declarations = css_parser.parse_declarations();
+        assert_eq!(declarations[0].name, "--Main-Color");
+        assert_eq!(
+            declarations[0].value,
+            Value::Tokens(vec![Token::Hash("c00".to_string())])
+        );
+        assert!(declarations[0].important);
+        assert_eq!(declarations[1].value, Value::Tokens(Vec::new()));
+        assert_eq!(
+            declarations[2].value,
+            Value::Tokens(vec![
+                Token::Function("var".to_string()),
+                Token::Ident("--Main-Color".to_string()),
+                Token::CloseParen,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_numeric_and_math_values() {
+        let test_input = "margin-top: 1.5em; width: calc(100% - 2 * 16px); height: calc(1px + 2);
+            padding-top: calc(2 * 3px); opacity: 50%; z-index: 2; top: 3foo";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let values: Vec<(String, Value)> = css_parser
+            .parse_declarations()
+            .into_iter()
+            .map(|declaration| (declaration.name, declaration.value))
+            .collect();
+        // invalid math functions and units drop the declaration
+        assert_eq!(values.len(), 5);
+        assert_eq!(values[0].1, Value::Length(1.5, Unit::Em));
+        assert!(matches!(values[1].1, Value::Calc(CalcNode::Sum(_))));
+        assert_eq!(values[2].1, Value::Length(6.0, Unit::Px));
+        assert_eq!(values[3].1, Value::Percentage(50.0));
+        assert_eq!(values[4].1, Value::Number(2.0));
+
+        let context = LengthContext {
+            percentage_basis: 400.0,
+            ..LengthContext::default()
+        };
+        assert_eq!(values[0].1.to_px(&context), Some(24.0));
+        assert_eq!(values[1].1.to_px(&context), Some(368.0));
+        assert_eq!(values[4].1.to_px(&context), None);
+    }
+
+    #[test]
+    fn test_non_length_dimensions() {
+        let test_input = "transition: all .3s ease; animation: spin 1S infinite;
+            transition-duration: 200ms; rotate: 0.25turn; width: 2s";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let values: Vec<(String, Value)> = css_parser
+            .parse_declarations()
+            .into_iter()
+            .map(|declaration| (declaration.name, declaration.value))
+            .collect();
+        // dimensions are kept, and still invalid where a length is expected
+        assert_eq!(values.len(), 4);
+        let keyword = |keyword: &str| Value::Keyword(keyword.to_string());
+        assert_eq!(
+            values[0].1,
+            Value::List(vec![
+                keyword("all"),
+                Value::Dimension(0.3, "s".to_string()),
+                keyword("ease"),
+            ])
+        );
+        assert_eq!(
+            values[1].1,
+            Value::List(vec![
+                keyword("spin"),
+                Value::Dimension(1.0, "s".to_string()),
+                keyword("infinite"),
+            ])
+        );
+        assert_eq!(values[2].1, Value::Dimension(200.0, "ms".to_string()));
+        assert_eq!(values[3].1, Value::Dimension(0.25, "turn".to_string()));
+        assert_eq!(values[3].1.to_px(&LengthContext::default()), None);
+    }
+
+    #[test]
+    fn test_content_and_counter_values() {
+        let value = |name: &str, value: &str| Declaration::parse_value(name, value.to_string());
+        let keyword = |keyword: &str| Some(Value::Keyword(keyword.to_string()));
+        assert_eq!(value("content", "inherit"), keyword("inherit"));
+        assert_eq!(value("content", "None"), keyword("None"));
+        assert_eq!(
+            value(
+                "content",
+                "open-quote url(a.png) counters(a, \".\") NO-CLOSE-QUOTE"
+            ),
+            Some(Value::Content(vec![
+                ContentItem::OpenQuote,
+                ContentItem::Url("a.png".to_string()),
+                ContentItem::Counters("a".to_string(), ".".to_string(), "decimal".to_string()),
+                ContentItem::NoCloseQuote,
+            ]))
+        );
+        for invalid in [
+            "counter()",
+            "attr()",
+            "counters(a)",
+            "counters(a, b)",
+            "counter(a, \"b\")",
+            "image(a.png)",
+            "url(a.png",
+            "quote",
+            "\"a\" +",
+            "attr(a",
+        ] {
+            assert_eq!(value("content", invalid), None, "{}", invalid);
+        }
+
+        assert_eq!(
+            value("counter-reset", "a -2 b +3 c"),
+            Some(Value::CounterList(vec![
+                ("a".to_string(), -2),
+                ("b".to_string(), 3),
+                ("c".to_string(), 0),
+            ]))
+        );
+        assert_eq!(value("counter-increment", "INHERIT"), keyword("INHERIT"));
+        for invalid in ["item 1.5", "item !", "2", "item 1px", "a, b"] {
+            assert_eq!(value("counter-reset", invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        let value = |name: &str, value: &str| Declaration::parse_value(name, value.to_string());
+        let string = |text: &str| Some(Value::Content(vec![ContentItem::String(text.to_string())]));
+        assert_eq!(value("content", "\"\\201C\""), string("\u{201c}"));
+        assert_eq!(value("content", "'\\41 B\\\"'"), string("AB\""));
+        assert_eq!(
+            value("content", "\"\\0 \\110000\""),
+            string("\u{fffd}\u{fffd}")
+        );
+        assert_eq!(value("content", "\"a\\\nb\""), string("ab"));
+        // the end of the input closes a string
+        assert_eq!(value("content", "\"open"), string("open"));
+        assert_eq!(value("content", "\"a\\"), string("a"));
+
+        // strings can't span lines, and a bad string drops its declaration
+        let parse = |css: &str| CSSParser::new(css.to_string()).parse_stylesheet().rules;
+        match &parse("p { content: \"a\n; margin-top: 0 }")[..] {
+            [CssRule::Style(p)] => {
+                assert_eq!(p.declarations.len(), 1);
+                assert_eq!(p.declarations[0].name, "margin-top");
+            }
+            other => panic!("unexpected rules {:?}", other),
+        }
+        assert!(parse("a[href=\"x] {}").is_empty());
+        match &parse("b { color: \"red }")[..] {
+            [CssRule::Style(b)] => assert!(b.declarations.is_empty()),
+            other => panic!("unexpected rules {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_declaration_values_with_strings() {
+        let test_input = "content: \"a; b\" attr(data-label) counter(item, upper-roman) \"}\";
+            counter-reset: section 2 figure;
+            counter-increment: item\n}";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let declarations = css_parser.parse_declarations();
+        assert_eq!(declarations.len(), 3);
+        assert_eq!(
+            declarations[0].value,
+            Value::Content(vec![
+                ContentItem::String("a; b".to_string()),
+                ContentItem::Attr("data-label".to_string()),
+                ContentItem::Counter("item".to_string(), "upper-roman".to_string()),
+                ContentItem::String("}".to_string()),
+            ])
+        );
+        assert_eq!(
+            declarations[1].value,
+            Value::CounterList(vec![("section".to_string(), 2), ("figure".to_string(), 0)])
+        );
+        assert_eq!(
+            declarations[2].value,
+            Value::CounterList(vec![("item".to_string(), 1)])
+        );
+    }
+
+    #[test]
+    fn test_track_list_parsing() {
+        let test_input =
+            "grid-template-columns: [a] 100px repeat(auto-fill, minmax(10%, 1fr)) [b c];
+            grid-template-rows: repeat(2, fit-content(2em) auto);
+            grid-auto-rows: min-content;
+            grid-auto-columns: [a] 1fr;
+            grid-template-rows: minmax(1fr, 2fr);
+            grid-template-columns: repeat(auto-fill, 1px) repeat(auto-fit, 1px)";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let declarations = css_parser.parse_declarations();
+        // names in auto tracks, flexible minimums and two automatic repetitions
+        // are invalid
+        assert_eq!(declarations.len(), 3);
+        let names = |names: &[&str]| {
+            TrackListItem::LineNames(names.iter().map(|name| name.to_string()).collect())
         };
-        assert!(test_declarations.get(0).unwrap().name == decl1.name);
-        assert!(test_declarations.get(1).unwrap().name == decl2.name);
+        assert_eq!(
+            declarations[0].value,
+            Value::TrackList(vec![
+                names(&["a"]),
+                TrackListItem::Track(TrackSize::Breadth(TrackBreadth::Length(Value::Length(
+                    100.0,
+                    Unit::Px
+                )))),
+                TrackListItem::Repeat(
+                    RepeatCount::AutoFill,
+                    vec![TrackListItem::Track(TrackSize::MinMax(
+                        TrackBreadth::Length(Value::Percentage(10.0)),
+                        TrackBreadth::Flex(1.0)
+                    ))]
+                ),
+                names(&["b", "c"]),
+            ])
+        );
+        assert_eq!(
+            declarations[1].value,
+            Value::TrackList(vec![TrackListItem::Repeat(
+                RepeatCount::Count(2),
+                vec![
+                    TrackListItem::Track(TrackSize::FitContent(Value::Length(2.0, Unit::Em))),
+                    TrackListItem::Track(TrackSize::Breadth(TrackBreadth::Auto)),
+                ]
+            )])
+        );
+        assert_eq!(
+            declarations[2].value,
+            Value::TrackList(vec![TrackListItem::Track(TrackSize::Breadth(
+                TrackBreadth::MinContent
+            ))])
+        );
+    }
+
+    #[test]
+    fn test_pseudo_element_parsing() {
+        let selector = parse_selector("li.note::before");
+        assert_eq!(selector.pseudo_element(), Some(PseudoElement::Before));
+        assert_eq!(selector.specificity(), (0, 1, 2));
+
+        assert_eq!(
+            parse_selector("p:first-line").pseudo_element(),
+            Some(PseudoElement::FirstLine)
+        );
+        assert_eq!(
+            parse_selector("li::marker").pseudo_element(),
+            Some(PseudoElement::Marker)
+        );
+        assert_eq!(parse_selector("a:first-child").pseudo_element(), None);
+
+        // unknown pseudo-elements drop only their rule
+        let test_input = "::selection { color: red; } input::placeholder { color: red; }
+            input::-webkit-inner-spin-button, p { color: red; } p::after { content: 'x'; }";
+        let stylesheet = CSSParser::new(test_input.to_string()).parse_stylesheet();
+        match &stylesheet.rules[..] {
+            [CssRule::Style(after)] => {
+                assert_eq!(
+                    after.selectors[0].pseudo_element(),
+                    Some(PseudoElement::After)
+                )
+            }
+            other => panic!("unexpected rules {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_important_declarations() {
+        let test_input =
+            "color: #cc0000 ! IMPORTANT; margin-top: auto!important; padding-top: 10px";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let declarations = css_parser.parse_declarations();
+        assert!(declarations[0].important);
+        assert_eq!(
+            declarations[0].value,
+            Value::ColorValue(Color::rgb(0xcc, 0, 0))
+        );
+        assert!(declarations[1].important);
+        assert_eq!(declarations[1].value, Value::Keyword("auto".to_string()));
+        assert!(!declarations[2].important);
+    }
+
+    #[test]
+    fn test_id_specificity() {
+        // the length of the id does not matter
+        let long_id = parse_selector("#verylongid").specificity();
+        let id_and_class = parse_selector("#a.b").specificity();
+        assert_eq!(long_id, (1, 0, 0));
+        assert_eq!(id_and_class, (1, 1, 0));
+        assert!(long_id < id_and_class);
+    }
+
+    #[test]
+    fn test_media_rule_parsing() {
+        let test_input = "p { margin: auto; }
+        @media screen and (max-width: 600px) {
+            p { margin: 0px; }
+            @media (orientation: portrait) { h1 { margin: 1px; } }
+        }
+        @unknown-rule foo { bar { baz: 1px; } }
+        @charset \"utf-8\";
+        h1 { padding: 10px; }";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let stylesheet = css_parser.parse_stylesheet();
+        assert_eq!(stylesheet.rules.len(), 4);
+
+        match &stylesheet.rules[1] {
+            CssRule::Media(media) => {
+                assert_eq!(media.queries.queries.len(), 1);
+                assert_eq!(media.rules.len(), 2);
+                assert!(matches!(media.rules[1], CssRule::Media(_)));
+            }
+            other => panic!("expected a media rule, got {:?}", other),
+        }
+        assert!(matches!(stylesheet.rules[2], CssRule::Unknown(_)));
+        assert!(matches!(stylesheet.rules[3], CssRule::Style(_)));
+    }
+
+    #[test]
+    fn test_unterminated_media_rules() {
+        let parse = |css: &str| CSSParser::new(css.to_string()).parse_stylesheet().rules;
+        assert!(parse("@media screen").is_empty());
+        match &parse("@media print; p { margin: 0 }")[..] {
+            [CssRule::Style(_)] => {}
+            other => panic!("unexpected rules {:?}", other),
+        }
+        match &parse("@media screen { p { margin: 0 } @media print { a { margin: 0 }")[..] {
+            [CssRule::Media(media)] => match &media.rules[..] {
+                [CssRule::Style(_), CssRule::Media(print)] => assert_eq!(print.rules.len(), 1),
+                other => panic!("unexpected rules {:?}", other),
+            },
+            other => panic!("unexpected rules {:?}", other),
+        }
+        // a media rule ending at the end of its parent block is dropped
+        match &parse("@supports (a: b) { @media screen } p { margin: 0 }")[..] {
+            [CssRule::Supports(supports), CssRule::Style(_)] => assert!(supports.rules.is_empty()),
+            other => panic!("unexpected rules {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_at_rules_without_blocks() {
+        let parse = |css: &str| CSSParser::new(css.to_string()).parse_stylesheet().rules;
+        // at-rules ending before their block are invalid
+        for css in [
+            "@supports (display: grid)",
+            "@font-face",
+            "@page :first",
+            "@keyframes spin",
+        ] {
+            assert!(parse(css).is_empty(), "{}", css);
+        }
+        match &parse("@supports (display: grid); @font-face; p { margin: 0 }")[..] {
+            [CssRule::Style(_)] => {}
+            other => panic!("unexpected rules {:?}", other),
+        }
+
+        // the end of the input closes blocks
+        match &parse("@supports (display: grid) { p { margin: 0 }")[..] {
+            [CssRule::Supports(supports)] => assert_eq!(supports.rules.len(), 1),
+            other => panic!("unexpected rules {:?}", other),
+        }
+        match &parse("@layer a {")[..] {
+            [CssRule::Layer(layer)] => assert!(layer.rules.as_ref().is_some_and(Vec::is_empty)),
+            other => panic!("unexpected rules {:?}", other),
+        }
+        match &parse("@font-face { font-family: a")[..] {
+            [CssRule::FontFace(font_face)] => assert_eq!(font_face.descriptors.len(), 1),
+            other => panic!("unexpected rules {:?}", other),
+        }
+        match &parse("@page { margin: 1in; @top-center { content: \"a\"")[..] {
+            [CssRule::Page(page)] => {
+                assert_eq!(page.declarations.len(), 4);
+                assert_eq!(page.margin_boxes.len(), 1);
+            }
+            other => panic!("unexpected rules {:?}", other),
+        }
+        match &parse("@page { @top-center; margin: 1in }")[..] {
+            [CssRule::Page(page)] => assert!(page.margin_boxes.is_empty()),
+            other => panic!("unexpected rules {:?}", other),
+        }
+        match &parse("@keyframes 'spin' { from { opacity: 0 } 50%; to { opacity: 1")[..] {
+            [CssRule::Keyframes(keyframes)] => {
+                assert_eq!(keyframes.name, "spin");
+                assert_eq!(keyframes.keyframes.len(), 2);
+            }
+            other => panic!("unexpected rules {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_at_rule_parsing() {
+        let test_input = "@layer base, theme.dark;
+        @supports (display: flex) and (not (float: nope)) { p { margin: 0px; } }
+        @layer { p { margin: 1px; } }
+        @keyframes 'fade' {
+            from { opacity: 0; }
+            50%, 75% { opacity: 0.5; color: red !important; }
+            to { opacity: 1; }
+            120% { opacity: 2; }
+        }
+        @font-face {
+            font-family: \"Open Sans\";
+            src: local(\"Open Sans\"), url(fonts/open.woff2) format(\"woff2\"), url('open.ttf');
+        }
+        @page :first { margin: 1in; @top-center { content: \"title\"; } size: a4; }
+        @namespace svg url(http://www.w3.org/2000/svg);
+        @counter-style thumbs { symbols: \"👍\"; suffix: \" \"; }";
+        let stylesheet = CSSParser::new(test_input.to_string()).parse_stylesheet();
+        assert_eq!(stylesheet.rules.len(), 8);
+
+        match (&stylesheet.rules[0], &stylesheet.rules[2]) {
+            (CssRule::Layer(statement), CssRule::Layer(block)) => {
+                assert_eq!(statement.names, vec!["base", "theme.dark"]);
+                assert!(statement.rules.is_none());
+                assert!(block.names.is_empty());
+                assert_eq!(block.rules.as_ref().map(|rules| rules.len()), Some(1));
+            }
+            other => panic!("expected layer rules, got {:?}", other),
+        }
+        match &stylesheet.rules[1] {
+            CssRule::Supports(supports) => {
+                assert!(supports.condition.matches());
+                assert_eq!(supports.rules.len(), 1);
+            }
+            other => panic!("expected a supports rule, got {:?}", other),
+        }
+        match &stylesheet.rules[3] {
+            CssRule::Keyframes(keyframes) => {
+                assert_eq!(keyframes.name, "fade");
+                let offsets: Vec<&[f32]> = keyframes
+                    .keyframes
+                    .iter()
+                    .map(|keyframe| keyframe.offsets.as_slice())
+                    .collect();
+                assert_eq!(offsets, vec![&[0.0][..], &[0.5, 0.75], &[1.0]]);
+                assert_eq!(
+                    keyframes.keyframes[1].declarations,
+                    vec![(String::from("opacity"), Value::Number(0.5))]
+                );
+            }
+            other => panic!("expected a keyframes rule, got {:?}", other),
+        }
+        match &stylesheet.rules[4] {
+            CssRule::FontFace(font_face) => {
+                assert_eq!(font_face.family(), Some(String::from("Open Sans")));
+                assert_eq!(
+                    font_face.sources(),
+                    vec![
+                        FontSource::Local(String::from("Open Sans")),
+                        FontSource::Url(
+                            String::from("fonts/open.woff2"),
+                            Some(String::from("woff2"))
+                        ),
+                        FontSource::Url(String::from("open.ttf"), None),
+                    ]
+                );
+            }
+            other => panic!("expected a font-face rule, got {:?}", other),
+        }
+        match &stylesheet.rules[5] {
+            CssRule::Page(page) => {
+                assert_eq!(page.selectors.len(), 1);
+                assert!(page.selectors[0].matches(1));
+                assert!(!page.selectors[0].matches(2));
+                assert_eq!(page.declarations.len(), 5);
+                assert_eq!(page.margin_boxes.len(), 1);
+                assert_eq!(page.margin_boxes[0].0, "top-center");
+            }
+            other => panic!("expected a page rule, got {:?}", other),
+        }
+        let namespaces: Vec<&AtRule> = stylesheet.at_rules("namespace").collect();
+        assert_eq!(
+            namespaces,
+            vec![&AtRule {
+                name: String::from("namespace"),
+                prelude: String::from("svg url(http://www.w3.org/2000/svg)"),
+                block: None,
+            }]
+        );
+        let counter_style = stylesheet.at_rules("counter-style").next().unwrap();
+        assert_eq!(
+            counter_style.block.as_deref(),
+            Some("symbols: \"👍\"; suffix: \" \";")
+        );
+    }
+
+    #[test]
+    fn test_supported_selectors() {
+        assert!(is_supported_selector("a > b, p:nth-child(2n of .x)"));
+        assert!(is_supported_selector("[title='a:b{']::before"));
+        assert!(!is_supported_selector("a:unknown"));
+        assert!(!is_supported_selector("p::nope"));
+        assert!(!is_supported_selector("a & b"));
+        assert!(!is_supported_selector(""));
+    }
+
+    #[test]
+    fn test_import_rule_parsing() {
+        let test_input = "@charset \"utf-8\";
+        @import url(\"base.css\") screen;
+        @import 'print.css' print, (max-width: 600px);
+        @import url(theme/dark.css);
+        p { margin: auto; }
+        @import \"ignored.css\";";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let stylesheet = css_parser.parse_stylesheet();
+        assert_eq!(stylesheet.rules.len(), 4);
+
+        let imports: Vec<&ImportRule> = stylesheet
+            .rules
+            .iter()
+            .filter_map(|rule| match rule {
+                CssRule::Import(import) => Some(import),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(imports.len(), 3);
+        assert_eq!(imports[0].url, "base.css");
+        assert_eq!(imports[0].queries.queries.len(), 1);
+        assert_eq!(imports[1].url, "print.css");
+        assert_eq!(imports[1].queries.queries.len(), 2);
+        assert_eq!(imports[2].url, "theme/dark.css");
+        assert!(imports[2].queries.queries.is_empty());
+    }
+
+    #[test]
+    fn test_import_layers() {
+        let test_input = "@import url(a.css) layer(base . theme) screen;
+        @import 'b.css' LAYER;
+        @import 'c.css' layer\tprint;
+        @import 'd.css' layers;
+        @import 'e.css' layer();
+        @import 'f.css' layer(a b);";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let stylesheet = css_parser.parse_stylesheet();
+        let imports: Vec<(&str, Option<Option<&str>>, usize)> = stylesheet
+            .rules
+            .iter()
+            .filter_map(|rule| match rule {
+                CssRule::Import(import) => Some((
+                    import.url.as_str(),
+                    import.layer.as_ref().map(Option::as_deref),
+                    import.queries.queries.len(),
+                )),
+                _ => None,
+            })
+            .collect();
+        // `layers` is a media type, invalid layer names drop the import
+        assert_eq!(
+            imports,
+            vec![
+                ("a.css", Some(Some("base.theme")), 1),
+                ("b.css", Some(None), 0),
+                ("c.css", Some(None), 1),
+                ("d.css", None, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_import_rules() {
+        let test_input = "@import foo; @import url(a.css; @import url b.css { }
+        @import 'kept.css'; p { margin: auto; } @import url(";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let stylesheet = css_parser.parse_stylesheet();
+        match &stylesheet.rules[..] {
+            [CssRule::Import(import), CssRule::Style(_)] => assert_eq!(import.url, "kept.css"),
+            other => panic!("unexpected rules {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        let test_input = "/* header */ p /* tag */ { margin-top: auto; /* color: #cc0000; */ }
+        h1 { content: \"/* kept */\"; }";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let stylesheet = css_parser.parse_stylesheet();
+        assert_eq!(stylesheet.rules.len(), 2);
+        match (&stylesheet.rules[0], &stylesheet.rules[1]) {
+            (CssRule::Style(p), CssRule::Style(h1)) => {
+                assert_eq!(p.declarations.len(), 1);
+                assert_eq!(
+                    h1.declarations[0].value,
+                    Value::Content(vec![ContentItem::String("/* kept */".to_string())])
+                );
+            }
+            other => panic!("unexpected rules {:?}", other),
+        }
     }
 
     #[test]
     fn test_selector_parsing() {
         let test_input = "h1, p, div.toto, #param.sasa";
         let mut css_parser = CSSParser::new(test_input.to_string());
-        let test_selectors = css_parser.parse_selectors();
+        let test_selectors = css_parser.parse_selectors().unwrap();
         dbg!(test_selectors);
     }
 
@@ -278,7 +3173,7 @@ mod tests {
     fn test_rule_parsing_multi_selectors() {
         let test_input = "h1, h2, h3 { margin: auto; color: #cc0000; }";
         let mut css_parser = CSSParser::new(test_input.to_string());
-        let rule = css_parser.parse_rule();
+        let rule = css_parser.parse_rule().unwrap();
         dbg!(rule);
     }
 
@@ -286,7 +3181,7 @@ mod tests {
     fn test_rule_parsing_selector_with_cls() {
         let test_input = "div.note { margin-bottom: 20px; padding: 10px; }";
         let mut css_parser = CSSParser::new(test_input.to_string());
-        let rule = css_parser.parse_rule();
+        let rule = css_parser.parse_rule().unwrap();
         dbg!(rule);
     }
 
@@ -294,7 +3189,7 @@ mod tests {
     fn test_rule_parsing_selector_with_id() {
         let test_input = "#answer { display: none; }";
         let mut css_parser = CSSParser::new(test_input.to_string());
-        let rule = css_parser.parse_rule();
+        let rule = css_parser.parse_rule().unwrap();
         dbg!(rule);
     }
 
@@ -307,4 +3202,185 @@ mod tests {
         let stylesheet = css_parser.parse_stylesheet();
         dbg!(stylesheet);
     }
+
+    #[test]
+    fn test_attribute_selector_parsing() {
+        let test_input = "a[href^=\"https\"][data-state~=open], [href$='.pdf' i], [lang|=en]";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let selectors = css_parser.parse_selectors().unwrap();
+        assert_eq!(selectors.len(), 3);
+
+        let selectors: Vec<&SimpleSelector> =
+            selectors.iter().map(|sel| &sel.compounds[0]).collect();
+        let link = selectors[0];
+        assert_eq!(link.tag_name, Some("a".to_string()));
+        assert_eq!(link.attributes[0].name, "href");
+        assert_eq!(link.attributes[0].operator, AttributeOperator::Prefix);
+        assert_eq!(link.attributes[0].value, "https");
+        assert_eq!(link.attributes[1].operator, AttributeOperator::Includes);
+        assert_eq!(link.attributes[1].value, "open");
+
+        let pdf = &selectors[1].attributes[0];
+        assert_eq!(pdf.operator, AttributeOperator::Suffix);
+        assert_eq!(pdf.value, ".pdf");
+        assert!(pdf.case_insensitive);
+
+        assert_eq!(
+            selectors[2].attributes[0].operator,
+            AttributeOperator::DashMatch
+        );
+    }
+
+    #[test]
+    fn test_invalid_attribute_selectors() {
+        for selector in [
+            "a[href!=x]",
+            "[*|href]",
+            "[|href]",
+            "[svg|href]",
+            "[href=x y]",
+            "[href=]",
+            "[href=x",
+            "[]",
+        ] {
+            let mut css_parser = CSSParser::new(selector.to_string());
+            assert!(
+                css_parser.parse_selectors().is_none(),
+                "{} is valid",
+                selector
+            );
+        }
+
+        let test_input = "a[href!=x] { color: red; } [*|href] { color: red; }
+            a[HREF=x S] { margin-top: 1px; }";
+        let stylesheet = CSSParser::new(test_input.to_string()).parse_stylesheet();
+        match &stylesheet.rules[..] {
+            [CssRule::Style(link)] => {
+                let attribute = &link.selectors[0].compounds[0].attributes[0];
+                assert_eq!(attribute.name, "href");
+                assert!(!attribute.case_insensitive);
+            }
+            other => panic!("unexpected rules {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_attribute_selector_specificity() {
+        let mut css_parser = CSSParser::new("input[type=checkbox].big".to_string());
+        let selector = css_parser.parse_simple_selector().unwrap();
+        assert_eq!(selector.specificity(), (0, 2, 1));
+    }
+
+    fn parse_selector(input: &str) -> Selector {
+        let mut css_parser = CSSParser::new(input.to_string());
+        css_parser.parse_selectors().unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_combinator_parsing() {
+        let selector = parse_selector("ul > li.item + li ~ p a");
+        assert_eq!(selector.compounds.len(), 5);
+        assert_eq!(
+            selector.combinators,
+            vec![
+                Combinator::Child,
+                Combinator::NextSibling,
+                Combinator::SubsequentSibling,
+                Combinator::Descendant
+            ]
+        );
+        assert_eq!(selector.compounds[1].class, vec!["item".to_string()]);
+        assert_eq!(selector.specificity(), (0, 1, 5));
+    }
+
+    #[test]
+    fn test_nth_parsing() {
+        let nth = |input: &str| match &parse_selector(&format!(":nth-child({})", input)).compounds
+            [0]
+        .pseudo_classes[0]
+        {
+            PseudoClass::NthChild(nth, _) => *nth,
+            other => panic!("unexpected pseudo-class {:?}", other),
+        };
+        assert_eq!(nth("odd"), Nth { a: 2, b: 1 });
+        assert_eq!(nth("even"), Nth { a: 2, b: 0 });
+        assert_eq!(nth("3"), Nth { a: 0, b: 3 });
+        assert_eq!(nth("-2"), Nth { a: 0, b: -2 });
+        assert_eq!(nth("n"), Nth { a: 1, b: 0 });
+        assert_eq!(nth("2n+1"), Nth { a: 2, b: 1 });
+        assert_eq!(nth(" 3n - 2 "), Nth { a: 3, b: -2 });
+        assert_eq!(nth("-n+3"), Nth { a: -1, b: 3 });
+        assert_eq!(nth("+n+3"), Nth { a: 1, b: 3 });
+        assert_eq!(nth("-2n"), Nth { a: -2, b: 0 });
+        assert_eq!(nth("2N+1"), Nth { a: 2, b: 1 });
+        assert_eq!(nth("-N"), Nth { a: -1, b: 0 });
+
+        assert!(Nth { a: 2, b: 1 }.matches(5));
+        assert!(!Nth { a: 2, b: 1 }.matches(4));
+        assert!(Nth { a: -1, b: 3 }.matches(3));
+        assert!(!Nth { a: -1, b: 3 }.matches(4));
+        assert!(Nth { a: 0, b: 2 }.matches(2));
+    }
+
+    #[test]
+    fn test_invalid_selectors_drop_their_rule() {
+        let test_input = "a:lang(en) { margin-top: 1px; } a:foo, p { margin-top: 2px; }
+            :-webkit-autofill { color: red; } li:nth-child(foo) { color: red; }
+            li:nth-child(2n+) { color: red; } a:not(b:foo) { color: red; }
+            :is(a, :hover(x)) { color: red; } li:nth-child(2N+1) { margin-top: 3px; }
+            @media screen { a:foo { color: red; } p { margin-top: 4px; } }";
+        let mut css_parser = CSSParser::new(test_input.to_string());
+        let stylesheet = css_parser.parse_stylesheet();
+        match &stylesheet.rules[..] {
+            [CssRule::Style(nth), CssRule::Media(media)] => {
+                assert_eq!(nth.selectors.len(), 1);
+                assert_eq!(nth.declarations.len(), 1);
+                match &media.rules[..] {
+                    [CssRule::Style(p)] => {
+                        assert_eq!(p.selectors[0].compounds[0].tag_name, Some("p".to_string()))
+                    }
+                    other => panic!("unexpected rules {:?}", other),
+                }
+            }
+            other => panic!("unexpected rules {:?}", other),
+        }
+        assert!(CSSParser::new("a:foo".to_string())
+            .parse_selectors()
+            .is_none());
+    }
+
+    #[test]
+    fn test_pseudo_class_specificity() {
+        let specificity = |input: &str| parse_selector(input).specificity();
+        assert_eq!(specificity("li:first-child"), (0, 1, 1));
+        assert_eq!(specificity(":where(#a, .b) p"), (0, 0, 1));
+        assert_eq!(specificity(":is(#a, .b) p"), (1, 0, 1));
+        assert_eq!(specificity("a:not(.x, p.y)"), (0, 1, 2));
+        assert_eq!(specificity("section:has(> img, .icon)"), (0, 1, 1));
+        assert_eq!(specificity(":nth-child(2n of .item)"), (0, 2, 0));
+        assert_eq!(specificity(":nth-of-type(2)"), (0, 1, 0));
+    }
+
+    #[test]
+    fn test_attribute_operators() {
+        let selector = |operator, value: &str, case_insensitive| AttributeSelector {
+            name: "x".to_string(),
+            operator,
+            value: value.to_string(),
+            case_insensitive,
+        };
+        assert!(selector(AttributeOperator::Exists, "", false).matches(""));
+        assert!(selector(AttributeOperator::Equals, "checkbox", false).matches("checkbox"));
+        assert!(!selector(AttributeOperator::Equals, "checkbox", false).matches("Checkbox"));
+        assert!(selector(AttributeOperator::Equals, "checkbox", true).matches("Checkbox"));
+        assert!(selector(AttributeOperator::Includes, "open", false).matches("closed open"));
+        assert!(!selector(AttributeOperator::Includes, "ope", false).matches("closed open"));
+        assert!(selector(AttributeOperator::DashMatch, "en", false).matches("en-US"));
+        assert!(selector(AttributeOperator::DashMatch, "en", false).matches("en"));
+        assert!(!selector(AttributeOperator::DashMatch, "en", false).matches("english"));
+        assert!(selector(AttributeOperator::Prefix, "https", false).matches("https://a"));
+        assert!(!selector(AttributeOperator::Prefix, "", false).matches("https://a"));
+        assert!(selector(AttributeOperator::Suffix, ".pdf", true).matches("/A.PDF"));
+        assert!(selector(AttributeOperator::Substring, "doc", false).matches("/docs/"));
+    }
 }
@@ -1,3 +1,6 @@
+use crate::css::{CSSParser, Stylesheet};
+use crate::loader::{load_stylesheet, resolve_imports, resolve_url, StylesheetLoader};
+use crate::media::MediaQueryList;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
@@ -31,6 +34,17 @@ impl ElementData {
         self.attributes.get("id")
     }
 
+    // `<link rel="stylesheet" href="...">`, alternate stylesheets being disabled
+    // by default
+    pub fn is_stylesheet_link(&self) -> bool {
+        let rel = self.attributes.get("rel").map(|rel| rel.to_lowercase());
+        let has = |keyword: &str| {
+            rel.as_deref()
+                .is_some_and(|rel| rel.split_whitespace().any(|rel| rel == keyword))
+        };
+        has("stylesheet") && !has("alternate") && self.attributes.contains_key("href")
+    }
+
     pub fn classes(&self) -> HashSet<&str> {
         match self.attributes.get("class") {
             Some(cls) => cls.split(' ').collect(),
@@ -100,11 +114,81 @@ impl Node {
 }
 pub struct Document {
     root: Node,
+    // where the document was loaded from, to resolve relative urls
+    url: Option<String>,
 }
 
 impl Document {
     pub fn new(root: Node) -> Document {
-        Document { root }
+        Document { root, url: None }
+    }
+
+    pub fn with_url(mut self, url: &str) -> Document {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    // the author stylesheets of the document, in tree order: the content of
+    // `<style>` elements and the stylesheets of `<link rel="stylesheet">`
+    // elements, loaded with `loader`. stylesheets failing to load are skipped
+    pub fn stylesheets(&self, loader: &dyn StylesheetLoader) -> Vec<Stylesheet> {
+        let mut stylesheets = Vec::new();
+        self.collect_stylesheets(&self.root, loader, &mut stylesheets);
+        stylesheets
+    }
+
+    fn collect_stylesheets(
+        &self,
+        node: &Node,
+        loader: &dyn StylesheetLoader,
+        stylesheets: &mut Vec<Stylesheet>,
+    ) {
+        let elem = match &node.node_type {
+            NodeType::Element(elem) => elem,
+            _ => return,
+        };
+        let stylesheet = match elem.tag_name.to_lowercase().as_str() {
+            "style" => {
+                let css: String = node
+                    .children
+                    .iter()
+                    .filter_map(|child| match &child.node_type {
+                        NodeType::Text(text) => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                let mut stylesheet = CSSParser::new(css).parse_stylesheet();
+                if let Some(url) = &self.url {
+                    stylesheet = stylesheet.with_url(url);
+                }
+                resolve_imports(&mut stylesheet, loader);
+                Some(stylesheet)
+            }
+            "link" if elem.is_stylesheet_link() => {
+                let url = resolve_url(self.url.as_deref(), &elem.attributes["href"]);
+                load_stylesheet(&url, loader).ok()
+            }
+            _ => None,
+        };
+        match stylesheet {
+            Some(stylesheet) => stylesheets.push(match elem.attributes.get("media") {
+                Some(media) => stylesheet.with_media(MediaQueryList::parse(media)),
+                None => stylesheet,
+            }),
+            None => {
+                for child in &node.children {
+                    self.collect_stylesheets(child, loader, stylesheets);
+                }
+            }
+        }
     }
 
     pub fn display(&self) {
@@ -151,3 +235,83 @@ pub fn pretty_print_tree(root: &Node) {
     dfs(root, &mut result_string, &mut visited, depth);
     println!("{}", result_string);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assign::cascade_style_tree;
+    use crate::cascade::Cascade;
+    use crate::css::{Color, Value};
+    use crate::html::HTMLParser;
+    use crate::media::MediaEnvironment;
+    use std::io;
+
+    struct MemoryLoader(HashMap<&'static str, &'static str>);
+
+    impl StylesheetLoader for MemoryLoader {
+        fn load(&self, url: &str) -> io::Result<String> {
+            self.0
+                .get(url)
+                .map(|css| css.to_string())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, url.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_stylesheet_links() {
+        let html = "<html>
+            <link rel='stylesheet' href='a.css'>
+            <link rel=' icon\tSTYLESHEET ' href='a.css'>
+            <link rel='alternate stylesheet' href='a.css'>
+            <link rel='stylesheets' href='a.css'>
+            <link rel='preload-stylesheet' href='a.css'>
+            <link rel='stylesheet'>
+        </html>";
+        let document = HTMLParser::new(html.to_string()).parse_document();
+        let links: Vec<bool> = document
+            .root()
+            .children
+            .iter()
+            .filter_map(|child| match &child.node_type {
+                NodeType::Element(elem) => Some(elem.is_stylesheet_link()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(links, vec![true, true, false, false, false, false]);
+    }
+
+    #[test]
+    fn test_document_stylesheets() {
+        let html = "<html><head>
+            <link rel='preload' href='ignored.css'>
+            <link rel='Stylesheet' href='css/main.css'/>
+            <link rel='stylesheet' href='missing.css'>
+            <style media='print'>p { color: #000002; }</style>
+            <style>@import 'css/theme.css'; p > b { color: #000003; }</style>
+        </head><body><p style='margin-top: 1px'>a <b>b</b> <i>c</i></p></body></html>";
+        let loader = MemoryLoader(HashMap::from([
+            ("site/css/main.css", "p { color: #000001; }"),
+            ("site/css/theme.css", "i { color: #000004; }"),
+        ]));
+        let document = HTMLParser::new(html.to_string())
+            .parse_document()
+            .with_url("site/index.html");
+        let stylesheets = document.stylesheets(&loader);
+        assert_eq!(stylesheets.len(), 3);
+
+        let mut cascade = Cascade::with_media(MediaEnvironment::default());
+        for stylesheet in &stylesheets {
+            cascade.add_stylesheet(stylesheet);
+        }
+        let root = cascade_style_tree(document.root(), &cascade);
+        let paragraph = &root.children[1].children[0];
+        let color = |b| Some(Value::ColorValue(Color::rgb(0, 0, b)));
+        assert_eq!(paragraph.value("color").cloned(), color(1));
+        assert_eq!(paragraph.children[1].value("color").cloned(), color(3));
+        assert_eq!(paragraph.children[2].value("color").cloned(), color(4));
+        assert_eq!(
+            paragraph.value("margin-top").cloned(),
+            Some(Value::Length(1.0, crate::css::Unit::Px))
+        );
+    }
+}
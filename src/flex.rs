@@ -0,0 +1,893 @@
+use crate::layout::{ContainingBlock, Dimensions, EdgeSizes, LayoutBox, LayoutContext};
+use crate::style::{
+    Alignment, BoxSizing, ComputedStyle, Direction, Display, FlexDirection, FlexWrap,
+    LengthPercentage, Overflow, Size,
+};
+use std::ops::Range;
+
+// a flex item of a container, with its sizes along the main axis
+struct FlexItem {
+    // the index of the box among the children of the container
+    index: usize,
+    // the margins, padding, borders, and the content width of column items
+    dimensions: Dimensions,
+    // whether the margins before and after the item along the main and the
+    // cross axes are auto, in physical order
+    auto_margins: [bool; 4],
+    // the padding, borders and margins along the main axis
+    main_edges: f32,
+    flex_base_size: f32,
+    hypothetical_size: f32,
+    min_size: f32,
+    max_size: f32,
+    target_size: f32,
+    frozen: bool,
+    violation: f32,
+    // the outer cross size, and the distance from the cross start of the
+    // margin box to the first baseline
+    cross_size: f32,
+    baseline: f32,
+}
+
+impl FlexItem {
+    fn flex_factor(&self, grow: bool, style: &ComputedStyle) -> f32 {
+        match grow {
+            true => style.flex_grow,
+            false => style.flex_shrink,
+        }
+    }
+}
+
+// the content size for a size property, percentages resolving against
+// `basis`; `edges` are the padding and borders along the same axis
+pub(crate) fn content_size(
+    style: &ComputedStyle,
+    size: &Size,
+    basis: Option<f32>,
+    edges: f32,
+) -> Option<f32> {
+    size.resolve(basis).map(|size| match style.box_sizing {
+        BoxSizing::ContentBox => size,
+        BoxSizing::BorderBox => (size - edges).max(0.0),
+    })
+}
+
+// the alignment of an item in the cross axis of its line
+pub(crate) fn self_alignment(item: &ComputedStyle, container: &ComputedStyle) -> Alignment {
+    match item.align_self {
+        Alignment::Auto => container.align_items,
+        alignment => alignment,
+    }
+}
+
+// the space before the first of `count` boxes, and between each of them, to
+// distribute `free` space; `start` and `end` are swapped for `flipped` axes.
+// Distributions fall back to alignments when the boxes overflow
+pub(crate) fn distribute(
+    alignment: Alignment,
+    free: f32,
+    count: usize,
+    flipped: bool,
+) -> (f32, f32) {
+    let count = count as f32;
+    match alignment {
+        Alignment::Center => (free / 2.0, 0.0),
+        Alignment::FlexEnd => (free, 0.0),
+        Alignment::Start | Alignment::SelfStart | Alignment::Left if flipped => (free, 0.0),
+        Alignment::End | Alignment::SelfEnd | Alignment::Right if !flipped => (free, 0.0),
+        Alignment::SpaceBetween if free > 0.0 && count > 1.0 => (0.0, free / (count - 1.0)),
+        Alignment::SpaceAround if free > 0.0 => (free / count / 2.0, free / count),
+        Alignment::SpaceEvenly if free > 0.0 => (free / (count + 1.0), free / (count + 1.0)),
+        Alignment::SpaceAround | Alignment::SpaceEvenly => (free / 2.0, 0.0),
+        _ => (0.0, 0.0),
+    }
+}
+
+// §9.7: grow or shrink the items of a line to fill the `available` space,
+// freezing the ones reaching their minimum or maximum sizes
+fn resolve_flexible_lengths(
+    items: &mut [FlexItem],
+    styles: &[&ComputedStyle],
+    available: Option<f32>,
+    gap: f32,
+) {
+    for item in items.iter_mut() {
+        item.target_size = item.hypothetical_size;
+    }
+    let Some(available) = available else {
+        return;
+    };
+    let available = available - gap * items.len().saturating_sub(1) as f32;
+    let used: f32 = items
+        .iter()
+        .map(|item| item.hypothetical_size + item.main_edges)
+        .sum();
+    let grow = used < available;
+    for (item, style) in items.iter_mut().zip(styles) {
+        item.frozen = item.flex_factor(grow, style) == 0.0
+            || grow && item.flex_base_size > item.hypothetical_size
+            || !grow && item.flex_base_size < item.hypothetical_size;
+    }
+
+    let free_space = |items: &[FlexItem]| {
+        available
+            - items
+                .iter()
+                .map(|item| match item.frozen {
+                    true => item.target_size + item.main_edges,
+                    false => item.flex_base_size + item.main_edges,
+                })
+                .sum::<f32>()
+    };
+    let initial_free_space = free_space(items);
+    while items.iter().any(|item| !item.frozen) {
+        let mut remaining = free_space(items);
+        let unfrozen = || items.iter().zip(styles).filter(|(item, _)| !item.frozen);
+        let factors: f32 = unfrozen()
+            .map(|(item, style)| item.flex_factor(grow, style))
+            .sum();
+        let scaled_shrink_factors: f32 = unfrozen()
+            .map(|(item, style)| style.flex_shrink * item.flex_base_size)
+            .sum();
+        // factors summing to less than 1 only take part of the free space
+        if factors < 1.0 && (initial_free_space * factors).abs() < remaining.abs() {
+            remaining = initial_free_space * factors;
+        }
+
+        let mut total_violation = 0.0;
+        for (item, style) in items.iter_mut().zip(styles) {
+            if item.frozen {
+                continue;
+            }
+            let share = match grow {
+                true if factors > 0.0 => style.flex_grow / factors,
+                false if scaled_shrink_factors > 0.0 => {
+                    style.flex_shrink * item.flex_base_size / scaled_shrink_factors
+                }
+                _ => 0.0,
+            };
+            let size = item.flex_base_size + remaining * share;
+            let clamped = size.min(item.max_size).max(item.min_size);
+            item.violation = clamped - size;
+            item.target_size = clamped;
+            total_violation += item.violation;
+        }
+        for item in items.iter_mut().filter(|item| !item.frozen) {
+            item.frozen = total_violation == 0.0
+                || total_violation > 0.0 && item.violation > 0.0
+                || total_violation < 0.0 && item.violation < 0.0;
+        }
+    }
+}
+
+impl<'a> LayoutBox<'a> {
+    pub(crate) fn is_flex_container(&self) -> bool {
+        matches!(self.style().display, Display::Flex | Display::InlineFlex)
+    }
+
+    // the margins, padding and borders of a flex item, and its flex base
+    // size and main size constraints; column items also get their width
+    fn flex_item(
+        &mut self,
+        context: &LayoutContext,
+        index: usize,
+        container: &ComputedStyle,
+        containing: &ContainingBlock,
+        inner_main: Option<f32>,
+    ) -> FlexItem {
+        let row = matches!(
+            container.flex_direction,
+            FlexDirection::Row | FlexDirection::RowReverse
+        );
+        let containing_width = containing.width;
+        let (padding, border) = self.padding_and_border(containing_width);
+        let style = self.style();
+        let margins = [
+            &style.margin.left,
+            &style.margin.right,
+            &style.margin.top,
+            &style.margin.bottom,
+        ]
+        .map(|margin| margin.resolve(Some(containing_width)));
+        let [left, right, top, bottom] = margins.map(|margin| margin.is_none());
+        let auto_margins = match row {
+            true => [left, right, top, bottom],
+            false => [top, bottom, left, right],
+        };
+        let mut dimensions = Dimensions {
+            padding,
+            border,
+            margin: EdgeSizes {
+                left: margins[0].unwrap_or(0.0),
+                right: margins[1].unwrap_or(0.0),
+                top: margins[2].unwrap_or(0.0),
+                bottom: margins[3].unwrap_or(0.0),
+            },
+            ..Dimensions::default()
+        };
+        let edges_x = padding.left + padding.right + border.left + border.right;
+        let edges_y = padding.top + padding.bottom + border.top + border.bottom;
+        let width = |size: &Size| content_size(style, size, Some(containing_width), edges_x);
+
+        // column items fit their content, or stretch across a single line
+        if !row {
+            let margins = dimensions.margin.left + dimensions.margin.right;
+            let available = containing_width - edges_x - margins;
+            let stretched = matches!(
+                self_alignment(style, container),
+                Alignment::Stretch | Alignment::Normal
+            ) && style.width == Size::Auto
+                && !left
+                && !right
+                && container.flex_wrap == FlexWrap::Nowrap;
+            let mut content_width = width(&style.width).unwrap_or_else(|| match stretched {
+                true => available,
+                false => {
+                    let (min_content, max_content) = self.intrinsic_widths(context);
+                    max_content.min(available).max(min_content)
+                }
+            });
+            if let Some(max_width) = width(&style.max_width) {
+                content_width = content_width.min(max_width);
+            }
+            if let Some(min_width) = width(&style.min_width) {
+                content_width = content_width.max(min_width);
+            }
+            dimensions.content.width = content_width;
+        }
+
+        let (main_edges, main_size, min_main, max_main, overflow) = match row {
+            true => (
+                edges_x + dimensions.margin.left + dimensions.margin.right,
+                &style.width,
+                &style.min_width,
+                &style.max_width,
+                style.overflow_x,
+            ),
+            false => (
+                edges_y + dimensions.margin.top + dimensions.margin.bottom,
+                &style.height,
+                &style.min_height,
+                &style.max_height,
+                style.overflow_y,
+            ),
+        };
+        let pb_main = if row { edges_x } else { edges_y };
+        let main = |size: &Size| content_size(style, size, inner_main, pb_main);
+        let specified = main(main_size);
+        let basis = match &style.flex_basis {
+            Size::Auto => specified,
+            basis => main(basis),
+        };
+        let max_size = main(max_main).unwrap_or(f32::INFINITY);
+        // §4.5: the automatic minimum size of items not scrolling their
+        // overflow is their min-content size, up to their specified size
+        let auto_min_size =
+            *min_main == Size::Auto && matches!(overflow, Overflow::Visible | Overflow::Clip);
+        let min_size = main(min_main).unwrap_or(0.0);
+
+        // the min-content and max-content sizes along the main axis
+        let mut content_sizes = || match row {
+            true => self.intrinsic_widths(context),
+            false => {
+                self.layout_sized(context, dimensions, containing.height, None);
+                let height = self.dimensions.content.height;
+                (height, height)
+            }
+        };
+        let (flex_base_size, content_min) = match basis {
+            Some(basis) if auto_min_size => (basis, content_sizes().0),
+            Some(basis) => (basis, 0.0),
+            None => {
+                let (min_content, max_content) = content_sizes();
+                (max_content, min_content)
+            }
+        };
+        let min_size = match auto_min_size {
+            true => specified
+                .map_or(content_min, |size| size.min(content_min))
+                .min(max_size),
+            false => min_size,
+        };
+        let hypothetical_size = flex_base_size.min(max_size).max(min_size);
+
+        FlexItem {
+            index,
+            dimensions,
+            auto_margins,
+            main_edges,
+            flex_base_size,
+            hypothetical_size,
+            min_size,
+            max_size,
+            target_size: hypothetical_size,
+            frozen: false,
+            violation: 0.0,
+            cross_size: 0.0,
+            baseline: 0.0,
+        }
+    }
+
+    // lays out the item at its main size, and a definite cross size for
+    // stretched items, updating its outer cross size and baseline
+    fn layout_flex_item(
+        &mut self,
+        context: &LayoutContext,
+        item: &mut FlexItem,
+        row: bool,
+        containing_height: Option<f32>,
+        cross_size: Option<f32>,
+    ) {
+        let mut dimensions = item.dimensions;
+        match row {
+            true => {
+                dimensions.content.width = item.target_size;
+                self.layout_sized(context, dimensions, containing_height, cross_size);
+            }
+            false => {
+                if let Some(width) = cross_size {
+                    dimensions.content.width = width;
+                }
+                self.layout_sized(
+                    context,
+                    dimensions,
+                    containing_height,
+                    Some(item.target_size),
+                );
+            }
+        }
+        let margin_box = self.dimensions.margin_box();
+        item.cross_size = match row {
+            true => margin_box.height,
+            false => margin_box.width,
+        };
+        item.baseline = self
+            .first_baseline()
+            .unwrap_or(margin_box.y + margin_box.height)
+            - margin_box.y;
+    }
+
+    // lays out the flex items of a flex container from the top of its
+    // content; returns the height of its content
+    pub(crate) fn layout_flex_items(
+        &mut self,
+        context: &LayoutContext,
+        containing: &ContainingBlock,
+    ) -> f32 {
+        let style = &self.styled_node().unwrap().style;
+        let row = matches!(
+            style.flex_direction,
+            FlexDirection::Row | FlexDirection::RowReverse
+        );
+        let reverse = matches!(
+            style.flex_direction,
+            FlexDirection::RowReverse | FlexDirection::ColumnReverse
+        );
+        // whether the main axis goes right to left, or bottom to top
+        let flipped = reverse != (row && style.direction == Direction::Rtl);
+        let wrap = style.flex_wrap != FlexWrap::Nowrap;
+        let wrap_reverse = style.flex_wrap == FlexWrap::WrapReverse;
+        let (inner_main, inner_cross) = match row {
+            true => (Some(containing.width), containing.height),
+            false => (containing.height, Some(containing.width)),
+        };
+        // `normal` gaps are 0, and percentages of an indefinite size too
+        let gap = |gap: &Option<LengthPercentage>, basis: Option<f32>| {
+            gap.as_ref()
+                .map_or(0.0, |gap| gap.resolve(basis.unwrap_or(0.0)))
+        };
+        let (main_gap, cross_gap) = match row {
+            true => (
+                gap(&style.column_gap, inner_main),
+                gap(&style.row_gap, inner_cross),
+            ),
+            false => (
+                gap(&style.row_gap, inner_main),
+                gap(&style.column_gap, inner_cross),
+            ),
+        };
+
+        // the items in `order`, with their flex base sizes
+        let mut order: Vec<usize> = (0..self.children.len())
+            .filter(|&index| !self.children[index].is_absolutely_positioned())
+            .collect();
+        order.sort_by_key(|&index| self.children[index].style().order);
+        let mut items: Vec<FlexItem> = order
+            .into_iter()
+            .map(|index| {
+                self.children[index].flex_item(context, index, style, containing, inner_main)
+            })
+            .collect();
+
+        // collect the items into lines
+        let available = inner_main.unwrap_or(f32::INFINITY);
+        let mut lines: Vec<Range<usize>> = Vec::new();
+        let (mut start, mut used) = (0, 0.0);
+        for (index, item) in items.iter().enumerate() {
+            let outer = item.hypothetical_size + item.main_edges;
+            if wrap && index > start && used + main_gap + outer > available {
+                lines.push(start..index);
+                (start, used) = (index, outer);
+            } else if index > start {
+                used += main_gap + outer;
+            } else {
+                used = outer;
+            }
+        }
+        if !items.is_empty() {
+            lines.push(start..items.len());
+        }
+
+        // resolve the main sizes, and lay out the items to find their cross
+        // sizes
+        for line in &lines {
+            let styles: Vec<&ComputedStyle> = items[line.clone()]
+                .iter()
+                .map(|item| self.children[item.index].style())
+                .collect();
+            resolve_flexible_lengths(&mut items[line.clone()], &styles, inner_main, main_gap);
+        }
+        for item in &mut items {
+            self.children[item.index].layout_flex_item(context, item, row, containing.height, None);
+        }
+
+        // the cross size of each line: the container's for a single line,
+        // or the tallest item, aligning baselines
+        let is_baseline_aligned = |item: &FlexItem| {
+            let item_style = self.children[item.index].style();
+            row && self_alignment(item_style, style) == Alignment::Baseline
+                && !item.auto_margins[2]
+                && !item.auto_margins[3]
+        };
+        let mut line_sizes: Vec<f32> = lines
+            .iter()
+            .map(|line| match inner_cross {
+                Some(cross) if !wrap => cross,
+                _ => {
+                    let (mut above, mut below, mut size) = (0.0, 0.0, 0.0f32);
+                    for item in &items[line.clone()] {
+                        match is_baseline_aligned(item) {
+                            true => {
+                                above = f32::max(above, item.baseline);
+                                below = f32::max(below, item.cross_size - item.baseline);
+                            }
+                            false => size = size.max(item.cross_size),
+                        }
+                    }
+                    size.max(above + below)
+                }
+            })
+            .collect();
+        let line_baselines: Vec<f32> = lines
+            .iter()
+            .map(|line| {
+                items[line.clone()]
+                    .iter()
+                    .filter(|item| is_baseline_aligned(item))
+                    .map(|item| item.baseline)
+                    .fold(0.0, f32::max)
+            })
+            .collect();
+
+        // lines stretch to fill the container
+        let lines_size = |sizes: &[f32]| {
+            sizes.iter().sum::<f32>() + cross_gap * sizes.len().saturating_sub(1) as f32
+        };
+        let mut cross_free = 0.0;
+        if let (Some(cross), true) = (inner_cross, wrap) {
+            cross_free = cross - lines_size(&line_sizes);
+            let stretch = matches!(style.align_content, Alignment::Stretch | Alignment::Normal);
+            if stretch && cross_free > 0.0 {
+                for size in &mut line_sizes {
+                    *size += cross_free / lines.len() as f32;
+                }
+                cross_free = 0.0;
+            }
+        }
+
+        // stretched items take the cross size of their line
+        for (line, &line_size) in lines.iter().zip(&line_sizes) {
+            for item in &mut items[line.clone()] {
+                let child = &mut self.children[item.index];
+                let item_style = child.style();
+                let (cross_property, min_cross, max_cross) = match row {
+                    true => (
+                        &item_style.height,
+                        &item_style.min_height,
+                        &item_style.max_height,
+                    ),
+                    false => (
+                        &item_style.width,
+                        &item_style.min_width,
+                        &item_style.max_width,
+                    ),
+                };
+                let stretched = matches!(
+                    self_alignment(item_style, style),
+                    Alignment::Stretch | Alignment::Normal
+                ) && *cross_property == Size::Auto
+                    && !item.auto_margins[2]
+                    && !item.auto_margins[3];
+                if !stretched {
+                    continue;
+                }
+                let dimensions = child.dimensions;
+                let edges = match row {
+                    true => dimensions.margin_box().height - dimensions.content.height,
+                    false => dimensions.margin_box().width - dimensions.content.width,
+                };
+                let basis = if row {
+                    containing.height
+                } else {
+                    Some(containing.width)
+                };
+                let inner_edges = match row {
+                    true => dimensions.border_box().height - dimensions.content.height,
+                    false => dimensions.border_box().width - dimensions.content.width,
+                };
+                let mut size = (line_size - edges).max(0.0);
+                if let Some(max) = content_size(item_style, max_cross, basis, inner_edges) {
+                    size = size.min(max);
+                }
+                if let Some(min) = content_size(item_style, min_cross, basis, inner_edges) {
+                    size = size.max(min);
+                }
+                child.layout_flex_item(context, item, row, containing.height, Some(size));
+            }
+        }
+
+        // the main size of the container, the longest line when it depends
+        // on its content
+        let outer_main = |item: &FlexItem| item.target_size + item.main_edges;
+        let line_main = |line: &Range<usize>| {
+            items[line.clone()].iter().map(outer_main).sum::<f32>()
+                + main_gap * line.len().saturating_sub(1) as f32
+        };
+        let main_size =
+            inner_main.unwrap_or_else(|| lines.iter().map(line_main).fold(0.0, f32::max));
+        let cross_size = inner_cross.unwrap_or_else(|| lines_size(&line_sizes));
+
+        // place the lines along the cross axis, and the items along the main
+        // axis of their line, then within the line
+        let (first_line, line_spacing) = match wrap {
+            true => distribute(style.align_content, cross_free, lines.len(), wrap_reverse),
+            false => (0.0, 0.0),
+        };
+        let mut line_position = first_line;
+        for ((line, &line_size), &line_baseline) in
+            lines.iter().zip(&line_sizes).zip(&line_baselines)
+        {
+            let free = main_size - line_main(line);
+            let auto_margins = items[line.clone()]
+                .iter()
+                .map(|item| item.auto_margins[..2].iter().filter(|auto| **auto).count())
+                .sum::<usize>();
+            let (leading, between, auto_margin) = match auto_margins {
+                count if count > 0 && free > 0.0 => (0.0, 0.0, free / count as f32),
+                _ => {
+                    let (leading, between) =
+                        distribute(style.justify_content, free, line.len(), reverse);
+                    (leading, between, 0.0)
+                }
+            };
+            let cross_start = match wrap_reverse {
+                true => cross_size - line_position - line_size,
+                false => line_position,
+            };
+
+            let mut position = leading;
+            for item in &items[line.clone()] {
+                let child = &mut self.children[item.index];
+                let item_style = child.style();
+                let [main_before, main_after, cross_before, cross_after] =
+                    item.auto_margins.map(|auto| auto as u8 as f32);
+                let outer = outer_main(item) + (main_before + main_after) * auto_margin;
+                let main_start = match flipped {
+                    true => main_size - position - outer,
+                    false => position,
+                };
+                position += outer + main_gap + between;
+
+                let free = line_size - item.cross_size;
+                let cross_offset = if cross_before + cross_after > 0.0 {
+                    free.max(0.0) * cross_before / (cross_before + cross_after)
+                } else {
+                    let offset = match self_alignment(item_style, style) {
+                        Alignment::Baseline if row => line_baseline - item.baseline,
+                        alignment => distribute(alignment, free, 1, false).0,
+                    };
+                    match (wrap_reverse, self_alignment(item_style, style)) {
+                        (
+                            true,
+                            Alignment::FlexStart
+                            | Alignment::FlexEnd
+                            | Alignment::Stretch
+                            | Alignment::Normal,
+                        ) => free - offset,
+                        _ => offset,
+                    }
+                };
+
+                let margin_box = child.dimensions.margin_box();
+                let main_offset = main_start + main_before * auto_margin;
+                let (dx, dy) = match row {
+                    true => (
+                        containing.x + main_offset,
+                        containing.y + cross_start + cross_offset,
+                    ),
+                    false => (
+                        containing.x + cross_start + cross_offset,
+                        containing.y + main_offset,
+                    ),
+                };
+                child.translate(dx - margin_box.x, dy - margin_box.y);
+            }
+            line_position += line_size + cross_gap + line_spacing;
+        }
+
+        match row {
+            true => cross_size,
+            false => main_size,
+        }
+    }
+
+    // the min-content and max-content widths of the content of a flex
+    // container
+    pub(crate) fn flex_intrinsic_widths(&self, context: &LayoutContext) -> (f32, f32) {
+        let style = self.style();
+        let widths: Vec<(f32, f32)> = self
+            .children
+            .iter()
+            .filter(|child| !child.is_absolutely_positioned())
+            .map(|child| child.outer_intrinsic_widths(context))
+            .collect();
+        let column = matches!(
+            style.flex_direction,
+            FlexDirection::Column | FlexDirection::ColumnReverse
+        );
+        let largest = |widths: &[(f32, f32)]| {
+            widths.iter().fold((0.0, 0.0), |(min, max), child| {
+                (f32::max(min, child.0), f32::max(max, child.1))
+            })
+        };
+        if column {
+            return largest(&widths);
+        }
+        let gaps = style
+            .column_gap
+            .as_ref()
+            .map_or(0.0, |gap| gap.resolve(0.0))
+            * widths.len().saturating_sub(1) as f32;
+        let max = widths.iter().map(|widths| widths.1).sum::<f32>() + gaps;
+        let min = match style.flex_wrap {
+            FlexWrap::Nowrap => widths.iter().map(|widths| widths.0).sum::<f32>() + gaps,
+            _ => largest(&widths).0,
+        };
+        (min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::test_util::{border_box, find, with_layout};
+    use crate::layout::BoxType;
+
+    // the x positions of the items `a` and `b` in a container 100px wide
+    fn positions(css: &str) -> (f32, f32) {
+        let mut positions = (0.0, 0.0);
+        with_layout(
+            "<div id='c'><div id='a'></div><div id='b'></div></div>",
+            &format!(
+                "#c {{ display: flex; width: 100px; }} #a, #b {{ width: 20px; }} {}",
+                css
+            ),
+            |root| positions = (border_box(root, "a").0, border_box(root, "b").0),
+        );
+        positions
+    }
+
+    #[test]
+    fn test_flexible_lengths() {
+        with_layout(
+            "<div id='c'><div id='a'></div><div id='b'></div><div id='d'></div></div>",
+            "#c { display: flex; width: 700px; height: 50px; } #a { flex: 1; } #b { flex: 2; }
+            #d { width: 100px; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (0.0, 0.0, 200.0, 50.0));
+                assert_eq!(border_box(root, "b"), (200.0, 0.0, 400.0, 50.0));
+                assert_eq!(border_box(root, "d"), (600.0, 0.0, 100.0, 50.0));
+            },
+        );
+        // items shrink in proportion to their shrink factor and base size
+        with_layout(
+            "<div id='c'><div id='a'></div><div id='b'></div></div>",
+            "#c { display: flex; width: 300px; } #a, #b { width: 200px; } #b { flex-shrink: 3; }",
+            |root| {
+                assert_eq!(border_box(root, "a").2, 175.0);
+                assert_eq!(border_box(root, "b"), (175.0, 0.0, 125.0, 0.0));
+            },
+        );
+        // items reaching their maximum size leave the rest to the others
+        with_layout(
+            "<div id='c'><div id='a'></div><div id='b'></div></div>",
+            "#c { display: flex; width: 600px; } #a, #b { flex: 1 1 0; padding: 0 5px; }
+            #a { max-width: 90px; }",
+            |root| {
+                assert_eq!(border_box(root, "a").2, 100.0);
+                assert_eq!(border_box(root, "b"), (100.0, 0.0, 500.0, 0.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_automatic_minimum_size() {
+        with_layout(
+            "<div id='c'><div id='a'>aaaa</div><div id='b'>bbbb</div></div>",
+            "#c { display: flex; width: 30px; }",
+            |root| {
+                assert_eq!(border_box(root, "a").2, 24.0);
+                assert_eq!(border_box(root, "b"), (24.0, 0.0, 24.0, 10.0));
+            },
+        );
+        // items scrolling their overflow shrink below their content
+        with_layout(
+            "<div id='c'><div id='a'>aaaa</div><div id='b'>bbbb</div></div>",
+            "#c { display: flex; width: 30px; } #b { overflow: hidden; }",
+            |root| {
+                assert_eq!(border_box(root, "a").2, 24.0);
+                assert_eq!(border_box(root, "b"), (24.0, 0.0, 6.0, 10.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_justify_content() {
+        assert_eq!(positions(""), (0.0, 20.0));
+        assert_eq!(positions("#c { justify-content: flex-end; }"), (60.0, 80.0));
+        assert_eq!(positions("#c { justify-content: center; }"), (30.0, 50.0));
+        assert_eq!(
+            positions("#c { justify-content: space-between; }"),
+            (0.0, 80.0)
+        );
+        assert_eq!(
+            positions("#c { justify-content: space-around; }"),
+            (15.0, 65.0)
+        );
+        assert_eq!(
+            positions("#c { justify-content: space-evenly; }"),
+            (20.0, 60.0)
+        );
+        assert_eq!(
+            positions("#c { flex-direction: row-reverse; }"),
+            (80.0, 60.0)
+        );
+        assert_eq!(
+            positions("#c { flex-direction: row-reverse; justify-content: start; }"),
+            (20.0, 0.0)
+        );
+        assert_eq!(positions("#c { column-gap: 10px; }"), (0.0, 30.0));
+        // auto margins take the free space first
+        assert_eq!(
+            positions("#c { justify-content: center; } #b { margin-left: auto; }"),
+            (0.0, 80.0)
+        );
+        assert_eq!(positions("#b { order: -1; }"), (20.0, 0.0));
+        // overflowing items fall back to aligning at the start or center
+        assert_eq!(
+            positions(
+                "#a, #b { flex-shrink: 0; width: 60px; } #c { justify-content: space-between; }"
+            ),
+            (0.0, 60.0)
+        );
+        assert_eq!(
+            positions(
+                "#a, #b { flex-shrink: 0; width: 60px; } #c { justify-content: space-around; }"
+            ),
+            (-10.0, 50.0)
+        );
+    }
+
+    #[test]
+    fn test_align_items() {
+        with_layout(
+            "<div id='c'><div id='a'></div><div id='b'></div><div id='d'></div><div id='e'></div></div>",
+            "#c { display: flex; height: 100px; } #a, #d, #e { height: 20px; }
+            #d { align-self: center; } #e { align-self: flex-end; margin-bottom: 5px; }",
+            |root| {
+                assert_eq!(border_box(root, "a").1, 0.0);
+                assert_eq!(border_box(root, "b").3, 100.0);
+                assert_eq!(border_box(root, "d").1, 40.0);
+                assert_eq!(border_box(root, "e").1, 75.0);
+            },
+        );
+        with_layout(
+            "<div id='c'><div id='a'>x</div><div id='b'>x</div><div id='d'></div></div>",
+            "#c { display: flex; align-items: baseline; } #a { font-size: 20px; line-height: 20px; }
+            #d { height: 10px; margin-top: auto; }",
+            |root| {
+                assert_eq!(border_box(root, "a").1, 0.0);
+                assert_eq!(border_box(root, "b").1, 8.0);
+                assert_eq!(border_box(root, "c").3, 20.0);
+                assert_eq!(border_box(root, "d").1, 10.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let html = "<div id='c'><div id='a'></div><div id='b'></div><div id='d'></div></div>";
+        let css = "#c { display: flex; width: 100px; flex-wrap: wrap; gap: 10px 5px; }
+            #a, #b, #d { width: 40px; height: 20px; }";
+        with_layout(html, css, |root| {
+            assert_eq!(border_box(root, "a"), (0.0, 0.0, 40.0, 20.0));
+            assert_eq!(border_box(root, "b"), (45.0, 0.0, 40.0, 20.0));
+            assert_eq!(border_box(root, "d"), (0.0, 30.0, 40.0, 20.0));
+            assert_eq!(border_box(root, "c").3, 50.0);
+        });
+        // lines stretch to fill the container, or are aligned in it
+        with_layout(html, &format!("{} #c {{ height: 100px; }}", css), |root| {
+            assert_eq!(border_box(root, "d").1, 55.0);
+        });
+        with_layout(
+            html,
+            &format!("{} #c {{ height: 100px; align-content: center; }}", css),
+            |root| {
+                assert_eq!(border_box(root, "a").1, 25.0);
+                assert_eq!(border_box(root, "d").1, 55.0);
+            },
+        );
+        with_layout(
+            html,
+            &format!("{} #c {{ flex-wrap: wrap-reverse; }}", css),
+            |root| {
+                assert_eq!(border_box(root, "a").1, 30.0);
+                assert_eq!(border_box(root, "d").1, 0.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_column_direction() {
+        with_layout(
+            "<div id='c'><div id='a'></div><div id='b'></div><div id='d'></div></div>",
+            "#c { display: flex; flex-direction: column; width: 200px; height: 100px; }
+            #a { order: 2; height: 30px; } #b { height: 20px; width: 50px; align-self: center; }
+            #d { flex-grow: 1; }",
+            |root| {
+                assert_eq!(border_box(root, "b"), (75.0, 0.0, 50.0, 20.0));
+                assert_eq!(border_box(root, "d"), (0.0, 20.0, 200.0, 50.0));
+                assert_eq!(border_box(root, "a"), (0.0, 70.0, 200.0, 30.0));
+            },
+        );
+        // the container takes the height of its items, which fit their
+        // content when not stretched
+        with_layout(
+            "<div id='c'><div id='a'>aa bb</div><div id='b'>c</div></div>",
+            "#c { display: flex; flex-direction: column-reverse;
+              align-items: flex-start; row-gap: 4px; }",
+            |root| {
+                assert_eq!(border_box(root, "c").3, 24.0);
+                assert_eq!(border_box(root, "a"), (0.0, 14.0, 30.0, 10.0));
+                assert_eq!(border_box(root, "b"), (0.0, 0.0, 6.0, 10.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_flex_items_and_inline_flex() {
+        with_layout(
+            "<p>x<span id='c'>ab <em>cde</em></span></p>",
+            "#c { display: inline-flex; column-gap: 4px; }",
+            |root| {
+                let container = find(root, "c");
+                // the text run and the blockified `em` are two block items
+                assert!(container.children.iter().all(|item| matches!(
+                    item.box_type,
+                    BoxType::AnonymousBlock(_) | BoxType::Block(_)
+                )));
+                assert_eq!(container.children.len(), 2);
+                assert_eq!(border_box(root, "c"), (6.0, 0.0, 34.0, 10.0));
+            },
+        );
+    }
+}
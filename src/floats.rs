@@ -0,0 +1,298 @@
+use crate::block::MarginStrut;
+use crate::layout::{ContainingBlock, LayoutBox, LayoutContext, Rect};
+use crate::style::{Clear, ComputedStyle, Direction, Float};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+// the side a float is placed on, `None` when it doesn't float
+pub fn float_side(style: &ComputedStyle) -> Option<Side> {
+    let rtl = style.direction == Direction::Rtl;
+    match style.float {
+        Float::None => None,
+        Float::Left => Some(Side::Left),
+        Float::Right => Some(Side::Right),
+        Float::InlineStart if rtl => Some(Side::Right),
+        Float::InlineStart => Some(Side::Left),
+        Float::InlineEnd if rtl => Some(Side::Left),
+        Float::InlineEnd => Some(Side::Right),
+    }
+}
+
+// the sides whose floats a box clears
+fn cleared_sides(style: &ComputedStyle) -> &'static [Side] {
+    let rtl = style.direction == Direction::Rtl;
+    match style.clear {
+        Clear::None => &[],
+        Clear::Left => &[Side::Left],
+        Clear::Right => &[Side::Right],
+        Clear::Both => &[Side::Left, Side::Right],
+        Clear::InlineStart if rtl => &[Side::Right],
+        Clear::InlineStart => &[Side::Left],
+        Clear::InlineEnd if rtl => &[Side::Left],
+        Clear::InlineEnd => &[Side::Right],
+    }
+}
+
+// the floats placed in a block formatting context, as their margin boxes;
+// the line boxes and formatting context roots of its flow go around them
+#[derive(Debug, Default)]
+pub struct FloatContext {
+    floats: Vec<(Side, Rect)>,
+}
+
+impl FloatContext {
+    pub fn new() -> FloatContext {
+        FloatContext::default()
+    }
+
+    // the floats along the band from `y` down `height`, or at `y` when the
+    // band is empty
+    fn overlapping(&self, y: f32, height: f32) -> impl Iterator<Item = &(Side, Rect)> {
+        self.floats.iter().filter(move |(_, rect)| {
+            rect.y + rect.height > y && (rect.y < y + height || rect.y <= y)
+        })
+    }
+
+    // the edges of the space the floats leave between `left` and `right`,
+    // along the band from `y` down `height`
+    pub fn available(&self, y: f32, height: f32, left: f32, right: f32) -> (f32, f32) {
+        self.overlapping(y, height)
+            .fold((left, right), |(left, right), (side, rect)| match side {
+                Side::Left => (left.max(rect.x + rect.width), right),
+                Side::Right => (left, right.min(rect.x)),
+            })
+    }
+
+    // the bottom of the first float the band ends past, where there may be
+    // more space
+    pub fn next_bottom(&self, y: f32, height: f32) -> Option<f32> {
+        self.overlapping(y, height)
+            .map(|(_, rect)| rect.y + rect.height)
+            .min_by(f32::total_cmp)
+    }
+
+    // the bottom of the floats a box with the style clears, `None` without
+    // any
+    pub fn clearance(&self, style: &ComputedStyle) -> Option<f32> {
+        let sides = cleared_sides(style);
+        self.floats
+            .iter()
+            .filter(|(side, _)| sides.contains(side))
+            .map(|(_, rect)| rect.y + rect.height)
+            .max_by(f32::total_cmp)
+    }
+
+    // the bottom of all the floats, which a formatting context root contains
+    pub fn bottom(&self) -> Option<f32> {
+        self.floats
+            .iter()
+            .map(|(_, rect)| rect.y + rect.height)
+            .max_by(f32::total_cmp)
+    }
+
+    // CSS 2.1 §9.5.1: places a float with the style and a margin box of the
+    // size as high as possible below `y`, and as far to its side between
+    // `left` and `right`; returns the origin of its margin box
+    pub fn place(
+        &mut self,
+        style: &ComputedStyle,
+        (width, height): (f32, f32),
+        y: f32,
+        left: f32,
+        right: f32,
+    ) -> (f32, f32) {
+        let side = float_side(style).unwrap_or(Side::Left);
+        // not above an earlier float, nor the floats it clears
+        let mut y = self
+            .floats
+            .last()
+            .map_or(y, |(_, rect)| y.max(rect.y))
+            .max(self.clearance(style).unwrap_or(y));
+        let (start, end) = loop {
+            let (start, end) = self.available(y, height, left, right);
+            match self.next_bottom(y, height) {
+                Some(bottom) if end - start < width => y = bottom,
+                _ => break (start, end),
+            }
+        };
+        let x = match side {
+            Side::Left => start,
+            Side::Right => end - width,
+        };
+        self.floats.push((
+            side,
+            Rect {
+                x,
+                y,
+                width,
+                height,
+            },
+        ));
+        (x, y)
+    }
+}
+
+impl<'a> LayoutBox<'a> {
+    // lays out a float with its margin box at the origin, shrinking to fit
+    // its containing block; returns the size of its margin box
+    pub(crate) fn layout_float_box(
+        &mut self,
+        context: &LayoutContext,
+        containing: &ContainingBlock,
+    ) -> (f32, f32) {
+        let origin = ContainingBlock {
+            x: 0.0,
+            y: 0.0,
+            ..*containing
+        };
+        self.layout_block(
+            context,
+            &origin,
+            0.0,
+            MarginStrut::default(),
+            true,
+            &mut FloatContext::new(),
+        );
+        let margin_box = self.dimensions.margin_box();
+        (margin_box.width, margin_box.height)
+    }
+
+    // lays out a float in its containing block, placed below `y` among the
+    // `floats` of its formatting context
+    pub(crate) fn layout_float(
+        &mut self,
+        context: &LayoutContext,
+        containing: &ContainingBlock,
+        y: f32,
+        floats: &mut FloatContext,
+    ) {
+        let size = self.layout_float_box(context, containing);
+        let right = containing.x + containing.width;
+        let (x, y) = floats.place(self.style(), size, y, containing.x, right);
+        let margin_box = self.dimensions.margin_box();
+        self.translate(x - margin_box.x, y - margin_box.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::test_util::{border_box, text_position, with_layout};
+
+    fn style(float: Float, clear: Clear) -> ComputedStyle {
+        ComputedStyle {
+            float,
+            clear,
+            ..ComputedStyle::default()
+        }
+    }
+
+    #[test]
+    fn test_placement() {
+        let mut floats = FloatContext::new();
+        let left = style(Float::Left, Clear::None);
+        let right = style(Float::Right, Clear::None);
+        assert_eq!(
+            floats.place(&left, (30.0, 20.0), 0.0, 0.0, 100.0),
+            (0.0, 0.0)
+        );
+        assert_eq!(
+            floats.place(&left, (30.0, 10.0), 0.0, 0.0, 100.0),
+            (30.0, 0.0)
+        );
+        assert_eq!(
+            floats.place(&right, (30.0, 40.0), 0.0, 0.0, 100.0),
+            (70.0, 0.0)
+        );
+        // too wide beside the others: below the first one to end
+        assert_eq!(
+            floats.place(&left, (50.0, 10.0), 0.0, 0.0, 100.0),
+            (0.0, 20.0)
+        );
+        // never above an earlier float
+        assert_eq!(
+            floats.place(&left, (10.0, 10.0), 0.0, 0.0, 100.0),
+            (50.0, 20.0)
+        );
+        assert_eq!(floats.available(5.0, 10.0, 0.0, 100.0), (60.0, 70.0));
+        assert_eq!(floats.available(35.0, 0.0, 0.0, 100.0), (0.0, 70.0));
+        assert_eq!(floats.next_bottom(0.0, 10.0), Some(10.0));
+
+        let both = style(Float::None, Clear::Both);
+        assert_eq!(floats.clearance(&both), Some(40.0));
+        assert_eq!(
+            floats.clearance(&style(Float::None, Clear::Left)),
+            Some(30.0)
+        );
+        let cleared = style(Float::Right, Clear::Right);
+        assert_eq!(
+            floats.place(&cleared, (10.0, 10.0), 0.0, 0.0, 100.0),
+            (90.0, 40.0)
+        );
+        assert_eq!(floats.bottom(), Some(50.0));
+    }
+
+    #[test]
+    fn test_lines_around_floats() {
+        with_layout(
+            "<div id='c'><div id='l'></div><div id='r'>xxxx</div>aaa bbb ccc</div>",
+            "#c { width: 100px; } #l { float: left; width: 20px; height: 15px; }
+            #r { float: right; }",
+            |root| {
+                assert_eq!(border_box(root, "l"), (0.0, 0.0, 20.0, 15.0));
+                // shrinks to fit its content
+                assert_eq!(border_box(root, "r"), (76.0, 0.0, 24.0, 10.0));
+                // the lines are shortened by the floats beside them
+                assert_eq!(text_position(root, "c", "aaa "), (20.0, 0.0));
+                assert_eq!(text_position(root, "c", "ccc"), (20.0, 10.0));
+                // and floats take no height
+                assert_eq!(border_box(root, "c").3, 20.0);
+            },
+        );
+        // a float too wide for the rest of its line goes below it
+        with_layout(
+            "<div id='c'>aaaa aaaa bb<span id='f'></span></div>",
+            "#c { width: 100px; } #f { float: left; width: 60px; height: 10px; }",
+            |root| {
+                assert_eq!(text_position(root, "c", "bb"), (60.0, 0.0));
+                assert_eq!(border_box(root, "f"), (0.0, 10.0, 60.0, 10.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_clear() {
+        with_layout(
+            "<div id='l'></div><div id='a'></div><div id='b'></div>",
+            "#l { float: left; width: 10px; height: 50px; } #a { height: 10px; }
+            #b { clear: left; height: 10px; margin-top: 5px; }",
+            |root| {
+                // blocks go under floats, their lines around them
+                assert_eq!(border_box(root, "a"), (0.0, 0.0, 800.0, 10.0));
+                assert_eq!(border_box(root, "b"), (0.0, 50.0, 800.0, 10.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_formatting_context_roots() {
+        with_layout(
+            "<div id='c'><div class='l'></div></div><div id='f'><div class='l'></div></div>\
+            <div id='p'><div class='l'></div></div><div id='b'></div>",
+            ".l { float: left; width: 10px; height: 30px; } #c { overflow: hidden; }
+            #f { display: flow-root; } #b { overflow: hidden; height: 10px; }",
+            |root| {
+                // formatting context roots contain their floats
+                assert_eq!(border_box(root, "c"), (0.0, 0.0, 800.0, 30.0));
+                assert_eq!(border_box(root, "f"), (0.0, 30.0, 800.0, 30.0));
+                assert_eq!(border_box(root, "p"), (0.0, 60.0, 800.0, 0.0));
+                // and go beside the floats around them
+                assert_eq!(border_box(root, "b"), (10.0, 60.0, 790.0, 10.0));
+            },
+        );
+    }
+}
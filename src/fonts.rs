@@ -0,0 +1,1126 @@
+use crate::cascade::Cascade;
+use crate::css::{FontFaceRule, FontSource};
+use crate::loader::{resolve_url, StylesheetLoader};
+use crate::style::{ComputedStyle, FontStyle};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+// the metrics of text without a font: every glyph advances by 0.6em, and
+// the ascent and descent take 0.8em and 0.2em, like a monospace font
+const FALLBACK_ADVANCE: f32 = 0.6;
+const FALLBACK_ASCENT: f32 = 0.8;
+const FALLBACK_DESCENT: f32 = 0.2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontError {
+    // the data ends before a table or record it declares
+    Truncated,
+    MissingTable(&'static str),
+    // a container format that isn't read, like woff
+    Unsupported(String),
+    // the font file couldn't be read
+    Load(String),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Truncated => write!(f, "truncated font data"),
+            FontError::MissingTable(tag) => write!(f, "missing `{}` table", tag),
+            FontError::Unsupported(format) => write!(f, "unsupported font format: {}", format),
+            FontError::Load(error) => write!(f, "cannot load font: {}", error),
+        }
+    }
+}
+
+// big-endian values at offsets of font data
+#[derive(Debug, Clone, Copy)]
+struct Data<'d>(&'d [u8]);
+
+impl<'d> Data<'d> {
+    fn bytes(self, offset: usize, len: usize) -> Result<&'d [u8], FontError> {
+        self.0.get(offset..offset + len).ok_or(FontError::Truncated)
+    }
+
+    fn u16(self, offset: usize) -> Result<u16, FontError> {
+        self.bytes(offset, 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(self, offset: usize) -> Result<i16, FontError> {
+        self.u16(offset).map(|value| value as i16)
+    }
+
+    fn u32(self, offset: usize) -> Result<u32, FontError> {
+        self.bytes(offset, 4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    // the data from an offset
+    fn at(self, offset: usize) -> Result<Data<'d>, FontError> {
+        self.0.get(offset..).map(Data).ok_or(FontError::Truncated)
+    }
+}
+
+// the glyphs of a coverage table, in coverage index order
+fn parse_coverage(table: Data) -> Result<Vec<u16>, FontError> {
+    let mut glyphs = Vec::new();
+    match table.u16(0)? {
+        1 => {
+            for index in 0..table.u16(2)? as usize {
+                glyphs.push(table.u16(4 + 2 * index)?);
+            }
+        }
+        2 => {
+            for index in 0..table.u16(2)? as usize {
+                let record = 4 + 6 * index;
+                glyphs.extend(table.u16(record)?..=table.u16(record + 2)?);
+            }
+        }
+        format => {
+            return Err(FontError::Unsupported(format!(
+                "coverage format {}",
+                format
+            )))
+        }
+    }
+    Ok(glyphs)
+}
+
+// the classes of the glyphs of a class definition table; others are in 0
+fn parse_class_def(table: Data) -> Result<HashMap<u16, u16>, FontError> {
+    let mut classes = HashMap::new();
+    match table.u16(0)? {
+        1 => {
+            let start = table.u16(2)?;
+            for index in 0..table.u16(4)? {
+                classes.insert(start + index, table.u16(6 + 2 * index as usize)?);
+            }
+        }
+        2 => {
+            for index in 0..table.u16(2)? as usize {
+                let record = 4 + 6 * index;
+                let class = table.u16(record + 4)?;
+                for glyph in table.u16(record)?..=table.u16(record + 2)? {
+                    classes.insert(glyph, class);
+                }
+            }
+        }
+        format => return Err(FontError::Unsupported(format!("class format {}", format))),
+    }
+    Ok(classes)
+}
+
+// the subtables of the lookups of a feature in a GSUB or GPOS table, with
+// their lookup type; extension lookups are unwrapped
+fn feature_subtables<'d>(
+    table: Data<'d>,
+    feature: &[u8],
+    extension_type: u16,
+) -> Result<Vec<(u16, Data<'d>)>, FontError> {
+    let features = table.at(table.u16(6)? as usize)?;
+    let lookups = table.at(table.u16(8)? as usize)?;
+    let mut indices = Vec::new();
+    for index in 0..features.u16(0)? as usize {
+        let record = 2 + 6 * index;
+        if features.bytes(record, 4)? != feature {
+            continue;
+        }
+        let feature = features.at(features.u16(record + 4)? as usize)?;
+        for lookup in 0..feature.u16(2)? as usize {
+            let lookup = feature.u16(4 + 2 * lookup)?;
+            if !indices.contains(&lookup) {
+                indices.push(lookup);
+            }
+        }
+    }
+    indices.sort();
+
+    let mut subtables = Vec::new();
+    for index in indices {
+        let lookup = lookups.at(lookups.u16(2 + 2 * index as usize)? as usize)?;
+        let lookup_type = lookup.u16(0)?;
+        for subtable in 0..lookup.u16(4)? as usize {
+            let subtable = lookup.at(lookup.u16(6 + 2 * subtable)? as usize)?;
+            match lookup_type == extension_type {
+                true => subtables.push((subtable.u16(2)?, subtable.at(subtable.u32(4)? as usize)?)),
+                false => subtables.push((lookup_type, subtable)),
+            }
+        }
+    }
+    Ok(subtables)
+}
+
+// the size of a value record, and the offset of its x advance if it has one
+fn value_record_layout(format: u16) -> (usize, Option<usize>) {
+    let size = 2 * format.count_ones() as usize;
+    let x_advance = (format & 4 != 0).then(|| 2 * (format & 3).count_ones() as usize);
+    (size, x_advance)
+}
+
+// kerning between the classes of glyphs, from a GPOS pair adjustment
+#[derive(Debug, Clone)]
+struct ClassKerning {
+    coverage: HashSet<u16>,
+    first_classes: HashMap<u16, u16>,
+    second_classes: HashMap<u16, u16>,
+    second_class_count: usize,
+    values: Vec<i16>,
+}
+
+// a TrueType or OpenType font, with the tables needed to lay out text
+#[derive(Debug, Clone)]
+pub struct Font {
+    pub family: String,
+    pub full_name: String,
+    pub postscript_name: String,
+    pub weight: f32,
+    pub style: FontStyle,
+    units_per_em: f32,
+    ascender: f32,
+    descender: f32,
+    advances: Vec<u16>,
+    glyphs: HashMap<u32, u16>,
+    kerning: HashMap<(u16, u16), i16>,
+    class_kerning: Vec<ClassKerning>,
+    // the ligatures starting with a glyph: the following components, and
+    // the glyph replacing them all
+    ligatures: HashMap<u16, Vec<(Vec<u16>, u16)>>,
+}
+
+// a glyph of shaped text, with its advance in px and the index of the first
+// char it displays
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    pub glyph: u16,
+    pub advance: f32,
+    pub cluster: usize,
+}
+
+impl Font {
+    pub fn parse(data: &[u8]) -> Result<Font, FontError> {
+        Font::parse_index(data, 0)
+    }
+
+    // the font at `index` of a collection, or of a single font file
+    pub fn parse_index(data: &[u8], index: usize) -> Result<Font, FontError> {
+        let file = Data(data);
+        let offset = match file.bytes(0, 4)? {
+            b"ttcf" => file.u32(12 + 4 * index)? as usize,
+            b"wOFF" | b"wOF2" => return Err(FontError::Unsupported(String::from("woff"))),
+            [0, 1, 0, 0] | b"OTTO" | b"true" if index == 0 => 0,
+            _ => return Err(FontError::Unsupported(String::from("unknown format"))),
+        };
+
+        let header = file.at(offset)?;
+        let mut tables = HashMap::new();
+        for table in 0..header.u16(4)? as usize {
+            let record = 12 + 16 * table;
+            let tag = header.bytes(record, 4)?;
+            let start = header.u32(record + 8)? as usize;
+            let len = header.u32(record + 12)? as usize;
+            let table = file.bytes(start, len)?;
+            tables.insert(tag, Data(table));
+        }
+        let table = |tag: &'static str| {
+            tables
+                .get(tag.as_bytes())
+                .copied()
+                .ok_or(FontError::MissingTable(tag))
+        };
+
+        let head = table("head")?;
+        let hhea = table("hhea")?;
+        let mut font = Font {
+            family: String::new(),
+            full_name: String::new(),
+            postscript_name: String::new(),
+            weight: 400.0,
+            style: FontStyle::Normal,
+            units_per_em: head.u16(18)? as f32,
+            ascender: hhea.i16(4)? as f32,
+            descender: -hhea.i16(6)? as f32,
+            advances: Vec::new(),
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+            class_kerning: Vec::new(),
+            ligatures: HashMap::new(),
+        };
+
+        let hmtx = table("hmtx")?;
+        let metrics = hhea.u16(34)? as usize;
+        let glyph_count = match table("maxp") {
+            Ok(maxp) => maxp.u16(4)? as usize,
+            Err(_) => metrics,
+        };
+        for glyph in 0..glyph_count {
+            font.advances
+                .push(hmtx.u16(4 * glyph.min(metrics.max(1) - 1))?);
+        }
+
+        font.parse_cmap(table("cmap")?)?;
+        font.parse_names(table("name")?)?;
+        match table("OS/2") {
+            Ok(os2) => {
+                font.weight = os2.u16(4)? as f32;
+                let selection = os2.u16(62).unwrap_or(0);
+                if selection & 1 != 0 {
+                    font.style = FontStyle::Italic;
+                } else if selection & 0x200 != 0 {
+                    font.style = FontStyle::Oblique;
+                }
+            }
+            Err(_) => {
+                let mac_style = head.u16(44)?;
+                if mac_style & 1 != 0 {
+                    font.weight = 700.0;
+                }
+                if mac_style & 2 != 0 {
+                    font.style = FontStyle::Italic;
+                }
+            }
+        }
+
+        // broken layout tables only disable the features they provide
+        if let Ok(gsub) = table("GSUB") {
+            let _ = font.parse_gsub(gsub);
+        }
+        if let Ok(gpos) = table("GPOS") {
+            let _ = font.parse_gpos(gpos);
+        }
+        if font.kerning.is_empty() && font.class_kerning.is_empty() {
+            if let Ok(kern) = table("kern") {
+                let _ = font.parse_kern(kern);
+            }
+        }
+        Ok(font)
+    }
+
+    // the mapping of unicode code points to glyphs, from the best subtable
+    fn parse_cmap(&mut self, cmap: Data) -> Result<(), FontError> {
+        let mut best = None;
+        for index in 0..cmap.u16(2)? as usize {
+            let record = 4 + 8 * index;
+            let encoding = (cmap.u16(record)?, cmap.u16(record + 2)?);
+            let subtable = cmap.at(cmap.u32(record + 4)? as usize)?;
+            let rank = match (encoding, subtable.u16(0)?) {
+                ((3, 10), 12) | ((0, _), 12) => 0,
+                ((3, 1), 4) | ((0, _), 4) => 1,
+                _ => continue,
+            };
+            if best.is_none_or(|(best_rank, _)| rank < best_rank) {
+                best = Some((rank, subtable));
+            }
+        }
+        let Some((_, subtable)) = best else {
+            return Err(FontError::Unsupported(String::from("no unicode cmap")));
+        };
+
+        match subtable.u16(0)? {
+            4 => {
+                let segments = subtable.u16(6)? as usize / 2;
+                let ends = 14;
+                let starts = ends + 2 * segments + 2;
+                let deltas = starts + 2 * segments;
+                let range_offsets = deltas + 2 * segments;
+                for segment in 0..segments {
+                    let start = subtable.u16(starts + 2 * segment)?;
+                    let end = subtable.u16(ends + 2 * segment)?;
+                    let delta = subtable.u16(deltas + 2 * segment)?;
+                    let range_offset = subtable.u16(range_offsets + 2 * segment)? as usize;
+                    for code in start..=end {
+                        if code == 0xFFFF {
+                            break;
+                        }
+                        let glyph = match range_offset {
+                            0 => code.wrapping_add(delta),
+                            _ => {
+                                let address = range_offsets
+                                    + 2 * segment
+                                    + range_offset
+                                    + 2 * (code - start) as usize;
+                                match subtable.u16(address)? {
+                                    0 => 0,
+                                    glyph => glyph.wrapping_add(delta),
+                                }
+                            }
+                        };
+                        if glyph != 0 {
+                            self.glyphs.insert(code as u32, glyph);
+                        }
+                    }
+                }
+            }
+            _ => {
+                for group in 0..subtable.u32(12)? as usize {
+                    let record = 16 + 12 * group;
+                    let start = subtable.u32(record)?;
+                    let end = subtable.u32(record + 4)?;
+                    let glyph = subtable.u32(record + 8)?;
+                    for code in start..=end.min(0x10FFFF) {
+                        self.glyphs.insert(code, (glyph + code - start) as u16);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // the family and full names, preferring windows english names and the
+    // typographic family
+    fn parse_names(&mut self, name: Data) -> Result<(), FontError> {
+        let strings = name.u16(4)? as usize;
+        let mut names: HashMap<u16, (u8, String)> = HashMap::new();
+        for index in 0..name.u16(2)? as usize {
+            let record = 6 + 12 * index;
+            let platform = name.u16(record)?;
+            let language = name.u16(record + 4)?;
+            let name_id = name.u16(record + 6)?;
+            let bytes = name.bytes(
+                strings + name.u16(record + 10)? as usize,
+                name.u16(record + 8)? as usize,
+            )?;
+            let (rank, text) = match platform {
+                0 | 3 => {
+                    let units: Vec<u16> = bytes
+                        .chunks_exact(2)
+                        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                        .collect();
+                    let rank = match language {
+                        0x409 => 0,
+                        _ => 1,
+                    };
+                    (rank, String::from_utf16_lossy(&units))
+                }
+                1 => (2, bytes.iter().map(|byte| *byte as char).collect()),
+                _ => continue,
+            };
+            if names.get(&name_id).is_none_or(|(best, _)| rank < *best) {
+                names.insert(name_id, (rank, text));
+            }
+        }
+        let get = |id: u16| names.get(&id).map(|(_, name)| name.clone());
+        self.family = get(16).or_else(|| get(1)).unwrap_or_default();
+        self.full_name = get(4).unwrap_or_else(|| self.family.clone());
+        self.postscript_name = get(6).unwrap_or_default();
+        Ok(())
+    }
+
+    // the standard ligatures
+    fn parse_gsub(&mut self, gsub: Data) -> Result<(), FontError> {
+        for (lookup_type, subtable) in feature_subtables(gsub, b"liga", 7)? {
+            if lookup_type != 4 || subtable.u16(0)? != 1 {
+                continue;
+            }
+            let coverage = parse_coverage(subtable.at(subtable.u16(2)? as usize)?)?;
+            for (index, first) in coverage.into_iter().enumerate() {
+                if index >= subtable.u16(4)? as usize {
+                    break;
+                }
+                let set = subtable.at(subtable.u16(6 + 2 * index)? as usize)?;
+                for ligature in 0..set.u16(0)? as usize {
+                    let ligature = set.at(set.u16(2 + 2 * ligature)? as usize)?;
+                    let glyph = ligature.u16(0)?;
+                    let components = (1..ligature.u16(2)? as usize)
+                        .map(|component| ligature.u16(2 + 2 * component))
+                        .collect::<Result<Vec<u16>, FontError>>()?;
+                    self.ligatures
+                        .entry(first)
+                        .or_default()
+                        .push((components, glyph));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // the horizontal advance adjustments of the kerning feature
+    fn parse_gpos(&mut self, gpos: Data) -> Result<(), FontError> {
+        for (lookup_type, subtable) in feature_subtables(gpos, b"kern", 9)? {
+            if lookup_type != 2 {
+                continue;
+            }
+            let coverage = parse_coverage(subtable.at(subtable.u16(2)? as usize)?)?;
+            let (first_size, x_advance) = value_record_layout(subtable.u16(4)?);
+            let (second_size, _) = value_record_layout(subtable.u16(6)?);
+            let Some(x_advance) = x_advance else {
+                continue;
+            };
+            match subtable.u16(0)? {
+                1 => {
+                    let record_size = 2 + first_size + second_size;
+                    for (index, first) in coverage.into_iter().enumerate() {
+                        if index >= subtable.u16(8)? as usize {
+                            break;
+                        }
+                        let set = subtable.at(subtable.u16(10 + 2 * index)? as usize)?;
+                        for pair in 0..set.u16(0)? as usize {
+                            let record = 2 + pair * record_size;
+                            let second = set.u16(record)?;
+                            let value = set.i16(record + 2 + x_advance)?;
+                            self.kerning.entry((first, second)).or_insert(value);
+                        }
+                    }
+                }
+                2 => {
+                    let first_count = subtable.u16(12)? as usize;
+                    let second_count = subtable.u16(14)? as usize;
+                    let record_size = first_size + second_size;
+                    let mut values = Vec::with_capacity(first_count * second_count);
+                    for record in 0..first_count * second_count {
+                        values.push(subtable.i16(16 + record * record_size + x_advance)?);
+                    }
+                    self.class_kerning.push(ClassKerning {
+                        coverage: coverage.into_iter().collect(),
+                        first_classes: parse_class_def(subtable.at(subtable.u16(8)? as usize)?)?,
+                        second_classes: parse_class_def(subtable.at(subtable.u16(10)? as usize)?)?,
+                        second_class_count: second_count,
+                        values,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // the pairs of the horizontal subtables of a version 0 `kern` table
+    fn parse_kern(&mut self, kern: Data) -> Result<(), FontError> {
+        if kern.u16(0)? != 0 {
+            return Err(FontError::Unsupported(String::from("apple kern table")));
+        }
+        let mut subtable = kern.at(4)?;
+        for _ in 0..kern.u16(2)? {
+            let length = subtable.u16(2)? as usize;
+            let coverage = subtable.u16(4)?;
+            if coverage >> 8 == 0 && coverage & 0b101 == 1 {
+                for pair in 0..subtable.u16(6)? as usize {
+                    let record = 14 + 6 * pair;
+                    let glyphs = (subtable.u16(record)?, subtable.u16(record + 2)?);
+                    self.kerning.insert(glyphs, subtable.i16(record + 4)?);
+                }
+            }
+            subtable = subtable.at(length)?;
+        }
+        Ok(())
+    }
+
+    pub fn glyph_index(&self, c: char) -> Option<u16> {
+        self.glyphs.get(&(c as u32)).copied()
+    }
+
+    // the adjustment of the advance of `left` before `right`, in font units
+    fn kerning(&self, left: u16, right: u16) -> i16 {
+        if let Some(value) = self.kerning.get(&(left, right)) {
+            return *value;
+        }
+        self.class_kerning
+            .iter()
+            .find(|kerning| kerning.coverage.contains(&left))
+            .map_or(0, |kerning| {
+                let first = *kerning.first_classes.get(&left).unwrap_or(&0) as usize;
+                let second = *kerning.second_classes.get(&right).unwrap_or(&0) as usize;
+                kerning
+                    .values
+                    .get(first * kerning.second_class_count + second)
+                    .copied()
+                    .unwrap_or(0)
+            })
+    }
+
+    // the glyphs displaying text at a font size; characters missing from the
+    // font take the fallback advance, and `features` enables ligatures and
+    // kerning
+    pub fn shape(&self, text: &str, size: f32, features: bool) -> Vec<ShapedGlyph> {
+        let scale = size / self.units_per_em;
+        let chars: Vec<(usize, Option<u16>)> = text
+            .chars()
+            .enumerate()
+            .map(|(index, c)| (index, self.glyph_index(c)))
+            .collect();
+
+        let mut glyphs: Vec<(usize, Option<u16>)> = Vec::new();
+        let mut index = 0;
+        while index < chars.len() {
+            let (cluster, glyph) = chars[index];
+            let ligature = match (features, glyph) {
+                (true, Some(glyph)) => self.ligatures.get(&glyph).and_then(|ligatures| {
+                    ligatures.iter().find(|(components, _)| {
+                        components.iter().enumerate().all(|(offset, component)| {
+                            chars.get(index + 1 + offset).and_then(|(_, glyph)| *glyph)
+                                == Some(*component)
+                        })
+                    })
+                }),
+                _ => None,
+            };
+            match ligature {
+                Some((components, ligature)) => {
+                    glyphs.push((cluster, Some(*ligature)));
+                    index += 1 + components.len();
+                }
+                None => {
+                    glyphs.push((cluster, glyph));
+                    index += 1;
+                }
+            }
+        }
+
+        let mut shaped: Vec<ShapedGlyph> = glyphs
+            .iter()
+            .map(|(cluster, glyph)| ShapedGlyph {
+                glyph: glyph.unwrap_or(0),
+                advance: match glyph {
+                    Some(glyph) => {
+                        let advance = self.advances.get(*glyph as usize).copied().unwrap_or(0);
+                        advance as f32 * scale
+                    }
+                    None => size * FALLBACK_ADVANCE,
+                },
+                cluster: *cluster,
+            })
+            .collect();
+        if features {
+            for index in 1..glyphs.len() {
+                if let (Some(left), Some(right)) = (glyphs[index - 1].1, glyphs[index].1) {
+                    shaped[index - 1].advance += self.kerning(left, right) as f32 * scale;
+                }
+            }
+        }
+        shaped
+    }
+
+    // the ascent and descent of the font at a size, in px
+    pub fn metrics(&self, size: f32) -> (f32, f32) {
+        let scale = size / self.units_per_em;
+        (self.ascender * scale, self.descender * scale)
+    }
+}
+
+// a font of the database, with the family, weights and style it matches
+#[derive(Debug, Clone)]
+struct Face {
+    family: String,
+    weight: (f32, f32),
+    style: FontStyle,
+    font: Rc<Font>,
+}
+
+// the distance of a weight to the desired one, in the order of preference of
+// CSS Fonts §5.2: the closest heavier weights up to 500 for normal weights,
+// then lighter ones, then heavier ones; lighter first for light weights, and
+// heavier first for bold ones
+fn weight_rank(desired: f32, weight: (f32, f32)) -> (u8, f32) {
+    let weight = desired.clamp(weight.0, weight.1);
+    let distance = (weight - desired).abs();
+    let group = if weight == desired {
+        0
+    } else if (400.0..=500.0).contains(&desired) {
+        match weight {
+            weight if weight > desired && weight <= 500.0 => 1,
+            weight if weight < desired => 2,
+            _ => 3,
+        }
+    } else if desired < 400.0 {
+        match weight < desired {
+            true => 1,
+            false => 2,
+        }
+    } else {
+        match weight > desired {
+            true => 1,
+            false => 2,
+        }
+    };
+    (group, distance)
+}
+
+// the order of preference of the styles of faces for a desired style
+fn style_rank(desired: FontStyle, style: FontStyle) -> u8 {
+    let order = match desired {
+        FontStyle::Italic => [FontStyle::Italic, FontStyle::Oblique, FontStyle::Normal],
+        FontStyle::Oblique => [FontStyle::Oblique, FontStyle::Italic, FontStyle::Normal],
+        FontStyle::Normal => [FontStyle::Normal, FontStyle::Oblique, FontStyle::Italic],
+    };
+    order
+        .iter()
+        .position(|candidate| *candidate == style)
+        .unwrap() as u8
+}
+
+// the `font-weight` descriptor of a font face: a weight or a range
+fn parse_weight_descriptor(descriptor: &str) -> Option<(f32, f32)> {
+    let weights = descriptor
+        .split_whitespace()
+        .map(|weight| match weight {
+            "normal" => Some(400.0),
+            "bold" => Some(700.0),
+            weight => weight.parse().ok(),
+        })
+        .collect::<Option<Vec<f32>>>()?;
+    match weights.as_slice() {
+        [weight] => Some((*weight, *weight)),
+        [min, max] => Some((min.min(*max), min.max(*max))),
+        _ => None,
+    }
+}
+
+// the fonts available to lay out text, from files and `@font-face` rules
+#[derive(Debug, Default)]
+pub struct FontDatabase {
+    faces: Vec<Face>,
+}
+
+impl FontDatabase {
+    pub fn new() -> FontDatabase {
+        FontDatabase::default()
+    }
+
+    // adds a font under its own family, weight and style
+    pub fn add_font(&mut self, font: Font) {
+        self.faces.push(Face {
+            family: font.family.clone(),
+            weight: (font.weight, font.weight),
+            style: font.style,
+            font: Rc::new(font),
+        });
+    }
+
+    pub fn add_file(&mut self, path: &Path) -> Result<(), FontError> {
+        let data = fs::read(path).map_err(|error| FontError::Load(error.to_string()))?;
+        self.add_font(Font::parse(&data)?);
+        Ok(())
+    }
+
+    // adds the font of the first usable source of the rule, under the
+    // family it names; urls are relative to `base`, and local sources name
+    // fonts already in the database
+    pub fn add_font_face(
+        &mut self,
+        rule: &FontFaceRule,
+        base: Option<&str>,
+        loader: &dyn StylesheetLoader,
+    ) -> Result<(), FontError> {
+        let family = rule
+            .family()
+            .ok_or(FontError::Unsupported(String::from("no font family")))?;
+        let mut error = FontError::Unsupported(String::from("no font source"));
+        for source in rule.sources() {
+            let font = match source {
+                FontSource::Url(_, Some(format))
+                    if !matches!(
+                        format.as_str(),
+                        "truetype" | "opentype" | "collection" | "ttf" | "otf"
+                    ) =>
+                {
+                    continue
+                }
+                FontSource::Url(url, _) => loader
+                    .load_bytes(&resolve_url(base, &url))
+                    .map_err(|load_error| FontError::Load(load_error.to_string()))
+                    .and_then(|data| Font::parse(&data).map(Rc::new)),
+                FontSource::Local(name) => self
+                    .faces
+                    .iter()
+                    .find(|face| {
+                        face.font.full_name.eq_ignore_ascii_case(&name)
+                            || face.font.postscript_name.eq_ignore_ascii_case(&name)
+                    })
+                    .map(|face| face.font.clone())
+                    .ok_or(FontError::Load(format!("no local font {}", name))),
+            };
+            match font {
+                Ok(font) => {
+                    let weight = rule
+                        .descriptor("font-weight")
+                        .and_then(parse_weight_descriptor)
+                        .unwrap_or((font.weight, font.weight));
+                    let style = rule
+                        .descriptor("font-style")
+                        .and_then(|style| style.split_whitespace().next())
+                        .and_then(FontStyle::from_keyword)
+                        .unwrap_or(font.style);
+                    self.faces.push(Face {
+                        family,
+                        weight,
+                        style,
+                        font,
+                    });
+                    return Ok(());
+                }
+                Err(source_error) => error = source_error,
+            }
+        }
+        Err(error)
+    }
+
+    // adds the fonts of the `@font-face` rules of the cascade; faces that
+    // fail to load are left to fallback fonts
+    pub fn load_font_faces(&mut self, cascade: &Cascade, loader: &dyn StylesheetLoader) {
+        for (base, rule) in cascade.font_faces() {
+            let _ = self.add_font_face(rule, *base, loader);
+        }
+    }
+
+    // the face of the first family of the list that has one, closest to
+    // the style and weight
+    pub fn query(&self, families: &[String], weight: f32, style: FontStyle) -> Option<&Font> {
+        families.iter().find_map(|family| {
+            self.faces
+                .iter()
+                .filter(|face| face.family.eq_ignore_ascii_case(family))
+                .min_by(|a, b| {
+                    let rank = |face: &Face| {
+                        let (group, distance) = weight_rank(weight, face.weight);
+                        (style_rank(style, face.style), group, distance)
+                    };
+                    rank(a).partial_cmp(&rank(b)).unwrap()
+                })
+                .map(|face| face.font.as_ref())
+        })
+    }
+
+    pub fn font(&self, style: &ComputedStyle) -> Option<&Font> {
+        self.query(&style.font_family, style.font_weight, style.font_style)
+    }
+
+    // the width of text in a style, with its letter and word spacing;
+    // ligatures are disabled by letter spacing
+    pub fn measure(&self, text: &str, style: &ComputedStyle) -> f32 {
+        let size = style.font_size;
+        let font = self.font(style);
+        let glyphs = match font {
+            Some(font) => font.shape(text, size, style.letter_spacing == 0.0),
+            None => text
+                .chars()
+                .enumerate()
+                .map(|(cluster, _)| ShapedGlyph {
+                    glyph: 0,
+                    advance: size * FALLBACK_ADVANCE,
+                    cluster,
+                })
+                .collect(),
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let space = match font {
+            Some(font) => font.shape(" ", size, false)[0].advance,
+            None => size * FALLBACK_ADVANCE,
+        };
+        let word_spacing = style.word_spacing.resolve(space);
+        glyphs
+            .iter()
+            .map(|glyph| {
+                let advance = match chars[glyph.cluster] {
+                    '\t' => space * 8.0,
+                    ' ' => glyph.advance + word_spacing,
+                    _ => glyph.advance,
+                };
+                advance + style.letter_spacing
+            })
+            .sum()
+    }
+
+    // the ascent and descent of the font of a style, in px
+    pub fn metrics(&self, style: &ComputedStyle) -> (f32, f32) {
+        match self.font(style) {
+            Some(font) => font.metrics(style.font_size),
+            None => (
+                style.font_size * FALLBACK_ASCENT,
+                style.font_size * FALLBACK_DESCENT,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assign::PropertyMap;
+    use crate::css::CSSParser;
+    use crate::layout::anonymous_style;
+    use crate::style::Display;
+    use std::io;
+
+    fn be(values: &[u16]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect()
+    }
+
+    fn utf16(text: &str) -> Vec<u8> {
+        be(&text.encode_utf16().collect::<Vec<u16>>())
+    }
+
+    // a font with glyphs for ` AVfi` and a `fi` ligature, whose `A V` pair
+    // is kerned by its `kern` table, and `V A` by its GPOS table if `gpos`
+    fn test_font(weight: u16, italic: bool, gpos: bool) -> Vec<u8> {
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        let mut hhea = vec![0; 36];
+        hhea[4..8].copy_from_slice(&be(&[800, (-200i16) as u16]));
+        hhea[34..36].copy_from_slice(&7u16.to_be_bytes());
+        let maxp = be(&[0, 0x5000, 7]);
+        let hmtx = be(&[500, 0, 600, 0, 600, 0, 300, 0, 250, 0, 500, 0, 250, 0]);
+
+        let codes = [0x20, 0x41, 0x56, 0x66, 0x69, 0xFFFF];
+        let glyphs = [6u16, 1, 2, 3, 4, 0];
+        let mut cmap = be(&[0, 1, 3, 1, 0, 12, 4, 64, 0, 12, 8, 2, 4]);
+        cmap.extend(be(&codes));
+        cmap.extend(be(&[0]));
+        cmap.extend(be(&codes));
+        let deltas: Vec<u16> = codes
+            .iter()
+            .zip(glyphs)
+            .map(|(code, glyph)| match glyph {
+                0 => 1,
+                glyph => glyph.wrapping_sub(*code),
+            })
+            .collect();
+        cmap.extend(be(&deltas));
+        cmap.extend(be(&[0; 6]));
+
+        let (family, full_name) = (utf16("Test Sans"), utf16("Test Sans Regular"));
+        let mut name = be(&[0, 2, 30]);
+        name.extend(be(&[3, 1, 0x409, 1, family.len() as u16, 0]));
+        name.extend(be(&[
+            3,
+            1,
+            0x409,
+            4,
+            full_name.len() as u16,
+            family.len() as u16,
+        ]));
+        name.extend(family);
+        name.extend(full_name);
+
+        let mut os2 = vec![0; 64];
+        os2[4..6].copy_from_slice(&weight.to_be_bytes());
+        os2[62..64].copy_from_slice(&(italic as u16).to_be_bytes());
+
+        let kern = be(&[0, 1, 0, 20, 1, 1, 6, 0, 0, 1, 2, (-80i16) as u16]);
+        let mut gsub = be(&[1, 0, 10, 12, 26, 0, 1]);
+        gsub.extend(b"liga");
+        gsub.extend(be(&[
+            8, 0, 1, 0, 1, 4, 4, 0, 1, 8, 1, 8, 1, 14, 1, 1, 3, 1, 4, 5, 2, 4,
+        ]));
+        let mut gpos_table = be(&[1, 0, 10, 12, 26, 0, 1]);
+        gpos_table.extend(b"kern");
+        gpos_table.extend(be(&[
+            8,
+            0,
+            1,
+            0,
+            1,
+            4,
+            2,
+            0,
+            1,
+            8,
+            1,
+            12,
+            4,
+            0,
+            1,
+            18,
+            1,
+            1,
+            2,
+            1,
+            1,
+            (-50i16) as u16,
+        ]));
+
+        let mut tables: Vec<(&[u8], Vec<u8>)> = vec![
+            (b"GSUB", gsub),
+            (b"OS/2", os2),
+            (b"cmap", cmap),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"kern", kern),
+            (b"maxp", maxp),
+            (b"name", name),
+        ];
+        if gpos {
+            tables.push((b"GPOS", gpos_table));
+        }
+
+        let mut data = be(&[1, 0, tables.len() as u16, 0, 0, 0]);
+        let mut offset = 12 + 16 * tables.len();
+        let mut bodies: Vec<u8> = Vec::new();
+        for (tag, table) in &tables {
+            data.extend(*tag);
+            data.extend(0u32.to_be_bytes());
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len();
+            bodies.extend(table);
+        }
+        data.extend(bodies);
+        data
+    }
+
+    // the advances of the glyphs, rounded to hundredths of px
+    fn advances(glyphs: &[ShapedGlyph]) -> Vec<f32> {
+        glyphs
+            .iter()
+            .map(|glyph| (glyph.advance * 100.0).round() / 100.0)
+            .collect()
+    }
+
+    fn style(family: &str) -> ComputedStyle {
+        let mut style = anonymous_style(&PropertyMap::new(), Display::Block);
+        style.font_family = vec![family.to_string()];
+        style.font_size = 10.0;
+        style
+    }
+
+    #[test]
+    fn test_parse_font() {
+        let font = Font::parse(&test_font(400, false, false)).unwrap();
+        assert_eq!(font.family, "Test Sans");
+        assert_eq!(font.full_name, "Test Sans Regular");
+        assert_eq!(font.weight, 400.0);
+        assert_eq!(font.style, FontStyle::Normal);
+        assert_eq!(font.glyph_index('A'), Some(1));
+        assert_eq!(font.glyph_index('z'), None);
+        assert_eq!(font.metrics(10.0), (8.0, 2.0));
+
+        let italic = Font::parse(&test_font(700, true, false)).unwrap();
+        assert_eq!((italic.weight, italic.style), (700.0, FontStyle::Italic));
+
+        assert_eq!(
+            Font::parse(b"wOF2....").unwrap_err(),
+            FontError::Unsupported(String::from("woff"))
+        );
+        let truncated = test_font(400, false, false)[..40].to_vec();
+        assert_eq!(Font::parse(&truncated).unwrap_err(), FontError::Truncated);
+    }
+
+    #[test]
+    fn test_shaping() {
+        let font = Font::parse(&test_font(400, false, false)).unwrap();
+        assert_eq!(advances(&font.shape("AV", 10.0, true)), [5.2, 6.0]);
+        assert_eq!(advances(&font.shape("AV", 10.0, false)), [6.0, 6.0]);
+        let ligature = font.shape("fiA", 10.0, true);
+        assert_eq!(
+            ligature.iter().map(|glyph| glyph.glyph).collect::<Vec<_>>(),
+            [5, 1]
+        );
+        assert_eq!(ligature[1].cluster, 2);
+        assert_eq!(advances(&font.shape("fi", 10.0, false)), [3.0, 2.5]);
+        // missing glyphs take the fallback advance
+        assert_eq!(advances(&font.shape("z", 10.0, true)), [6.0]);
+
+        // GPOS kerning replaces the `kern` table
+        let font = Font::parse(&test_font(400, false, true)).unwrap();
+        assert_eq!(advances(&font.shape("VA", 10.0, true)), [5.5, 6.0]);
+        assert_eq!(advances(&font.shape("AV", 10.0, true)), [6.0, 6.0]);
+    }
+
+    #[test]
+    fn test_font_matching() {
+        let mut fonts = FontDatabase::new();
+        for (weight, italic) in [(300, false), (400, false), (700, false), (400, true)] {
+            fonts.add_font(Font::parse(&test_font(weight, italic, false)).unwrap());
+        }
+        let families = [String::from("Nope"), String::from("test sans")];
+        let matched = |weight: f32, style: FontStyle| {
+            let font = fonts.query(&families, weight, style).unwrap();
+            (font.weight, font.style)
+        };
+        assert_eq!(
+            matched(500.0, FontStyle::Normal),
+            (400.0, FontStyle::Normal)
+        );
+        assert_eq!(
+            matched(600.0, FontStyle::Normal),
+            (700.0, FontStyle::Normal)
+        );
+        assert_eq!(
+            matched(350.0, FontStyle::Normal),
+            (300.0, FontStyle::Normal)
+        );
+        assert_eq!(
+            matched(200.0, FontStyle::Normal),
+            (300.0, FontStyle::Normal)
+        );
+        assert_eq!(
+            matched(900.0, FontStyle::Normal),
+            (700.0, FontStyle::Normal)
+        );
+        assert_eq!(
+            matched(700.0, FontStyle::Oblique),
+            (400.0, FontStyle::Italic)
+        );
+        assert!(fonts
+            .query(&[String::from("Nope")], 400.0, FontStyle::Normal)
+            .is_none());
+    }
+
+    #[test]
+    fn test_measure_text() {
+        let mut fonts = FontDatabase::new();
+        fonts.add_font(Font::parse(&test_font(400, false, false)).unwrap());
+        assert_eq!(fonts.measure("AV", &style("Test Sans")), 11.2);
+        assert_eq!(fonts.measure("fi", &style("Test Sans")), 5.0);
+        let mut spaced = style("Test Sans");
+        spaced.letter_spacing = 1.0;
+        assert_eq!(fonts.measure("fi", &spaced), 7.5);
+        assert_eq!(fonts.metrics(&style("Test Sans")), (8.0, 2.0));
+        // text without a font has the fallback metrics
+        assert_eq!(fonts.measure("abc", &style("serif")), 18.0);
+        assert_eq!(fonts.metrics(&style("serif")), (8.0, 2.0));
+    }
+
+    // serves font files from memory
+    struct FontLoader(Vec<u8>);
+
+    impl StylesheetLoader for FontLoader {
+        fn load(&self, url: &str) -> io::Result<String> {
+            Err(io::Error::new(io::ErrorKind::NotFound, url.to_string()))
+        }
+
+        fn load_bytes(&self, url: &str) -> io::Result<Vec<u8>> {
+            match url {
+                "fonts/test.ttf" => Ok(self.0.clone()),
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, url.to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_font_faces() {
+        let stylesheet = CSSParser::new(String::from(
+            "@font-face {
+                font-family: 'Custom';
+                src: url(missing.woff2) format('woff2'), url(../fonts/test.ttf) format('truetype');
+                font-weight: 600 800;
+                font-style: italic;
+            }
+            @font-face { font-family: Local; src: local('Test Sans Regular'); }
+            @font-face { font-family: Missing; src: url(missing.ttf); }",
+        ))
+        .parse_stylesheet()
+        .with_url("css/main.css");
+        let mut cascade = Cascade::new();
+        cascade.add_stylesheet(&stylesheet);
+        let mut fonts = FontDatabase::new();
+        fonts.load_font_faces(&cascade, &FontLoader(test_font(400, false, false)));
+
+        let custom = [String::from("Custom")];
+        assert!(fonts.query(&custom, 700.0, FontStyle::Italic).is_some());
+        assert!(fonts
+            .query(&[String::from("Local")], 400.0, FontStyle::Normal)
+            .is_some());
+        assert!(fonts
+            .query(&[String::from("Missing")], 400.0, FontStyle::Normal)
+            .is_none());
+        assert_eq!(fonts.faces[0].weight, (600.0, 800.0));
+        assert_eq!(fonts.faces[0].style, FontStyle::Italic);
+    }
+}
@@ -0,0 +1,1343 @@
+use crate::css::{LengthContext, RepeatCount, TrackBreadth, TrackListItem, TrackSize, Value};
+use crate::flex::{content_size, distribute, self_alignment};
+use crate::layout::{ContainingBlock, Dimensions, EdgeSizes, LayoutBox, LayoutContext};
+use crate::style::{Alignment, ComputedStyle, Display, GridAutoFlow, GridLine, Size};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+// a track sizing function with its lengths in px; percentages of an
+// indefinite size behave as `auto`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sizing {
+    Fixed(f32),
+    MinContent,
+    MaxContent,
+    Auto,
+    Flex(f32),
+    // the limit of `fit-content()`, only as a maximum
+    FitContent(f32),
+}
+
+impl Sizing {
+    fn is_intrinsic(self) -> bool {
+        matches!(
+            self,
+            Sizing::MinContent | Sizing::MaxContent | Sizing::Auto | Sizing::FitContent(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Track {
+    min: Sizing,
+    max: Sizing,
+    base: f32,
+    limit: f32,
+    // an empty track repeated by `auto-fit`, which takes no space
+    collapsed: bool,
+}
+
+// the explicit tracks of an axis, the names of their lines, and the tracks
+// repeated by `auto-fit`
+#[derive(Default)]
+struct ExplicitTracks {
+    sizes: Vec<(Sizing, Sizing)>,
+    names: Vec<Vec<String>>,
+    auto_fit: Option<Range<usize>>,
+}
+
+impl ExplicitTracks {
+    fn push(&mut self, item: &TrackListItem, available: Option<f32>) {
+        match item {
+            TrackListItem::LineNames(names) => {
+                self.names.last_mut().unwrap().extend(names.iter().cloned())
+            }
+            TrackListItem::Track(size) => {
+                self.sizes.push(track_sizing(size, available));
+                self.names.push(Vec::new());
+            }
+            // nested repetitions are invalid
+            TrackListItem::Repeat(..) => {}
+        }
+    }
+}
+
+// the lines of an item along an axis, numbered from 1 at the start of the
+// explicit grid, or its span when it is placed automatically
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Placement {
+    Definite(i32, i32),
+    Auto(usize),
+}
+
+// a named area of `grid-template-areas`
+struct TemplateArea {
+    name: String,
+    rows: Range<usize>,
+    columns: Range<usize>,
+}
+
+// the contributions of an item to the tracks it spans
+struct Contribution {
+    span: Range<usize>,
+    min_content: f32,
+    max_content: f32,
+}
+
+// a grid item, and the tracks of its area from the start of the implicit
+// grid
+struct GridItem {
+    index: usize,
+    rows: Range<usize>,
+    columns: Range<usize>,
+}
+
+struct Grid {
+    items: Vec<GridItem>,
+    rows: Vec<Track>,
+    columns: Vec<Track>,
+}
+
+fn sizing(breadth: &TrackBreadth, available: Option<f32>) -> Sizing {
+    match breadth {
+        TrackBreadth::Length(Value::Percentage(_) | Value::Calc(_)) if available.is_none() => {
+            Sizing::Auto
+        }
+        TrackBreadth::Length(length) => {
+            let context = LengthContext {
+                percentage_basis: available.unwrap_or(0.0),
+                ..LengthContext::default()
+            };
+            Sizing::Fixed(length.to_px(&context).unwrap_or(0.0))
+        }
+        TrackBreadth::Flex(flex) => Sizing::Flex(*flex),
+        TrackBreadth::MinContent => Sizing::MinContent,
+        TrackBreadth::MaxContent => Sizing::MaxContent,
+        TrackBreadth::Auto => Sizing::Auto,
+    }
+}
+
+// the minimum and maximum sizing functions of a track
+fn track_sizing(size: &TrackSize, available: Option<f32>) -> (Sizing, Sizing) {
+    match size {
+        TrackSize::Breadth(TrackBreadth::Flex(flex)) => (Sizing::Auto, Sizing::Flex(*flex)),
+        TrackSize::Breadth(breadth) => (sizing(breadth, available), sizing(breadth, available)),
+        TrackSize::MinMax(min, max) => (sizing(min, available), sizing(max, available)),
+        TrackSize::FitContent(limit) => {
+            match sizing(&TrackBreadth::Length(limit.clone()), available) {
+                Sizing::Fixed(limit) => (Sizing::Auto, Sizing::FitContent(limit)),
+                _ => (Sizing::Auto, Sizing::MaxContent),
+            }
+        }
+    }
+}
+
+// the number of repetitions of an automatic `repeat()` fitting in the
+// `available` space with the other tracks of the list, at least 1
+fn auto_repetitions(
+    items: &[TrackListItem],
+    repeated: &[TrackListItem],
+    available: Option<f32>,
+    gap: f32,
+) -> usize {
+    let Some(available) = available else {
+        return 1;
+    };
+    // tracks count as their maximum when it is fixed, or their minimum
+    let fixed = |size: &TrackSize| match track_sizing(size, Some(available)) {
+        (Sizing::Fixed(min), Sizing::Fixed(max)) => Some(max.max(min)),
+        (_, Sizing::Fixed(size)) | (Sizing::Fixed(size), _) => Some(size),
+        _ => None,
+    };
+    let tracks = |items: &[TrackListItem]| {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                TrackListItem::Track(size) => Some(size.clone()),
+                _ => None,
+            })
+            .collect::<Vec<TrackSize>>()
+    };
+    let repeated = tracks(repeated);
+    let Some(repeated_size) = repeated.iter().map(fixed).sum::<Option<f32>>() else {
+        return 1;
+    };
+    let (mut other_size, mut other_count) = (0.0, 0);
+    for item in items {
+        let (sizes, count) = match item {
+            TrackListItem::Track(size) => (vec![size.clone()], 1),
+            TrackListItem::Repeat(RepeatCount::Count(count), items) => (tracks(items), *count),
+            _ => continue,
+        };
+        for size in &sizes {
+            other_size += fixed(size).unwrap_or(0.0) * count as f32;
+        }
+        other_count += sizes.len() * count;
+    }
+    let step = repeated_size + gap * repeated.len() as f32;
+    if step <= 0.0 {
+        return 1;
+    }
+    let space = available - other_size - gap * (other_count as f32 - 1.0);
+    ((space / step).floor() as usize).max(1)
+}
+
+fn explicit_tracks(items: &[TrackListItem], available: Option<f32>, gap: f32) -> ExplicitTracks {
+    let mut tracks = ExplicitTracks {
+        names: vec![Vec::new()],
+        ..ExplicitTracks::default()
+    };
+    for item in items {
+        match item {
+            TrackListItem::Repeat(count, repeated) => {
+                let repetitions = match count {
+                    RepeatCount::Count(count) => *count,
+                    _ => auto_repetitions(items, repeated, available, gap),
+                };
+                let start = tracks.sizes.len();
+                for _ in 0..repetitions {
+                    for item in repeated {
+                        tracks.push(item, available);
+                    }
+                }
+                if *count == RepeatCount::AutoFit {
+                    tracks.auto_fit = Some(start..tracks.sizes.len());
+                }
+            }
+            item => tracks.push(item, available),
+        }
+    }
+    tracks
+}
+
+// the areas named by the rows of `grid-template-areas`, spanning the cells
+// with their name; `.` marks unnamed cells
+fn template_areas(rows: &[String]) -> Vec<TemplateArea> {
+    let mut areas: Vec<TemplateArea> = Vec::new();
+    for (row, text) in rows.iter().enumerate() {
+        for (column, name) in text.split_whitespace().enumerate() {
+            if name.chars().all(|c| c == '.') {
+                continue;
+            }
+            match areas.iter_mut().find(|area| area.name == name) {
+                Some(area) => {
+                    area.rows.end = area.rows.end.max(row + 1);
+                    area.columns.start = area.columns.start.min(column);
+                    area.columns.end = area.columns.end.max(column + 1);
+                }
+                None => areas.push(TemplateArea {
+                    name: name.to_string(),
+                    rows: row..row + 1,
+                    columns: column..column + 1,
+                }),
+            }
+        }
+    }
+    areas
+}
+
+// the lines named `name`, numbered from 1
+fn named_lines(names: &[Vec<String>], name: &str) -> Vec<i32> {
+    (0..names.len())
+        .filter(|&line| names[line].iter().any(|other| other == name))
+        .map(|line| line as i32 + 1)
+        .collect()
+}
+
+// the `nth` line named `name`, counting from the end for negative numbers;
+// all the implicit lines are assumed to have the name when there are not
+// enough of them
+fn nth_named_line(names: &[Vec<String>], nth: i32, name: &str) -> i32 {
+    let lines = named_lines(names, name);
+    let count = lines.len() as i32;
+    match nth > 0 {
+        true if nth <= count => lines[nth as usize - 1],
+        true => names.len() as i32 + nth - count,
+        false if -nth <= count => lines[(count + nth) as usize],
+        false => 1 + nth + count,
+    }
+}
+
+// the line of a definite `grid-*-start` or `grid-*-end`, on the given side
+fn resolve_line(line: &GridLine, names: &[Vec<String>], side: &str) -> Option<i32> {
+    let count = names.len() as i32;
+    match line {
+        GridLine::Line(line, None) if *line > 0 => Some(*line),
+        GridLine::Line(line, None) => Some(count + 1 + line),
+        GridLine::Line(nth, Some(name)) => Some(nth_named_line(names, *nth, name)),
+        GridLine::Name(name) => {
+            let area_lines = named_lines(names, &format!("{}-{}", name, side));
+            Some(match area_lines.first() {
+                Some(line) => *line,
+                None => nth_named_line(names, 1, name),
+            })
+        }
+        GridLine::Auto | GridLine::Span(..) => None,
+    }
+}
+
+// the line `span` lines named `name` after `line`, or before it
+fn span_line(names: &[Vec<String>], line: i32, span: u32, name: &str, after: bool) -> i32 {
+    let span = span as i32;
+    let lines = named_lines(names, name);
+    let found: Vec<i32> = match after {
+        true => lines.into_iter().filter(|other| *other > line).collect(),
+        false => lines
+            .into_iter()
+            .rev()
+            .filter(|other| *other < line)
+            .collect(),
+    };
+    let count = found.len() as i32;
+    match (after, found.get(span as usize - 1)) {
+        (_, Some(line)) => *line,
+        (true, None) => line.max(names.len() as i32) + span - count,
+        (false, None) => line.min(1) - span + count,
+    }
+}
+
+// §8.3: the placement of an item along an axis
+fn placement(start: &GridLine, end: &GridLine, names: &[Vec<String>]) -> Placement {
+    let span = |line: &GridLine| match line {
+        GridLine::Span(span, None) => *span,
+        _ => 1,
+    };
+    match (
+        resolve_line(start, names, "start"),
+        resolve_line(end, names, "end"),
+    ) {
+        (Some(start), Some(end)) if start == end => Placement::Definite(start, start + 1),
+        (Some(start), Some(end)) => Placement::Definite(start.min(end), start.max(end)),
+        (Some(start), None) => Placement::Definite(
+            start,
+            match end {
+                GridLine::Span(span, Some(name)) => span_line(names, start, *span, name, true),
+                end => start + span(end) as i32,
+            },
+        ),
+        (None, Some(end)) => Placement::Definite(
+            match start {
+                GridLine::Span(span, Some(name)) => span_line(names, end, *span, name, false),
+                start => end - span(start) as i32,
+            },
+            end,
+        ),
+        // spans of named lines count as 1 for automatic positions
+        (None, None) => Placement::Auto(match start {
+            GridLine::Span(..) => span(start),
+            _ => span(end),
+        } as usize),
+    }
+}
+
+fn fits(occupied: &HashSet<(usize, usize)>, major: &Range<usize>, minor: &Range<usize>) -> bool {
+    major
+        .clone()
+        .all(|a| minor.clone().all(|b| !occupied.contains(&(a, b))))
+}
+
+fn occupy(occupied: &mut HashSet<(usize, usize)>, major: &Range<usize>, minor: &Range<usize>) {
+    for a in major.clone() {
+        occupied.extend(minor.clone().map(|b| (a, b)));
+    }
+}
+
+// §8.5: the areas of the items, given their placements along the rows and
+// the columns in `order` and the numbers of explicit tracks, as ranges of
+// tracks from the start of the implicit grid; also returns the number of
+// implicit tracks before the explicit grid, and the number of tracks, along
+// each axis
+fn place_items(
+    placements: &[[Placement; 2]],
+    explicit: [usize; 2],
+    column_flow: bool,
+    dense: bool,
+) -> (Vec<[Range<usize>; 2]>, [usize; 2], [usize; 2]) {
+    let offsets = [0, 1].map(|axis| {
+        placements
+            .iter()
+            .filter_map(|placement| match placement[axis] {
+                Placement::Definite(start, _) => Some((1 - start).max(0) as usize),
+                Placement::Auto(_) => None,
+            })
+            .max()
+            .unwrap_or(0)
+    });
+    let (major, minor) = match column_flow {
+        true => (1, 0),
+        false => (0, 1),
+    };
+    let tracks = |axis: usize, start: i32, end: i32| {
+        let offset = offsets[axis] as i32 - 1;
+        (start + offset) as usize..(end + offset) as usize
+    };
+    let mut minor_count = placements
+        .iter()
+        .map(|placement| match placement[minor] {
+            Placement::Definite(start, end) => tracks(minor, start, end).end,
+            Placement::Auto(span) => span,
+        })
+        .fold(offsets[minor] + explicit[minor], usize::max);
+
+    // items with a definite position, then the ones locked to a row (or a
+    // column), then the others from an auto-placement cursor
+    let mut areas: Vec<Option<[Range<usize>; 2]>> = vec![None; placements.len()];
+    let mut occupied = HashSet::new();
+    for (area, placement) in areas.iter_mut().zip(placements) {
+        if let (Placement::Definite(a, b), Placement::Definite(c, d)) =
+            (placement[major], placement[minor])
+        {
+            let (major, minor) = (tracks(major, a, b), tracks(minor, c, d));
+            occupy(&mut occupied, &major, &minor);
+            *area = Some([major, minor]);
+        }
+    }
+    let mut cursors: HashMap<usize, usize> = HashMap::new();
+    for (area, placement) in areas.iter_mut().zip(placements) {
+        if let (Placement::Definite(a, b), Placement::Auto(span)) =
+            (placement[major], placement[minor])
+        {
+            let major = tracks(major, a, b);
+            let mut start = match dense {
+                true => 0,
+                false => cursors.get(&major.start).copied().unwrap_or(0),
+            };
+            while !fits(&occupied, &major, &(start..start + span)) {
+                start += 1;
+            }
+            let minor = start..start + span;
+            minor_count = minor_count.max(minor.end);
+            cursors.insert(major.start, minor.end);
+            occupy(&mut occupied, &major, &minor);
+            *area = Some([major, minor]);
+        }
+    }
+    let (mut cursor_major, mut cursor_minor) = (0, 0);
+    for (area, placement) in areas.iter_mut().zip(placements) {
+        if area.is_some() {
+            continue;
+        }
+        let major_span = match placement[major] {
+            Placement::Auto(span) => span,
+            Placement::Definite(start, end) => (end - start) as usize,
+        };
+        let minor = match placement[minor] {
+            Placement::Definite(start, end) => {
+                let minor = tracks(minor, start, end);
+                if dense {
+                    cursor_major = 0;
+                } else if minor.start < cursor_minor {
+                    cursor_major += 1;
+                }
+                while !fits(
+                    &occupied,
+                    &(cursor_major..cursor_major + major_span),
+                    &minor,
+                ) {
+                    cursor_major += 1;
+                }
+                cursor_minor = minor.start;
+                minor
+            }
+            Placement::Auto(span) => {
+                if dense {
+                    (cursor_major, cursor_minor) = (0, 0);
+                }
+                loop {
+                    let minor = cursor_minor..cursor_minor + span;
+                    if minor.end > minor_count {
+                        (cursor_major, cursor_minor) = (cursor_major + 1, 0);
+                    } else if fits(
+                        &occupied,
+                        &(cursor_major..cursor_major + major_span),
+                        &minor,
+                    ) {
+                        break minor;
+                    } else {
+                        cursor_minor += 1;
+                    }
+                }
+            }
+        };
+        let major = cursor_major..cursor_major + major_span;
+        occupy(&mut occupied, &major, &minor);
+        *area = Some([major, minor]);
+    }
+
+    let areas: Vec<[Range<usize>; 2]> = areas.into_iter().flatten().collect();
+    let major_count = areas
+        .iter()
+        .map(|area| area[0].end)
+        .fold(offsets[major] + explicit[major], usize::max);
+    let mut counts = [major_count, minor_count];
+    let areas = match column_flow {
+        true => {
+            counts.swap(0, 1);
+            areas.into_iter().map(|[a, b]| [b, a]).collect()
+        }
+        false => areas,
+    };
+    (areas, offsets, counts)
+}
+
+// the tracks of an axis: the explicit ones from `offset`, and implicit ones
+// sized by `grid-auto-rows` or `grid-auto-columns` around them
+fn axis_tracks(
+    explicit: &ExplicitTracks,
+    auto: &[(Sizing, Sizing)],
+    offset: usize,
+    count: usize,
+    items: &[Range<usize>],
+) -> Vec<Track> {
+    let auto = match auto.is_empty() {
+        true => &[(Sizing::Auto, Sizing::Auto)][..],
+        false => auto,
+    };
+    let explicit_count = explicit.sizes.len();
+    (0..count)
+        .map(|index| {
+            let (min, max) = match index.checked_sub(offset) {
+                Some(explicit_index) if explicit_index < explicit_count => {
+                    explicit.sizes[explicit_index]
+                }
+                // implicit tracks repeat the pattern from the explicit grid
+                Some(after) => auto[(after - explicit_count) % auto.len()],
+                None => auto[(auto.len() - (offset - index) % auto.len()) % auto.len()],
+            };
+            let collapsed = explicit.auto_fit.as_ref().is_some_and(|auto_fit| {
+                index >= offset
+                    && auto_fit.contains(&(index - offset))
+                    && !items.iter().any(|span| span.contains(&index))
+            });
+            Track {
+                min,
+                max,
+                base: 0.0,
+                limit: 0.0,
+                collapsed,
+            }
+        })
+        .collect()
+}
+
+// the size of the gaps between the visible tracks of `span`
+fn gaps(tracks: &[Track], span: Range<usize>, gap: f32) -> f32 {
+    let visible = tracks[span].iter().filter(|track| !track.collapsed).count();
+    gap * visible.saturating_sub(1) as f32
+}
+
+// the size of the tracks and the gaps between them
+fn extent(tracks: &[Track], gap: f32) -> f32 {
+    tracks.iter().map(|track| track.base).sum::<f32>() + gaps(tracks, 0..tracks.len(), gap)
+}
+
+// grows the base sizes, or the growth limits, of the `affected` tracks of
+// `span` equally so that the tracks take `size` in total
+fn grow(
+    tracks: &mut [Track],
+    span: Range<usize>,
+    affected: impl Fn(&Track) -> bool,
+    size: f32,
+    limits: bool,
+) {
+    let current = |track: &Track| match limits && track.limit.is_finite() {
+        true => track.limit,
+        false => track.base,
+    };
+    let extra = size - tracks[span.clone()].iter().map(current).sum::<f32>();
+    let affected: Vec<usize> = span.filter(|&index| affected(&tracks[index])).collect();
+    if extra <= 0.0 || affected.is_empty() {
+        return;
+    }
+    let share = extra / affected.len() as f32;
+    for index in affected {
+        let track = &mut tracks[index];
+        match limits {
+            true => {
+                track.limit = current(track) + share;
+                if let Sizing::FitContent(limit) = track.max {
+                    track.limit = track.limit.min(limit.max(track.base));
+                }
+            }
+            false => track.base += share,
+        }
+    }
+}
+
+// §12.7.1: the size of `1fr` for the flexible tracks of `span` to fill
+// `space`, treating the tracks whose base size is larger than their share
+// as inflexible
+fn fr_size(tracks: &[Track], span: Range<usize>, space: f32) -> f32 {
+    let flex = |track: &Track| match track.max {
+        Sizing::Flex(flex) => Some(flex),
+        _ => None,
+    };
+    let mut inflexible: Vec<bool> = tracks[span.clone()]
+        .iter()
+        .map(|track| flex(track).is_none())
+        .collect();
+    loop {
+        let spanned = || tracks[span.clone()].iter().zip(inflexible.iter());
+        let leftover = space
+            - spanned()
+                .filter(|(_, inflexible)| **inflexible)
+                .map(|(track, _)| track.base)
+                .sum::<f32>();
+        let factors = spanned()
+            .filter(|(_, inflexible)| !**inflexible)
+            .filter_map(|(track, _)| flex(track))
+            .sum::<f32>()
+            .max(1.0);
+        let size = leftover.max(0.0) / factors;
+        let mut changed = false;
+        for (track, inflexible) in tracks[span.clone()].iter().zip(inflexible.iter_mut()) {
+            if !*inflexible && size * flex(track).unwrap_or(0.0) < track.base {
+                *inflexible = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            return size;
+        }
+    }
+}
+
+// §12.3: sizes the tracks of an axis for the `available` space, or under a
+// min-content or max-content constraint when it is indefinite; `stretch`
+// expands the `auto` tracks into the free space
+fn size_tracks(
+    tracks: &mut [Track],
+    contributions: &[Contribution],
+    available: Option<f32>,
+    gap: f32,
+    min_content: bool,
+    stretch: bool,
+) {
+    for track in tracks.iter_mut() {
+        track.base = match track.min {
+            Sizing::Fixed(size) => size,
+            _ => 0.0,
+        };
+        track.limit = match track.max {
+            Sizing::Fixed(size) => size.max(track.base),
+            Sizing::Flex(_) => track.base,
+            _ => f32::INFINITY,
+        };
+        if track.collapsed {
+            (track.base, track.limit) = (0.0, 0.0);
+        }
+    }
+
+    // the intrinsic sizes of the tracks fit the items spanning them, from
+    // the items spanning the fewest tracks
+    let crosses_flex = |tracks: &[Track], span: &Range<usize>| {
+        tracks[span.clone()]
+            .iter()
+            .any(|track| matches!(track.max, Sizing::Flex(_)))
+    };
+    let mut sorted: Vec<&Contribution> = contributions.iter().collect();
+    sorted.sort_by_key(|contribution| contribution.span.len());
+    let (flexible, inflexible): (Vec<&Contribution>, Vec<&Contribution>) = sorted
+        .into_iter()
+        .partition(|contribution| crosses_flex(tracks, &contribution.span));
+    for contribution in inflexible {
+        let span = contribution.span.clone();
+        let gaps = gaps(tracks, span.clone(), gap);
+        let (min_size, max_size) = (
+            contribution.min_content - gaps,
+            contribution.max_content - gaps,
+        );
+        grow(
+            tracks,
+            span.clone(),
+            |t| t.min.is_intrinsic(),
+            min_size,
+            false,
+        );
+        grow(
+            tracks,
+            span.clone(),
+            |t| t.min == Sizing::MaxContent,
+            max_size,
+            false,
+        );
+        for track in &mut tracks[span.clone()] {
+            track.limit = track.limit.max(track.base);
+        }
+        grow(
+            tracks,
+            span.clone(),
+            |t| t.max.is_intrinsic(),
+            min_size,
+            true,
+        );
+        let max_content = |t: &Track| t.max.is_intrinsic() && t.max != Sizing::MinContent;
+        grow(tracks, span, max_content, max_size, true);
+    }
+    for contribution in flexible {
+        let span = contribution.span.clone();
+        let size = contribution.min_content - gaps(tracks, span.clone(), gap);
+        let flexible =
+            |track: &Track| matches!(track.max, Sizing::Flex(_)) && track.min.is_intrinsic();
+        grow(tracks, span, flexible, size, false);
+    }
+    for track in tracks.iter_mut() {
+        if !track.limit.is_finite() || track.limit < track.base {
+            track.limit = track.base;
+        }
+    }
+    if min_content {
+        return;
+    }
+
+    // grow the tracks up to their growth limits, then the flexible ones
+    match available {
+        Some(available) => {
+            let mut free = available - extent(tracks, gap);
+            while free > 0.0 {
+                let growing: Vec<usize> = (0..tracks.len())
+                    .filter(|&index| tracks[index].base < tracks[index].limit)
+                    .collect();
+                if growing.is_empty() {
+                    break;
+                }
+                let share = free / growing.len() as f32;
+                for index in growing {
+                    let track = &mut tracks[index];
+                    let grown = (track.base + share).min(track.limit);
+                    free -= grown - track.base;
+                    track.base = grown;
+                }
+            }
+        }
+        None => {
+            for track in tracks.iter_mut() {
+                track.base = track.limit;
+            }
+        }
+    }
+    let fraction = match available {
+        Some(available) => fr_size(
+            tracks,
+            0..tracks.len(),
+            available - gaps(tracks, 0..tracks.len(), gap),
+        ),
+        // the largest fraction needed by a track or the items spanning it
+        None => {
+            let tracks_fraction = tracks
+                .iter()
+                .filter_map(|track| match track.max {
+                    Sizing::Flex(flex) if flex > 1.0 => Some(track.base / flex),
+                    Sizing::Flex(_) => Some(track.base),
+                    _ => None,
+                })
+                .fold(0.0, f32::max);
+            contributions
+                .iter()
+                .filter(|c| crosses_flex(tracks, &c.span))
+                .map(|c| {
+                    let space = c.max_content - gaps(tracks, c.span.clone(), gap);
+                    fr_size(tracks, c.span.clone(), space)
+                })
+                .fold(tracks_fraction, f32::max)
+        }
+    };
+    for track in tracks.iter_mut() {
+        if let Sizing::Flex(flex) = track.max {
+            track.base = track.base.max(fraction * flex);
+        }
+    }
+
+    if let (Some(available), true) = (available, stretch) {
+        let free = available - extent(tracks, gap);
+        let auto: Vec<&mut Track> = tracks
+            .iter_mut()
+            .filter(|track| track.max == Sizing::Auto && !track.collapsed)
+            .collect();
+        if free > 0.0 && !auto.is_empty() {
+            let share = free / auto.len() as f32;
+            for track in auto {
+                track.base += share;
+            }
+        }
+    }
+}
+
+// the start of each track, with `leading` space before the first one and
+// `between` space added to each gap
+fn track_positions(tracks: &[Track], gap: f32, leading: f32, between: f32) -> Vec<f32> {
+    let mut position = leading;
+    let mut first = true;
+    tracks
+        .iter()
+        .map(|track| {
+            if !track.collapsed {
+                if !first {
+                    position += gap + between;
+                }
+                first = false;
+            }
+            let start = position;
+            position += track.base;
+            start
+        })
+        .collect()
+}
+
+// the start and the size of the area spanning tracks
+fn area(tracks: &[Track], positions: &[f32], span: &Range<usize>) -> (f32, f32) {
+    let last = span.end - 1;
+    let start = positions[span.start];
+    (start, positions[last] + tracks[last].base - start)
+}
+
+// the alignment of an item along the rows of the grid
+fn justification(item: &ComputedStyle, container: &ComputedStyle) -> Alignment {
+    match item.justify_self {
+        Alignment::Auto => container.justify_items,
+        alignment => alignment,
+    }
+}
+
+// the offset of the margin box of an item in its area, auto margins taking
+// the free space first
+fn item_offset(alignment: Alignment, free: f32, before: &Size, after: &Size) -> f32 {
+    match (*before == Size::Auto, *after == Size::Auto) {
+        (true, true) => free.max(0.0) / 2.0,
+        (true, false) => free.max(0.0),
+        (false, true) => 0.0,
+        (false, false) => distribute(alignment, free, 1, false).0,
+    }
+}
+
+fn is_stretch(alignment: Alignment) -> bool {
+    matches!(alignment, Alignment::Stretch | Alignment::Normal)
+}
+
+impl<'a> LayoutBox<'a> {
+    pub(crate) fn is_grid_container(&self) -> bool {
+        matches!(self.style().display, Display::Grid | Display::InlineGrid)
+    }
+
+    // the explicit grid of the container and its items placed in it, with
+    // the sizing functions of the tracks
+    fn grid(&self, width: Option<f32>, height: Option<f32>, column_gap: f32, row_gap: f32) -> Grid {
+        let style = self.style();
+        let areas = template_areas(&style.grid_template_areas);
+        let auto_rows: Vec<(Sizing, Sizing)> = style
+            .grid_auto_rows
+            .iter()
+            .map(|size| track_sizing(size, height))
+            .collect();
+        let auto_columns: Vec<(Sizing, Sizing)> = style
+            .grid_auto_columns
+            .iter()
+            .map(|size| track_sizing(size, width))
+            .collect();
+        let mut rows = explicit_tracks(&style.grid_template_rows, height, row_gap);
+        let mut columns = explicit_tracks(&style.grid_template_columns, width, column_gap);
+
+        // the template areas extend the explicit grid, and name the lines
+        // around them
+        for (tracks, auto, end) in [
+            (
+                &mut rows,
+                &auto_rows,
+                areas.iter().map(|area| area.rows.end).max(),
+            ),
+            (
+                &mut columns,
+                &auto_columns,
+                areas.iter().map(|area| area.columns.end).max(),
+            ),
+        ] {
+            while tracks.sizes.len() < end.unwrap_or(0) {
+                let implicit = auto.get(tracks.sizes.len() % auto.len().max(1));
+                tracks
+                    .sizes
+                    .push(*implicit.unwrap_or(&(Sizing::Auto, Sizing::Auto)));
+                tracks.names.push(Vec::new());
+            }
+        }
+        for area in &areas {
+            rows.names[area.rows.start].push(format!("{}-start", area.name));
+            rows.names[area.rows.end].push(format!("{}-end", area.name));
+            columns.names[area.columns.start].push(format!("{}-start", area.name));
+            columns.names[area.columns.end].push(format!("{}-end", area.name));
+        }
+
+        let mut order: Vec<usize> = (0..self.children.len())
+            .filter(|&index| !self.children[index].is_absolutely_positioned())
+            .collect();
+        order.sort_by_key(|&index| self.children[index].style().order);
+        let placements: Vec<[Placement; 2]> = order
+            .iter()
+            .map(|&index| {
+                let item = self.children[index].style();
+                [
+                    placement(&item.grid_row_start, &item.grid_row_end, &rows.names),
+                    placement(
+                        &item.grid_column_start,
+                        &item.grid_column_end,
+                        &columns.names,
+                    ),
+                ]
+            })
+            .collect();
+        let (placed, offsets, counts) = place_items(
+            &placements,
+            [rows.sizes.len(), columns.sizes.len()],
+            style.grid_auto_flow == GridAutoFlow::Column,
+            style.grid_auto_flow_dense,
+        );
+
+        let items: Vec<GridItem> = order
+            .into_iter()
+            .zip(placed)
+            .map(|(index, [rows, columns])| GridItem {
+                index,
+                rows,
+                columns,
+            })
+            .collect();
+        let spans = |axis: fn(&GridItem) -> &Range<usize>| {
+            items
+                .iter()
+                .map(axis)
+                .cloned()
+                .collect::<Vec<Range<usize>>>()
+        };
+        Grid {
+            rows: axis_tracks(
+                &rows,
+                &auto_rows,
+                offsets[0],
+                counts[0],
+                &spans(|item| &item.rows),
+            ),
+            columns: axis_tracks(
+                &columns,
+                &auto_columns,
+                offsets[1],
+                counts[1],
+                &spans(|item| &item.columns),
+            ),
+            items,
+        }
+    }
+
+    // lays out a grid item in its area `width` wide, and `height` tall once
+    // the rows are sized, with its margin box at the origin
+    fn layout_grid_item(
+        &mut self,
+        context: &LayoutContext,
+        container: &ComputedStyle,
+        width: f32,
+        height: Option<f32>,
+    ) {
+        let (padding, border) = self.padding_and_border(width);
+        let style = self.style();
+        let margin = |size: &Size| size.resolve(Some(width)).unwrap_or(0.0);
+        let mut dimensions = Dimensions {
+            padding,
+            border,
+            margin: EdgeSizes {
+                left: margin(&style.margin.left),
+                right: margin(&style.margin.right),
+                top: margin(&style.margin.top),
+                bottom: margin(&style.margin.bottom),
+            },
+            ..Dimensions::default()
+        };
+        let edges_x = padding.left + padding.right + border.left + border.right;
+        let edges_y = padding.top + padding.bottom + border.top + border.bottom;
+
+        // items stretch across their area, or fit their content
+        let available = width - edges_x - dimensions.margin.left - dimensions.margin.right;
+        let stretched = is_stretch(justification(style, container))
+            && style.width == Size::Auto
+            && style.margin.left != Size::Auto
+            && style.margin.right != Size::Auto;
+        let content_width = |size: &Size| content_size(style, size, Some(width), edges_x);
+        let mut content = content_width(&style.width).unwrap_or_else(|| match stretched {
+            true => available.max(0.0),
+            false => {
+                let (min_content, max_content) = self.intrinsic_widths(context);
+                max_content.min(available).max(min_content)
+            }
+        });
+        if let Some(max_width) = content_width(&style.max_width) {
+            content = content.min(max_width);
+        }
+        if let Some(min_width) = content_width(&style.min_width) {
+            content = content.max(min_width);
+        }
+        dimensions.content.width = content;
+
+        let stretched = is_stretch(self_alignment(style, container))
+            && style.height == Size::Auto
+            && style.margin.top != Size::Auto
+            && style.margin.bottom != Size::Auto;
+        let content_height = |size: &Size| content_size(style, size, height, edges_y);
+        let definite = height.filter(|_| stretched).map(|height| {
+            let mut size = height - edges_y - dimensions.margin.top - dimensions.margin.bottom;
+            if let Some(max_height) = content_height(&style.max_height) {
+                size = size.min(max_height);
+            }
+            if let Some(min_height) = content_height(&style.min_height) {
+                size = size.max(min_height);
+            }
+            size.max(0.0)
+        });
+        self.layout_sized(context, dimensions, height, definite);
+    }
+
+    // lays out the grid items of a grid container from the top of its
+    // content; returns the height of its content
+    pub(crate) fn layout_grid_items(
+        &mut self,
+        context: &LayoutContext,
+        containing: &ContainingBlock,
+    ) -> f32 {
+        let style = &self.styled_node().unwrap().style;
+        let column_gap = style
+            .column_gap
+            .as_ref()
+            .map_or(0.0, |gap| gap.resolve(containing.width));
+        let row_gap = style
+            .row_gap
+            .as_ref()
+            .map_or(0.0, |gap| gap.resolve(containing.height.unwrap_or(0.0)));
+        let Grid {
+            items,
+            mut rows,
+            mut columns,
+        } = self.grid(
+            Some(containing.width),
+            containing.height,
+            column_gap,
+            row_gap,
+        );
+
+        // size the columns, then the rows from the heights of the items laid
+        // out in their columns
+        let contributions: Vec<Contribution> = items
+            .iter()
+            .map(|item| {
+                let (min_content, max_content) =
+                    self.children[item.index].outer_intrinsic_widths(context);
+                Contribution {
+                    span: item.columns.clone(),
+                    min_content,
+                    max_content,
+                }
+            })
+            .collect();
+        size_tracks(
+            &mut columns,
+            &contributions,
+            Some(containing.width),
+            column_gap,
+            false,
+            is_stretch(style.justify_content),
+        );
+        let visible = |tracks: &[Track]| tracks.iter().filter(|track| !track.collapsed).count();
+        let free = containing.width - extent(&columns, column_gap);
+        let (leading, between) = distribute(style.justify_content, free, visible(&columns), false);
+        let column_positions = track_positions(&columns, column_gap, leading, between);
+
+        let contributions: Vec<Contribution> = items
+            .iter()
+            .map(|item| {
+                let (_, width) = area(&columns, &column_positions, &item.columns);
+                let child = &mut self.children[item.index];
+                child.layout_grid_item(context, style, width, None);
+                let height = child.dimensions.margin_box().height;
+                Contribution {
+                    span: item.rows.clone(),
+                    min_content: height,
+                    max_content: height,
+                }
+            })
+            .collect();
+        size_tracks(
+            &mut rows,
+            &contributions,
+            containing.height,
+            row_gap,
+            false,
+            is_stretch(style.align_content),
+        );
+        let height = extent(&rows, row_gap);
+        let (leading, between) = match containing.height {
+            Some(available) => distribute(
+                style.align_content,
+                available - height,
+                visible(&rows),
+                false,
+            ),
+            None => (0.0, 0.0),
+        };
+        let row_positions = track_positions(&rows, row_gap, leading, between);
+
+        // lay out the items in their areas, sharing a baseline with the
+        // baseline-aligned items starting in the same row
+        let mut baselines: Vec<f32> = Vec::new();
+        let mut row_baselines: HashMap<usize, f32> = HashMap::new();
+        for item in &items {
+            let (_, width) = area(&columns, &column_positions, &item.columns);
+            let (_, height) = area(&rows, &row_positions, &item.rows);
+            let child = &mut self.children[item.index];
+            child.layout_grid_item(context, style, width, Some(height));
+            let margin_box = child.dimensions.margin_box();
+            let baseline = child
+                .first_baseline()
+                .unwrap_or(margin_box.y + margin_box.height)
+                - margin_box.y;
+            baselines.push(baseline);
+            if self_alignment(child.style(), style) == Alignment::Baseline {
+                let shared = row_baselines.entry(item.rows.start).or_insert(0.0);
+                *shared = shared.max(baseline);
+            }
+        }
+        for (item, baseline) in items.iter().zip(baselines) {
+            let (x, width) = area(&columns, &column_positions, &item.columns);
+            let (y, height) = area(&rows, &row_positions, &item.rows);
+            let child = &mut self.children[item.index];
+            let item_style = child.style();
+            let margin_box = child.dimensions.margin_box();
+            let dx = item_offset(
+                justification(item_style, style),
+                width - margin_box.width,
+                &item_style.margin.left,
+                &item_style.margin.right,
+            );
+            let dy = match self_alignment(item_style, style) {
+                Alignment::Baseline => row_baselines[&item.rows.start] - baseline,
+                alignment => item_offset(
+                    alignment,
+                    height - margin_box.height,
+                    &item_style.margin.top,
+                    &item_style.margin.bottom,
+                ),
+            };
+            child.translate(
+                containing.x + x + dx - margin_box.x,
+                containing.y + y + dy - margin_box.y,
+            );
+        }
+        height
+    }
+
+    // the min-content and max-content widths of the content of a grid
+    // container
+    pub(crate) fn grid_intrinsic_widths(&self, context: &LayoutContext) -> (f32, f32) {
+        let style = self.style();
+        let gap = style
+            .column_gap
+            .as_ref()
+            .map_or(0.0, |gap| gap.resolve(0.0));
+        let Grid { items, columns, .. } = self.grid(None, None, gap, 0.0);
+        let contributions: Vec<Contribution> = items
+            .iter()
+            .map(|item| {
+                let (min_content, max_content) =
+                    self.children[item.index].outer_intrinsic_widths(context);
+                Contribution {
+                    span: item.columns.clone(),
+                    min_content,
+                    max_content,
+                }
+            })
+            .collect();
+        let mut min_columns = columns.clone();
+        size_tracks(&mut min_columns, &contributions, None, gap, true, false);
+        let mut max_columns = columns;
+        size_tracks(&mut max_columns, &contributions, None, gap, false, false);
+        (extent(&min_columns, gap), extent(&max_columns, gap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::test_util::{border_box, with_layout};
+
+    const ITEMS: &str = "<div id='c'><div id='a'></div><div id='b'></div><div id='d'></div>\
+        <div id='e'></div></div>";
+
+    #[test]
+    fn test_track_sizing() {
+        with_layout(
+            "<div id='c'><div id='a'></div><div id='b'>aaaa</div><div id='d'></div></div>",
+            "#c { display: grid; width: 400px;
+              grid-template-columns: 50px auto minmax(100px, 1fr); }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (0.0, 0.0, 50.0, 10.0));
+                assert_eq!(border_box(root, "b"), (50.0, 0.0, 24.0, 10.0));
+                assert_eq!(border_box(root, "d"), (74.0, 0.0, 326.0, 10.0));
+            },
+        );
+        // flexible tracks don't shrink below the content of their items
+        with_layout(
+            "<div id='c'><div id='a'>aaaa</div><div id='b'>b</div></div>",
+            "#c { display: grid; width: 30px; grid-template-columns: 1fr 1fr; }",
+            |root| {
+                assert_eq!(border_box(root, "a").2, 24.0);
+                assert_eq!(border_box(root, "b"), (24.0, 0.0, 6.0, 10.0));
+            },
+        );
+        // rows fit the tallest item, and auto tracks stretch
+        with_layout(
+            ITEMS,
+            "#c { display: grid; width: 100px; height: 100px; grid-template-columns: 20px 1fr;
+              grid-template-rows: 30px auto; gap: 10px 5px; } #d { height: 20px; }",
+            |root| {
+                assert_eq!(border_box(root, "b"), (25.0, 0.0, 75.0, 30.0));
+                assert_eq!(border_box(root, "d"), (0.0, 40.0, 20.0, 20.0));
+                assert_eq!(border_box(root, "e"), (25.0, 40.0, 75.0, 60.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_repeat() {
+        let css = "#c { display: grid; width: 350px; column-gap: 10px; grid-auto-rows: 20px; }";
+        with_layout(
+            ITEMS,
+            &format!(
+                "{} #c {{ grid-template-columns: repeat(auto-fill, 100px); }}",
+                css
+            ),
+            |root| {
+                assert_eq!(border_box(root, "d"), (220.0, 0.0, 100.0, 20.0));
+                assert_eq!(border_box(root, "e"), (0.0, 20.0, 100.0, 20.0));
+            },
+        );
+        // empty tracks repeated by `auto-fit` collapse
+        with_layout(
+            "<div id='c'><div id='a'></div></div>",
+            &format!(
+                "{} #c {{ grid-template-columns: repeat(auto-fit, 100px); justify-content: center; }}",
+                css
+            ),
+            |root| assert_eq!(border_box(root, "a"), (125.0, 0.0, 100.0, 20.0)),
+        );
+        with_layout(
+            ITEMS,
+            "#c { display: grid; width: 100px; grid-template-columns: repeat(2, [x] 10px) [x];
+              grid-auto-rows: 10px; } #a { grid-column: x 2 / x -1; } #b { grid-column: x; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (10.0, 0.0, 10.0, 10.0));
+                assert_eq!(border_box(root, "b"), (0.0, 10.0, 10.0, 10.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_placement() {
+        with_layout(
+            ITEMS,
+            "#c { display: grid; grid-template-columns: [a] 50px [b] 50px [c] 50px;
+              grid-template-rows: 20px 20px; }
+            #a { grid-column: b / -1; } #b { grid-row: 2; grid-column: span 2; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (50.0, 0.0, 100.0, 20.0));
+                assert_eq!(border_box(root, "b"), (0.0, 20.0, 100.0, 20.0));
+                // the cursor never goes back in sparse packing
+                assert_eq!(border_box(root, "d"), (100.0, 20.0, 50.0, 20.0));
+                assert_eq!(border_box(root, "e"), (0.0, 40.0, 50.0, 0.0));
+            },
+        );
+        // dense packing fills the holes left before
+        let css =
+            "#c { display: grid; grid-template-columns: repeat(3, 10px); grid-auto-rows: 10px; }
+            #a, #b { grid-column: span 2; }";
+        with_layout(ITEMS, css, |root| {
+            assert_eq!(border_box(root, "b"), (0.0, 10.0, 20.0, 10.0));
+            assert_eq!(border_box(root, "d"), (20.0, 10.0, 10.0, 10.0));
+        });
+        with_layout(
+            ITEMS,
+            &format!("{} #c {{ grid-auto-flow: row dense; }}", css),
+            |root| assert_eq!(border_box(root, "d"), (20.0, 0.0, 10.0, 10.0)),
+        );
+        with_layout(
+            ITEMS,
+            "#c { display: grid; width: 100px;
+              grid-auto-flow: column; grid-template-rows: 10px 10px; }
+            #a { order: 1; }",
+            |root| {
+                assert_eq!(border_box(root, "b"), (0.0, 0.0, 50.0, 10.0));
+                assert_eq!(border_box(root, "e"), (50.0, 0.0, 50.0, 10.0));
+                assert_eq!(border_box(root, "a"), (50.0, 10.0, 50.0, 10.0));
+            },
+        );
+        // lines before the explicit grid add implicit tracks
+        with_layout(
+            ITEMS,
+            "#c { display: grid; width: 100px;
+              grid-template-columns: 30px; grid-auto-columns: 10px; }
+            #a { grid-column: -3; }",
+            |root| {
+                assert_eq!(border_box(root, "a").0, 0.0);
+                assert_eq!(border_box(root, "b"), (10.0, 0.0, 30.0, 0.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_template_areas() {
+        with_layout(
+            ITEMS,
+            "#c { display: grid; grid-template-areas: \"head head\" \"side main\" \". main\";
+              grid-template-columns: 30px 70px; grid-template-rows: 10px 40px 5px; }
+            #a { grid-area: main; } #b { grid-area: head; } #d { grid-row: side; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (30.0, 10.0, 70.0, 45.0));
+                assert_eq!(border_box(root, "b"), (0.0, 0.0, 100.0, 10.0));
+                assert_eq!(border_box(root, "d"), (0.0, 10.0, 30.0, 40.0));
+                assert_eq!(border_box(root, "e"), (0.0, 50.0, 30.0, 5.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_alignment() {
+        with_layout(
+            ITEMS,
+            "#c { display: grid; width: 200px;
+              grid-template-columns: 50px 50px; grid-template-rows: 40px;
+              justify-content: space-between; align-items: center; }
+            #a { width: 20px; height: 10px; justify-self: center; align-self: end; }
+            #b { height: 10px; } #d { width: 10px; margin-left: auto; } #e { align-self: stretch; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (15.0, 30.0, 20.0, 10.0));
+                assert_eq!(border_box(root, "b"), (150.0, 15.0, 50.0, 10.0));
+                assert_eq!(border_box(root, "d"), (40.0, 40.0, 10.0, 0.0));
+                assert_eq!(border_box(root, "e"), (150.0, 40.0, 50.0, 0.0));
+            },
+        );
+        with_layout(
+            "<div id='c'><div id='a'>x</div><div id='b'>x</div></div>",
+            "#c { display: grid; grid-template-columns: 20px 20px; align-items: baseline; }
+            #a { font-size: 20px; line-height: 20px; }",
+            |root| {
+                assert_eq!(border_box(root, "a").1, 0.0);
+                assert_eq!(border_box(root, "b").1, 8.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_inline_grid() {
+        with_layout(
+            "<p><span id='c'><span id='a'>ab</span><span id='b'>cde</span></span></p>",
+            "p { margin: 0; } #c { display: inline-grid; grid-template-columns: auto 1fr;
+              column-gap: 4px; }",
+            |root| {
+                assert_eq!(border_box(root, "c"), (0.0, 0.0, 34.0, 10.0));
+                assert_eq!(border_box(root, "b"), (16.0, 0.0, 18.0, 10.0));
+            },
+        );
+    }
+}
@@ -6,6 +6,15 @@ pub struct HTMLParser {
     text_parser: TextParser,
 }
 
+// elements that never have children nor an end tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+// elements whose content is text up to their end tag, even if it contains `<`
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
 impl HTMLParser {
     pub fn new(input: String) -> HTMLParser {
         let text_parser = TextParser::new(input);
@@ -21,7 +30,7 @@ impl HTMLParser {
         Node::text(self.text_parser.parse_text_data())
     }
 
-    fn parse_nodes(&mut self) -> Vec<Box<Node>> {
+    fn parse_nodes(&mut self) -> Vec<Node> {
         let mut nodes = Vec::new();
         loop {
             self.text_parser.remove_whitespaces();
@@ -33,15 +42,38 @@ impl HTMLParser {
                 '<' => self.parse_element_node(),
                 _ => self.parse_text_node(),
             };
-            nodes.push(Box::new(node));
+            nodes.push(node);
         }
         nodes
     }
 
+    fn parse_raw_text(&mut self, tag_name: &str) -> Vec<Node> {
+        let end_tag = format!("</{}", tag_name);
+        let mut text = String::new();
+        while !self.text_parser.eol() && !self.text_parser.starts_with(&end_tag) {
+            text.push(self.text_parser.consume_char());
+        }
+        match text.trim().is_empty() {
+            true => Vec::new(),
+            false => vec![Node::text(text)],
+        }
+    }
+
     fn parse_element_node(&mut self) -> Node {
         // parse tag name
-        let (tag_name, attrs) = self.consume_element_tag();
-        let children = self.parse_nodes();
+        let (tag_name, attrs, self_closing) = self.consume_element_tag();
+        if self_closing || VOID_ELEMENTS.contains(&tag_name.to_lowercase().as_str()) {
+            // tolerate an end tag anyway
+            let end_tag = format!("</{}>", tag_name);
+            if self.text_parser.starts_with(&end_tag) {
+                self.text_parser.consume_pattern(end_tag);
+            }
+            return Node::element(tag_name, attrs, Vec::new());
+        }
+        let children = match RAW_TEXT_ELEMENTS.contains(&tag_name.to_lowercase().as_str()) {
+            true => self.parse_raw_text(&tag_name),
+            false => self.parse_nodes(),
+        };
 
         // Check for tag closing
         assert!(self.text_parser.consume_char() == '<');
@@ -49,43 +81,73 @@ impl HTMLParser {
         assert!(self.parse_tag_name() == tag_name);
         assert!(self.text_parser.consume_char() == '>');
 
-        Node::element(tag_name, attrs, children)
+        Node::element(
+            tag_name,
+            attrs,
+            children.into_iter().map(Box::new).collect(),
+        )
+    }
+
+    fn parse_attribute_value(&mut self) -> String {
+        match self.text_parser.get_current_char() {
+            quote @ ('"' | '\'') => {
+                self.text_parser.consume_char();
+                let value = self.text_parser.consume_chars_while(|c| c != quote);
+                assert!(self.text_parser.consume_char() == quote);
+                value
+            }
+            _ => self
+                .text_parser
+                .consume_chars_while(|c| !c.is_whitespace() && c != '>'),
+        }
     }
 
     fn parse_element_attributes(&mut self) -> HashMap<String, String> {
         let mut attrs: HashMap<String, String> = HashMap::new();
-        let cur_char = self.text_parser.get_current_char();
-        match cur_char {
-            '>' => attrs,
-            _ => {
-                self.text_parser.consume_char(); // consume the first whitespace
-                let cur = self.text_parser.consume_chars_while(|c| c != '>');
-                let all_pairs = cur.split(" ");
-                for pair in all_pairs {
-                    let (attr, val) = pair.split_once("=").unwrap();
-                    attrs.insert(attr.to_string(), val.to_string());
-                }
-                attrs
+        loop {
+            self.text_parser.remove_whitespaces();
+            if self.text_parser.starts_with(">") || self.text_parser.starts_with("/>") {
+                break;
             }
+            let attr = self
+                .text_parser
+                .consume_chars_while(|c| !c.is_whitespace() && c != '=' && c != '>' && c != '/');
+            assert!(!attr.is_empty());
+
+            // attributes without a value (e.g. `checked`) are stored with an empty one
+            let value = if self.text_parser.starts_with("=") {
+                self.text_parser.consume_char();
+                self.parse_attribute_value()
+            } else {
+                String::new()
+            };
+            attrs.insert(attr.to_lowercase(), value);
         }
+        attrs
     }
 
-    fn consume_element_tag(&mut self) -> (String, HashMap<String, String>) {
+    // the name and attributes of a start tag, and whether it is self-closing
+    fn consume_element_tag(&mut self) -> (String, HashMap<String, String>, bool) {
         assert!(self.text_parser.consume_char() == '<');
         let tag_name = self.parse_tag_name();
         let attrs = self.parse_element_attributes();
+        let self_closing = self.text_parser.starts_with("/");
+        if self_closing {
+            self.text_parser.consume_char();
+        }
         assert!(self.text_parser.consume_char() == '>');
-        (tag_name, attrs)
+        (tag_name, attrs, self_closing)
     }
 
     pub fn parse_document(&mut self) -> Document {
         // parse a document and return the root node
         // parse the root html node
-        let (document_tag, document_attributes) = self.consume_element_tag();
+        let (document_tag, document_attributes, _) = self.consume_element_tag();
         assert!(document_tag == "html");
 
         let all_nodes = self.parse_nodes();
-        let root = Node::element(document_tag, document_attributes, all_nodes);
+        let children = all_nodes.into_iter().map(Box::new).collect();
+        let root = Node::element(document_tag, document_attributes, children);
         Document::new(root)
     }
 }
@@ -108,4 +170,34 @@ mod tests {
         let mut test_parser = HTMLParser::new(test_string.to_string());
         test_parser.parse_element_node();
     }
+
+    #[test]
+    fn test_void_and_raw_text_elements() {
+        let test_string = "<head><meta charset=utf-8><link rel=stylesheet href=a.css/>\
+            <style>p > b { content: \"</b>\"; }</style></head>";
+        let mut test_parser = HTMLParser::new(test_string.to_string());
+        let node = test_parser.parse_element_node();
+        assert_eq!(node.children.len(), 3);
+        assert!(node.children.iter().all(|child| child.children.len() <= 1));
+        assert_eq!(
+            node.children[2].children[0].node_type,
+            crate::dom::NodeType::Text("p > b { content: \"</b>\"; }".to_string())
+        );
+    }
+
+    #[test]
+    fn test_element_attributes() {
+        let test_string = "<a href=\"/docs/a b.pdf\" data-id=3 lang='en-US' hidden></a>";
+        let mut test_parser = HTMLParser::new(test_string.to_string());
+        let node = test_parser.parse_element_node();
+        match node.node_type {
+            crate::dom::NodeType::Element(elem) => {
+                assert_eq!(elem.attributes["href"], "/docs/a b.pdf");
+                assert_eq!(elem.attributes["data-id"], "3");
+                assert_eq!(elem.attributes["lang"], "en-US");
+                assert_eq!(elem.attributes["hidden"], "");
+            }
+            _ => panic!("expected an element node"),
+        }
+    }
 }
@@ -0,0 +1,1111 @@
+use std::ops::Range;
+
+use crate::assign::StyledNode;
+use crate::block::MarginStrut;
+use crate::floats::FloatContext;
+use crate::fonts::FontDatabase;
+use crate::layout::{BoxType, ContainingBlock, LayoutBox, LayoutContext, Rect};
+use crate::style::{
+    BoxSizing, ComputedStyle, Direction, Overflow, TextAlign, TextTransform, VerticalAlign,
+    WhiteSpace,
+};
+
+// a line of inline content in a block container
+#[derive(Debug, Clone)]
+pub struct LineBox<'a> {
+    pub rect: Rect,
+    // the distance from the top of the line to its baseline
+    pub baseline: f32,
+    pub fragments: Vec<Fragment<'a>>,
+}
+
+// the part of an inline-level box on a line
+#[derive(Debug, Clone)]
+pub struct Fragment<'a> {
+    pub rect: Rect,
+    pub kind: FragmentKind<'a>,
+}
+
+#[derive(Debug, Clone)]
+pub enum FragmentKind<'a> {
+    // text as displayed, its rect being the content area of its font
+    Text(&'a StyledNode<'a>, String),
+    // the content area of an inline box, which starts on the line when
+    // `first` and ends on it when `last`
+    Inline {
+        node: &'a StyledNode<'a>,
+        first: bool,
+        last: bool,
+    },
+    // the margin box of an inline-block, laid out as a child of the line
+    Atomic(&'a StyledNode<'a>),
+}
+
+// the space an inline box takes above and below its baseline: its content
+// area and half the leading of its line height on each side
+fn layout_bounds(fonts: &FontDatabase, style: &ComputedStyle) -> (f32, f32) {
+    let (ascent, descent) = fonts.metrics(style);
+    let half_leading = (style.line_height.resolve(style.font_size) - ascent - descent) / 2.0;
+    (ascent + half_leading, descent + half_leading)
+}
+
+// how far `vertical-align` raises a box of the given bounds above the
+// baseline of its parent; `top` and `bottom` align with the parent's text
+fn vertical_offset(
+    fonts: &FontDatabase,
+    style: &ComputedStyle,
+    parent: &ComputedStyle,
+    bounds: (f32, f32),
+) -> f32 {
+    let (parent_ascent, parent_descent) = fonts.metrics(parent);
+    let (above, below) = bounds;
+    match &style.vertical_align {
+        VerticalAlign::Baseline => 0.0,
+        VerticalAlign::Sub => -parent.font_size * 0.2,
+        VerticalAlign::Super => parent.font_size * 0.4,
+        VerticalAlign::TextTop | VerticalAlign::Top => parent_ascent - above,
+        VerticalAlign::TextBottom | VerticalAlign::Bottom => below - parent_descent,
+        // the middle of the box on the middle of the parent's x-height
+        VerticalAlign::Middle => parent.font_size * 0.25 - (above - below) / 2.0,
+        VerticalAlign::LengthPercentage(length) => {
+            length.resolve(style.line_height.resolve(style.font_size))
+        }
+    }
+}
+
+fn collapses_spaces(white_space: WhiteSpace) -> bool {
+    matches!(
+        white_space,
+        WhiteSpace::Normal | WhiteSpace::Nowrap | WhiteSpace::PreLine
+    )
+}
+
+fn preserves_newlines(white_space: WhiteSpace) -> bool {
+    !matches!(white_space, WhiteSpace::Normal | WhiteSpace::Nowrap)
+}
+
+fn wraps(white_space: WhiteSpace) -> bool {
+    !matches!(white_space, WhiteSpace::Pre | WhiteSpace::Nowrap)
+}
+
+fn is_ideographic(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x2E80..=0x2FFF | 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF
+            | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF01..=0xFF60 | 0x20000..=0x2FFFF
+    )
+}
+
+// whether a line can break between two characters, after the classes of
+// UAX #14 that matter most for latin and ideographic text
+pub fn is_break_opportunity(before: char, after: char) -> bool {
+    const GLUE: &[char] = &['\u{A0}', '\u{202F}', '\u{2060}', '\u{FEFF}', '\u{2007}'];
+    const CLOSING: &[char] = &[
+        ')', ']', '}', '!', '?', ',', '.', ':', ';', '/', '\u{3001}', '\u{3002}', '\u{FF09}',
+    ];
+    const OPENING: &[char] = &['(', '[', '{', '\u{FF08}'];
+    const BREAK_AFTER: &[char] = &['-', '\u{2010}', '\u{2013}', '|', '\u{AD}'];
+
+    if after == ' ' || after == '\u{200B}' {
+        return false;
+    }
+    if before == ' ' || before == '\u{200B}' {
+        return true;
+    }
+    if GLUE.contains(&before) || GLUE.contains(&after) {
+        return false;
+    }
+    if CLOSING.contains(&after) || OPENING.contains(&before) {
+        return false;
+    }
+    if BREAK_AFTER.contains(&before) {
+        return after.is_alphanumeric();
+    }
+    is_ideographic(before) || is_ideographic(after)
+}
+
+fn transform_text(text: &str, transform: TextTransform) -> String {
+    match transform {
+        TextTransform::None => text.to_string(),
+        TextTransform::Uppercase => text.to_uppercase(),
+        TextTransform::Lowercase => text.to_lowercase(),
+        TextTransform::Capitalize => {
+            let mut start = true;
+            text.chars()
+                .flat_map(|c| {
+                    let capitalize = start && c.is_alphanumeric();
+                    start = c.is_whitespace();
+                    match capitalize {
+                        true => c.to_uppercase().collect::<Vec<_>>(),
+                        false => vec![c],
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+// the width of the margin, border and padding at the start or the end of
+// an inline box
+fn inline_edge(style: &ComputedStyle, containing_width: f32, start: bool) -> f32 {
+    let (margin, border, padding) = match start {
+        true => (
+            &style.margin.left,
+            style.border_width.left,
+            &style.padding.left,
+        ),
+        false => (
+            &style.margin.right,
+            style.border_width.right,
+            &style.padding.right,
+        ),
+    };
+    margin.resolve(Some(containing_width)).unwrap_or(0.0)
+        + border
+        + padding.resolve(containing_width)
+}
+
+// inline content flattened in order, atomic inlines numbered
+#[derive(Debug, Clone)]
+enum Item<'a> {
+    Text(&'a StyledNode<'a>, String),
+    Open(&'a StyledNode<'a>),
+    Close(&'a StyledNode<'a>),
+    Atomic(&'a StyledNode<'a>, usize),
+    // where an absolutely positioned box would have been, which takes no
+    // space
+    Anchor(&'a StyledNode<'a>),
+    // a float, placed beside the line it is on when it fits
+    Float(&'a StyledNode<'a>),
+}
+
+fn collect_items<'a>(boxes: &[LayoutBox<'a>], items: &mut Vec<Item<'a>>, atomics: &mut usize) {
+    for layout_box in boxes {
+        match &layout_box.box_type {
+            BoxType::Text(node, text) => items.push(Item::Text(node, text.clone())),
+            BoxType::Inline(node) => {
+                items.push(Item::Open(node));
+                collect_items(&layout_box.children, items, atomics);
+                items.push(Item::Close(node));
+            }
+            BoxType::InlineBlock(_) if layout_box.is_outside_marker() => {}
+            BoxType::InlineBlock(node) => {
+                items.push(Item::Atomic(node, *atomics));
+                *atomics += 1;
+            }
+            BoxType::Block(node) if layout_box.is_floated() => items.push(Item::Float(node)),
+            BoxType::Block(node) if layout_box.is_out_of_flow() => items.push(Item::Anchor(node)),
+            BoxType::Block(_) | BoxType::AnonymousBlock(_) => {}
+        }
+    }
+}
+
+// the boxes of inline content that are `selected`, in order
+fn inline_content_boxes<'b, 'a>(
+    boxes: &'b [LayoutBox<'a>],
+    selected: fn(&LayoutBox<'a>) -> bool,
+    found: &mut Vec<&'b LayoutBox<'a>>,
+) {
+    for layout_box in boxes {
+        if selected(layout_box) {
+            found.push(layout_box);
+        } else if let BoxType::Inline(_) = layout_box.box_type {
+            inline_content_boxes(&layout_box.children, selected, found);
+        }
+    }
+}
+
+// calls `f` on the boxes of inline content that are `selected`, in order
+fn for_each_inline_content_box<'a>(
+    boxes: &mut [LayoutBox<'a>],
+    selected: fn(&LayoutBox<'a>) -> bool,
+    f: &mut impl FnMut(&mut LayoutBox<'a>),
+) {
+    for layout_box in boxes {
+        if selected(layout_box) {
+            f(layout_box);
+        } else if let BoxType::Inline(_) = layout_box.box_type {
+            for_each_inline_content_box(&mut layout_box.children, selected, f);
+        }
+    }
+}
+
+fn is_atomic(layout_box: &LayoutBox) -> bool {
+    matches!(layout_box.box_type, BoxType::InlineBlock(_)) && !layout_box.is_outside_marker()
+}
+
+// an unbreakable piece of inline content
+#[derive(Debug, Clone)]
+struct Piece<'a> {
+    item: Item<'a>,
+    width: f32,
+    // the width of the spaces ending the piece, which hang past the end of
+    // a line
+    hanging: f32,
+    break_after: bool,
+    // a preserved newline ends the piece
+    forced_break: bool,
+}
+
+impl<'a> Piece<'a> {
+    fn new(item: Item<'a>, width: f32) -> Piece<'a> {
+        Piece {
+            item,
+            width,
+            hanging: 0.0,
+            break_after: false,
+            forced_break: false,
+        }
+    }
+
+    fn style(&self) -> &'a ComputedStyle {
+        match self.item {
+            Item::Text(node, _)
+            | Item::Open(node)
+            | Item::Close(node)
+            | Item::Atomic(node, _)
+            | Item::Anchor(node)
+            | Item::Float(node) => &node.style,
+        }
+    }
+
+    fn text(&self) -> Option<&str> {
+        match &self.item {
+            Item::Text(_, text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+// a break opportunity follows the last piece, or comes before the inline
+// boxes that were just opened
+fn allow_break(pieces: &mut [Piece]) {
+    if let Some(piece) = pieces
+        .iter_mut()
+        .rev()
+        .find(|piece| !matches!(piece.item, Item::Open(_)))
+    {
+        piece.break_after = true;
+    }
+}
+
+// processes the whitespace of the text, and splits the content at break
+// opportunities; atomic inlines take their width from `atomic_widths`
+fn build_pieces<'a>(
+    fonts: &FontDatabase,
+    items: &[Item<'a>],
+    atomic_widths: &[f32],
+    container: &ComputedStyle,
+    containing_width: f32,
+) -> Vec<Piece<'a>> {
+    let mut pieces: Vec<Piece<'a>> = Vec::new();
+    // collapsible spaces at the start of the content are removed
+    let mut space_before = true;
+    let mut previous: Option<char> = None;
+
+    for item in items {
+        match item {
+            Item::Open(node) => {
+                let width = inline_edge(&node.style, containing_width, true);
+                pieces.push(Piece::new(item.clone(), width));
+            }
+            Item::Close(node) => {
+                let width = inline_edge(&node.style, containing_width, false);
+                pieces.push(Piece::new(item.clone(), width));
+            }
+            Item::Anchor(_) | Item::Float(_) => pieces.push(Piece::new(item.clone(), 0.0)),
+            Item::Atomic(_, index) => {
+                if previous.is_some() && wraps(container.white_space) {
+                    allow_break(&mut pieces);
+                }
+                let mut piece = Piece::new(item.clone(), atomic_widths[*index]);
+                piece.break_after = wraps(container.white_space);
+                pieces.push(piece);
+                previous = Some('\u{FFFC}');
+                space_before = false;
+            }
+            Item::Text(node, text) => {
+                let style = &node.style;
+                let white_space = style.white_space;
+                let push = |pieces: &mut Vec<Piece<'a>>, text: &mut String, forced: bool| {
+                    if text.is_empty() && !forced {
+                        return;
+                    }
+                    let text = std::mem::take(text);
+                    let mut piece = Piece::new(Item::Text(node, text.clone()), 0.0);
+                    piece.width = fonts.measure(&text, style);
+                    if white_space != WhiteSpace::Pre {
+                        let trimmed = text.trim_end_matches(' ');
+                        piece.hanging = piece.width - fonts.measure(trimmed, style);
+                    }
+                    piece.forced_break = forced;
+                    pieces.push(piece);
+                };
+
+                let mut current = String::new();
+                for c in transform_text(text, style.text_transform).chars() {
+                    let c = match c {
+                        '\r' => continue,
+                        '\n' if preserves_newlines(white_space) => {
+                            if collapses_spaces(white_space) {
+                                current.truncate(current.trim_end_matches(' ').len());
+                            }
+                            push(&mut pieces, &mut current, true);
+                            space_before = collapses_spaces(white_space);
+                            previous = None;
+                            continue;
+                        }
+                        ' ' | '\t' | '\n' | '\x0C' if collapses_spaces(white_space) => {
+                            if space_before {
+                                continue;
+                            }
+                            ' '
+                        }
+                        c => c,
+                    };
+                    if let Some(before) = previous {
+                        if wraps(white_space) && is_break_opportunity(before, c) {
+                            push(&mut pieces, &mut current, false);
+                            allow_break(&mut pieces);
+                        }
+                    }
+                    current.push(c);
+                    space_before = c == ' ' && collapses_spaces(white_space);
+                    previous = Some(c);
+                }
+                push(&mut pieces, &mut current, false);
+            }
+        }
+    }
+    pieces
+}
+
+// the end of the pieces on the line starting with the piece `start`, filling
+// a line of width `available`
+fn break_line(pieces: &[Piece], start: usize, available: f32, indent: f32) -> usize {
+    let mut width = indent;
+    let mut index = start;
+    while index < pieces.len() {
+        let mut end = index;
+        while end + 1 < pieces.len() && !pieces[end].break_after && !pieces[end].forced_break {
+            end += 1;
+        }
+        let run_width: f32 = pieces[index..=end].iter().map(|piece| piece.width).sum();
+        if index > start && width + run_width - pieces[end].hanging > available {
+            return index;
+        }
+        width += run_width;
+        index = end + 1;
+        if pieces[end].forced_break {
+            return index;
+        }
+    }
+    index
+}
+
+// the ranges of pieces on each line, filling lines of width `available`
+fn break_lines(pieces: &[Piece], available: f32, indent: f32) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while start < pieces.len() {
+        let indent = if start == 0 { indent } else { 0.0 };
+        let end = break_line(pieces, start, available, indent);
+        lines.push(start..end);
+        start = end;
+    }
+    lines
+}
+
+// an inline box open on the current line
+#[derive(Debug, Clone)]
+struct OpenBox<'a> {
+    node: &'a StyledNode<'a>,
+    start: f32,
+    first: bool,
+    // how far the baseline of the box is raised above the line's
+    raise: f32,
+}
+
+// a fragment before the line is aligned vertically, with how far it is
+// raised, the distance from its top to its baseline, and the space it
+// takes above and below its baseline
+struct Placed<'a> {
+    fragment: Fragment<'a>,
+    raise: f32,
+    ascent: f32,
+    above: f32,
+    below: f32,
+    // the bounds of the fragment are part of the line's height
+    extends_line: bool,
+    atomic: Option<usize>,
+}
+
+// the width of a line's pieces without the spaces collapsing or hanging at
+// its end, and the number of spaces that justification can stretch
+fn trimmed_pieces<'a>(fonts: &FontDatabase, pieces: &[Piece<'a>]) -> (Vec<Piece<'a>>, f32, usize) {
+    let mut pieces = pieces.to_vec();
+    let mut hanging = 0.0;
+    if let Some(last) = pieces.iter_mut().rev().find(|piece| piece.text().is_some()) {
+        let style = last.style();
+        if let Item::Text(node, text) = &last.item {
+            if collapses_spaces(style.white_space) {
+                let trimmed = text.trim_end_matches(' ').to_string();
+                last.width = fonts.measure(&trimmed, style);
+                last.item = Item::Text(node, trimmed);
+            } else {
+                hanging = last.hanging;
+            }
+        }
+    }
+    let width = pieces.iter().map(|piece| piece.width).sum::<f32>() - hanging;
+    let spaces = pieces
+        .iter()
+        .filter_map(Piece::text)
+        .map(|text| text.matches(' ').count())
+        .sum();
+    (pieces, width, spaces)
+}
+
+impl<'a> LayoutBox<'a> {
+    // the style of a box for the parent of the inline boxes inside it
+    fn parent_style(&self, stack: &[OpenBox<'a>]) -> &ComputedStyle {
+        match stack.last() {
+            Some(open) => &open.node.style,
+            None => self.style(),
+        }
+    }
+
+    // the baseline of the first line inside the box, for aligning flex and
+    // grid items
+    pub(crate) fn first_baseline(&self) -> Option<f32> {
+        if let Some(line) = self.lines.first() {
+            return Some(line.rect.y + line.baseline);
+        }
+        self.children
+            .iter()
+            .filter(|child| child.is_block_level() && !child.is_out_of_flow())
+            .find_map(LayoutBox::first_baseline)
+    }
+
+    // the baseline of the last line inside the box, for aligning inline-blocks
+    fn last_baseline(&self) -> Option<f32> {
+        if let Some(line) = self.lines.last() {
+            return Some(line.rect.y + line.baseline);
+        }
+        self.children
+            .iter()
+            .rev()
+            .filter(|child| child.is_block_level() && !child.is_out_of_flow())
+            .find_map(LayoutBox::last_baseline)
+    }
+
+    // lays out the inline-level children of a block container in line boxes
+    // from the top of its content; returns the height of the lines
+    pub(crate) fn layout_inline_content(
+        &mut self,
+        context: &LayoutContext,
+        floats: &mut FloatContext,
+    ) -> f32 {
+        let fonts = context.fonts;
+        let content = self.dimensions.content;
+
+        // inline-blocks are laid out first, to be moved onto their line
+        let mut atomic_sizes = Vec::new();
+        let containing = ContainingBlock {
+            width: content.width,
+            ..ContainingBlock::default()
+        };
+        for_each_inline_content_box(&mut self.children, is_atomic, &mut |atomic| {
+            atomic.layout_block(
+                context,
+                &containing,
+                0.0,
+                MarginStrut::default(),
+                true,
+                &mut FloatContext::new(),
+            );
+            let margin_box = atomic.dimensions.margin_box();
+            let baseline = match atomic.style().overflow_x {
+                Overflow::Visible => atomic.last_baseline(),
+                _ => None,
+            };
+            let baseline = baseline.unwrap_or(margin_box.y + margin_box.height) - margin_box.y;
+            atomic_sizes.push((margin_box.width, margin_box.height, baseline));
+        });
+        // and so are floats, to be placed beside their line
+        let mut float_sizes = Vec::new();
+        for_each_inline_content_box(&mut self.children, LayoutBox::is_floated, &mut |float| {
+            float_sizes.push(float.layout_float_box(context, &containing));
+        });
+
+        let mut items = Vec::new();
+        collect_items(&self.children, &mut items, &mut 0);
+        let widths: Vec<f32> = atomic_sizes.iter().map(|size| size.0).collect();
+        let style = self.style();
+        let pieces = build_pieces(fonts, &items, &widths, style, content.width);
+        let first_indent = style.text_indent.resolve(content.width);
+        let mut float_count = 0;
+        let float_numbers: Vec<Option<usize>> = pieces
+            .iter()
+            .map(|piece| {
+                matches!(piece.item, Item::Float(_)).then(|| {
+                    float_count += 1;
+                    float_count - 1
+                })
+            })
+            .collect();
+        let strut_height = {
+            let (above, below) = layout_bounds(fonts, style);
+            above + below
+        };
+        let (content_left, content_right) = (content.x, content.x + content.width);
+
+        let mut stack: Vec<OpenBox<'a>> = Vec::new();
+        let mut lines = Vec::new();
+        let mut atomic_positions = vec![(0.0, 0.0); atomic_sizes.len()];
+        let mut float_positions = Vec::new();
+        let mut anchors = Vec::new();
+        let mut y = content.y;
+        let mut start = 0;
+        while start < pieces.len() {
+            // the line is shortened by the floats beside it
+            let indent = if lines.is_empty() { first_indent } else { 0.0 };
+            let (left, right) = floats.available(y, strut_height, content_left, content_right);
+            let end = break_line(&pieces, start, right - left, indent);
+
+            // the next float of the line is placed beside it if it fits
+            // after the content before it, and the line is broken again
+            let next_float =
+                (start..end).find(|&index| float_numbers[index] == Some(float_positions.len()));
+            if let Some(index) = next_float {
+                let before: f32 = pieces[start..index].iter().map(|piece| piece.width).sum();
+                let size = float_sizes[float_positions.len()];
+                if index == start || indent + before + size.0 <= right - left {
+                    let position =
+                        floats.place(pieces[index].style(), size, y, content_left, content_right);
+                    float_positions.push(position);
+                    continue;
+                }
+            }
+
+            let (line_pieces, width, spaces) = trimmed_pieces(fonts, &pieces[start..end]);
+            // content too wide beside the floats goes below them
+            if indent + width > right - left {
+                if let Some(bottom) = floats.next_bottom(y, strut_height) {
+                    y = bottom;
+                    continue;
+                }
+            }
+            let ends_paragraph = end == pieces.len() || pieces[end - 1].forced_break;
+            let slack = right - left - indent - width;
+            let rtl = style.direction == Direction::Rtl;
+            let (offset, stretch) = match style.text_align {
+                TextAlign::Left => (0.0, 0.0),
+                TextAlign::Start if !rtl => (0.0, 0.0),
+                TextAlign::End if rtl => (0.0, 0.0),
+                TextAlign::Center => (slack / 2.0, 0.0),
+                TextAlign::Justify if !ends_paragraph && spaces > 0 && slack > 0.0 => {
+                    (0.0, slack / spaces as f32)
+                }
+                TextAlign::Justify if !rtl => (0.0, 0.0),
+                _ => (slack, 0.0),
+            };
+
+            let mut x = left + indent + offset;
+            for open in &mut stack {
+                open.start = x;
+            }
+            let mut placed: Vec<Placed<'a>> = Vec::new();
+            let mut has_content = false;
+            for piece in &line_pieces {
+                match &piece.item {
+                    Item::Open(node) => {
+                        x += piece.width;
+                        has_content |= piece.width != 0.0;
+                        let bounds = layout_bounds(fonts, &node.style);
+                        let parent_raise = stack.last().map_or(0.0, |open| open.raise);
+                        let raise = parent_raise
+                            + vertical_offset(
+                                fonts,
+                                &node.style,
+                                self.parent_style(&stack),
+                                bounds,
+                            );
+                        stack.push(OpenBox {
+                            node,
+                            start: x,
+                            first: true,
+                            raise,
+                        });
+                    }
+                    Item::Close(_) => {
+                        let open = stack.pop().unwrap();
+                        placed.push(self.inline_fragment(fonts, &open, x, true));
+                        x += piece.width;
+                        has_content |= piece.width != 0.0;
+                    }
+                    Item::Text(node, text) => {
+                        let width = piece.width + stretch * text.matches(' ').count() as f32;
+                        let (ascent, descent) = fonts.metrics(&node.style);
+                        placed.push(Placed {
+                            fragment: Fragment {
+                                rect: Rect {
+                                    x,
+                                    width,
+                                    height: ascent + descent,
+                                    ..Rect::default()
+                                },
+                                kind: FragmentKind::Text(node, text.clone()),
+                            },
+                            raise: stack.last().map_or(0.0, |open| open.raise),
+                            ascent,
+                            above: ascent,
+                            below: descent,
+                            extends_line: false,
+                            atomic: None,
+                        });
+                        x += width;
+                        has_content = true;
+                    }
+                    Item::Atomic(node, index) => {
+                        let (width, height, baseline) = atomic_sizes[*index];
+                        let bounds = (baseline, height - baseline);
+                        let parent_raise = stack.last().map_or(0.0, |open| open.raise);
+                        placed.push(Placed {
+                            fragment: Fragment {
+                                rect: Rect {
+                                    x,
+                                    width,
+                                    height,
+                                    ..Rect::default()
+                                },
+                                kind: FragmentKind::Atomic(node),
+                            },
+                            raise: parent_raise
+                                + vertical_offset(
+                                    fonts,
+                                    &node.style,
+                                    self.parent_style(&stack),
+                                    bounds,
+                                ),
+                            ascent: baseline,
+                            above: bounds.0,
+                            below: bounds.1,
+                            extends_line: true,
+                            atomic: Some(*index),
+                        });
+                        x += width;
+                        has_content = true;
+                    }
+                    Item::Anchor(_) => anchors.push((x, y)),
+                    Item::Float(_) => {}
+                }
+            }
+            // inline boxes continuing on the next line
+            for open in stack.iter_mut().rev() {
+                placed.push(self.inline_fragment(fonts, open, x, false));
+                open.first = false;
+            }
+
+            // the line is as tall as the bounds of its boxes, including a
+            // strut with the font and line height of the container
+            let (mut above, mut below) = match has_content {
+                true => layout_bounds(fonts, style),
+                false => (0.0, 0.0),
+            };
+            for fragment in placed.iter().filter(|placed| placed.extends_line) {
+                above = above.max(fragment.above + fragment.raise);
+                below = below.max(fragment.below - fragment.raise);
+            }
+            let mut fragments = Vec::new();
+            for mut fragment in placed {
+                fragment.fragment.rect.y = y + above - fragment.raise - fragment.ascent;
+                if let Some(index) = fragment.atomic {
+                    let rect = fragment.fragment.rect;
+                    atomic_positions[index] = (rect.x, rect.y);
+                }
+                fragments.push(fragment.fragment);
+            }
+            lines.push(LineBox {
+                rect: Rect {
+                    x: left,
+                    y,
+                    width: right - left,
+                    height: above + below,
+                },
+                baseline: above,
+                fragments,
+            });
+            y += above + below;
+
+            // the floats that didn't fit go below the line
+            for index in start..end {
+                if float_numbers[index] == Some(float_positions.len()) {
+                    let size = float_sizes[float_positions.len()];
+                    let style = pieces[index].style();
+                    float_positions.push(floats.place(style, size, y, content_left, content_right));
+                }
+            }
+            start = end;
+        }
+
+        let mut index = 0;
+        for_each_inline_content_box(&mut self.children, is_atomic, &mut |atomic| {
+            let margin_box = atomic.dimensions.margin_box();
+            let (x, y) = atomic_positions[index];
+            atomic.translate(x - margin_box.x, y - margin_box.y);
+            index += 1;
+        });
+        let mut float_positions = float_positions.into_iter();
+        for_each_inline_content_box(&mut self.children, LayoutBox::is_floated, &mut |float| {
+            let margin_box = float.dimensions.margin_box();
+            let (x, y) = float_positions.next().unwrap();
+            float.translate(x - margin_box.x, y - margin_box.y);
+        });
+        let mut anchors = anchors.into_iter();
+        for_each_inline_content_box(
+            &mut self.children,
+            LayoutBox::is_absolutely_positioned,
+            &mut |out_of_flow| {
+                let (x, y) = anchors.next().unwrap();
+                out_of_flow.set_static_position(x, y);
+            },
+        );
+        self.lines = lines;
+        y - content.y
+    }
+
+    // the fragment of an open inline box, from its start to `end`; its
+    // bounds extend the line
+    fn inline_fragment(
+        &self,
+        fonts: &FontDatabase,
+        open: &OpenBox<'a>,
+        end: f32,
+        last: bool,
+    ) -> Placed<'a> {
+        let (ascent, descent) = fonts.metrics(&open.node.style);
+        let (above, below) = layout_bounds(fonts, &open.node.style);
+        Placed {
+            fragment: Fragment {
+                rect: Rect {
+                    x: open.start,
+                    width: end - open.start,
+                    height: ascent + descent,
+                    ..Rect::default()
+                },
+                kind: FragmentKind::Inline {
+                    node: open.node,
+                    first: open.first,
+                    last,
+                },
+            },
+            raise: open.raise,
+            ascent,
+            above,
+            below,
+            extends_line: true,
+            atomic: None,
+        }
+    }
+
+    // the min-content and max-content widths of the content of the box
+    pub(crate) fn intrinsic_widths(&self, context: &LayoutContext) -> (f32, f32) {
+        let fonts = context.fonts;
+        if self.is_flex_container() {
+            return self.flex_intrinsic_widths(context);
+        }
+        if self.is_grid_container() {
+            return self.grid_intrinsic_widths(context);
+        }
+        if self.is_table() {
+            return self.table_intrinsic_widths(context);
+        }
+        if !self.has_inline_children() {
+            return self
+                .children
+                .iter()
+                .filter(|child| !child.is_absolutely_positioned() && !child.is_outside_marker())
+                .map(|child| child.outer_intrinsic_widths(context))
+                .fold((0.0, 0.0), |(min, max), child| {
+                    (f32::max(min, child.0), f32::max(max, child.1))
+                });
+        }
+
+        let mut items = Vec::new();
+        collect_items(&self.children, &mut items, &mut 0);
+        let mut atomics = Vec::new();
+        inline_content_boxes(&self.children, is_atomic, &mut atomics);
+        let atomic_widths: Vec<(f32, f32)> = atomics
+            .iter()
+            .map(|atomic| atomic.outer_intrinsic_widths(context))
+            .collect();
+        let style = self.style();
+        let min_widths: Vec<f32> = atomic_widths.iter().map(|widths| widths.0).collect();
+        let max_widths: Vec<f32> = atomic_widths.iter().map(|widths| widths.1).collect();
+
+        // the widest unbreakable run, and the widest line without wrapping
+        let pieces = build_pieces(fonts, &items, &min_widths, style, 0.0);
+        let min = break_lines(&pieces, 0.0, 0.0)
+            .into_iter()
+            .map(|range| trimmed_pieces(fonts, &pieces[range]).1)
+            .fold(0.0, f32::max);
+        let pieces = build_pieces(fonts, &items, &max_widths, style, 0.0);
+        let max = break_lines(&pieces, f32::INFINITY, 0.0)
+            .into_iter()
+            .map(|range| trimmed_pieces(fonts, &pieces[range]).1)
+            .fold(0.0, f32::max);
+        let indent = style.text_indent.resolve(0.0);
+
+        // floats go beside the lines
+        let mut floated = Vec::new();
+        inline_content_boxes(&self.children, LayoutBox::is_floated, &mut floated);
+        let (float_min, float_max) = floated
+            .iter()
+            .map(|float| float.outer_intrinsic_widths(context))
+            .fold((0.0, 0.0), |(min, max), float| {
+                (f32::max(min, float.0), max + float.1)
+            });
+        (f32::max(min + indent, float_min), max + indent + float_max)
+    }
+
+    // the min-content and max-content widths of the margin box
+    pub(crate) fn outer_intrinsic_widths(&self, context: &LayoutContext) -> (f32, f32) {
+        let style = self.style();
+        let (padding, border) = self.padding_and_border(0.0);
+        let edges = style.margin.left.resolve(Some(0.0)).unwrap_or(0.0)
+            + style.margin.right.resolve(Some(0.0)).unwrap_or(0.0)
+            + padding.left
+            + padding.right
+            + border.left
+            + border.right;
+        let content_width = |width: f32| match style.box_sizing {
+            BoxSizing::ContentBox => width,
+            BoxSizing::BorderBox => (width - edges).max(0.0),
+        };
+        let (mut min, mut max) = match style.width.resolve(None) {
+            Some(width) => (content_width(width), content_width(width)),
+            None => self.intrinsic_widths(context),
+        };
+        if let Some(max_width) = style.max_width.resolve(None) {
+            min = min.min(content_width(max_width));
+            max = max.min(content_width(max_width));
+        }
+        if let Some(min_width) = style.min_width.resolve(None) {
+            min = min.max(content_width(min_width));
+            max = max.max(content_width(min_width));
+        }
+        (min + edges, max + edges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::test_util::{find, with_layout};
+
+    // the text of each line of the box
+    fn line_texts(layout_box: &LayoutBox) -> Vec<String> {
+        layout_box
+            .lines
+            .iter()
+            .map(|line| {
+                line.fragments
+                    .iter()
+                    .filter_map(|fragment| match &fragment.kind {
+                        FragmentKind::Text(_, text) => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn fragment_rects<'b>(line: &'b LineBox) -> Vec<(&'b str, Rect)> {
+        line.fragments
+            .iter()
+            .map(|fragment| match &fragment.kind {
+                FragmentKind::Text(_, text) => (text.as_str(), fragment.rect),
+                FragmentKind::Inline { .. } => ("inline", fragment.rect),
+                FragmentKind::Atomic(_) => ("atomic", fragment.rect),
+            })
+            .collect()
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_break_opportunities() {
+        assert!(is_break_opportunity(' ', 'b'));
+        assert!(!is_break_opportunity('a', ' '));
+        assert!(!is_break_opportunity('a', 'b'));
+        assert!(is_break_opportunity('-', 'b'));
+        assert!(is_break_opportunity('中', '文'));
+        assert!(!is_break_opportunity('(', '中'));
+        assert!(!is_break_opportunity('中', '。'));
+        assert!(!is_break_opportunity('\u{A0}', 'a'));
+        assert!(is_break_opportunity('\u{200B}', 'a'));
+    }
+
+    #[test]
+    fn test_line_breaking() {
+        with_layout(
+            "<div id='d'>aaaa bbbb cccc</div>",
+            "div { width: 60px; line-height: 20px; }",
+            |root| {
+                let div = find(root, "d");
+                assert_eq!(line_texts(div), ["aaaa bbbb", "cccc"]);
+                assert_eq!(div.dimensions.content.height, 40.0);
+                assert_eq!(div.lines[1].rect, rect(0.0, 20.0, 60.0, 20.0));
+                assert_eq!(div.lines[1].baseline, 13.0);
+                assert_eq!(
+                    fragment_rects(&div.lines[1]),
+                    [("cccc", rect(0.0, 25.0, 24.0, 10.0))]
+                );
+            },
+        );
+        // an inline box continues on the next line
+        with_layout(
+            "<div id='d'>aa <em>bbbb cccc</em></div>",
+            "div { width: 60px; line-height: 20px; }",
+            |root| {
+                let div = find(root, "d");
+                assert_eq!(line_texts(div), ["aa bbbb", "cccc"]);
+                let inline = |line: &LineBox| {
+                    line.fragments
+                        .iter()
+                        .find_map(|fragment| match fragment.kind {
+                            FragmentKind::Inline { first, last, .. } => {
+                                Some((fragment.rect.x, fragment.rect.width, first, last))
+                            }
+                            _ => None,
+                        })
+                        .unwrap()
+                };
+                assert_eq!(inline(&div.lines[0]), (18.0, 24.0, true, false));
+                assert_eq!(inline(&div.lines[1]), (0.0, 24.0, false, true));
+            },
+        );
+    }
+
+    #[test]
+    fn test_white_space() {
+        with_layout(
+            "<div id='normal'>a \n  b <b> c</b></div>
+            <div id='pre'>a  b\nc</div>
+            <div id='nowrap'>aaaa bbbb cccc</div>
+            <div id='line'>a   b  \n  c</div>
+            <div id='wrap'>aaaa  bbbb  cccc</div>",
+            "div { width: 60px; line-height: 20px; }
+            #pre { white-space: pre; } #nowrap { white-space: nowrap; }
+            #line { white-space: pre-line; } #wrap { white-space: pre-wrap; }",
+            |root| {
+                assert_eq!(line_texts(find(root, "normal")), ["a b c"]);
+                assert_eq!(line_texts(find(root, "pre")), ["a  b", "c"]);
+                assert_eq!(line_texts(find(root, "nowrap")), ["aaaa bbbb cccc"]);
+                assert_eq!(line_texts(find(root, "line")), ["a b", "c"]);
+                assert_eq!(line_texts(find(root, "wrap")), ["aaaa  bbbb  ", "cccc"]);
+            },
+        );
+    }
+
+    #[test]
+    fn test_text_align() {
+        with_layout(
+            "<div id='center'>aa bb</div><div id='right'>aa bb</div>
+            <div id='justify'>aaaa bbbb cccc</div><div id='indent'>aa</div>",
+            "div { width: 60px; line-height: 20px; }
+            #center { text-align: center; } #right { text-align: right; }
+            #justify { text-align: justify; } #indent { text-indent: 12px; }",
+            |root| {
+                let x = |id: &str, line: usize, index: usize| {
+                    find(root, id).lines[line].fragments[index].rect.x
+                };
+                assert_eq!(x("center", 0, 0), 15.0);
+                assert_eq!(x("right", 0, 0), 30.0);
+                assert_eq!(x("justify", 0, 1), 36.0);
+                // the last line isn't justified
+                assert_eq!(x("justify", 1, 0), 0.0);
+                assert_eq!(x("indent", 0, 0), 12.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_baseline_alignment() {
+        let html = "<div id='d'>aa <span>bb</span></div>";
+        with_layout(
+            html,
+            "div { width: 60px; line-height: 20px; } span { font-size: 20px; }",
+            |root| {
+                let div = find(root, "d");
+                assert_eq!(div.lines[0].rect.height, 23.0);
+                assert_eq!(div.lines[0].baseline, 16.0);
+                assert_eq!(
+                    fragment_rects(&div.lines[0]),
+                    [
+                        ("aa ", rect(0.0, 8.0, 18.0, 10.0)),
+                        ("bb", rect(18.0, 0.0, 24.0, 20.0)),
+                        ("inline", rect(18.0, 0.0, 24.0, 20.0)),
+                    ]
+                );
+            },
+        );
+        with_layout(
+            html,
+            "div { width: 60px; line-height: 20px; } span { vertical-align: 5px; }",
+            |root| {
+                let div = find(root, "d");
+                assert_eq!(div.lines[0].rect.height, 25.0);
+                assert_eq!(fragment_rects(&div.lines[0])[1].1.y, 5.0);
+            },
+        );
+        with_layout(
+            html,
+            "div { width: 60px; line-height: 20px; } span { vertical-align: super; }",
+            |root| {
+                assert_eq!(find(root, "d").lines[0].rect.height, 24.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_inline_blocks() {
+        with_layout(
+            "<div id='d'>a<span id='ib'></span>b</div>",
+            "div { width: 60px; line-height: 20px; }
+            #ib { display: inline-block; width: 30px; height: 30px; }",
+            |root| {
+                let div = find(root, "d");
+                assert_eq!(div.lines[0].rect.height, 37.0);
+                assert_eq!(
+                    find(root, "ib").dimensions.border_box(),
+                    rect(6.0, 0.0, 30.0, 30.0)
+                );
+                assert_eq!(
+                    fragment_rects(&div.lines[0])[2],
+                    ("b", rect(36.0, 22.0, 6.0, 10.0))
+                );
+            },
+        );
+        // inline-blocks shrink to fit their content
+        with_layout(
+            "<div><span id='a'>abc def</span></div>
+            <div><span id='b'>aaaa bbbb cccc dddd</span></div>",
+            "div { width: 60px; line-height: 20px; } span { display: inline-block; }",
+            |root| {
+                assert_eq!(find(root, "a").dimensions.content.width, 42.0);
+                let b = find(root, "b");
+                assert_eq!(b.dimensions.content.width, 60.0);
+                assert_eq!(line_texts(b), ["aaaa bbbb", "cccc dddd"]);
+            },
+        );
+    }
+}
@@ -0,0 +1,847 @@
+use crate::assign::{PropertyMap, StyledNode};
+use crate::block::MarginStrut;
+use crate::css::{LengthContext, PseudoElement};
+use crate::dom::NodeType;
+use crate::floats::FloatContext;
+use crate::fonts::FontDatabase;
+use crate::inline::LineBox;
+use crate::properties::apply_defaults;
+use crate::style::{
+    compute_values, ComputedStyle, Display, Float, ListStylePosition, Overflow, Position,
+    WhiteSpace,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EdgeSizes {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+// the position and size of the content of a box, and the widths of the
+// areas around it
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Dimensions {
+    pub content: Rect,
+    pub padding: EdgeSizes,
+    pub border: EdgeSizes,
+    pub margin: EdgeSizes,
+}
+
+// the content box of the containing block of a box, whose height is `None`
+// while it depends on its content
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ContainingBlock {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: Option<f32>,
+}
+
+// what layout depends on besides the boxes: the fonts measuring text
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutContext<'f> {
+    pub fonts: &'f FontDatabase,
+}
+
+impl Rect {
+    pub fn expanded_by(self, edge: EdgeSizes) -> Rect {
+        Rect {
+            x: self.x - edge.left,
+            y: self.y - edge.top,
+            width: self.width + edge.left + edge.right,
+            height: self.height + edge.top + edge.bottom,
+        }
+    }
+}
+
+impl Dimensions {
+    pub fn padding_box(self) -> Rect {
+        self.content.expanded_by(self.padding)
+    }
+
+    pub fn border_box(self) -> Rect {
+        self.padding_box().expanded_by(self.border)
+    }
+
+    pub fn margin_box(self) -> Rect {
+        self.border_box().expanded_by(self.margin)
+    }
+}
+
+#[derive(Debug)]
+pub enum BoxType<'a> {
+    // a block-level box of an element
+    Block(&'a StyledNode<'a>),
+    // an inline box, whose content takes part in the inline formatting
+    // context of its block container
+    Inline(&'a StyledNode<'a>),
+    // an inline-level box laid out as a block: `inline-block`, `inline-flex`...
+    InlineBlock(&'a StyledNode<'a>),
+    // a run of text, or the text of a generated box, as written
+    Text(&'a StyledNode<'a>, String),
+    // the block wrapping the inline-level boxes of a block container that
+    // also has block-level children, with the style it inherits
+    AnonymousBlock(Box<ComputedStyle>),
+}
+
+#[derive(Debug)]
+pub struct LayoutBox<'a> {
+    pub dimensions: Dimensions,
+    pub box_type: BoxType<'a>,
+    pub children: Vec<LayoutBox<'a>>,
+    // the lines of a block container with inline content
+    pub lines: Vec<LineBox<'a>>,
+}
+
+impl<'a> LayoutBox<'a> {
+    pub fn new(box_type: BoxType<'a>) -> LayoutBox<'a> {
+        LayoutBox {
+            dimensions: Dimensions::default(),
+            box_type,
+            children: Vec::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    // moves the box and its content
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        self.dimensions.content.x += dx;
+        self.dimensions.content.y += dy;
+        for line in &mut self.lines {
+            line.rect.x += dx;
+            line.rect.y += dy;
+            for fragment in &mut line.fragments {
+                fragment.rect.x += dx;
+                fragment.rect.y += dy;
+            }
+        }
+        for child in &mut self.children {
+            child.translate(dx, dy);
+        }
+    }
+
+    // the styled node the box was generated for, `None` for anonymous boxes
+    pub fn styled_node(&self) -> Option<&'a StyledNode<'a>> {
+        match self.box_type {
+            BoxType::Block(node)
+            | BoxType::Inline(node)
+            | BoxType::InlineBlock(node)
+            | BoxType::Text(node, _) => Some(node),
+            BoxType::AnonymousBlock(_) => None,
+        }
+    }
+
+    pub fn style(&self) -> &ComputedStyle {
+        match &self.box_type {
+            BoxType::AnonymousBlock(style) => style,
+            _ => &self.styled_node().unwrap().style,
+        }
+    }
+
+    pub fn is_block_level(&self) -> bool {
+        matches!(
+            self.box_type,
+            BoxType::Block(_) | BoxType::AnonymousBlock(_)
+        )
+    }
+
+    pub fn is_inline_level(&self) -> bool {
+        !self.is_block_level()
+    }
+
+    // whether the box is taken out of the flow, and laid out in its
+    // containing block once the flow around it is
+    pub fn is_absolutely_positioned(&self) -> bool {
+        matches!(self.box_type, BoxType::Block(_))
+            && matches!(self.style().position, Position::Absolute | Position::Fixed)
+    }
+
+    // whether the box is taken out of the flow to the side of its block
+    // formatting context, the flow going around it
+    pub fn is_floated(&self) -> bool {
+        matches!(self.box_type, BoxType::Block(_))
+            && self.style().float != Float::None
+            && !self.is_absolutely_positioned()
+    }
+
+    // whether the box is the marker of a list item outside of its content,
+    // laid out on its own beside its first line
+    pub fn is_outside_marker(&self) -> bool {
+        match self.box_type {
+            BoxType::InlineBlock(node) => is_outside_marker(node),
+            _ => false,
+        }
+    }
+
+    pub fn is_out_of_flow(&self) -> bool {
+        self.is_absolutely_positioned() || self.is_floated() || self.is_outside_marker()
+    }
+
+    // whether the in-flow children of a block container are inline-level,
+    // laid out in line boxes
+    pub(crate) fn has_inline_children(&self) -> bool {
+        self.children
+            .iter()
+            .filter(|child| !child.is_out_of_flow())
+            .all(LayoutBox::is_inline_level)
+    }
+
+    // whether the box is the root of an independent formatting context,
+    // whose margins don't collapse with the margins of its children
+    pub fn establishes_formatting_context(&self) -> bool {
+        if !matches!(self.box_type, BoxType::Block(_) | BoxType::InlineBlock(_)) {
+            return false;
+        }
+        let style = self.style();
+        !matches!(style.display, Display::Block | Display::ListItem)
+            || !matches!(style.overflow_x, Overflow::Visible | Overflow::Clip)
+            || style.float != Float::None
+            || matches!(style.position, Position::Absolute | Position::Fixed)
+    }
+
+    // whitespace that inline layout would collapse away
+    pub(crate) fn is_collapsible_whitespace(&self) -> bool {
+        match &self.box_type {
+            BoxType::Text(node, text) => {
+                !matches!(
+                    node.style.white_space,
+                    WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::BreakSpaces
+                ) && text.chars().all(char::is_whitespace)
+            }
+            _ => false,
+        }
+    }
+}
+
+// the style of an anonymous box: inherited properties from its parent, the
+// initial value for the others
+pub fn anonymous_style(parent_values: &PropertyMap, display: Display) -> ComputedStyle {
+    let mut values = PropertyMap::new();
+    apply_defaults(&mut values, parent_values);
+    compute_values(&mut values, parent_values, &LengthContext::default());
+    let mut style = ComputedStyle::from_values(&values);
+    style.display = display;
+    style
+}
+
+fn is_outside_marker(node: &StyledNode) -> bool {
+    node.pseudo_element == Some(PseudoElement::Marker)
+        && node.style.list_style_position == ListStylePosition::Outside
+}
+
+// whether an element generates a block-level box
+fn is_block_level(display: Display) -> bool {
+    !matches!(
+        display,
+        Display::Inline
+            | Display::InlineBlock
+            | Display::InlineFlex
+            | Display::InlineGrid
+            | Display::InlineTable
+    )
+}
+
+// whether an element generates one of the boxes making up a table, inside a
+// table or another of them
+fn is_table_internal(display: Display) -> bool {
+    matches!(
+        display,
+        Display::TableRowGroup
+            | Display::TableHeaderGroup
+            | Display::TableFooterGroup
+            | Display::TableRow
+            | Display::TableCell
+            | Display::TableColumnGroup
+            | Display::TableColumn
+            | Display::TableCaption
+    )
+}
+
+pub(crate) fn is_row_group(display: Display) -> bool {
+    matches!(
+        display,
+        Display::TableRowGroup | Display::TableHeaderGroup | Display::TableFooterGroup
+    )
+}
+
+// the display of a box taken out of the flow, which is block-level
+fn blockified(display: Display) -> Display {
+    match display {
+        Display::Inline | Display::InlineBlock => Display::Block,
+        display if is_table_internal(display) => Display::Block,
+        Display::InlineFlex => Display::Flex,
+        Display::InlineGrid => Display::Grid,
+        Display::InlineTable => Display::Table,
+        display => display,
+    }
+}
+
+// the display of the boxes of a styled node
+fn display(node: &StyledNode) -> Display {
+    let style = &node.style;
+    match style.position {
+        Position::Absolute | Position::Fixed => blockified(style.display),
+        _ if style.float != Float::None => blockified(style.display),
+        _ => style.display,
+    }
+}
+
+// the boxes generated by a styled node, to be inserted in the box of its
+// parent: none for `display: none`, the boxes of the children for
+// `display: contents`, and an inline box split around its block-level
+// descendants
+fn build_boxes<'a>(node: &'a StyledNode<'a>) -> Vec<LayoutBox<'a>> {
+    if let Some(content) = &node.generated_content {
+        let mut generated = match display(node) {
+            Display::None => return Vec::new(),
+            _ if is_outside_marker(node) => LayoutBox::new(BoxType::InlineBlock(node)),
+            display if is_block_level(display) => LayoutBox::new(BoxType::Block(node)),
+            _ => LayoutBox::new(BoxType::Inline(node)),
+        };
+        if !content.is_empty() {
+            generated
+                .children
+                .push(LayoutBox::new(BoxType::Text(node, content.clone())));
+        }
+        return vec![generated];
+    }
+
+    match &node.node.node_type {
+        NodeType::Text(text) => return vec![LayoutBox::new(BoxType::Text(node, text.clone()))],
+        NodeType::Comment(_) => return Vec::new(),
+        NodeType::Element(_) => {}
+    }
+
+    let children = || node.children.iter().flat_map(build_boxes);
+    match display(node) {
+        Display::None => Vec::new(),
+        Display::Contents => children().collect(),
+        Display::Inline => {
+            // a block inside an inline splits the inline in two
+            let mut boxes = vec![LayoutBox::new(BoxType::Inline(node))];
+            for child in wrap_tables(node, children().collect()) {
+                if child.is_block_level() && !child.is_out_of_flow() {
+                    boxes.push(child);
+                    boxes.push(LayoutBox::new(BoxType::Inline(node)));
+                } else {
+                    boxes.last_mut().unwrap().children.push(child);
+                }
+            }
+            if boxes.len() > 1 {
+                boxes.retain(|inline| inline.is_block_level() || !inline.children.is_empty());
+            }
+            boxes
+        }
+        display => {
+            let box_type = match is_block_level(display) {
+                true => BoxType::Block(node),
+                false => BoxType::InlineBlock(node),
+            };
+            let mut layout_box = LayoutBox::new(box_type);
+            layout_box.children = match display {
+                Display::Flex | Display::InlineFlex | Display::Grid | Display::InlineGrid => {
+                    wrap_items(node, children().collect())
+                }
+                Display::Table
+                | Display::InlineTable
+                | Display::TableRowGroup
+                | Display::TableHeaderGroup
+                | Display::TableFooterGroup
+                | Display::TableRow => wrap_table_children(node, display, children().collect()),
+                Display::TableColumnGroup => children()
+                    .filter(|child| is_table_box(child, Display::TableColumn))
+                    .collect(),
+                Display::TableColumn => Vec::new(),
+                _ => wrap_inline_children(node, children().collect()),
+            };
+            vec![layout_box]
+        }
+    }
+}
+
+// the in-flow children of a block container are either all block-level or
+// all inline-level: runs of inline-level boxes among blocks are wrapped in
+// anonymous blocks, and dropped when they are only collapsible whitespace.
+// Out-of-flow boxes stay in the run they interrupt
+fn wrap_inline_children<'a>(
+    parent: &'a StyledNode<'a>,
+    children: Vec<LayoutBox<'a>>,
+) -> Vec<LayoutBox<'a>> {
+    let children = wrap_tables(parent, children);
+    let in_flow_block = |child: &LayoutBox| child.is_block_level() && !child.is_out_of_flow();
+    if !children.iter().any(in_flow_block) {
+        return children;
+    }
+
+    let mut wrapped = Vec::new();
+    let mut run: Vec<LayoutBox> = Vec::new();
+    for child in children {
+        if in_flow_block(&child) {
+            wrapped.extend(anonymous_block(parent, &mut run));
+            wrapped.push(child);
+        } else if child.is_out_of_flow() && run.is_empty() {
+            wrapped.push(child);
+        } else {
+            run.push(child);
+        }
+    }
+    wrapped.extend(anonymous_block(parent, &mut run));
+    wrapped
+}
+
+// the anonymous block wrapping a run of inline-level boxes; when they are
+// only collapsible whitespace, just the out-of-flow boxes of the run
+fn anonymous_block<'a>(
+    parent: &'a StyledNode<'a>,
+    run: &mut Vec<LayoutBox<'a>>,
+) -> Vec<LayoutBox<'a>> {
+    let mut run = std::mem::take(run);
+    if run
+        .iter()
+        .all(|child| child.is_collapsible_whitespace() || child.is_out_of_flow())
+    {
+        run.retain(LayoutBox::is_out_of_flow);
+        return run;
+    }
+    let style = anonymous_style(&parent.computed_values, Display::Block);
+    let mut anonymous = LayoutBox::new(BoxType::AnonymousBlock(Box::new(style)));
+    anonymous.children = run;
+    vec![anonymous]
+}
+
+// the children of a flex or grid container are all block-level items: runs
+// of text are wrapped in anonymous blocks, and inline-level boxes are
+// blockified
+fn wrap_items<'a>(parent: &'a StyledNode<'a>, children: Vec<LayoutBox<'a>>) -> Vec<LayoutBox<'a>> {
+    let mut items = Vec::new();
+    let mut run: Vec<LayoutBox> = Vec::new();
+    for mut child in wrap_tables(parent, children) {
+        match child.box_type {
+            BoxType::Text(..) => {
+                run.push(child);
+                continue;
+            }
+            BoxType::Inline(node) => {
+                child.box_type = BoxType::Block(node);
+                child.children = wrap_inline_children(node, std::mem::take(&mut child.children));
+            }
+            BoxType::InlineBlock(node) => child.box_type = BoxType::Block(node),
+            _ => {}
+        }
+        items.extend(anonymous_block(parent, &mut run));
+        items.push(child);
+    }
+    items.extend(anonymous_block(parent, &mut run));
+    items
+}
+
+// whether a box is an in-flow box with the display, like a row in a table
+fn is_table_box(layout_box: &LayoutBox, display: Display) -> bool {
+    layout_box.is_block_level()
+        && !layout_box.is_out_of_flow()
+        && layout_box.style().display == display
+}
+
+// CSS 2.1 §17.2.1: the children of a table, a row group or a row missing the
+// boxes between them are wrapped in anonymous ones: runs of children that
+// aren't rows in a row, and runs of children that aren't cells in a cell.
+// Whitespace between them is dropped
+fn wrap_table_children<'a>(
+    parent: &'a StyledNode<'a>,
+    display: Display,
+    children: Vec<LayoutBox<'a>>,
+) -> Vec<LayoutBox<'a>> {
+    let is_proper = |child: &LayoutBox| {
+        if !child.is_block_level() || child.is_out_of_flow() {
+            return false;
+        }
+        let child_display = child.style().display;
+        match display {
+            Display::TableRow => child_display == Display::TableCell,
+            Display::Table | Display::InlineTable => {
+                is_table_internal(child_display) && child_display != Display::TableCell
+            }
+            _ => child_display == Display::TableRow,
+        }
+    };
+    let wrapper = match display {
+        Display::TableRow => Display::TableCell,
+        _ => Display::TableRow,
+    };
+
+    let mut wrapped = Vec::new();
+    let mut run: Vec<LayoutBox> = Vec::new();
+    let flush = |run: &mut Vec<LayoutBox<'a>>, wrapped: &mut Vec<LayoutBox<'a>>| {
+        let run = std::mem::take(run);
+        if run.iter().all(LayoutBox::is_collapsible_whitespace) {
+            return;
+        }
+        let style = anonymous_style(&parent.computed_values, wrapper);
+        let mut anonymous = LayoutBox::new(BoxType::AnonymousBlock(Box::new(style)));
+        anonymous.children = match wrapper {
+            Display::TableCell => wrap_inline_children(parent, run),
+            _ => wrap_table_children(parent, wrapper, run),
+        };
+        wrapped.push(anonymous);
+    };
+    for child in children {
+        if is_proper(&child) {
+            flush(&mut run, &mut wrapped);
+            wrapped.push(child);
+        } else {
+            run.push(child);
+        }
+    }
+    flush(&mut run, &mut wrapped);
+    wrapped
+}
+
+// the boxes of a table outside of one, like cells in a block, are wrapped in
+// an anonymous table with the whitespace between them
+fn wrap_tables<'a>(parent: &'a StyledNode<'a>, children: Vec<LayoutBox<'a>>) -> Vec<LayoutBox<'a>> {
+    let is_internal = |child: &LayoutBox| {
+        child.is_block_level()
+            && !child.is_out_of_flow()
+            && is_table_internal(child.style().display)
+    };
+    if !children.iter().any(is_internal) {
+        return children;
+    }
+
+    let mut wrapped = Vec::new();
+    let mut run: Vec<LayoutBox> = Vec::new();
+    let flush = |run: &mut Vec<LayoutBox<'a>>, wrapped: &mut Vec<LayoutBox<'a>>| {
+        if run.is_empty() {
+            return;
+        }
+        let style = anonymous_style(&parent.computed_values, Display::Table);
+        let mut table = LayoutBox::new(BoxType::AnonymousBlock(Box::new(style)));
+        table.children = wrap_table_children(parent, Display::Table, std::mem::take(run));
+        wrapped.push(table);
+    };
+    for child in children {
+        if is_internal(&child) || (!run.is_empty() && child.is_collapsible_whitespace()) {
+            run.push(child);
+        } else {
+            flush(&mut run, &mut wrapped);
+            wrapped.push(child);
+        }
+    }
+    flush(&mut run, &mut wrapped);
+    wrapped
+}
+
+// the box tree of a styled tree; the root box is a block, anonymous when the
+// root element generates no box
+pub fn build_layout_tree<'a>(root: &'a StyledNode<'a>) -> LayoutBox<'a> {
+    let mut boxes = build_boxes(root);
+    if boxes.len() == 1 && matches!(boxes[0].box_type, BoxType::Block(_)) {
+        return boxes.pop().unwrap();
+    }
+    let style = anonymous_style(&root.computed_values, Display::Block);
+    let mut anonymous = LayoutBox::new(BoxType::AnonymousBlock(Box::new(style)));
+    anonymous.children = wrap_inline_children(root, boxes);
+    anonymous
+}
+
+// the box tree of a styled tree, laid out in the viewport
+pub fn layout_tree<'a>(
+    root: &'a StyledNode<'a>,
+    viewport: Rect,
+    fonts: &FontDatabase,
+) -> LayoutBox<'a> {
+    let mut root_box = build_layout_tree(root);
+    let containing = ContainingBlock {
+        x: viewport.x,
+        y: viewport.y,
+        width: viewport.width,
+        height: Some(viewport.height),
+    };
+    let context = LayoutContext { fonts };
+    root_box.layout_block(
+        &context,
+        &containing,
+        viewport.y,
+        MarginStrut::default(),
+        true,
+        &mut FloatContext::new(),
+    );
+    root_box.layout_positioned(&context, viewport);
+    root_box
+}
+
+// helpers shared by the layout tests
+#[cfg(test)]
+pub(crate) mod test_util {
+    use super::*;
+    use crate::assign::cascade_style_tree;
+    use crate::cascade::Cascade;
+    use crate::css::CSSParser;
+    use crate::html::HTMLParser;
+    use crate::inline::FragmentKind;
+
+    // the stylesheet of html fragments: no margin around them, and 10px lines
+    const CSS: &str = "body { margin: 0; font-size: 10px; line-height: 10px; }
+        p { margin: 0; } td { padding: 0; }";
+
+    // lays out a document in a 800x600 viewport, like a browser window; an
+    // html fragment is put in the body of a document, with the test stylesheet
+    pub fn with_layout(html: &str, css: &str, check: impl FnOnce(&LayoutBox)) {
+        let (html, css) = match html.starts_with("<html") {
+            true => (html.to_string(), css.to_string()),
+            false => (
+                format!("<html><body>{}</body></html>", html),
+                format!("{} {}", CSS, css),
+            ),
+        };
+        let document = HTMLParser::new(html).parse_document();
+        let stylesheet = CSSParser::new(css).parse_stylesheet();
+        let mut cascade = Cascade::new();
+        cascade.add_stylesheet(&stylesheet);
+        let styled = cascade_style_tree(document.root(), &cascade);
+        let viewport = Rect {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+        };
+        check(&layout_tree(&styled, viewport, &FontDatabase::new()));
+    }
+
+    pub fn id<'b>(layout_box: &'b LayoutBox) -> Option<&'b str> {
+        match &layout_box.styled_node()?.node.node_type {
+            NodeType::Element(elem) => elem.id().map(String::as_str),
+            _ => None,
+        }
+    }
+
+    // the box of the element with the id
+    pub fn find<'b, 'a>(layout_box: &'b LayoutBox<'a>, name: &str) -> &'b LayoutBox<'a> {
+        fn search<'b, 'a>(layout_box: &'b LayoutBox<'a>, name: &str) -> Option<&'b LayoutBox<'a>> {
+            match id(layout_box) == Some(name) {
+                true => Some(layout_box),
+                false => layout_box
+                    .children
+                    .iter()
+                    .find_map(|child| search(child, name)),
+            }
+        }
+        search(layout_box, name).unwrap_or_else(|| panic!("no box with the id {}", name))
+    }
+
+    // the border box of the element with the id, as `(x, y, width, height)`
+    pub fn border_box(root: &LayoutBox, name: &str) -> (f32, f32, f32, f32) {
+        let rect = find(root, name).dimensions.border_box();
+        (rect.x, rect.y, rect.width, rect.height)
+    }
+
+    // the position of the text fragment in the lines of the element
+    pub fn text_position(root: &LayoutBox, name: &str, text: &str) -> (f32, f32) {
+        let fragment = find(root, name)
+            .lines
+            .iter()
+            .flat_map(|line| &line.fragments)
+            .find(|fragment| matches!(&fragment.kind, FragmentKind::Text(_, t) if t == text))
+            .unwrap();
+        (fragment.rect.x, fragment.rect.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assign::cascade_style_tree;
+    use crate::cascade::Cascade;
+    use crate::css::CSSParser;
+    use crate::html::HTMLParser;
+
+    // the box tree as `type(tag)[children]`, text boxes as their text
+    fn describe(layout_box: &LayoutBox) -> String {
+        let tag = || match layout_box.styled_node().map(|node| &node.node.node_type) {
+            Some(NodeType::Element(elem)) => elem.tag_name.clone(),
+            _ => String::new(),
+        };
+        let description = match &layout_box.box_type {
+            BoxType::Block(_) => format!("block({})", tag()),
+            BoxType::Inline(_) => format!("inline({})", tag()),
+            BoxType::InlineBlock(_) => format!("inline-block({})", tag()),
+            BoxType::Text(_, text) => format!("'{}'", text),
+            BoxType::AnonymousBlock(style) => match style.display {
+                Display::Block => String::from("anonymous"),
+                display => format!("anonymous({:?})", display),
+            },
+        };
+        match layout_box.children.is_empty() {
+            true => description,
+            false => format!(
+                "{}[{}]",
+                description,
+                layout_box
+                    .children
+                    .iter()
+                    .map(describe)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    fn box_tree(html: &str, css: &str) -> String {
+        let document = HTMLParser::new(html.to_string()).parse_document();
+        let stylesheet = CSSParser::new(css.to_string()).parse_stylesheet();
+        let mut cascade = Cascade::new();
+        cascade.add_stylesheet(&stylesheet);
+        let styled = cascade_style_tree(document.root(), &cascade);
+        describe(&build_layout_tree(&styled))
+    }
+
+    #[test]
+    fn test_block_and_inline_boxes() {
+        assert_eq!(
+            box_tree(
+                "<html><body><p>Hello <em>world</em>!</p><div>a</div></body></html>",
+                ""
+            ),
+            "block(html)[block(body)[block(p)['Hello ', inline(em)['world'], '!'], block(div)['a']]]"
+        );
+        // `head` and its content are hidden by the user agent stylesheet
+        assert_eq!(
+            box_tree(
+                "<html><head><title>t</title></head><body>b</body></html>",
+                ""
+            ),
+            "block(html)[block(body)['b']]"
+        );
+    }
+
+    #[test]
+    fn test_anonymous_blocks() {
+        assert_eq!(
+            box_tree(
+                "<html><body>text<p>para</p><i>more</i> end</body></html>",
+                ""
+            ),
+            "block(html)[block(body)[anonymous['text'], block(p)['para'], anonymous[inline(i)['more'], 'end']]]"
+        );
+        // a block inside an inline splits the inline around it
+        assert_eq!(
+            box_tree(
+                "<html><body><span>a<div>b</div>c</span></body></html>",
+                ""
+            ),
+            "block(html)[block(body)[anonymous[inline(span)['a']], block(div)['b'], anonymous[inline(span)['c']]]]"
+        );
+    }
+
+    #[test]
+    fn test_out_of_flow_boxes() {
+        // absolutely positioned boxes are blockified, without splitting the
+        // inline content around them
+        assert_eq!(
+            box_tree(
+                "<html><body><p>a<span>b</span>c</p><div>d</div> <em>e</em> </body></html>",
+                "span, em { position: absolute; }"
+            ),
+            "block(html)[block(body)[block(p)['a', block(span)['b'], 'c'], block(div)['d'], block(em)['e']]]"
+        );
+    }
+
+    #[test]
+    fn test_table_boxes() {
+        // cells outside of rows and tables get anonymous ones, and the
+        // whitespace between them is dropped
+        assert_eq!(
+            box_tree(
+                "<html><body><div><span>a</span><td>b</td> <td>c</td></div></body></html>",
+                "span { display: table-cell; }"
+            ),
+            "block(html)[block(body)[block(div)[anonymous(Table)[anonymous(TableRow)[block(span)['a'], block(td)['b'], block(td)['c']]]]]]"
+        );
+        assert_eq!(
+            box_tree(
+                "<html><body><table><caption>t</caption><col><tr> <td>a</td>b</tr>c<tbody><p>d</p></tbody></table></body></html>",
+                ""
+            ),
+            "block(html)[block(body)[block(table)[block(caption)['t'], block(col), block(tr)[block(td)['a'], anonymous(TableCell)['b']], anonymous(TableRow)[anonymous(TableCell)['c']], block(tbody)[anonymous(TableRow)[anonymous(TableCell)[block(p)['d']]]]]]]"
+        );
+        // floated and positioned cells are blocks
+        assert_eq!(
+            box_tree(
+                "<html><body><table><tr><td>a</td><td class='f'>b</td></tr></table></body></html>",
+                ".f { float: left; }"
+            ),
+            "block(html)[block(body)[block(table)[block(tr)[block(td)['a'], anonymous(TableCell)[block(td)['b']]]]]]"
+        );
+    }
+
+    #[test]
+    fn test_display_values() {
+        assert_eq!(
+            box_tree(
+                "<html><body><div><span class='c'><b>x</b><i>y</i></span><em>z</em><button>w</button></div></body></html>",
+                ".c { display: contents; } em { display: none; } button { display: inline-block; }"
+            ),
+            "block(html)[block(body)[block(div)[inline(b)['x'], inline(i)['y'], inline-block(button)['w']]]]"
+        );
+        assert_eq!(
+            box_tree("<html><body>a</body></html>", "html { display: none; }"),
+            "anonymous"
+        );
+    }
+
+    #[test]
+    fn test_generated_boxes() {
+        assert_eq!(
+            box_tree(
+                "<html><body><p>b</p></body></html>",
+                "p::before { content: 'a'; } p::after { content: 'c'; display: block; }"
+            ),
+            "block(html)[block(body)[block(p)[anonymous[inline(p)['a'], 'b'], block(p)['c']]]]"
+        );
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let dimensions = Dimensions {
+            content: Rect {
+                x: 10.0,
+                y: 10.0,
+                width: 100.0,
+                height: 50.0,
+            },
+            padding: EdgeSizes {
+                left: 1.0,
+                right: 1.0,
+                top: 2.0,
+                bottom: 2.0,
+            },
+            border: EdgeSizes {
+                left: 3.0,
+                right: 3.0,
+                top: 3.0,
+                bottom: 3.0,
+            },
+            margin: EdgeSizes::default(),
+        };
+        assert_eq!(
+            dimensions.border_box(),
+            Rect {
+                x: 6.0,
+                y: 5.0,
+                width: 108.0,
+                height: 60.0,
+            }
+        );
+        assert_eq!(dimensions.margin_box(), dimensions.border_box());
+    }
+}
@@ -1,5 +1,27 @@
 pub mod assign;
+pub mod block;
+pub mod calc;
+pub mod cascade;
+pub mod counters;
 pub mod css;
 pub mod dom;
+pub mod flex;
+pub mod floats;
+pub mod fonts;
+pub mod grid;
 pub mod html;
+pub mod inline;
+pub mod layout;
+pub mod loader;
+pub mod media;
 pub mod parser;
+pub mod positioned;
+pub mod properties;
+pub mod serialize;
+pub mod shorthands;
+pub mod style;
+pub mod supports;
+pub mod table;
+pub mod tokenizer;
+pub mod user_agent;
+pub mod variables;
@@ -0,0 +1,351 @@
+use crate::css::{CSSParser, CssRule, Origin, Stylesheet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// fetches the text of the resources referenced by documents and stylesheets
+pub trait StylesheetLoader {
+    fn load(&self, url: &str) -> io::Result<String>;
+
+    // binary resources, like fonts
+    fn load_bytes(&self, url: &str) -> io::Result<Vec<u8>> {
+        self.load(url).map(String::into_bytes)
+    }
+}
+
+// loads urls as paths relative to a root directory
+pub struct FileSystemLoader {
+    root: PathBuf,
+}
+
+impl FileSystemLoader {
+    pub fn new<P: Into<PathBuf>>(root: P) -> FileSystemLoader {
+        FileSystemLoader { root: root.into() }
+    }
+
+    fn path(&self, url: &str) -> io::Result<PathBuf> {
+        let path = match url.split_once("://") {
+            Some(("file", path)) => path,
+            Some((scheme, _)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("cannot load {} urls from the file system", scheme),
+                ))
+            }
+            None => url,
+        };
+        // `..` segments must not escape the root, even through symbolic links
+        let path = self
+            .root
+            .join(path.trim_start_matches('/'))
+            .canonicalize()?;
+        if !path.starts_with(self.root.canonicalize()?) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} is outside of the loader root", url),
+            ));
+        }
+        Ok(path)
+    }
+}
+
+impl StylesheetLoader for FileSystemLoader {
+    fn load(&self, url: &str) -> io::Result<String> {
+        fs::read_to_string(self.path(url)?)
+    }
+
+    fn load_bytes(&self, url: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.path(url)?)
+    }
+}
+
+// remove the `.` and `..` segments of a path
+fn normalize_path(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let parts: Vec<&str> = path.split('/').collect();
+    for (index, segment) in parts.iter().enumerate() {
+        match *segment {
+            "." => {}
+            ".." => {
+                if segments
+                    .last()
+                    .is_some_and(|last| !last.is_empty() && *last != "..")
+                {
+                    segments.pop();
+                } else if segments.first() != Some(&"") {
+                    segments.push("..");
+                }
+            }
+            // keep the leading slash and empty segments in the middle
+            "" if index != 0 && index != parts.len() - 1 => {}
+            _ => segments.push(segment),
+        }
+    }
+    // a trailing `.` or `..` still designates a directory
+    if matches!(parts.last(), Some(&".") | Some(&"..")) {
+        segments.push("");
+    }
+    segments.join("/")
+}
+
+// resolve `url` against the url of the resource referencing it
+pub fn resolve_url(base: Option<&str>, url: &str) -> String {
+    if url.contains("://") || url.starts_with("data:") {
+        return url.to_string();
+    }
+    let base = match base {
+        Some(base) => base,
+        None => return normalize_path(url),
+    };
+
+    // keep the scheme and host of absolute base urls
+    let (origin, base_path) = match base.find("://") {
+        Some(index) => {
+            let host_end = base[index + 3..]
+                .find('/')
+                .map_or(base.len(), |end| index + 3 + end);
+            (&base[..host_end], &base[host_end..])
+        }
+        None => ("", base),
+    };
+
+    let path = if url.starts_with('/') {
+        url.to_string()
+    } else {
+        let directory = base_path.rfind('/').map_or("", |end| &base_path[..=end]);
+        format!("{}{}", directory, url)
+    };
+    format!("{}{}", origin, normalize_path(&path))
+}
+
+fn load_imported(
+    url: &str,
+    origin: Origin,
+    loader: &dyn StylesheetLoader,
+    loading: &mut Vec<String>,
+) -> io::Result<Stylesheet> {
+    let source = loader.load(url)?;
+    let mut stylesheet = CSSParser::new(source)
+        .parse_stylesheet()
+        .with_origin(origin)
+        .with_url(url);
+    resolve_nested_imports(&mut stylesheet, loader, loading);
+    Ok(stylesheet)
+}
+
+// `loading` holds the chain of stylesheets being imported, to detect cycles
+fn resolve_nested_imports(
+    stylesheet: &mut Stylesheet,
+    loader: &dyn StylesheetLoader,
+    loading: &mut Vec<String>,
+) {
+    let base = stylesheet.url.clone();
+    let origin = stylesheet.origin;
+    for rule in stylesheet.rules.iter_mut() {
+        if let CssRule::Import(import) = rule {
+            let url = resolve_url(base.as_deref(), &import.url);
+            if loading.contains(&url) {
+                continue;
+            }
+
+            // failing imports are ignored, like in browsers
+            loading.push(url.clone());
+            import.stylesheet = load_imported(&url, origin, loader, loading)
+                .ok()
+                .map(Box::new);
+            loading.pop();
+        }
+    }
+}
+
+// load the stylesheets imported by `stylesheet`, recursively
+pub fn resolve_imports(stylesheet: &mut Stylesheet, loader: &dyn StylesheetLoader) {
+    let mut loading: Vec<String> = stylesheet.url().map(String::from).into_iter().collect();
+    resolve_nested_imports(stylesheet, loader, &mut loading);
+}
+
+// load and parse the stylesheet at `url`, with all its imports
+pub fn load_stylesheet(url: &str, loader: &dyn StylesheetLoader) -> io::Result<Stylesheet> {
+    let mut loading = vec![url.to_string()];
+    load_imported(url, Origin::Author, loader, &mut loading)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::ImportRule;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    // serves stylesheets from memory, recording the requested urls
+    struct MemoryLoader {
+        files: HashMap<String, String>,
+        requests: RefCell<Vec<String>>,
+    }
+
+    impl MemoryLoader {
+        fn new(files: &[(&str, &str)]) -> MemoryLoader {
+            MemoryLoader {
+                files: files
+                    .iter()
+                    .map(|(url, css)| (url.to_string(), css.to_string()))
+                    .collect(),
+                requests: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl StylesheetLoader for MemoryLoader {
+        fn load(&self, url: &str) -> io::Result<String> {
+            self.requests.borrow_mut().push(url.to_string());
+            self.files
+                .get(url)
+                .cloned()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, url.to_string()))
+        }
+    }
+
+    fn imports(stylesheet: &Stylesheet) -> Vec<&ImportRule> {
+        stylesheet
+            .rules
+            .iter()
+            .filter_map(|rule| match rule {
+                CssRule::Import(import) => Some(import),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        assert_eq!(
+            resolve_url(Some("css/main.css"), "base.css"),
+            "css/base.css"
+        );
+        assert_eq!(
+            resolve_url(Some("css/main.css"), "../fonts.css"),
+            "fonts.css"
+        );
+        assert_eq!(
+            resolve_url(Some("css/main.css"), "./a/../b.css"),
+            "css/b.css"
+        );
+        assert_eq!(resolve_url(Some("/css/main.css"), "/root.css"), "/root.css");
+        assert_eq!(resolve_url(None, "a/./b.css"), "a/b.css");
+        assert_eq!(
+            resolve_url(Some("https://example.com/css/main.css"), "../img/a.css"),
+            "https://example.com/img/a.css"
+        );
+        assert_eq!(
+            resolve_url(Some("https://example.com/css/main.css"), "/a.css"),
+            "https://example.com/a.css"
+        );
+        assert_eq!(
+            resolve_url(Some("css/main.css"), "https://cdn.net/x.css"),
+            "https://cdn.net/x.css"
+        );
+    }
+
+    #[test]
+    fn test_nested_imports() {
+        let loader = MemoryLoader::new(&[
+            (
+                "css/main.css",
+                "@import url(\"parts/base.css\") screen; p { margin: auto; }",
+            ),
+            (
+                "css/parts/base.css",
+                "@import '../theme.css'; h1 { margin: auto; }",
+            ),
+            ("css/theme.css", "h2 { margin: auto; }"),
+        ]);
+        let stylesheet = load_stylesheet("css/main.css", &loader).unwrap();
+
+        let base = imports(&stylesheet)[0].stylesheet.as_ref().unwrap();
+        assert_eq!(base.url(), Some("css/parts/base.css"));
+        let theme = imports(base)[0].stylesheet.as_ref().unwrap();
+        assert_eq!(theme.url(), Some("css/theme.css"));
+        assert_eq!(theme.rules.len(), 1);
+    }
+
+    #[test]
+    fn test_import_cycles_and_failures() {
+        let loader = MemoryLoader::new(&[
+            (
+                "a.css",
+                "@import 'b.css'; @import 'missing.css'; p { margin: auto; }",
+            ),
+            (
+                "b.css",
+                "@import 'a.css'; @import 'b.css'; h1 { margin: auto; }",
+            ),
+        ]);
+        let stylesheet = load_stylesheet("a.css", &loader).unwrap();
+
+        let a_imports = imports(&stylesheet);
+        assert!(a_imports[1].stylesheet.is_none());
+        let b = a_imports[0].stylesheet.as_ref().unwrap();
+        assert!(imports(b).iter().all(|import| import.stylesheet.is_none()));
+        assert_eq!(
+            *loader.requests.borrow(),
+            vec!["a.css", "b.css", "missing.css"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_imports_of_parsed_stylesheet() {
+        let loader = MemoryLoader::new(&[("print.css", "p { margin: auto; }")]);
+        let mut stylesheet = CSSParser::new("@import 'print.css' print;".to_string())
+            .parse_stylesheet()
+            .with_origin(Origin::User);
+        resolve_imports(&mut stylesheet, &loader);
+
+        let print = imports(&stylesheet)[0].stylesheet.as_ref().unwrap();
+        assert_eq!(print.origin(), Origin::User);
+    }
+
+    #[test]
+    fn test_file_system_loader() {
+        let root = std::env::temp_dir().join(format!("brother-loader-{}", std::process::id()));
+        fs::create_dir_all(root.join("css")).unwrap();
+        fs::write(
+            root.join("css/main.css"),
+            "@import 'base.css'; p { margin: auto; }",
+        )
+        .unwrap();
+        fs::write(root.join("css/base.css"), "h1 { margin: auto; }").unwrap();
+
+        let loader = FileSystemLoader::new(&root);
+        let stylesheet = load_stylesheet("css/main.css", &loader).unwrap();
+        assert!(imports(&stylesheet)[0].stylesheet.is_some());
+        assert!(loader.load("file:///css/base.css").is_ok());
+        assert!(loader.load("https://example.com/a.css").is_err());
+        assert!(load_stylesheet("css/none.css", &loader).is_err());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_file_system_loader_root() {
+        let base = std::env::temp_dir().join(format!("brother-root-{}", std::process::id()));
+        let root = base.join("site");
+        fs::create_dir_all(root.join("css")).unwrap();
+        fs::write(base.join("secret.css"), "p { margin: auto; }").unwrap();
+        fs::write(
+            root.join("css/main.css"),
+            "@import '../../secret.css'; @import '/../secret.css'; h1 { margin: auto; }",
+        )
+        .unwrap();
+
+        let loader = FileSystemLoader::new(&root);
+        let stylesheet = load_stylesheet("css/main.css", &loader).unwrap();
+        assert!(imports(&stylesheet)
+            .iter()
+            .all(|import| import.stylesheet.is_none()));
+        let error = loader.load("../secret.css").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        assert!(loader.load("file:///../secret.css").is_err());
+
+        fs::remove_dir_all(base).unwrap();
+    }
+}
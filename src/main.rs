@@ -1,18 +1,107 @@
 pub mod assign;
+pub mod block;
+pub mod calc;
+pub mod cascade;
+pub mod counters;
 pub mod css;
 pub mod dom;
+pub mod flex;
+pub mod floats;
+pub mod fonts;
+pub mod grid;
 pub mod html;
+pub mod inline;
+pub mod layout;
+pub mod loader;
+pub mod media;
 pub mod parser;
+pub mod positioned;
+pub mod properties;
+pub mod serialize;
+pub mod shorthands;
+pub mod style;
+pub mod supports;
+pub mod table;
+pub mod tokenizer;
+pub mod user_agent;
+pub mod variables;
 
+use assign::cascade_style_tree;
+use cascade::Cascade;
 use css::CSSParser;
+use fonts::FontDatabase;
 use html::HTMLParser;
+use inline::FragmentKind;
+use layout::{layout_tree, LayoutBox, Rect};
+use loader::FileSystemLoader;
+use serialize::ToCss;
+use std::env;
+use std::fs;
+use std::process;
+
+// `brother [--minify] <file.css>` prints the stylesheet serialized, or minified
+fn print_stylesheet(args: &[String]) {
+    let minify = args.iter().any(|arg| arg == "--minify");
+    let path = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: brother [--minify] <file.css>");
+            process::exit(2);
+        }
+    };
+    let css = match fs::read_to_string(path) {
+        Ok(css) => css,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            process::exit(1);
+        }
+    };
+    let stylesheet = CSSParser::new(css)
+        .with_validation(false)
+        .parse_stylesheet();
+    match minify {
+        true => println!("{}", stylesheet.to_minified_css()),
+        false => print!("{}", stylesheet.to_css()),
+    }
+}
+
+// prints the text of each line box, with its position
+fn print_lines(layout_box: &LayoutBox) {
+    for line in &layout_box.lines {
+        let text: String = line
+            .fragments
+            .iter()
+            .filter_map(|fragment| match &fragment.kind {
+                FragmentKind::Text(_, text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        println!("({}, {}) {}", line.rect.x, line.rect.y, text);
+    }
+    for child in &layout_box.children {
+        print_lines(child);
+    }
+}
 
 fn main() {
-    // html
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        return print_stylesheet(&args);
+    }
+
+    // html, with its own styles
     let test_html = "<html>
+    <head>
+        <style>
+            h1, h2, h3 { margin: auto; color: #cc0000; }
+            div.note { margin-bottom: 20px; padding: 10px; }
+            #answer { display: none; }
+        </style>
+        <link rel='stylesheet' href='style.css'>
+    </head>
     <body>
         <h1>Title</h1>
-        <div id='main' class='test'>
+        <div id='main' class='test' style='padding: 4px'>
             <p>Hello <em>world</em>!</p>
         </div>
     </body>
@@ -21,12 +110,26 @@ fn main() {
     let document = html_parser.parse_document();
     document.display();
 
-    // css
-    let test_stylesheet = "
-    h1, h2, h3 { margin: auto; color: #cc0000; }
-    div.note { margin-bottom: 20px; padding: 10px; }
-    #answer { display: none; }
-    ";
-    let mut css_parser = CSSParser::new(test_stylesheet.to_string());
-    let _stylesheet = css_parser.parse_stylesheet();
+    // css, collected from the document
+    let loader = FileSystemLoader::new(".");
+    let stylesheets = document.stylesheets(&loader);
+    let mut cascade = Cascade::new();
+    for stylesheet in &stylesheets {
+        cascade.add_stylesheet(stylesheet);
+    }
+    let styled = cascade_style_tree(document.root(), &cascade);
+
+    // fonts, from the `@font-face` rules
+    let mut fonts = FontDatabase::new();
+    fonts.load_font_faces(&cascade, &loader);
+
+    // boxes, generated from the styled tree and laid out in the viewport
+    let viewport = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 800.0,
+        height: 600.0,
+    };
+    let layout = layout_tree(&styled, viewport, &fonts);
+    print_lines(&layout);
 }
@@ -0,0 +1,648 @@
+use crate::css::valid_identifier_char;
+use crate::parser::TextParser;
+
+// the device a document is rendered for, which media queries are evaluated against
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaEnvironment {
+    pub media_type: String,
+    // viewport size in px
+    pub width: f32,
+    pub height: f32,
+    // in dots per px
+    pub resolution: f32,
+    pub prefers_color_scheme: String,
+}
+
+impl Default for MediaEnvironment {
+    fn default() -> MediaEnvironment {
+        MediaEnvironment::new(1024.0, 768.0)
+    }
+}
+
+impl MediaEnvironment {
+    pub fn new(width: f32, height: f32) -> MediaEnvironment {
+        MediaEnvironment {
+            media_type: String::from("screen"),
+            width,
+            height,
+            resolution: 1.0,
+            prefers_color_scheme: String::from("light"),
+        }
+    }
+}
+
+// a comma separated list of queries, matching when any of them does
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MediaQueryList {
+    pub queries: Vec<MediaQuery>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    pub negated: bool,
+    pub media_type: Option<String>,
+    pub condition: Option<MediaCondition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaCondition {
+    Not(Box<MediaCondition>),
+    And(Vec<MediaCondition>),
+    Or(Vec<MediaCondition>),
+    Feature(MediaFeature),
+    // syntactically valid but unknown conditions never match
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaFeature {
+    // `(color)`: the feature is not zero or none
+    Boolean(String),
+    // `(min-width: 600px)`, `(400px <= width < 800px)`, ... normalized to
+    // comparisons of the feature against values
+    Range(String, Vec<(Comparison, MediaValue)>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaValue {
+    // lengths are resolved to px
+    Length(f32),
+    // resolutions are resolved to dppx
+    Resolution(f32),
+    Number(f32),
+    // `16/9`
+    Ratio(f32, f32),
+    Ident(String),
+}
+
+impl Comparison {
+    fn flip(self) -> Comparison {
+        match self {
+            Comparison::Equal => Comparison::Equal,
+            Comparison::Less => Comparison::Greater,
+            Comparison::LessOrEqual => Comparison::GreaterOrEqual,
+            Comparison::Greater => Comparison::Less,
+            Comparison::GreaterOrEqual => Comparison::LessOrEqual,
+        }
+    }
+
+    fn compare(self, left: f32, right: f32) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+impl MediaQueryList {
+    pub fn parse(input: &str) -> MediaQueryList {
+        MediaQueryParser::new(input.to_string()).parse_query_list()
+    }
+
+    // an empty list matches all media
+    pub fn matches(&self, environment: &MediaEnvironment) -> bool {
+        self.queries.is_empty() || self.queries.iter().any(|query| query.matches(environment))
+    }
+}
+
+impl MediaQuery {
+    pub fn matches(&self, environment: &MediaEnvironment) -> bool {
+        let type_matches = match &self.media_type {
+            Some(media_type) => media_type == "all" || *media_type == environment.media_type,
+            None => true,
+        };
+        let condition_matches = match &self.condition {
+            Some(condition) => condition.matches(environment),
+            None => true,
+        };
+        (type_matches && condition_matches) != self.negated
+    }
+}
+
+impl MediaCondition {
+    pub fn matches(&self, environment: &MediaEnvironment) -> bool {
+        match self {
+            MediaCondition::Not(condition) => !condition.matches(environment),
+            MediaCondition::And(conditions) => conditions
+                .iter()
+                .all(|condition| condition.matches(environment)),
+            MediaCondition::Or(conditions) => conditions
+                .iter()
+                .any(|condition| condition.matches(environment)),
+            MediaCondition::Feature(feature) => feature.matches(environment),
+            MediaCondition::Unknown(_) => false,
+        }
+    }
+}
+
+impl MediaFeature {
+    pub fn matches(&self, environment: &MediaEnvironment) -> bool {
+        let orientation = if environment.height >= environment.width {
+            "portrait"
+        } else {
+            "landscape"
+        };
+        let size = |name: &str| match name {
+            "width" => environment.width,
+            _ => environment.height,
+        };
+        let aspect_ratio = environment.width / environment.height;
+
+        match self {
+            MediaFeature::Boolean(name) => match name.as_str() {
+                "width" => environment.width != 0.0,
+                "height" => environment.height != 0.0,
+                "aspect-ratio" => environment.width != 0.0 && environment.height != 0.0,
+                "resolution" => environment.resolution != 0.0,
+                "orientation" | "prefers-color-scheme" | "color" => true,
+                _ => false,
+            },
+            MediaFeature::Range(name, comparisons) => {
+                comparisons.iter().all(|(comparison, value)| {
+                    match (name.as_str(), value) {
+                        ("width" | "height", MediaValue::Length(length)) => {
+                            comparison.compare(size(name), *length)
+                        }
+                        // zero is the only length accepted without unit
+                        ("width" | "height", MediaValue::Number(number)) if *number == 0.0 => {
+                            comparison.compare(size(name), 0.0)
+                        }
+                        ("aspect-ratio", MediaValue::Ratio(width, height)) => {
+                            comparison.compare(aspect_ratio, width / height)
+                        }
+                        ("aspect-ratio", MediaValue::Number(ratio)) => {
+                            comparison.compare(aspect_ratio, *ratio)
+                        }
+                        ("resolution", MediaValue::Resolution(resolution)) => {
+                            comparison.compare(environment.resolution, *resolution)
+                        }
+                        ("orientation", MediaValue::Ident(ident)) => {
+                            *comparison == Comparison::Equal && ident == orientation
+                        }
+                        ("prefers-color-scheme", MediaValue::Ident(ident)) => {
+                            *comparison == Comparison::Equal
+                                && *ident == environment.prefers_color_scheme
+                        }
+                        _ => false,
+                    }
+                })
+            }
+        }
+    }
+}
+
+struct MediaQueryParser {
+    text_parser: TextParser,
+}
+
+impl MediaQueryParser {
+    fn new(input: String) -> MediaQueryParser {
+        MediaQueryParser {
+            text_parser: TextParser::new(input),
+        }
+    }
+
+    fn remove_whitespaces(&mut self) {
+        self.text_parser.remove_whitespaces();
+    }
+
+    fn current_char(&self) -> Option<char> {
+        if self.text_parser.eol() {
+            None
+        } else {
+            Some(self.text_parser.get_current_char())
+        }
+    }
+
+    fn parse_identifier(&mut self) -> String {
+        self.text_parser
+            .consume_chars_while(valid_identifier_char)
+            .to_lowercase()
+    }
+
+    // consume the keyword if it comes next, as a whole word
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        let state = self.text_parser._state();
+        let matches = state.len() >= keyword.len()
+            && state[..keyword.len()].eq_ignore_ascii_case(keyword)
+            && !state[keyword.len()..]
+                .chars()
+                .next()
+                .is_some_and(valid_identifier_char);
+        if matches {
+            self.text_parser
+                .consume_pattern(state[..keyword.len()].to_string());
+            self.remove_whitespaces();
+        }
+        matches
+    }
+
+    // the text up to the closing parenthesis of a block, for unknown conditions
+    fn skip_block(&mut self) -> String {
+        let mut depth = 1;
+        let mut skipped = String::new();
+        while let Some(c) = self.current_char() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 1 => break,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            skipped.push(self.text_parser.consume_char());
+        }
+        skipped
+    }
+
+    // the text up to the next comma outside of parentheses, for invalid queries
+    fn skip_query(&mut self) {
+        let mut depth = 0;
+        while let Some(c) = self.current_char() {
+            match c {
+                ',' if depth == 0 => break,
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            self.text_parser.consume_char();
+        }
+    }
+
+    fn parse_query_list(&mut self) -> MediaQueryList {
+        let mut queries = Vec::new();
+        loop {
+            self.remove_whitespaces();
+            if self.text_parser.eol() {
+                break;
+            }
+            let query = self.parse_query();
+            self.remove_whitespaces();
+            match query {
+                Some(query) if matches!(self.current_char(), None | Some(',')) => {
+                    queries.push(query)
+                }
+                // queries that fail to parse become `not all`
+                _ => {
+                    self.skip_query();
+                    queries.push(MediaQuery {
+                        negated: true,
+                        media_type: Some(String::from("all")),
+                        condition: None,
+                    });
+                }
+            }
+            if self.current_char() == Some(',') {
+                self.text_parser.consume_char();
+            }
+        }
+        MediaQueryList { queries }
+    }
+
+    fn parse_query(&mut self) -> Option<MediaQuery> {
+        if self.current_char() == Some('(') || self.text_parser._state().starts_with("not (") {
+            return Some(MediaQuery {
+                negated: false,
+                media_type: None,
+                condition: Some(self.parse_condition(true)?),
+            });
+        }
+
+        let negated = self.consume_keyword("not");
+        // `only` only hides the query from legacy user agents
+        self.consume_keyword("only");
+        let media_type = self.parse_identifier();
+        if let "" | "and" | "or" | "not" | "only" = media_type.as_str() {
+            return None;
+        }
+        self.remove_whitespaces();
+
+        let condition = if self.consume_keyword("and") {
+            Some(self.parse_condition(false)?)
+        } else {
+            None
+        };
+
+        Some(MediaQuery {
+            negated,
+            media_type: Some(media_type),
+            condition,
+        })
+    }
+
+    fn parse_condition(&mut self, allow_or: bool) -> Option<MediaCondition> {
+        if self.consume_keyword("not") {
+            return Some(MediaCondition::Not(Box::new(self.parse_in_parens()?)));
+        }
+
+        let mut conditions = vec![self.parse_in_parens()?];
+        let mut is_or = false;
+        loop {
+            self.remove_whitespaces();
+            if self.consume_keyword("and") {
+                conditions.push(self.parse_in_parens()?);
+            } else if allow_or && self.consume_keyword("or") {
+                is_or = true;
+                conditions.push(self.parse_in_parens()?);
+            } else {
+                break;
+            }
+        }
+
+        Some(match (conditions.len(), is_or) {
+            (1, _) => conditions.remove(0),
+            (_, true) => MediaCondition::Or(conditions),
+            (_, false) => MediaCondition::And(conditions),
+        })
+    }
+
+    // a condition or feature in parentheses; anything else in balanced
+    // parentheses is an unknown condition
+    fn parse_in_parens(&mut self) -> Option<MediaCondition> {
+        self.remove_whitespaces();
+        if self.current_char() != Some('(') {
+            return None;
+        }
+        self.text_parser.consume_char();
+        self.remove_whitespaces();
+        let content = self.text_parser._state().to_string();
+
+        let condition =
+            if self.current_char() == Some('(') || self.text_parser._state().starts_with("not ") {
+                self.parse_condition(true)
+            } else {
+                self.parse_feature()
+            };
+
+        self.remove_whitespaces();
+        let condition = match condition {
+            Some(condition) if self.current_char() == Some(')') => condition,
+            _ => {
+                self.skip_block();
+                let length = content.len() - self.text_parser._state().len();
+                MediaCondition::Unknown(content[..length].trim_end().to_string())
+            }
+        };
+        if self.current_char() != Some(')') {
+            return None;
+        }
+        self.text_parser.consume_char();
+        self.remove_whitespaces();
+        Some(condition)
+    }
+
+    fn parse_comparison(&mut self) -> Option<Comparison> {
+        let comparison = match self.current_char()? {
+            '=' => Comparison::Equal,
+            '<' => Comparison::Less,
+            '>' => Comparison::Greater,
+            _ => return None,
+        };
+        self.text_parser.consume_char();
+        let comparison = match (comparison, self.current_char()) {
+            (Comparison::Less, Some('=')) => Comparison::LessOrEqual,
+            (Comparison::Greater, Some('=')) => Comparison::GreaterOrEqual,
+            _ => comparison,
+        };
+        if comparison != Comparison::Equal && self.current_char() == Some('=') {
+            self.text_parser.consume_char();
+        }
+        self.remove_whitespaces();
+        Some(comparison)
+    }
+
+    fn parse_number(&mut self) -> Option<f32> {
+        self.text_parser
+            .consume_chars_while(|c| c.is_ascii_digit() || c == '.' || c == '-' || c == '+')
+            .parse::<f32>()
+            .ok()
+    }
+
+    fn parse_value(&mut self) -> Option<MediaValue> {
+        self.remove_whitespaces();
+        let c = self.current_char()?;
+        if !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+') {
+            let ident = self.parse_identifier();
+            self.remove_whitespaces();
+            return match ident.is_empty() {
+                true => None,
+                false => Some(MediaValue::Ident(ident)),
+            };
+        }
+
+        let number = self.parse_number()?;
+        let unit = self.text_parser.consume_chars_while(|c| c.is_alphabetic());
+        self.remove_whitespaces();
+
+        Some(match unit.to_lowercase().as_str() {
+            "" if self.current_char() == Some('/') => {
+                self.text_parser.consume_char();
+                self.remove_whitespaces();
+                let denominator = self.parse_number()?;
+                self.remove_whitespaces();
+                MediaValue::Ratio(number, denominator)
+            }
+            "" => MediaValue::Number(number),
+            "px" => MediaValue::Length(number),
+            "em" | "rem" => MediaValue::Length(number * 16.0),
+            "in" => MediaValue::Length(number * 96.0),
+            "cm" => MediaValue::Length(number * 96.0 / 2.54),
+            "mm" => MediaValue::Length(number * 96.0 / 25.4),
+            "pt" => MediaValue::Length(number * 96.0 / 72.0),
+            "pc" => MediaValue::Length(number * 16.0),
+            "dppx" | "x" => MediaValue::Resolution(number),
+            "dpi" => MediaValue::Resolution(number / 96.0),
+            "dpcm" => MediaValue::Resolution(number * 2.54 / 96.0),
+            _ => MediaValue::Ident(format!("{}{}", number, unit)),
+        })
+    }
+
+    // the content of `( ... )` when it is a media feature, `None` when it is
+    // not a feature this parser understands
+    fn parse_feature(&mut self) -> Option<MediaCondition> {
+        let starts_with_name = self
+            .current_char()
+            .is_some_and(|c| c.is_alphabetic() || c == '-');
+
+        if starts_with_name {
+            let name = self.parse_identifier();
+            self.remove_whitespaces();
+            match self.current_char() {
+                Some(')') => return Some(MediaCondition::Feature(MediaFeature::Boolean(name))),
+                Some(':') => {
+                    self.text_parser.consume_char();
+                    let value = self.parse_value()?;
+                    let (name, comparison) = match name.split_once('-') {
+                        Some(("min", feature)) => (feature.to_string(), Comparison::GreaterOrEqual),
+                        Some(("max", feature)) => (feature.to_string(), Comparison::LessOrEqual),
+                        _ => (name, Comparison::Equal),
+                    };
+                    return Some(MediaCondition::Feature(MediaFeature::Range(
+                        name,
+                        vec![(comparison, value)],
+                    )));
+                }
+                _ => {}
+            }
+
+            // `width < 600px`
+            let comparison = self.parse_comparison()?;
+            let value = self.parse_value()?;
+            return Some(MediaCondition::Feature(MediaFeature::Range(
+                name,
+                vec![(comparison, value)],
+            )));
+        }
+
+        // `600px < width` or `400px <= width < 800px`
+        let value = self.parse_value()?;
+        let comparison = self.parse_comparison()?;
+        let name = self.parse_identifier();
+        if name.is_empty() {
+            return None;
+        }
+        self.remove_whitespaces();
+        let mut comparisons = vec![(comparison.flip(), value)];
+        if let Some(comparison) = self.parse_comparison() {
+            comparisons.push((comparison, self.parse_value()?));
+        }
+        Some(MediaCondition::Feature(MediaFeature::Range(
+            name,
+            comparisons,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(query: &str, environment: &MediaEnvironment) -> bool {
+        MediaQueryList::parse(query).matches(environment)
+    }
+
+    #[test]
+    fn test_media_types() {
+        let screen = MediaEnvironment::default();
+        assert!(matches("", &screen));
+        assert!(matches("all", &screen));
+        assert!(matches("screen, print", &screen));
+        assert!(matches("only screen", &screen));
+        assert!(!matches("print", &screen));
+        assert!(matches("not print", &screen));
+        assert!(!matches("tv", &screen));
+    }
+
+    #[test]
+    fn test_width_features() {
+        let mobile = MediaEnvironment::new(375.0, 667.0);
+        let desktop = MediaEnvironment::new(1280.0, 800.0);
+
+        assert!(matches("(max-width: 600px)", &mobile));
+        assert!(!matches("(max-width: 600px)", &desktop));
+        assert!(matches("screen and (min-width: 40em)", &desktop));
+        assert!(!matches("screen and (min-width: 40em)", &mobile));
+        assert!(matches("(width: 375px)", &mobile));
+        assert!(matches("(width)", &mobile));
+        assert!(matches(
+            "(min-height: 600px) and (max-height: 700px)",
+            &mobile
+        ));
+    }
+
+    #[test]
+    fn test_range_syntax() {
+        let mobile = MediaEnvironment::new(375.0, 667.0);
+        let desktop = MediaEnvironment::new(1280.0, 800.0);
+
+        assert!(matches("(width < 600px)", &mobile));
+        assert!(!matches("(width < 600px)", &desktop));
+        assert!(matches("(600px < width)", &desktop));
+        assert!(matches("(320px <= width <= 480px)", &mobile));
+        assert!(!matches("(320px <= width <= 480px)", &desktop));
+        assert!(matches("(height >= 667px)", &mobile));
+        assert!(!matches("(height > 667px)", &mobile));
+    }
+
+    #[test]
+    fn test_logical_conditions() {
+        let mobile = MediaEnvironment::new(375.0, 667.0);
+        assert!(matches("(orientation: portrait)", &mobile));
+        assert!(!matches("(orientation: landscape)", &mobile));
+        assert!(matches("not (orientation: landscape)", &mobile));
+        assert!(matches(
+            "(width > 1000px) or (orientation: portrait)",
+            &mobile
+        ));
+        assert!(!matches(
+            "screen and (width > 1000px) and (orientation: portrait)",
+            &mobile
+        ));
+        assert!(matches("not screen and (width > 1000px)", &mobile));
+        assert!(matches("((width < 400px) and (height > 600px))", &mobile));
+        // unknown features never match
+        assert!(!matches("(max-weight: 3kg)", &mobile));
+        assert!(!matches("(scan: interlace)", &mobile));
+    }
+
+    #[test]
+    fn test_color_scheme_and_resolution() {
+        let environment = MediaEnvironment {
+            prefers_color_scheme: String::from("dark"),
+            resolution: 2.0,
+            ..Default::default()
+        };
+
+        assert!(matches("(prefers-color-scheme: dark)", &environment));
+        assert!(!matches("(prefers-color-scheme: light)", &environment));
+        assert!(matches("(min-resolution: 2dppx)", &environment));
+        assert!(matches("(min-resolution: 192dpi)", &environment));
+        assert!(!matches("(resolution > 2x)", &environment));
+    }
+
+    #[test]
+    fn test_aspect_ratio() {
+        let wide = MediaEnvironment::new(1920.0, 1080.0);
+        let desktop = MediaEnvironment::new(1280.0, 800.0);
+        assert!(matches("(min-aspect-ratio: 16/9)", &wide));
+        assert!(!matches("(min-aspect-ratio: 16/9)", &desktop));
+        assert!(matches("(aspect-ratio: 16 / 10)", &desktop));
+        assert!(matches("(aspect-ratio < 1.7)", &desktop));
+    }
+
+    #[test]
+    fn test_unknown_and_invalid_queries() {
+        let screen = MediaEnvironment::default();
+        // unsupported values are unknown conditions
+        assert!(!matches("(min-width: calc(100px))", &screen));
+        assert_eq!(
+            MediaQueryList::parse("(min-width: calc(100px))").queries[0].condition,
+            Some(MediaCondition::Unknown(
+                "min-width: calc(100px)".to_string()
+            ))
+        );
+        assert!(!matches("(width: -)", &screen));
+        assert!(!matches("(width: )", &screen));
+        assert!(!matches("(600px <)", &screen));
+        // invalid queries are `not all`, without affecting the others
+        assert!(!matches("screen and foo", &screen));
+        assert!(!matches("screen foo", &screen));
+        assert!(!matches("and", &screen));
+        assert!(!matches("(width", &screen));
+        assert!(matches("screen and foo, screen", &screen));
+        assert!(matches(
+            "screen and (width > 600px) or (color), all",
+            &screen
+        ));
+    }
+}
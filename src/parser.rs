@@ -29,7 +29,7 @@ impl TextParser {
     }
 
     pub fn get_current_char(&self) -> char {
-        self.input.chars().nth(self.position).unwrap()
+        self.input[self.position..].chars().next().unwrap()
     }
 
     pub fn consume_char(&mut self) -> char {
@@ -37,10 +37,10 @@ impl TextParser {
             panic!("Trying to consume character when end of input is reached");
         }
 
-        let mut char_iter = self.input[self.position..].chars();
-        self.position += 1;
+        let cur_char = self.get_current_char();
+        self.position += cur_char.len_utf8();
 
-        char_iter.next().unwrap()
+        cur_char
     }
 
     pub fn consume_chars_while<F>(&mut self, predicate: F) -> String
@@ -140,4 +140,13 @@ mod tests {
         let mut test_parser = TextParser::new(String::from("toto: tata;"));
         assert!(test_parser.consume_sequence(|c| c != ':', |c| c == 'o', true) == "tt");
     }
+
+    #[test]
+    fn test_consume_multibyte_chars() {
+        let mut test_parser = TextParser::new(String::from("«é» toto"));
+        assert!(test_parser.consume_char() == '«');
+        assert!(test_parser.consume_chars_while(|c| c != ' ') == "é»");
+        test_parser.remove_whitespaces();
+        assert!(test_parser.starts_with("toto"));
+    }
 }
@@ -0,0 +1,708 @@
+use crate::assign::StyledNode;
+use crate::inline::{FragmentKind, LineBox};
+use crate::layout::{BoxType, Dimensions, EdgeSizes, LayoutBox, LayoutContext, Rect};
+use crate::style::{BoxSizing, ComputedStyle, Direction, Overflow, Position, Size};
+
+// the rectangles that positioned boxes are placed against
+#[derive(Debug, Clone, Copy)]
+struct Frames {
+    // the padding box of the nearest positioned ancestor
+    containing: Rect,
+    viewport: Rect,
+    // the padding box of the nearest scroll container, where sticky boxes
+    // stick
+    scrollport: Rect,
+}
+
+// the insets and margins of an absolutely positioned box along one axis, in
+// px, `None` when auto
+struct Insets {
+    start: Option<f32>,
+    end: Option<f32>,
+    margin_start: Option<f32>,
+    margin_end: Option<f32>,
+}
+
+impl Insets {
+    // CSS 2.1 §10.3.7 and §10.6.4: the offset of the margin box from the
+    // start of a containing block `available` long, and the margins, once the
+    // border box is `size` long. Without insets the box stays at its static
+    // position; with both, auto margins share the space left, and otherwise
+    // the end inset is ignored, or the start one when `rtl`. Horizontal auto
+    // margins don't share negative space
+    fn solve(
+        &self,
+        size: f32,
+        available: f32,
+        static_start: f32,
+        rtl: bool,
+        vertical: bool,
+    ) -> (f32, f32, f32) {
+        let (Some(start), Some(end)) = (self.start, self.end) else {
+            let margin_start = self.margin_start.unwrap_or(0.0);
+            let margin_end = self.margin_end.unwrap_or(0.0);
+            let offset = match (self.start, self.end) {
+                (Some(start), _) => start,
+                (None, Some(end)) => available - end - margin_end - size - margin_start,
+                (None, None) => static_start,
+            };
+            return (offset, margin_start, margin_end);
+        };
+
+        let rest = available - start - end - size;
+        let (margin_start, margin_end) = match (self.margin_start, self.margin_end) {
+            (None, None) if rest >= 0.0 || vertical => (rest / 2.0, rest / 2.0),
+            (None, None) if rtl => (rest, 0.0),
+            (None, None) => (0.0, rest),
+            (None, Some(margin_end)) => (rest - margin_end, margin_end),
+            (Some(margin_start), None) => (margin_start, rest - margin_start),
+            (Some(margin_start), Some(margin_end)) if rtl && !vertical => {
+                let offset = available - end - margin_end - size - margin_start;
+                return (offset, margin_start, margin_end);
+            }
+            (Some(margin_start), Some(margin_end)) => (margin_start, margin_end),
+        };
+        (start, margin_start, margin_end)
+    }
+}
+
+// the offset of a relatively positioned box from its place in the flow,
+// percentages resolving against its containing block
+fn relative_offset(style: &ComputedStyle, containing: Rect) -> (f32, f32) {
+    let inset = &style.inset;
+    let width = Some(containing.width);
+    let height = Some(containing.height);
+    let dx = match (inset.left.resolve(width), inset.right.resolve(width)) {
+        (Some(_), Some(right)) if style.direction == Direction::Rtl => -right,
+        (Some(left), _) => left,
+        (None, Some(right)) => -right,
+        (None, None) => 0.0,
+    };
+    let dy = match (inset.top.resolve(height), inset.bottom.resolve(height)) {
+        (Some(top), _) => top,
+        (None, Some(bottom)) => -bottom,
+        (None, None) => 0.0,
+    };
+    (dx, dy)
+}
+
+// the offset along one axis keeping the border box of a sticky box, from
+// `start` to `end`, inside the scrollport `view` shrunk by the `insets`, as
+// far as its margin box stays within the `limits` of its parent's content;
+// the start inset wins over the end one
+fn stick(
+    (start, end): (f32, f32),
+    (margin_start, margin_end): (f32, f32),
+    insets: (Option<f32>, Option<f32>),
+    view: (f32, f32),
+    limits: (f32, f32),
+) -> f32 {
+    let mut offset = 0.0;
+    if let Some(inset) = insets.1 {
+        let past = view.1 - inset - end;
+        if past < 0.0 {
+            offset = past.max(limits.0 - start + margin_start).min(0.0);
+        }
+    }
+    if let Some(inset) = insets.0 {
+        let before = view.0 + inset - start;
+        if before > 0.0 {
+            offset = before.min(limits.1 - end - margin_end).max(0.0);
+        }
+    }
+    offset
+}
+
+// the offset keeping a sticky box inside its scrollport, without leaving the
+// content box of its parent
+fn sticky_offset(layout_box: &LayoutBox, parent: Rect, scrollport: Rect) -> (f32, f32) {
+    let style = layout_box.style();
+    let dimensions = layout_box.dimensions;
+    let border_box = dimensions.border_box();
+    let margin = dimensions.margin;
+    let width = Some(scrollport.width);
+    let height = Some(scrollport.height);
+    let dx = stick(
+        (border_box.x, border_box.x + border_box.width),
+        (margin.left, margin.right),
+        (
+            style.inset.left.resolve(width),
+            style.inset.right.resolve(width),
+        ),
+        (scrollport.x, scrollport.x + scrollport.width),
+        (parent.x, parent.x + parent.width),
+    );
+    let dy = stick(
+        (border_box.y, border_box.y + border_box.height),
+        (margin.top, margin.bottom),
+        (
+            style.inset.top.resolve(height),
+            style.inset.bottom.resolve(height),
+        ),
+        (scrollport.y, scrollport.y + scrollport.height),
+        (parent.y, parent.y + parent.height),
+    );
+    (dx, dy)
+}
+
+// the styled nodes of the fragments an inline box puts on its lines
+fn fragment_nodes<'a>(layout_box: &LayoutBox<'a>, nodes: &mut Vec<&'a StyledNode<'a>>) {
+    match layout_box.box_type {
+        BoxType::Inline(node) => {
+            nodes.push(node);
+            for child in &layout_box.children {
+                fragment_nodes(child, nodes);
+            }
+        }
+        BoxType::Text(node, _) | BoxType::InlineBlock(node) => nodes.push(node),
+        BoxType::Block(_) | BoxType::AnonymousBlock(_) => {}
+    }
+}
+
+fn fragment_node<'a>(kind: &FragmentKind<'a>) -> &'a StyledNode<'a> {
+    match *kind {
+        FragmentKind::Text(node, _)
+        | FragmentKind::Inline { node, .. }
+        | FragmentKind::Atomic(node) => node,
+    }
+}
+
+// moves the fragments of relatively positioned inline-level boxes, and the
+// boxes inside them
+fn offset_inline_boxes(boxes: &mut [LayoutBox], lines: &mut [LineBox], containing: Rect) {
+    for layout_box in boxes {
+        let node = match layout_box.box_type {
+            BoxType::Inline(node) | BoxType::InlineBlock(node) => node,
+            _ => continue,
+        };
+        if node.style.position == Position::Relative {
+            let (dx, dy) = relative_offset(&node.style, containing);
+            let mut nodes = Vec::new();
+            fragment_nodes(layout_box, &mut nodes);
+            for fragment in lines.iter_mut().flat_map(|line| &mut line.fragments) {
+                let fragment_node = fragment_node(&fragment.kind);
+                if nodes.iter().any(|node| std::ptr::eq(*node, fragment_node)) {
+                    fragment.rect.x += dx;
+                    fragment.rect.y += dy;
+                }
+            }
+            // an inline-block moves itself, with the other positioned boxes
+            if let BoxType::Inline(_) = layout_box.box_type {
+                for child in &mut layout_box.children {
+                    child.translate(dx, dy);
+                }
+            }
+        }
+        if let BoxType::Inline(_) = layout_box.box_type {
+            offset_inline_boxes(&mut layout_box.children, lines, containing);
+        }
+    }
+}
+
+// the padding box around the fragments of a positioned inline box, which
+// contains its absolutely positioned descendants
+fn inline_containing_block(
+    lines: &[LineBox],
+    node: &StyledNode,
+    containing_width: f32,
+) -> Option<Rect> {
+    let rects = lines
+        .iter()
+        .flat_map(|line| &line.fragments)
+        .filter(|fragment| {
+            matches!(fragment.kind, FragmentKind::Inline { node: inline, .. } if std::ptr::eq(inline, node))
+        })
+        .map(|fragment| fragment.rect);
+    let (left, top, right, bottom) = rects.fold(None, |bounds, rect| {
+        let (left, top, right, bottom) = bounds.unwrap_or((
+            f32::INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        ));
+        Some((
+            f32::min(left, rect.x),
+            f32::min(top, rect.y),
+            f32::max(right, rect.x + rect.width),
+            f32::max(bottom, rect.y + rect.height),
+        ))
+    })?;
+    let style = &node.style;
+    let padding = EdgeSizes {
+        left: style.padding.left.resolve(containing_width),
+        right: style.padding.right.resolve(containing_width),
+        top: style.padding.top.resolve(containing_width),
+        bottom: style.padding.bottom.resolve(containing_width),
+    };
+    let content = Rect {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    };
+    Some(content.expanded_by(padding))
+}
+
+// positions the boxes of a box's content: the inline boxes of its lines
+// pass the frames on to the boxes inside them
+fn position_boxes(
+    boxes: &mut [LayoutBox],
+    lines: &[LineBox],
+    context: &LayoutContext,
+    parent: Rect,
+    frames: Frames,
+) {
+    for layout_box in boxes {
+        let BoxType::Inline(node) = layout_box.box_type else {
+            layout_box.position(context, parent, frames);
+            continue;
+        };
+        let mut frames = frames;
+        if node.style.position != Position::Static {
+            if let Some(containing) = inline_containing_block(lines, node, parent.width) {
+                frames.containing = containing;
+            }
+        }
+        position_boxes(&mut layout_box.children, lines, context, parent, frames);
+    }
+}
+
+impl<'a> LayoutBox<'a> {
+    pub(crate) fn is_positioned(&self) -> bool {
+        !matches!(self.box_type, BoxType::Text(..)) && self.style().position != Position::Static
+    }
+
+    // an out-of-flow box takes no space in the flow; the origin of its margin
+    // box keeps its static position, where it would have been, until it is
+    // laid out in its containing block
+    pub(crate) fn set_static_position(&mut self, x: f32, y: f32) {
+        self.dimensions = Dimensions::default();
+        self.dimensions.content.x = x;
+        self.dimensions.content.y = y;
+    }
+
+    // lays out the positioned boxes of a laid out tree, in the viewport
+    pub(crate) fn layout_positioned(&mut self, context: &LayoutContext, viewport: Rect) {
+        let frames = Frames {
+            containing: viewport,
+            viewport,
+            scrollport: viewport,
+        };
+        self.position(context, viewport, frames);
+    }
+
+    // places a box by its `position`, in the content box of its `parent`,
+    // then the positioned boxes inside it
+    fn position(&mut self, context: &LayoutContext, parent: Rect, frames: Frames) {
+        if let BoxType::Text(..) = self.box_type {
+            return;
+        }
+        match self.style().position {
+            Position::Static => {}
+            Position::Relative => {
+                let (dx, dy) = relative_offset(self.style(), parent);
+                self.translate(dx, dy);
+            }
+            Position::Sticky => {
+                let (dx, dy) = sticky_offset(self, parent, frames.scrollport);
+                self.translate(dx, dy);
+            }
+            Position::Absolute => self.layout_absolute(context, frames.containing),
+            Position::Fixed => self.layout_absolute(context, frames.viewport),
+        }
+
+        let style = self.style();
+        let mut frames = frames;
+        if style.position != Position::Static {
+            frames.containing = self.dimensions.padding_box();
+        }
+        // `overflow` only applies to block containers
+        let scrolls = |overflow| !matches!(overflow, Overflow::Visible | Overflow::Clip);
+        let block_container = matches!(self.box_type, BoxType::Block(_) | BoxType::InlineBlock(_));
+        if block_container && (scrolls(style.overflow_x) || scrolls(style.overflow_y)) {
+            frames.scrollport = self.dimensions.padding_box();
+        }
+        let content = self.dimensions.content;
+        offset_inline_boxes(&mut self.children, &mut self.lines, content);
+        position_boxes(&mut self.children, &self.lines, context, content, frames);
+    }
+
+    // lays out an absolutely positioned box in the padding box of its
+    // containing block, from its static position
+    fn layout_absolute(&mut self, context: &LayoutContext, containing: Rect) {
+        let static_x = self.dimensions.content.x - containing.x;
+        let static_y = self.dimensions.content.y - containing.y;
+        let style = self.style();
+        let (padding, border) = self.padding_and_border(containing.width);
+        let width_basis = Some(containing.width);
+        let height_basis = Some(containing.height);
+        let horizontal = Insets {
+            start: style.inset.left.resolve(width_basis),
+            end: style.inset.right.resolve(width_basis),
+            margin_start: style.margin.left.resolve(width_basis),
+            margin_end: style.margin.right.resolve(width_basis),
+        };
+        let vertical = Insets {
+            start: style.inset.top.resolve(height_basis),
+            end: style.inset.bottom.resolve(height_basis),
+            margin_start: style.margin.top.resolve(width_basis),
+            margin_end: style.margin.bottom.resolve(width_basis),
+        };
+
+        // an auto width fills the space between the insets, or shrinks to fit
+        // the content
+        let edges = padding.left + padding.right + border.left + border.right;
+        let content_width = |size: &Size| {
+            size.resolve(width_basis)
+                .map(|width| match style.box_sizing {
+                    BoxSizing::ContentBox => width,
+                    BoxSizing::BorderBox => (width - edges).max(0.0),
+                })
+        };
+        let mut width = content_width(&style.width).unwrap_or_else(|| {
+            let available = containing.width
+                - horizontal.start.unwrap_or(0.0)
+                - horizontal.end.unwrap_or(0.0)
+                - horizontal.margin_start.unwrap_or(0.0)
+                - horizontal.margin_end.unwrap_or(0.0)
+                - edges;
+            match (horizontal.start, horizontal.end) {
+                (Some(_), Some(_)) => available.max(0.0),
+                _ => {
+                    let (min_content, max_content) = self.intrinsic_widths(context);
+                    max_content.min(available).max(min_content)
+                }
+            }
+        });
+        if let Some(max_width) = content_width(&style.max_width) {
+            width = width.min(max_width);
+        }
+        if let Some(min_width) = content_width(&style.min_width) {
+            width = width.max(min_width);
+        }
+        let rtl = style.direction == Direction::Rtl;
+        let (x, margin_left, margin_right) =
+            horizontal.solve(width + edges, containing.width, static_x, rtl, false);
+
+        self.dimensions = Dimensions {
+            padding,
+            border,
+            margin: EdgeSizes {
+                left: margin_left,
+                right: margin_right,
+                top: vertical.margin_start.unwrap_or(0.0),
+                bottom: vertical.margin_end.unwrap_or(0.0),
+            },
+            ..Dimensions::default()
+        };
+        self.dimensions.content.width = width;
+
+        // likewise an auto height fills the space between the insets, and
+        // otherwise depends on the content
+        let style = self.style();
+        let edges = padding.top + padding.bottom + border.top + border.bottom;
+        let height = self
+            .content_height(&style.height, height_basis)
+            .or(match (vertical.start, vertical.end) {
+                (Some(top), Some(bottom)) => Some(
+                    (containing.height
+                        - top
+                        - bottom
+                        - vertical.margin_start.unwrap_or(0.0)
+                        - vertical.margin_end.unwrap_or(0.0)
+                        - edges)
+                        .max(0.0),
+                ),
+                _ => None,
+            })
+            .map(|mut height| {
+                if let Some(max_height) = self.content_height(&style.max_height, height_basis) {
+                    height = height.min(max_height);
+                }
+                let min_height = self.content_height(&style.min_height, height_basis);
+                height.max(min_height.unwrap_or(0.0))
+            });
+        // auto margins only take up space around a definite height
+        let mut dimensions = self.dimensions;
+        if let Some(height) = height {
+            let (_, margin_top, margin_bottom) =
+                vertical.solve(height + edges, containing.height, static_y, false, true);
+            dimensions.margin.top = margin_top;
+            dimensions.margin.bottom = margin_bottom;
+        }
+        self.layout_sized(context, dimensions, height_basis, height);
+
+        let size = self.dimensions.border_box().height;
+        let (y, _, _) = vertical.solve(size, containing.height, static_y, false, true);
+        // the margin box was laid out at the origin
+        self.translate(containing.x + x, containing.y + y);
+    }
+}
+
+// a box painting its content as a unit, stacked by `z-index` with the other
+// layers of its parent context
+#[derive(Debug)]
+pub struct StackingContext<'b, 'a> {
+    pub layout_box: &'b LayoutBox<'a>,
+    pub z_index: i32,
+    // the stacking contexts and positioned boxes inside it, in stacking
+    // order; a positioned box with `z-index: auto` is stacked like a context,
+    // but its positioned descendants are layers of the parent context
+    pub layers: Vec<StackingContext<'b, 'a>>,
+}
+
+// whether a child of `parent` creates a stacking context
+fn creates_stacking_context(layout_box: &LayoutBox, parent: &LayoutBox) -> bool {
+    if !matches!(
+        layout_box.box_type,
+        BoxType::Block(_) | BoxType::Inline(_) | BoxType::InlineBlock(_)
+    ) {
+        return false;
+    }
+    let style = layout_box.style();
+    let item = parent.is_flex_container() || parent.is_grid_container();
+    match style.position {
+        Position::Fixed | Position::Sticky => true,
+        Position::Static if !item => style.opacity < 1.0,
+        _ => style.z_index.is_some() || style.opacity < 1.0,
+    }
+}
+
+// whether a child of `parent` is stacked apart from the flow
+fn is_layer(layout_box: &LayoutBox, parent: &LayoutBox) -> bool {
+    layout_box.is_positioned() || creates_stacking_context(layout_box, parent)
+}
+
+// the layers inside a box that belong to its stacking context
+fn collect_layers<'b, 'a>(parent: &'b LayoutBox<'a>, layers: &mut Vec<StackingContext<'b, 'a>>) {
+    for child in &parent.children {
+        if creates_stacking_context(child, parent) {
+            layers.push(StackingContext::new(child));
+            continue;
+        }
+        if child.is_positioned() {
+            layers.push(StackingContext {
+                layout_box: child,
+                z_index: 0,
+                layers: Vec::new(),
+            });
+        }
+        collect_layers(child, layers);
+    }
+}
+
+// the steps painting the flow of a stacking context
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Blocks,
+    Floats,
+    Inlines,
+}
+
+// the boxes of the flow inside `parent` painted in the phase; floats and
+// inline-blocks paint their content at once
+fn flow_boxes<'b, 'a>(parent: &'b LayoutBox<'a>, phase: Phase, order: &mut Vec<&'b LayoutBox<'a>>) {
+    for child in &parent.children {
+        if is_layer(child, parent) {
+            continue;
+        }
+        let atomic = match child.box_type {
+            BoxType::InlineBlock(_) => Some(Phase::Inlines),
+            _ if child.is_floated() => Some(Phase::Floats),
+            _ => None,
+        };
+        match atomic {
+            Some(atomic) if atomic == phase => {
+                order.push(child);
+                for phase in [Phase::Blocks, Phase::Floats, Phase::Inlines] {
+                    flow_boxes(child, phase, order);
+                }
+            }
+            Some(_) => {}
+            None => {
+                let own_phase = match child.is_block_level() {
+                    true => Phase::Blocks,
+                    false => Phase::Inlines,
+                };
+                if own_phase == phase {
+                    order.push(child);
+                }
+                flow_boxes(child, phase, order);
+            }
+        }
+    }
+}
+
+impl<'b, 'a> StackingContext<'b, 'a> {
+    // the stacking context of the root box of a tree, or of a box creating
+    // one
+    pub fn new(layout_box: &'b LayoutBox<'a>) -> StackingContext<'b, 'a> {
+        let mut layers = Vec::new();
+        collect_layers(layout_box, &mut layers);
+        layers.sort_by_key(|layer| layer.z_index);
+        StackingContext {
+            layout_box,
+            z_index: layout_box.style().z_index.unwrap_or(0),
+            layers,
+        }
+    }
+
+    // the boxes of the context in the order they are painted, after CSS 2.1
+    // Appendix E: its box, the layers with negative z-indices, the
+    // block-level boxes of the flow, its floats, its inline-level boxes, and
+    // the other layers
+    pub fn paint_order(&self) -> Vec<&'b LayoutBox<'a>> {
+        let mut order = vec![self.layout_box];
+        let negative = self.layers.partition_point(|layer| layer.z_index < 0);
+        for layer in &self.layers[..negative] {
+            order.extend(layer.paint_order());
+        }
+        for phase in [Phase::Blocks, Phase::Floats, Phase::Inlines] {
+            flow_boxes(self.layout_box, phase, &mut order);
+        }
+        for layer in &self.layers[negative..] {
+            order.extend(layer.paint_order());
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::test_util::{border_box, id, text_position, with_layout};
+
+    #[test]
+    fn test_relative_offsets() {
+        with_layout(
+            "<div id='a'></div><div id='b'></div><div id='c'></div>\
+            <p id='p'>ab<span id='s'>cd</span></p>",
+            "div { height: 10px; } #a { position: relative; top: 5px; left: 10%; }
+            #b { position: relative; right: 20px; bottom: 5px; }
+            #c { position: relative; left: 1px; right: 100px; }
+            #s { position: relative; top: 2px; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (80.0, 5.0, 800.0, 10.0));
+                assert_eq!(border_box(root, "b"), (-20.0, 5.0, 800.0, 10.0));
+                // over-constrained: `right` is ignored
+                assert_eq!(border_box(root, "c"), (1.0, 20.0, 800.0, 10.0));
+                // the flow around the boxes doesn't move
+                assert_eq!(border_box(root, "p").1, 30.0);
+                assert_eq!(text_position(root, "p", "ab"), (0.0, 30.0));
+                assert_eq!(text_position(root, "p", "cd"), (12.0, 32.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_static_position() {
+        with_layout(
+            "<div id='a'></div><div id='x'></div><div id='b'></div>\
+            <p>ab<span id='y'></span>cd</p>",
+            "div { height: 10px; }
+            #x, #y { position: absolute; width: 5px; height: 5px; }",
+            |root| {
+                // out-of-flow boxes take no space where they would have been
+                assert_eq!(border_box(root, "x"), (0.0, 10.0, 5.0, 5.0));
+                assert_eq!(border_box(root, "b").1, 10.0);
+                assert_eq!(border_box(root, "y"), (12.0, 20.0, 5.0, 5.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_absolute_insets() {
+        with_layout(
+            "<div id='c'><div id='a'></div><div id='b'>aaaa bb</div><div id='d'></div>\
+            <div id='e'></div></div>",
+            "#c { position: relative; margin: 10px; padding: 10px; width: 200px;
+                height: 100px; border: 1px solid; }
+            #a, #b, #d, #e { position: absolute; }
+            #a { left: 10px; right: 10px; top: 0; height: 10px; }
+            #b { right: 0; bottom: 0; }
+            #d { left: 0; right: 0; width: 100px; margin: 0 auto; top: 10%; height: 50%; }
+            #e { left: 10px; right: 10px; width: 300px; top: 0; bottom: 0; margin: auto 0;
+                height: 20px; }",
+            |root| {
+                // in the padding box of the containing block
+                assert_eq!(border_box(root, "c"), (10.0, 10.0, 222.0, 122.0));
+                assert_eq!(border_box(root, "a"), (21.0, 11.0, 200.0, 10.0));
+                // shrinks to fit its content
+                assert_eq!(border_box(root, "b"), (189.0, 121.0, 42.0, 10.0));
+                // auto margins center the box between its insets
+                assert_eq!(border_box(root, "d"), (71.0, 23.0, 100.0, 60.0));
+                // over-constrained: `right` is ignored, not the margins
+                assert_eq!(border_box(root, "e"), (21.0, 61.0, 300.0, 20.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_containing_blocks() {
+        with_layout(
+            "<div id='r'><div id='f'></div><div id='a'></div></div><div id='v'></div>\
+            <p><span id='s'>ab<i id='i'></i></span></p>",
+            "#r { position: relative; left: 50px; top: 50px; height: 10px; }
+            #f { position: fixed; right: 0; bottom: 0; width: 10px; height: 10px; }
+            #a, #v { position: absolute; top: 0; left: 0; width: 1px; height: 1px; }
+            p { padding-left: 100px; } #s { position: relative; }
+            #i { position: absolute; right: 0; top: 0; width: 2px; height: 2px; }",
+            |root| {
+                assert_eq!(border_box(root, "f"), (790.0, 590.0, 10.0, 10.0));
+                // after the offset of the relatively positioned ancestor
+                assert_eq!(border_box(root, "a"), (50.0, 50.0, 1.0, 1.0));
+                // the initial containing block, without a positioned ancestor
+                assert_eq!(border_box(root, "v"), (0.0, 0.0, 1.0, 1.0));
+                // the fragments of a positioned inline box
+                assert_eq!(border_box(root, "i"), (110.0, 10.0, 2.0, 2.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_sticky() {
+        with_layout(
+            "<div id='q'><div id='t'></div></div>\
+            <div id='p'><div id='spacer'></div><div id='s'></div></div>",
+            "#q { height: 25px; } #t { position: sticky; top: 10px; height: 20px; }
+            #spacer { height: 700px; } #s { position: sticky; bottom: 10px; height: 20px; }",
+            |root| {
+                // as far as the parent allows
+                assert_eq!(border_box(root, "t"), (0.0, 5.0, 800.0, 20.0));
+                assert_eq!(border_box(root, "s"), (0.0, 570.0, 800.0, 20.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_inapplicable_properties() {
+        // offsets and `z-index` don't apply to static boxes
+        with_layout(
+            "<div id='s'></div><div id='n'></div>",
+            "#s { top: 10px; z-index: -1; height: 10px; } #n { height: 10px; }",
+            |root| {
+                assert_eq!(border_box(root, "s"), (0.0, 0.0, 800.0, 10.0));
+                assert_eq!(border_box(root, "n"), (0.0, 10.0, 800.0, 10.0));
+                let context = StackingContext::new(root);
+                let ids: Vec<&str> = context.paint_order().into_iter().filter_map(id).collect();
+                assert_eq!(ids, ["s", "n"]);
+            },
+        );
+    }
+
+    #[test]
+    fn test_paint_order() {
+        with_layout(
+            "<div id='a'><div id='n'></div><div id='b'></div><span id='i'>x</span>\
+            <div id='f'></div></div><div id='z'><div id='zc'></div></div><div id='p'></div>",
+            "#n { position: relative; z-index: -1; } #z { position: absolute; z-index: 2; }
+            #f { float: left; }
+            #zc { position: relative; z-index: 5; } #p { position: relative; }",
+            |root| {
+                let context = StackingContext::new(root);
+                let ids: Vec<&str> = context.paint_order().into_iter().filter_map(id).collect();
+                assert_eq!(ids, ["n", "a", "b", "f", "i", "p", "z", "zc"]);
+            },
+        );
+    }
+}
@@ -0,0 +1,1277 @@
+use crate::assign::PropertyMap;
+use crate::calc::CalcType;
+use crate::css::{is_css_wide_keyword, Declaration, Value};
+use crate::shorthands::is_shorthand;
+use crate::variables::is_custom_property;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// a kind of component value accepted by a property
+#[derive(Debug, Clone, Copy)]
+pub enum Component {
+    Keywords(&'static [&'static str]),
+    Length,
+    NonNegativeLength,
+    LengthPercentage,
+    NonNegativeLengthPercentage,
+    Number,
+    NonNegativeNumber,
+    Integer,
+    Percentage,
+    Time,
+    NonNegativeTime,
+    Angle,
+    Color,
+    Image,
+    QuotedString,
+    // any identifier that isn't a css-wide keyword
+    CustomIdent,
+}
+
+// the value definition syntax of a property
+#[derive(Debug, Clone, Copy)]
+pub enum Grammar {
+    // a single component
+    One(&'static [Component]),
+    // between `min` and `max` space-separated components
+    Repeat(&'static [Component], usize, usize),
+    // comma-separated values of the inner grammar
+    Comma(&'static Grammar),
+    // the rows of `grid-template-areas`: as many cells in each row, and the
+    // cells of each name forming a rectangle
+    TemplateAreas,
+    // values parsed by dedicated code, like `content` and the counter properties
+    Custom,
+}
+
+// the elements a property applies to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AppliesTo {
+    AllElements,
+    BlockContainers,
+    PositionedElements,
+    PositionedElementsAndFlexAndGridItems,
+    FlexContainers,
+    FlexItems,
+    FlexAndGridContainers,
+    FlexAndGridItems,
+    GridContainers,
+    GridItems,
+    ListItems,
+    Tables,
+    TableCells,
+    PseudoElements,
+    Pages,
+}
+
+#[derive(Debug)]
+pub struct Property {
+    pub name: &'static str,
+    pub grammar: Grammar,
+    // the css text of the initial value
+    pub initial: &'static str,
+    pub inherited: bool,
+    pub applies_to: AppliesTo,
+}
+
+const fn property(
+    name: &'static str,
+    grammar: Grammar,
+    initial: &'static str,
+    inherited: bool,
+    applies_to: AppliesTo,
+) -> Property {
+    Property {
+        name,
+        grammar,
+        initial,
+        inherited,
+        applies_to,
+    }
+}
+
+use AppliesTo::*;
+use Component::*;
+use Grammar::*;
+
+const AUTO: Component = Keywords(&["auto"]);
+const SIZE: Grammar = One(&[
+    NonNegativeLengthPercentage,
+    Keywords(&["auto", "min-content", "max-content", "fit-content"]),
+]);
+const MAX_SIZE: Grammar = One(&[
+    NonNegativeLengthPercentage,
+    Keywords(&["none", "min-content", "max-content", "fit-content"]),
+]);
+const MARGIN: Grammar = One(&[LengthPercentage, AUTO]);
+const INSET: Grammar = One(&[LengthPercentage, AUTO]);
+const PADDING: Grammar = One(&[NonNegativeLengthPercentage]);
+const LINE_WIDTH: Grammar = One(&[NonNegativeLength, Keywords(&["thin", "medium", "thick"])]);
+const LINE_STYLE: Grammar = One(&[Keywords(&[
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+])]);
+const COLOR: Grammar = One(&[Color]);
+const BOX: Grammar = One(&[Keywords(&["border-box", "padding-box", "content-box"])]);
+const ALIGNMENT: &[&str] = &[
+    "normal",
+    "stretch",
+    "start",
+    "end",
+    "center",
+    "flex-start",
+    "flex-end",
+    "self-start",
+    "self-end",
+    "baseline",
+    "left",
+    "right",
+    "space-between",
+    "space-around",
+    "space-evenly",
+];
+const COUNTERS: Grammar = Custom;
+// `auto`, `span`, a line number and a line name
+const GRID_LINE: Grammar = Repeat(&[Integer, CustomIdent], 1, 3);
+
+// the supported longhands; properties missing from the table are accepted
+// without validation
+pub static PROPERTIES: &[Property] = &[
+    // display and positioning
+    property(
+        "display",
+        Repeat(
+            &[Keywords(&[
+                "none",
+                "contents",
+                "block",
+                "inline",
+                "inline-block",
+                "run-in",
+                "flow",
+                "flow-root",
+                "list-item",
+                "flex",
+                "inline-flex",
+                "grid",
+                "inline-grid",
+                "table",
+                "inline-table",
+                "table-row-group",
+                "table-header-group",
+                "table-footer-group",
+                "table-row",
+                "table-cell",
+                "table-column-group",
+                "table-column",
+                "table-caption",
+            ])],
+            1,
+            3,
+        ),
+        "inline",
+        false,
+        AllElements,
+    ),
+    property(
+        "position",
+        One(&[Keywords(&[
+            "static", "relative", "absolute", "fixed", "sticky",
+        ])]),
+        "static",
+        false,
+        AllElements,
+    ),
+    property("top", INSET, "auto", false, PositionedElements),
+    property("right", INSET, "auto", false, PositionedElements),
+    property("bottom", INSET, "auto", false, PositionedElements),
+    property("left", INSET, "auto", false, PositionedElements),
+    property(
+        "z-index",
+        One(&[Integer, AUTO]),
+        "auto",
+        false,
+        PositionedElementsAndFlexAndGridItems,
+    ),
+    property(
+        "float",
+        One(&[Keywords(&[
+            "none",
+            "left",
+            "right",
+            "inline-start",
+            "inline-end",
+        ])]),
+        "none",
+        false,
+        AllElements,
+    ),
+    property(
+        "clear",
+        One(&[Keywords(&[
+            "none",
+            "left",
+            "right",
+            "both",
+            "inline-start",
+            "inline-end",
+        ])]),
+        "none",
+        false,
+        AllElements,
+    ),
+    property(
+        "visibility",
+        One(&[Keywords(&["visible", "hidden", "collapse"])]),
+        "visible",
+        true,
+        AllElements,
+    ),
+    property(
+        "overflow",
+        Repeat(
+            &[Keywords(&["visible", "hidden", "clip", "scroll", "auto"])],
+            1,
+            2,
+        ),
+        "visible",
+        false,
+        BlockContainers,
+    ),
+    property(
+        "opacity",
+        One(&[Number, Percentage]),
+        "1",
+        false,
+        AllElements,
+    ),
+    // box model
+    property("width", SIZE, "auto", false, AllElements),
+    property("height", SIZE, "auto", false, AllElements),
+    property("min-width", SIZE, "auto", false, AllElements),
+    property("min-height", SIZE, "auto", false, AllElements),
+    property("max-width", MAX_SIZE, "none", false, AllElements),
+    property("max-height", MAX_SIZE, "none", false, AllElements),
+    property(
+        "box-sizing",
+        One(&[Keywords(&["content-box", "border-box"])]),
+        "content-box",
+        false,
+        AllElements,
+    ),
+    property("margin-top", MARGIN, "0", false, AllElements),
+    property("margin-right", MARGIN, "0", false, AllElements),
+    property("margin-bottom", MARGIN, "0", false, AllElements),
+    property("margin-left", MARGIN, "0", false, AllElements),
+    property("padding-top", PADDING, "0", false, AllElements),
+    property("padding-right", PADDING, "0", false, AllElements),
+    property("padding-bottom", PADDING, "0", false, AllElements),
+    property("padding-left", PADDING, "0", false, AllElements),
+    property("border-top-width", LINE_WIDTH, "medium", false, AllElements),
+    property(
+        "border-right-width",
+        LINE_WIDTH,
+        "medium",
+        false,
+        AllElements,
+    ),
+    property(
+        "border-bottom-width",
+        LINE_WIDTH,
+        "medium",
+        false,
+        AllElements,
+    ),
+    property(
+        "border-left-width",
+        LINE_WIDTH,
+        "medium",
+        false,
+        AllElements,
+    ),
+    property("border-top-style", LINE_STYLE, "none", false, AllElements),
+    property("border-right-style", LINE_STYLE, "none", false, AllElements),
+    property(
+        "border-bottom-style",
+        LINE_STYLE,
+        "none",
+        false,
+        AllElements,
+    ),
+    property("border-left-style", LINE_STYLE, "none", false, AllElements),
+    property(
+        "border-top-color",
+        COLOR,
+        "currentcolor",
+        false,
+        AllElements,
+    ),
+    property(
+        "border-right-color",
+        COLOR,
+        "currentcolor",
+        false,
+        AllElements,
+    ),
+    property(
+        "border-bottom-color",
+        COLOR,
+        "currentcolor",
+        false,
+        AllElements,
+    ),
+    property(
+        "border-left-color",
+        COLOR,
+        "currentcolor",
+        false,
+        AllElements,
+    ),
+    property("outline-width", LINE_WIDTH, "medium", false, AllElements),
+    property(
+        "outline-style",
+        One(&[Keywords(&[
+            "auto", "none", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset",
+            "outset",
+        ])]),
+        "none",
+        false,
+        AllElements,
+    ),
+    property("outline-color", COLOR, "currentcolor", false, AllElements),
+    // colors and backgrounds
+    property("color", COLOR, "black", true, AllElements),
+    property("background-color", COLOR, "transparent", false, AllElements),
+    property(
+        "background-image",
+        Comma(&One(&[Image, Keywords(&["none"])])),
+        "none",
+        false,
+        AllElements,
+    ),
+    property(
+        "background-repeat",
+        Comma(&Repeat(
+            &[Keywords(&[
+                "repeat",
+                "repeat-x",
+                "repeat-y",
+                "no-repeat",
+                "space",
+                "round",
+            ])],
+            1,
+            2,
+        )),
+        "repeat",
+        false,
+        AllElements,
+    ),
+    property(
+        "background-attachment",
+        Comma(&One(&[Keywords(&["scroll", "fixed", "local"])])),
+        "scroll",
+        false,
+        AllElements,
+    ),
+    property(
+        "background-position",
+        Comma(&Repeat(
+            &[
+                LengthPercentage,
+                Keywords(&["left", "right", "top", "bottom", "center"]),
+            ],
+            1,
+            4,
+        )),
+        "0% 0%",
+        false,
+        AllElements,
+    ),
+    property(
+        "background-size",
+        Comma(&Repeat(
+            &[
+                NonNegativeLengthPercentage,
+                Keywords(&["auto", "cover", "contain"]),
+            ],
+            1,
+            2,
+        )),
+        "auto",
+        false,
+        AllElements,
+    ),
+    property(
+        "background-origin",
+        Comma(&BOX),
+        "padding-box",
+        false,
+        AllElements,
+    ),
+    property(
+        "background-clip",
+        Comma(&BOX),
+        "border-box",
+        false,
+        AllElements,
+    ),
+    // fonts and text
+    property(
+        "font-family",
+        Comma(&Repeat(&[QuotedString, CustomIdent], 1, usize::MAX)),
+        "serif",
+        true,
+        AllElements,
+    ),
+    property(
+        "font-size",
+        One(&[
+            NonNegativeLengthPercentage,
+            Keywords(&[
+                "xx-small",
+                "x-small",
+                "small",
+                "medium",
+                "large",
+                "x-large",
+                "xx-large",
+                "xxx-large",
+                "larger",
+                "smaller",
+            ]),
+        ]),
+        "medium",
+        true,
+        AllElements,
+    ),
+    property(
+        "font-style",
+        One(&[Keywords(&["normal", "italic", "oblique"])]),
+        "normal",
+        true,
+        AllElements,
+    ),
+    property(
+        "font-weight",
+        One(&[
+            NonNegativeNumber,
+            Keywords(&["normal", "bold", "bolder", "lighter"]),
+        ]),
+        "normal",
+        true,
+        AllElements,
+    ),
+    property(
+        "font-variant",
+        One(&[Keywords(&["normal", "small-caps"])]),
+        "normal",
+        true,
+        AllElements,
+    ),
+    property(
+        "font-stretch",
+        One(&[
+            Percentage,
+            Keywords(&[
+                "normal",
+                "ultra-condensed",
+                "extra-condensed",
+                "condensed",
+                "semi-condensed",
+                "semi-expanded",
+                "expanded",
+                "extra-expanded",
+                "ultra-expanded",
+            ]),
+        ]),
+        "normal",
+        true,
+        AllElements,
+    ),
+    property(
+        "line-height",
+        One(&[
+            NonNegativeNumber,
+            NonNegativeLengthPercentage,
+            Keywords(&["normal"]),
+        ]),
+        "normal",
+        true,
+        AllElements,
+    ),
+    property(
+        "text-align",
+        One(&[Keywords(&[
+            "start", "end", "left", "right", "center", "justify",
+        ])]),
+        "start",
+        true,
+        BlockContainers,
+    ),
+    property(
+        "text-indent",
+        One(&[LengthPercentage]),
+        "0",
+        true,
+        BlockContainers,
+    ),
+    property(
+        "text-transform",
+        One(&[Keywords(&["none", "capitalize", "uppercase", "lowercase"])]),
+        "none",
+        true,
+        AllElements,
+    ),
+    property(
+        "text-decoration-line",
+        Repeat(
+            &[Keywords(&["none", "underline", "overline", "line-through"])],
+            1,
+            3,
+        ),
+        "none",
+        false,
+        AllElements,
+    ),
+    property(
+        "letter-spacing",
+        One(&[Length, Keywords(&["normal"])]),
+        "normal",
+        true,
+        AllElements,
+    ),
+    property(
+        "word-spacing",
+        One(&[LengthPercentage, Keywords(&["normal"])]),
+        "normal",
+        true,
+        AllElements,
+    ),
+    property(
+        "white-space",
+        One(&[Keywords(&[
+            "normal",
+            "pre",
+            "nowrap",
+            "pre-wrap",
+            "pre-line",
+            "break-spaces",
+        ])]),
+        "normal",
+        true,
+        AllElements,
+    ),
+    property(
+        "vertical-align",
+        One(&[
+            LengthPercentage,
+            Keywords(&[
+                "baseline",
+                "sub",
+                "super",
+                "text-top",
+                "text-bottom",
+                "middle",
+                "top",
+                "bottom",
+            ]),
+        ]),
+        "baseline",
+        false,
+        AllElements,
+    ),
+    property(
+        "direction",
+        One(&[Keywords(&["ltr", "rtl"])]),
+        "ltr",
+        true,
+        AllElements,
+    ),
+    // lists and generated content
+    property(
+        "list-style-type",
+        One(&[QuotedString, CustomIdent]),
+        "disc",
+        true,
+        ListItems,
+    ),
+    property(
+        "list-style-position",
+        One(&[Keywords(&["inside", "outside"])]),
+        "outside",
+        true,
+        ListItems,
+    ),
+    property(
+        "list-style-image",
+        One(&[Image, Keywords(&["none"])]),
+        "none",
+        true,
+        ListItems,
+    ),
+    property("content", Custom, "normal", false, PseudoElements),
+    property("counter-reset", COUNTERS, "none", false, AllElements),
+    property("counter-increment", COUNTERS, "none", false, AllElements),
+    property("counter-set", COUNTERS, "none", false, AllElements),
+    // flexible boxes
+    property(
+        "flex-direction",
+        One(&[Keywords(&[
+            "row",
+            "row-reverse",
+            "column",
+            "column-reverse",
+        ])]),
+        "row",
+        false,
+        FlexContainers,
+    ),
+    property(
+        "flex-wrap",
+        One(&[Keywords(&["nowrap", "wrap", "wrap-reverse"])]),
+        "nowrap",
+        false,
+        FlexContainers,
+    ),
+    property(
+        "flex-grow",
+        One(&[NonNegativeNumber]),
+        "0",
+        false,
+        FlexItems,
+    ),
+    property(
+        "flex-shrink",
+        One(&[NonNegativeNumber]),
+        "1",
+        false,
+        FlexItems,
+    ),
+    property(
+        "flex-basis",
+        One(&[NonNegativeLengthPercentage, Keywords(&["auto", "content"])]),
+        "auto",
+        false,
+        FlexItems,
+    ),
+    property("order", One(&[Integer]), "0", false, FlexAndGridItems),
+    property(
+        "justify-content",
+        One(&[Keywords(ALIGNMENT)]),
+        "normal",
+        false,
+        FlexAndGridContainers,
+    ),
+    property(
+        "align-items",
+        One(&[Keywords(ALIGNMENT)]),
+        "normal",
+        false,
+        FlexAndGridContainers,
+    ),
+    property(
+        "align-content",
+        One(&[Keywords(ALIGNMENT)]),
+        "normal",
+        false,
+        FlexAndGridContainers,
+    ),
+    property(
+        "align-self",
+        One(&[Keywords(ALIGNMENT), AUTO]),
+        "auto",
+        false,
+        FlexAndGridItems,
+    ),
+    property(
+        "row-gap",
+        One(&[NonNegativeLengthPercentage, Keywords(&["normal"])]),
+        "normal",
+        false,
+        FlexAndGridContainers,
+    ),
+    property(
+        "column-gap",
+        One(&[NonNegativeLengthPercentage, Keywords(&["normal"])]),
+        "normal",
+        false,
+        FlexAndGridContainers,
+    ),
+    // grids
+    property(
+        "grid-template-columns",
+        Custom,
+        "none",
+        false,
+        GridContainers,
+    ),
+    property("grid-template-rows", Custom, "none", false, GridContainers),
+    property(
+        "grid-template-areas",
+        TemplateAreas,
+        "none",
+        false,
+        GridContainers,
+    ),
+    property("grid-auto-columns", Custom, "auto", false, GridContainers),
+    property("grid-auto-rows", Custom, "auto", false, GridContainers),
+    property(
+        "grid-auto-flow",
+        Repeat(&[Keywords(&["row", "column", "dense"])], 1, 2),
+        "row",
+        false,
+        GridContainers,
+    ),
+    property("grid-row-start", GRID_LINE, "auto", false, GridItems),
+    property("grid-row-end", GRID_LINE, "auto", false, GridItems),
+    property("grid-column-start", GRID_LINE, "auto", false, GridItems),
+    property("grid-column-end", GRID_LINE, "auto", false, GridItems),
+    property(
+        "justify-items",
+        One(&[Keywords(ALIGNMENT)]),
+        "normal",
+        false,
+        GridContainers,
+    ),
+    property(
+        "justify-self",
+        One(&[Keywords(ALIGNMENT), AUTO]),
+        "auto",
+        false,
+        GridItems,
+    ),
+    // tables
+    property(
+        "border-collapse",
+        One(&[Keywords(&["separate", "collapse"])]),
+        "separate",
+        true,
+        Tables,
+    ),
+    property(
+        "border-spacing",
+        Repeat(&[NonNegativeLength], 1, 2),
+        "0",
+        true,
+        Tables,
+    ),
+    property(
+        "table-layout",
+        One(&[Keywords(&["auto", "fixed"])]),
+        "auto",
+        false,
+        Tables,
+    ),
+    property(
+        "caption-side",
+        One(&[Keywords(&["top", "bottom"])]),
+        "top",
+        true,
+        Tables,
+    ),
+    property(
+        "empty-cells",
+        One(&[Keywords(&["show", "hide"])]),
+        "show",
+        true,
+        TableCells,
+    ),
+    // transitions and animations, whose shorthands and timing functions
+    // aren't broken down
+    property("transition", Custom, "all 0s ease 0s", false, AllElements),
+    property(
+        "transition-property",
+        Comma(&One(&[Keywords(&["none", "all"]), CustomIdent])),
+        "all",
+        false,
+        AllElements,
+    ),
+    property(
+        "transition-duration",
+        Comma(&One(&[NonNegativeTime])),
+        "0s",
+        false,
+        AllElements,
+    ),
+    property(
+        "transition-timing-function",
+        Custom,
+        "ease",
+        false,
+        AllElements,
+    ),
+    property(
+        "transition-delay",
+        Comma(&One(&[Time])),
+        "0s",
+        false,
+        AllElements,
+    ),
+    property("animation", Custom, "none", false, AllElements),
+    property(
+        "animation-name",
+        Comma(&One(&[Keywords(&["none"]), CustomIdent, QuotedString])),
+        "none",
+        false,
+        AllElements,
+    ),
+    property(
+        "animation-duration",
+        Comma(&One(&[NonNegativeTime, AUTO])),
+        "0s",
+        false,
+        AllElements,
+    ),
+    property(
+        "animation-timing-function",
+        Custom,
+        "ease",
+        false,
+        AllElements,
+    ),
+    property(
+        "animation-delay",
+        Comma(&One(&[Time])),
+        "0s",
+        false,
+        AllElements,
+    ),
+    property(
+        "animation-iteration-count",
+        Comma(&One(&[NonNegativeNumber, Keywords(&["infinite"])])),
+        "1",
+        false,
+        AllElements,
+    ),
+    property(
+        "animation-direction",
+        Comma(&One(&[Keywords(&[
+            "normal",
+            "reverse",
+            "alternate",
+            "alternate-reverse",
+        ])])),
+        "normal",
+        false,
+        AllElements,
+    ),
+    property(
+        "animation-fill-mode",
+        Comma(&One(&[Keywords(&[
+            "none",
+            "forwards",
+            "backwards",
+            "both",
+        ])])),
+        "none",
+        false,
+        AllElements,
+    ),
+    property(
+        "animation-play-state",
+        Comma(&One(&[Keywords(&["running", "paused"])])),
+        "running",
+        false,
+        AllElements,
+    ),
+    // transforms
+    property(
+        "rotate",
+        Repeat(&[Angle, Number, Keywords(&["none", "x", "y", "z"])], 1, 4),
+        "none",
+        false,
+        AllElements,
+    ),
+    // paged media, the size of the pages of `@page` rules
+    property(
+        "size",
+        Repeat(
+            &[
+                NonNegativeLength,
+                Keywords(&[
+                    "auto",
+                    "portrait",
+                    "landscape",
+                    "a5",
+                    "a4",
+                    "a3",
+                    "b5",
+                    "b4",
+                    "jis-b5",
+                    "jis-b4",
+                    "letter",
+                    "legal",
+                    "ledger",
+                ]),
+            ],
+            1,
+            3,
+        ),
+        "auto",
+        false,
+        Pages,
+    ),
+];
+
+pub fn lookup(name: &str) -> Option<&'static Property> {
+    PROPERTIES.iter().find(|property| property.name == name)
+}
+
+fn is_calc_of(value: &Value, types: &[CalcType]) -> bool {
+    match value {
+        Value::Calc(node) => node
+            .calc_type()
+            .is_some_and(|calc_type| types.contains(&calc_type)),
+        _ => false,
+    }
+}
+
+impl Component {
+    pub fn matches(&self, value: &Value) -> bool {
+        use CalcType::{Length as L, LengthPercentage as LP, Percentage as P};
+        let non_negative = match value {
+            Value::Length(number, _)
+            | Value::Percentage(number)
+            | Value::Number(number)
+            | Value::Dimension(number, _) => *number >= 0.0,
+            _ => true,
+        };
+        match (self, value) {
+            (Keywords(keywords), Value::Keyword(keyword)) => {
+                keywords.contains(&keyword.to_lowercase().as_str())
+            }
+            // unitless zero is a length
+            (Length | NonNegativeLength, Value::Number(number)) => *number == 0.0,
+            (Length, value) => matches!(value, Value::Length(..)) || is_calc_of(value, &[L]),
+            (NonNegativeLength, value) => Length.matches(value) && non_negative,
+            (LengthPercentage, Value::Percentage(_)) => true,
+            (LengthPercentage, value) => Length.matches(value) || is_calc_of(value, &[P, LP]),
+            (NonNegativeLengthPercentage, value) => LengthPercentage.matches(value) && non_negative,
+            (Number, value) => {
+                matches!(value, Value::Number(_)) || is_calc_of(value, &[CalcType::Number])
+            }
+            (NonNegativeNumber, value) => Number.matches(value) && non_negative,
+            (Integer, Value::Number(number)) => number.fract() == 0.0,
+            (Percentage, value) => matches!(value, Value::Percentage(_)) || is_calc_of(value, &[P]),
+            (Time, Value::Dimension(_, unit)) => unit == "s" || unit == "ms",
+            (NonNegativeTime, value) => Time.matches(value) && non_negative,
+            // unitless zero is an angle
+            (Angle, Value::Number(number)) => *number == 0.0,
+            (Angle, Value::Dimension(_, unit)) => {
+                ["deg", "grad", "rad", "turn"].contains(&unit.as_str())
+            }
+            (Color, Value::ColorValue(_)) => true,
+            (Color, Value::Keyword(keyword)) => {
+                let keyword = keyword.to_lowercase();
+                keyword == "currentcolor"
+                    || keyword == "transparent"
+                    || keyword.starts_with("hsl(")
+                    || keyword.starts_with("hsla(")
+            }
+            (Image, Value::Url(_)) => true,
+            (Image, Value::Keyword(keyword)) => keyword.to_lowercase().contains("gradient("),
+            (QuotedString, Value::String(_)) => true,
+            (CustomIdent, Value::Keyword(keyword)) => {
+                !is_css_wide_keyword(value) && !keyword.contains('(') && keyword != "/"
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Grammar {
+    fn components(&self) -> &'static [Component] {
+        match self {
+            One(components) | Repeat(components, ..) => components,
+            Comma(grammar) => grammar.components(),
+            TemplateAreas | Custom => &[],
+        }
+    }
+
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            One(components) => components.iter().any(|component| component.matches(value)),
+            Repeat(components, min, max) => {
+                let values = match value {
+                    Value::List(values) => values.as_slice(),
+                    value => std::slice::from_ref(value),
+                };
+                (*min..=*max).contains(&values.len())
+                    && values
+                        .iter()
+                        .all(|value| components.iter().any(|component| component.matches(value)))
+            }
+            Comma(grammar) => match value {
+                Value::CommaList(values) => values.iter().all(|value| grammar.matches(value)),
+                value => grammar.matches(value),
+            },
+            TemplateAreas => match value {
+                Value::Keyword(keyword) => keyword.eq_ignore_ascii_case("none"),
+                value => {
+                    let values = match value {
+                        Value::List(values) => values.as_slice(),
+                        value => std::slice::from_ref(value),
+                    };
+                    let rows: Option<Vec<&str>> = values
+                        .iter()
+                        .map(|value| match value {
+                            Value::String(row) => Some(row.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    rows.is_some_and(|rows| valid_template_areas(&rows))
+                }
+            },
+            Custom => true,
+        }
+    }
+}
+
+// whether the rows of `grid-template-areas` have the same number of cells,
+// and each named area fills its bounding rectangle; `.` marks unnamed cells
+fn valid_template_areas(rows: &[&str]) -> bool {
+    let rows: Vec<Vec<&str>> = rows
+        .iter()
+        .map(|row| row.split_whitespace().collect())
+        .collect();
+    if rows[0].is_empty() || rows.iter().any(|row| row.len() != rows[0].len()) {
+        return false;
+    }
+    // the cells of each name, and their bounding rectangle
+    let mut areas: HashMap<&str, (usize, [usize; 4])> = HashMap::new();
+    for (row, names) in rows.iter().enumerate() {
+        for (column, name) in names.iter().enumerate() {
+            if name.chars().all(|c| c == '.') {
+                continue;
+            }
+            let (cells, bounds) = areas.entry(name).or_insert((0, [row, row, column, column]));
+            *cells += 1;
+            *bounds = [
+                bounds[0].min(row),
+                bounds[1].max(row),
+                bounds[2].min(column),
+                bounds[3].max(column),
+            ];
+        }
+    }
+    areas.values().all(|(cells, [top, bottom, left, right])| {
+        *cells == (bottom - top + 1) * (right - left + 1)
+    })
+}
+
+// whether the value is valid for the property; css-wide keywords are valid for
+// every property, shorthands are validated through their longhands, and
+// unknown properties are invalid
+pub fn is_valid(name: &str, value: &Value) -> bool {
+    if is_custom_property(name) || is_shorthand(name) {
+        return true;
+    }
+    match lookup(name) {
+        Some(property) => is_css_wide_keyword(value) || property.grammar.matches(value),
+        None => false,
+    }
+}
+
+// whether the property takes integers, to which math functions are rounded
+pub fn takes_integers(name: &str) -> bool {
+    lookup(name).is_some_and(|property| {
+        property
+            .grammar
+            .components()
+            .iter()
+            .any(|component| matches!(component, Integer))
+    })
+}
+
+// the initial values of all the properties, parsed once
+pub fn initial_values() -> &'static PropertyMap {
+    static INITIAL_VALUES: OnceLock<PropertyMap> = OnceLock::new();
+    INITIAL_VALUES.get_or_init(|| {
+        PROPERTIES
+            .iter()
+            .map(|property| {
+                let value = Declaration::parse_value(property.name, property.initial.to_string())
+                    .unwrap_or_else(|| panic!("Invalid initial value of {}", property.name));
+                (property.name.to_string(), value)
+            })
+            .collect()
+    })
+}
+
+pub fn initial_value(name: &str) -> Option<&'static Value> {
+    initial_values().get(name)
+}
+
+// fill in the properties without a cascaded value and resolve the css-wide
+// keywords: inherited properties take the value of the parent, others their
+// initial value
+pub fn apply_defaults(values: &mut PropertyMap, parent: &PropertyMap) {
+    let keywords: HashMap<String, String> = values
+        .iter()
+        .filter(|(_, value)| is_css_wide_keyword(value))
+        .map(|(name, value)| match value {
+            Value::Keyword(keyword) => (name.clone(), keyword.to_lowercase()),
+            _ => unreachable!(),
+        })
+        .collect();
+
+    // unknown properties can only inherit
+    for (name, keyword) in &keywords {
+        if lookup(name).is_none() {
+            match (keyword.as_str(), parent.get(name)) {
+                ("inherit", Some(value)) => values.insert(name.clone(), value.clone()),
+                _ => values.remove(name),
+            };
+        }
+    }
+
+    for property in PROPERTIES {
+        let inherit = match (
+            values.contains_key(property.name),
+            keywords.get(property.name),
+        ) {
+            (false, _) => property.inherited,
+            (true, None) => continue,
+            (true, Some(keyword)) => match keyword.as_str() {
+                "inherit" => true,
+                "initial" => false,
+                // `unset`, and `revert` when no previous origin sets the property
+                _ => property.inherited,
+            },
+        };
+        let value = match parent.get(property.name) {
+            Some(value) if inherit => value.clone(),
+            _ => initial_values()[property.name].clone(),
+        };
+        values.insert(property.name.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::{CSSParser, Unit};
+
+    fn declared(css: &str) -> PropertyMap {
+        CSSParser::new(css.to_string())
+            .parse_declarations()
+            .into_iter()
+            .map(|declaration| (declaration.name, declaration.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_validation() {
+        let values = declared(
+            "display: 12px; position: absolute; width: -1px; height: calc(50% - 2px);
+            z-index: 1.5; font-weight: 700; color: auto; list-style-type: \"-\";
+            font-family: \"Fira Sans\", sans-serif; unknown-property: 12px",
+        );
+        assert!(!values.contains_key("display"));
+        assert!(!values.contains_key("width"));
+        assert!(!values.contains_key("z-index"));
+        assert!(!values.contains_key("color"));
+        for name in [
+            "position",
+            "height",
+            "font-weight",
+            "list-style-type",
+            "font-family",
+        ] {
+            assert!(values.contains_key(name), "{} is missing", name);
+        }
+
+        let values = declared("margin: 1px auto; padding: 2px auto; display: inherit");
+        assert!(values.contains_key("margin-top"));
+        assert!(!values.contains_key("padding-top"));
+        assert!(values.contains_key("display"));
+    }
+
+    #[test]
+    fn test_unknown_properties() {
+        let values = declared(
+            "unknown-property: 12px; -webkit-appearance: none; colour: red; size: 2;
+            --custom-property: 12px; --Other: { a: b }; transition-duration: 1s, 20ms;
+            animation-iteration-count: infinite; size: a4 landscape",
+        );
+        // custom properties are never validated, other properties only when known
+        let mut names: Vec<&str> = values.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "--Other",
+                "--custom-property",
+                "animation-iteration-count",
+                "size",
+                "transition-duration",
+            ]
+        );
+        assert!(!is_valid(
+            "unknown-property",
+            &Value::Keyword("inherit".to_string())
+        ));
+        assert!(is_valid("margin", &Value::Number(0.0)));
+    }
+
+    #[test]
+    fn test_template_areas() {
+        let value = |css: &str| Declaration::parse_value("grid-template-areas", css.to_string());
+        for valid in ["none", "\"a a .\" \"a a b\"", "\"a\"", "\". ...\" \"b b\""] {
+            assert!(value(valid).is_some(), "{} is invalid", valid);
+        }
+        // rows of different lengths, and areas that aren't rectangles
+        for invalid in [
+            "\"a b\" \"c\"",
+            "\"a b\" \"a a\"",
+            "\"a . a\"",
+            "\"a\" \"b\" \"a\"",
+            "\"\"",
+            "\"a\" none",
+        ] {
+            assert!(value(invalid).is_none(), "{} is valid", invalid);
+        }
+    }
+
+    #[test]
+    fn test_initial_values() {
+        for property in PROPERTIES {
+            assert!(
+                property.grammar.matches(&initial_values()[property.name]),
+                "invalid initial value for {}",
+                property.name
+            );
+        }
+        assert_eq!(
+            initial_value("display"),
+            Some(&Value::Keyword("inline".to_string()))
+        );
+        assert_eq!(initial_value("margin-top"), Some(&Value::Number(0.0)));
+    }
+
+    #[test]
+    fn test_defaults() {
+        let mut parent = declared("color: #cc0000; margin-top: 2px; --x: 1");
+        apply_defaults(&mut parent, &PropertyMap::new());
+        assert_eq!(parent["display"], Value::Keyword("inline".to_string()));
+
+        let mut child = declared(
+            "font-size: initial; margin-left: inherit; padding-top: unset;
+            visibility: unset; custom: inherit",
+        );
+        apply_defaults(&mut child, &parent);
+        // inherited properties take the parent value, others the initial one
+        assert_eq!(child["color"], parent["color"]);
+        assert_eq!(child["margin-top"], Value::Number(0.0));
+        assert_eq!(child["margin-left"], parent["margin-left"]);
+        assert_eq!(child["font-size"], Value::Keyword("medium".to_string()));
+        assert_eq!(child["padding-top"], Value::Number(0.0));
+        assert_eq!(child["visibility"], parent["visibility"]);
+        assert!(!child.contains_key("custom"));
+
+        let mut root = declared("margin-top: inherit");
+        apply_defaults(&mut root, &PropertyMap::new());
+        assert_eq!(root["margin-top"], Value::Number(0.0));
+        assert_ne!(root["margin-top"], Value::Length(2.0, Unit::Px));
+    }
+}
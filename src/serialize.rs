@@ -0,0 +1,1438 @@
+use crate::calc::CalcNode;
+use crate::css::{
+    is_css_wide_keyword, AttributeOperator, AttributeSelector, Color, Combinator, ContentItem,
+    CssRule, Declaration, Nth, PageSelector, PseudoClass, PseudoElement, RelativeSelector,
+    RepeatCount, Rule, Selector, SimpleSelector, Stylesheet, TrackBreadth, TrackListItem,
+    TrackSize, Unit, Value, NAMED_COLORS,
+};
+use crate::media::{
+    Comparison, MediaCondition, MediaFeature, MediaQuery, MediaQueryList, MediaValue,
+};
+use crate::shorthands::longhands;
+use crate::supports::SupportsCondition;
+use crate::tokenizer::{quote_string, serialize};
+use std::sync::Arc;
+
+// where css text is written to: canonical css is indented, one declaration
+// per line, minified css has no optional whitespace and uses the shortest
+// forms of values
+pub struct CssWriter {
+    minify: bool,
+    indent: usize,
+    output: String,
+}
+
+pub trait ToCss {
+    fn write_css(&self, writer: &mut CssWriter);
+
+    fn to_css(&self) -> String {
+        let mut writer = CssWriter::new(false);
+        self.write_css(&mut writer);
+        writer.output
+    }
+
+    fn to_minified_css(&self) -> String {
+        let mut writer = CssWriter::new(true);
+        self.write_css(&mut writer);
+        writer.output
+    }
+}
+
+impl CssWriter {
+    pub fn new(minify: bool) -> CssWriter {
+        CssWriter {
+            minify,
+            indent: 0,
+            output: String::new(),
+        }
+    }
+
+    pub fn minify(&self) -> bool {
+        self.minify
+    }
+
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    pub fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    // whitespace that is only there for readability
+    fn space(&mut self) {
+        if !self.minify {
+            self.output.push(' ');
+        }
+    }
+
+    fn comma(&mut self) {
+        self.output.push(',');
+        self.space();
+    }
+
+    fn newline(&mut self) {
+        if !self.minify {
+            self.output.push('\n');
+            self.output.push_str(&"  ".repeat(self.indent));
+        }
+    }
+
+    fn write_all<T: ToCss>(&mut self, items: &[T], separator: fn(&mut CssWriter)) {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                separator(self);
+            }
+            item.write_css(self);
+        }
+    }
+
+    fn write_number(&mut self, number: f32) {
+        if !number.is_finite() {
+            return self.write(match number {
+                number if number.is_nan() => "NaN",
+                number if number > 0.0 => "infinity",
+                _ => "-infinity",
+            });
+        }
+        // at most 6 decimals, without negative zero
+        let number = (number * 1e6).round() / 1e6 + 0.0;
+        let text = number.to_string();
+        let text = match self.minify {
+            true if text.starts_with("0.") => &text[1..],
+            true if text.starts_with("-0.") => {
+                self.output.push('-');
+                &text[2..]
+            }
+            _ => &text,
+        };
+        self.output.push_str(text);
+    }
+
+    // infinite and NaN values have no literal, and are written as the product
+    // of their keyword and of a unit value in calc()
+    fn write_dimension(&mut self, number: f32, unit: impl FnOnce(&mut CssWriter)) {
+        self.write_number(number);
+        if !number.is_finite() {
+            self.space();
+            self.write("*");
+            self.space();
+            self.write("1");
+        }
+        unit(self);
+    }
+
+    fn write_string(&mut self, text: &str) {
+        self.output.push_str(&quote_string(text));
+    }
+
+    fn write_url(&mut self, url: &str) {
+        let plain = !url
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '(' | ')' | '\\'));
+        self.output.push_str("url(");
+        match self.minify && plain {
+            true => self.output.push_str(url),
+            false => self.write_string(url),
+        }
+        self.output.push(')');
+    }
+
+    // `{`, the content written by `content` one level deeper, and `}`
+    fn write_block(&mut self, content: impl FnOnce(&mut CssWriter)) {
+        self.space();
+        self.output.push('{');
+        self.indent += 1;
+        content(self);
+        self.indent -= 1;
+        self.newline();
+        self.output.push('}');
+    }
+
+    fn write_rules(&mut self, rules: &[CssRule]) {
+        let rules = match self.minify {
+            true => minify_rules(rules),
+            false => rules.to_vec(),
+        };
+        for (index, rule) in rules.iter().enumerate() {
+            if index > 0 || self.indent > 0 {
+                self.newline();
+            }
+            rule.write_css(self);
+        }
+    }
+
+    fn write_declaration(&mut self, name: &str, value: impl FnOnce(&mut CssWriter)) {
+        self.newline();
+        self.output.push_str(name);
+        self.output.push(':');
+        self.space();
+        value(self);
+        self.output.push(';');
+    }
+
+    fn write_declarations(&mut self, declarations: &[Declaration]) {
+        let declarations = match self.minify {
+            true => winning_declarations(declarations),
+            false => declarations.to_vec(),
+        };
+        let mut written = vec![false; declarations.len()];
+        for (index, declaration) in declarations.iter().enumerate() {
+            if written[index] {
+                continue;
+            }
+            let shorthand = match &declaration.value {
+                // the longhands of a shorthand using `var()` are written as the shorthand
+                Value::PendingShorthand(shorthand, tokens) => {
+                    for (other, other_written) in declarations.iter().zip(written.iter_mut()) {
+                        *other_written |= other.value == declaration.value;
+                    }
+                    Some((shorthand.clone(), serialize(tokens).trim().to_string()))
+                }
+                // other shorthands are written as they were declared, leaving
+                // out the longhands they implicitly reset
+                _ => match &declaration.shorthand {
+                    Some(source) if !BOX_SHORTHANDS.contains(&source.0.as_str()) => {
+                        for (other, other_written) in declarations.iter().zip(written.iter_mut()) {
+                            *other_written |= other
+                                .shorthand
+                                .as_ref()
+                                .is_some_and(|other| Arc::ptr_eq(other, source));
+                        }
+                        let mut writer = CssWriter::new(self.minify);
+                        source.1.write_css(&mut writer);
+                        Some((source.0.clone(), writer.output))
+                    }
+                    _ => box_shorthand(&declarations, &written, index).map(
+                        |(shorthand, indices, value)| {
+                            for index in indices {
+                                written[index] = true;
+                            }
+                            let mut writer = CssWriter::new(self.minify);
+                            value.write_css(&mut writer);
+                            (shorthand.to_string(), writer.output)
+                        },
+                    ),
+                },
+            };
+            match shorthand {
+                Some((name, value)) => self.write_declaration(&name, |writer| writer.write(&value)),
+                None => self.write_declaration(&declaration.name, |writer| {
+                    declaration.value.write_css(writer)
+                }),
+            }
+            if declaration.important {
+                // the `;` written with the declaration goes after the flag
+                self.output.pop();
+                self.space();
+                self.output.push_str("!important;");
+            }
+        }
+        // the last `;` of a block is optional
+        if self.minify && self.output.ends_with(';') {
+            self.output.pop();
+        }
+    }
+}
+
+// the shorthands of four sides written in their shortest form
+const BOX_SHORTHANDS: [&str; 6] = [
+    "margin",
+    "padding",
+    "inset",
+    "border-width",
+    "border-style",
+    "border-color",
+];
+
+// the box shorthand the declaration is a longhand of, when its four longhands
+// are each declared once with the same importance and not written yet: the
+// shorthand, the indices of the longhands and the shorthand value
+fn box_shorthand(
+    declarations: &[Declaration],
+    written: &[bool],
+    index: usize,
+) -> Option<(&'static str, Vec<usize>, Value)> {
+    let declaration = &declarations[index];
+    let shorthand = BOX_SHORTHANDS
+        .into_iter()
+        .find(|shorthand| longhands(shorthand).contains(&declaration.name.as_str()))?;
+
+    let mut indices = Vec::new();
+    let mut sides = Vec::new();
+    for longhand in longhands(shorthand) {
+        let mut matching = declarations
+            .iter()
+            .enumerate()
+            .filter(|(other_index, other)| other.name == *longhand && !written[*other_index]);
+        let (other_index, other) = matching.next()?;
+        if matching.next().is_some() || other.important != declaration.important {
+            return None;
+        }
+        indices.push(other_index);
+        sides.push(other.value.clone());
+    }
+
+    let wide_keywords = sides
+        .iter()
+        .filter(|side| is_css_wide_keyword(side))
+        .count();
+    match wide_keywords {
+        0 => {}
+        // `margin: inherit`
+        4 if sides.iter().all(|side| *side == sides[0]) => {
+            return Some((shorthand, indices, sides.swap_remove(0)))
+        }
+        _ => return None,
+    }
+    if sides
+        .iter()
+        .any(|side| matches!(side, Value::List(_) | Value::Unsupported(_)))
+    {
+        return None;
+    }
+
+    // top, right, bottom, left, leaving out the sides equal to their opposite
+    if sides[3] == sides[1] {
+        sides.pop();
+        if sides[2] == sides[0] {
+            sides.pop();
+            if sides[1] == sides[0] {
+                sides.pop();
+            }
+        }
+    }
+    let value = match sides.len() {
+        1 => sides.pop().unwrap(),
+        _ => Value::List(sides),
+    };
+    Some((shorthand, indices, value))
+}
+
+// the declarations overriding no later declaration of the same property;
+// unsupported declarations override nothing, as they may be fallen back from
+fn winning_declarations(declarations: &[Declaration]) -> Vec<Declaration> {
+    let overrides = |declaration: &Declaration, other: &Declaration| {
+        declaration.name == other.name && !matches!(declaration.value, Value::Unsupported(_))
+    };
+    declarations
+        .iter()
+        .enumerate()
+        .filter(|(index, declaration)| {
+            !declarations[index + 1..].iter().any(|later| {
+                overrides(later, declaration) && (later.important || !declaration.important)
+            }) && !declarations[..*index].iter().any(|earlier| {
+                overrides(earlier, declaration) && earlier.important && !declaration.important
+            })
+        })
+        .map(|(_, declaration)| declaration.clone())
+        .collect()
+}
+
+// adjacent style rules with the same selectors, or with the same
+// declarations, are merged, and empty style rules are dropped
+fn minify_rules(rules: &[CssRule]) -> Vec<CssRule> {
+    let mut minified: Vec<CssRule> = Vec::new();
+    for rule in rules {
+        let rule = match rule {
+            CssRule::Style(rule) if rule.declarations.is_empty() => continue,
+            CssRule::Style(rule) => rule.clone(),
+            rule => {
+                minified.push(rule.clone());
+                continue;
+            }
+        };
+        if let Some(CssRule::Style(previous)) = minified.last_mut() {
+            if previous.selectors == rule.selectors {
+                previous.declarations.extend(rule.declarations);
+                previous.declarations = winning_declarations(&previous.declarations);
+                continue;
+            }
+            let block = |rule: &Rule| {
+                let mut writer = CssWriter::new(true);
+                writer.write_declarations(&rule.declarations);
+                writer.output
+            };
+            if block(previous) == block(&rule) {
+                previous.selectors.extend(rule.selectors);
+                continue;
+            }
+        }
+        minified.push(CssRule::Style(rule));
+    }
+    minified
+}
+
+impl ToCss for Stylesheet {
+    fn write_css(&self, writer: &mut CssWriter) {
+        writer.write_rules(&self.rules);
+        if !writer.minify && !self.rules.is_empty() {
+            writer.write("\n");
+        }
+    }
+}
+
+impl ToCss for CssRule {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            CssRule::Style(rule) => {
+                writer.write_all(&rule.selectors, CssWriter::comma);
+                writer.write_block(|writer| writer.write_declarations(&rule.declarations));
+            }
+            CssRule::Media(media) => {
+                writer.write("@media ");
+                media.queries.write_css(writer);
+                writer.write_block(|writer| writer.write_rules(&media.rules));
+            }
+            CssRule::Import(import) => {
+                writer.write("@import ");
+                match writer.minify {
+                    true => writer.write_string(&import.url),
+                    false => writer.write_url(&import.url),
+                }
+                match &import.layer {
+                    Some(Some(name)) => {
+                        writer.write(" layer(");
+                        writer.write(name);
+                        writer.write(")");
+                    }
+                    Some(None) => writer.write(" layer"),
+                    None => {}
+                }
+                if !import.queries.queries.is_empty() {
+                    writer.write(" ");
+                    import.queries.write_css(writer);
+                }
+                writer.write(";");
+            }
+            CssRule::Supports(supports) => {
+                writer.write("@supports ");
+                supports.condition.write_css(writer);
+                writer.write_block(|writer| writer.write_rules(&supports.rules));
+            }
+            CssRule::Layer(layer) => {
+                writer.write("@layer");
+                for (index, name) in layer.names.iter().enumerate() {
+                    match index {
+                        0 => writer.write(" "),
+                        _ => writer.comma(),
+                    }
+                    writer.write(name);
+                }
+                match &layer.rules {
+                    Some(rules) => writer.write_block(|writer| writer.write_rules(rules)),
+                    None => writer.write(";"),
+                }
+            }
+            CssRule::Keyframes(keyframes) => {
+                writer.write("@keyframes ");
+                writer.write(&keyframes.name);
+                writer.write_block(|writer| {
+                    for keyframe in &keyframes.keyframes {
+                        writer.newline();
+                        for (index, offset) in keyframe.offsets.iter().enumerate() {
+                            if index > 0 {
+                                writer.comma();
+                            }
+                            match (writer.minify, *offset) {
+                                (true, 1.0) => writer.write("to"),
+                                _ => {
+                                    writer.write_number(offset * 100.0);
+                                    writer.write("%");
+                                }
+                            }
+                        }
+                        let declarations: Vec<Declaration> = keyframe
+                            .declarations
+                            .iter()
+                            .map(|(name, value)| Declaration {
+                                name: name.clone(),
+                                value: value.clone(),
+                                important: false,
+                                shorthand: None,
+                            })
+                            .collect();
+                        writer.write_block(|writer| writer.write_declarations(&declarations));
+                    }
+                });
+            }
+            CssRule::FontFace(font_face) => {
+                writer.write("@font-face");
+                writer.write_block(|writer| {
+                    for (name, value) in &font_face.descriptors {
+                        writer.write_declaration(name, |writer| writer.write(value));
+                    }
+                    if writer.minify && writer.output.ends_with(';') {
+                        writer.output.pop();
+                    }
+                });
+            }
+            CssRule::Page(page) => {
+                writer.write("@page");
+                if !page.selectors.is_empty() {
+                    writer.write(" ");
+                    writer.write_all(&page.selectors, CssWriter::comma);
+                }
+                writer.write_block(|writer| {
+                    writer.write_declarations(&page.declarations);
+                    for (name, declarations) in &page.margin_boxes {
+                        if writer.minify && !writer.output.ends_with(['{', '}']) {
+                            writer.write(";");
+                        }
+                        writer.newline();
+                        writer.write("@");
+                        writer.write(name);
+                        writer.write_block(|writer| writer.write_declarations(declarations));
+                    }
+                });
+            }
+            CssRule::Unknown(at_rule) => {
+                writer.write("@");
+                writer.write(&at_rule.name);
+                if !at_rule.prelude.is_empty() {
+                    writer.write(" ");
+                    writer.write(&at_rule.prelude);
+                }
+                match &at_rule.block {
+                    Some(block) => {
+                        writer.space();
+                        writer.write("{");
+                        writer.write(block);
+                        writer.write("}");
+                    }
+                    None => writer.write(";"),
+                }
+            }
+        }
+    }
+}
+
+impl ToCss for PageSelector {
+    fn write_css(&self, writer: &mut CssWriter) {
+        if let Some(name) = &self.name {
+            writer.write(name);
+        }
+        for pseudo_class in &self.pseudo_classes {
+            writer.write(":");
+            writer.write(pseudo_class);
+        }
+    }
+}
+
+impl ToCss for Selector {
+    fn write_css(&self, writer: &mut CssWriter) {
+        for (index, compound) in self.compounds.iter().enumerate() {
+            if index > 0 {
+                self.combinators[index - 1].write_css(writer);
+            }
+            compound.write_css(writer);
+        }
+    }
+}
+
+impl ToCss for Combinator {
+    fn write_css(&self, writer: &mut CssWriter) {
+        let combinator = match self {
+            Combinator::Descendant => return writer.write(" "),
+            Combinator::Child => ">",
+            Combinator::NextSibling => "+",
+            Combinator::SubsequentSibling => "~",
+        };
+        writer.space();
+        writer.write(combinator);
+        writer.space();
+    }
+}
+
+impl ToCss for RelativeSelector {
+    fn write_css(&self, writer: &mut CssWriter) {
+        let combinator = match self.combinator {
+            Combinator::Descendant => "",
+            Combinator::Child => ">",
+            Combinator::NextSibling => "+",
+            Combinator::SubsequentSibling => "~",
+        };
+        if !combinator.is_empty() {
+            writer.write(combinator);
+            writer.space();
+        }
+        self.selector.write_css(writer);
+    }
+}
+
+impl ToCss for SimpleSelector {
+    fn write_css(&self, writer: &mut CssWriter) {
+        let start = writer.output.len();
+        if let Some(tag_name) = &self.tag_name {
+            writer.write(tag_name);
+        }
+        if let Some(id) = &self.id {
+            writer.write("#");
+            writer.write(id);
+        }
+        for class in &self.class {
+            writer.write(".");
+            writer.write(class);
+        }
+        for attribute in &self.attributes {
+            attribute.write_css(writer);
+        }
+        for pseudo_class in &self.pseudo_classes {
+            pseudo_class.write_css(writer);
+        }
+        if writer.output.len() == start && self.pseudo_element.is_none() {
+            writer.write("*");
+        }
+        if let Some(pseudo_element) = self.pseudo_element {
+            writer.write(match pseudo_element {
+                PseudoElement::Before => "::before",
+                PseudoElement::After => "::after",
+                PseudoElement::Marker => "::marker",
+                PseudoElement::FirstLine => "::first-line",
+                PseudoElement::FirstLetter => "::first-letter",
+            });
+        }
+    }
+}
+
+impl ToCss for AttributeSelector {
+    fn write_css(&self, writer: &mut CssWriter) {
+        writer.write("[");
+        writer.write(&self.name);
+        let operator = match self.operator {
+            AttributeOperator::Exists => return writer.write("]"),
+            AttributeOperator::Equals => "=",
+            AttributeOperator::Includes => "~=",
+            AttributeOperator::DashMatch => "|=",
+            AttributeOperator::Prefix => "^=",
+            AttributeOperator::Suffix => "$=",
+            AttributeOperator::Substring => "*=",
+        };
+        writer.write(operator);
+        writer.write_string(&self.value);
+        if self.case_insensitive {
+            writer.write(" i");
+        }
+        writer.write("]");
+    }
+}
+
+impl ToCss for PseudoClass {
+    fn write_css(&self, writer: &mut CssWriter) {
+        let name = match self {
+            PseudoClass::Root => "root",
+            PseudoClass::Empty => "empty",
+            PseudoClass::FirstChild => "first-child",
+            PseudoClass::LastChild => "last-child",
+            PseudoClass::OnlyChild => "only-child",
+            PseudoClass::FirstOfType => "first-of-type",
+            PseudoClass::LastOfType => "last-of-type",
+            PseudoClass::OnlyOfType => "only-of-type",
+            PseudoClass::Checked => "checked",
+            PseudoClass::Disabled => "disabled",
+            PseudoClass::Enabled => "enabled",
+            PseudoClass::AnyLink => "any-link",
+            PseudoClass::UserAction(name) => name,
+            PseudoClass::NthChild(..) => "nth-child(",
+            PseudoClass::NthLastChild(..) => "nth-last-child(",
+            PseudoClass::NthOfType(_) => "nth-of-type(",
+            PseudoClass::NthLastOfType(_) => "nth-last-of-type(",
+            PseudoClass::Not(_) => "not(",
+            PseudoClass::Is(_) => "is(",
+            PseudoClass::Where(_) => "where(",
+            PseudoClass::Has(_) => "has(",
+        };
+        writer.write(":");
+        writer.write(name);
+        match self {
+            PseudoClass::NthChild(nth, selectors) | PseudoClass::NthLastChild(nth, selectors) => {
+                nth.write_css(writer);
+                if !selectors.is_empty() {
+                    writer.write(" of ");
+                    writer.write_all(selectors, CssWriter::comma);
+                }
+            }
+            PseudoClass::NthOfType(nth) | PseudoClass::NthLastOfType(nth) => nth.write_css(writer),
+            PseudoClass::Not(selectors)
+            | PseudoClass::Is(selectors)
+            | PseudoClass::Where(selectors) => writer.write_all(selectors, CssWriter::comma),
+            PseudoClass::Has(selectors) => writer.write_all(selectors, CssWriter::comma),
+            _ => return,
+        }
+        writer.write(")");
+    }
+}
+
+impl ToCss for Nth {
+    fn write_css(&self, writer: &mut CssWriter) {
+        let text = match (self.a, self.b) {
+            (0, b) => b.to_string(),
+            (a, b) => {
+                let a = match a {
+                    1 => String::from("n"),
+                    -1 => String::from("-n"),
+                    a => format!("{}n", a),
+                };
+                match b {
+                    0 => a,
+                    b if b > 0 => format!("{}+{}", a, b),
+                    b => format!("{}{}", a, b),
+                }
+            }
+        };
+        writer.write(&text);
+    }
+}
+
+impl ToCss for Unit {
+    fn write_css(&self, writer: &mut CssWriter) {
+        writer.write(match self {
+            Unit::Px => "px",
+            Unit::Cm => "cm",
+            Unit::Mm => "mm",
+            Unit::Q => "q",
+            Unit::In => "in",
+            Unit::Pt => "pt",
+            Unit::Pc => "pc",
+            Unit::Em => "em",
+            Unit::Rem => "rem",
+            Unit::Ex => "ex",
+            Unit::Ch => "ch",
+            Unit::Vw => "vw",
+            Unit::Vh => "vh",
+            Unit::Vmin => "vmin",
+            Unit::Vmax => "vmax",
+        });
+    }
+}
+
+impl ToCss for Color {
+    fn write_css(&self, writer: &mut CssWriter) {
+        if self.a < 255 {
+            // the shortest alpha giving back the same channel value
+            let alpha = self.a as f32 / 255.0;
+            let rounded = (alpha * 100.0).round() / 100.0;
+            let alpha = match (rounded * 255.0).round() as u8 == self.a {
+                true => rounded,
+                false => (alpha * 1000.0).round() / 1000.0,
+            };
+            let separator = if writer.minify { "," } else { ", " };
+            writer.write(&format!(
+                "rgba({r}{s}{g}{s}{b}{s}",
+                r = self.r,
+                g = self.g,
+                b = self.b,
+                s = separator
+            ));
+            writer.write_number(alpha);
+            return writer.write(")");
+        }
+        if !writer.minify {
+            return writer.write(&format!("rgb({}, {}, {})", self.r, self.g, self.b));
+        }
+        // the shortest of the hex notations and the color names
+        let hex = format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b);
+        let bytes = hex.as_bytes();
+        let mut shortest =
+            match bytes[1] == bytes[2] && bytes[3] == bytes[4] && bytes[5] == bytes[6] {
+                true => format!("#{}{}{}", &hex[1..2], &hex[3..4], &hex[5..6]),
+                false => hex,
+            };
+        for (name, rgb) in NAMED_COLORS {
+            if *rgb == (self.r, self.g, self.b) && name.len() < shortest.len() {
+                shortest = name.to_string();
+            }
+        }
+        writer.write(&shortest);
+    }
+}
+
+// a term of a sum or a product, in parentheses when it is itself a sum
+fn write_calc_term(node: &CalcNode, writer: &mut CssWriter) {
+    match node {
+        CalcNode::Sum(_) => {
+            writer.write("(");
+            write_calc_expression(node, writer);
+            writer.write(")");
+        }
+        node => write_calc_expression(node, writer),
+    }
+}
+
+// the negation of a subtracted term of a sum
+fn negated(node: &CalcNode) -> Option<CalcNode> {
+    match node {
+        CalcNode::Number(value) if *value < 0.0 => Some(CalcNode::Number(-value)),
+        CalcNode::Percentage(value) if *value < 0.0 => Some(CalcNode::Percentage(-value)),
+        CalcNode::Dimension(value, unit) if *value < 0.0 => {
+            Some(CalcNode::Dimension(-value, *unit))
+        }
+        CalcNode::Product(factors) if factors.first() == Some(&CalcNode::Number(-1.0)) => {
+            match &factors[1..] {
+                [factor] => Some(factor.clone()),
+                factors => Some(CalcNode::Product(factors.to_vec())),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn write_calc_expression(node: &CalcNode, writer: &mut CssWriter) {
+    match node {
+        CalcNode::Number(value) => writer.write_number(*value),
+        CalcNode::Percentage(value) => writer.write_dimension(*value, |writer| writer.write("%")),
+        CalcNode::Dimension(value, unit) => {
+            writer.write_dimension(*value, |writer| unit.write_css(writer))
+        }
+        CalcNode::Sum(terms) => {
+            for (index, term) in terms.iter().enumerate() {
+                match (index, negated(term)) {
+                    (0, _) => write_calc_term(term, writer),
+                    // `+` and `-` always need whitespace
+                    (_, Some(term)) => {
+                        writer.write(" - ");
+                        write_calc_term(&term, writer);
+                    }
+                    (_, None) => {
+                        writer.write(" + ");
+                        write_calc_term(term, writer);
+                    }
+                }
+            }
+        }
+        CalcNode::Product(factors) => {
+            for (index, factor) in factors.iter().enumerate() {
+                match factor {
+                    CalcNode::Invert(divisor) => {
+                        writer.space();
+                        writer.write("/");
+                        writer.space();
+                        write_calc_term(divisor, writer);
+                    }
+                    factor => {
+                        if index > 0 {
+                            writer.space();
+                            writer.write("*");
+                            writer.space();
+                        }
+                        write_calc_term(factor, writer);
+                    }
+                }
+            }
+        }
+        CalcNode::Invert(divisor) => {
+            writer.write("1");
+            writer.space();
+            writer.write("/");
+            writer.space();
+            write_calc_term(divisor, writer);
+        }
+        CalcNode::Min(arguments) | CalcNode::Max(arguments) => {
+            writer.write(match node {
+                CalcNode::Min(_) => "min(",
+                _ => "max(",
+            });
+            for (index, argument) in arguments.iter().enumerate() {
+                if index > 0 {
+                    writer.comma();
+                }
+                write_calc_expression(argument, writer);
+            }
+            writer.write(")");
+        }
+        CalcNode::Clamp(min, value, max) => {
+            writer.write("clamp(");
+            write_calc_expression(min, writer);
+            writer.comma();
+            write_calc_expression(value, writer);
+            writer.comma();
+            write_calc_expression(max, writer);
+            writer.write(")");
+        }
+    }
+}
+
+impl ToCss for CalcNode {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            CalcNode::Min(_) | CalcNode::Max(_) | CalcNode::Clamp(..) => {
+                write_calc_expression(self, writer)
+            }
+            node => {
+                writer.write("calc(");
+                write_calc_expression(node, writer);
+                writer.write(")");
+            }
+        }
+    }
+}
+
+impl ToCss for ContentItem {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            ContentItem::String(text) => writer.write_string(text),
+            ContentItem::Attr(name) => writer.write(&format!("attr({})", name)),
+            ContentItem::OpenQuote => writer.write("open-quote"),
+            ContentItem::CloseQuote => writer.write("close-quote"),
+            ContentItem::NoOpenQuote => writer.write("no-open-quote"),
+            ContentItem::NoCloseQuote => writer.write("no-close-quote"),
+            ContentItem::Url(url) => writer.write_url(url),
+            ContentItem::Counter(name, style) => {
+                writer.write("counter(");
+                writer.write(name);
+                if style != "decimal" {
+                    writer.comma();
+                    writer.write(style);
+                }
+                writer.write(")");
+            }
+            ContentItem::Counters(name, separator, style) => {
+                writer.write("counters(");
+                writer.write(name);
+                writer.comma();
+                writer.write_string(separator);
+                if style != "decimal" {
+                    writer.comma();
+                    writer.write(style);
+                }
+                writer.write(")");
+            }
+        }
+    }
+}
+
+impl ToCss for TrackBreadth {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            TrackBreadth::Length(length) => length.write_css(writer),
+            TrackBreadth::Flex(flex) => {
+                writer.write_number(*flex);
+                writer.write("fr");
+            }
+            TrackBreadth::MinContent => writer.write("min-content"),
+            TrackBreadth::MaxContent => writer.write("max-content"),
+            TrackBreadth::Auto => writer.write("auto"),
+        }
+    }
+}
+
+impl ToCss for TrackSize {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            TrackSize::Breadth(breadth) => breadth.write_css(writer),
+            TrackSize::MinMax(min, max) => {
+                writer.write("minmax(");
+                min.write_css(writer);
+                writer.comma();
+                max.write_css(writer);
+                writer.write(")");
+            }
+            TrackSize::FitContent(limit) => {
+                writer.write("fit-content(");
+                limit.write_css(writer);
+                writer.write(")");
+            }
+        }
+    }
+}
+
+impl ToCss for TrackListItem {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            TrackListItem::LineNames(names) => writer.write(&format!("[{}]", names.join(" "))),
+            TrackListItem::Track(size) => size.write_css(writer),
+            TrackListItem::Repeat(count, items) => {
+                writer.write("repeat(");
+                match count {
+                    RepeatCount::Count(count) => writer.write(&count.to_string()),
+                    RepeatCount::AutoFill => writer.write("auto-fill"),
+                    RepeatCount::AutoFit => writer.write("auto-fit"),
+                }
+                writer.comma();
+                writer.write_all(items, |writer| writer.write(" "));
+                writer.write(")");
+            }
+        }
+    }
+}
+
+impl ToCss for Value {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            Value::Keyword(keyword) => writer.write(keyword),
+            // the unit of a zero length is optional
+            Value::Length(value, _) if writer.minify && *value == 0.0 => writer.write("0"),
+            Value::Length(value, unit) if !value.is_finite() => {
+                CalcNode::Dimension(*value, *unit).write_css(writer)
+            }
+            Value::Length(value, unit) => {
+                writer.write_number(*value);
+                unit.write_css(writer);
+            }
+            Value::Percentage(value) if !value.is_finite() => {
+                CalcNode::Percentage(*value).write_css(writer)
+            }
+            Value::Number(value) if !value.is_finite() => {
+                CalcNode::Number(*value).write_css(writer)
+            }
+            Value::Dimension(value, unit) if !value.is_finite() => {
+                writer.write("calc(");
+                writer.write_dimension(*value, |writer| writer.write(unit));
+                writer.write(")");
+            }
+            Value::Dimension(value, unit) => {
+                writer.write_number(*value);
+                writer.write(unit);
+            }
+            Value::Percentage(value) => {
+                writer.write_number(*value);
+                writer.write("%");
+            }
+            Value::Number(value) => writer.write_number(*value),
+            Value::String(text) => writer.write_string(text),
+            Value::Url(url) => writer.write_url(url),
+            Value::ColorValue(color) => color.write_css(writer),
+            Value::Calc(node) => node.write_css(writer),
+            Value::List(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    let slash = |value: &Value| matches!(value, Value::Keyword(k) if k == "/");
+                    if index > 0 && !(writer.minify && (slash(value) || slash(&values[index - 1])))
+                    {
+                        writer.write(" ");
+                    }
+                    value.write_css(writer);
+                }
+            }
+            Value::CommaList(values) => writer.write_all(values, CssWriter::comma),
+            Value::Content(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        writer.write(" ");
+                    }
+                    item.write_css(writer);
+                }
+            }
+            Value::CounterList(counters) => {
+                for (index, (name, value)) in counters.iter().enumerate() {
+                    if index > 0 {
+                        writer.write(" ");
+                    }
+                    writer.write(&format!("{} {}", name, value));
+                }
+            }
+            Value::TrackList(items) => writer.write_all(items, |writer| writer.write(" ")),
+            Value::Tokens(tokens)
+            | Value::PendingShorthand(_, tokens)
+            | Value::Unsupported(tokens) => writer.write(serialize(tokens).trim()),
+        }
+    }
+}
+
+impl ToCss for MediaQueryList {
+    fn write_css(&self, writer: &mut CssWriter) {
+        writer.write_all(&self.queries, CssWriter::comma);
+    }
+}
+
+impl ToCss for MediaQuery {
+    fn write_css(&self, writer: &mut CssWriter) {
+        if self.negated {
+            writer.write("not ");
+        }
+        if let Some(media_type) = &self.media_type {
+            writer.write(media_type);
+            if self.condition.is_some() {
+                writer.write(" and ");
+            }
+        }
+        if let Some(condition) = &self.condition {
+            condition.write_css(writer);
+        }
+    }
+}
+
+impl MediaCondition {
+    fn write_in_parens(&self, writer: &mut CssWriter) {
+        match self {
+            MediaCondition::Feature(feature) => feature.write_css(writer),
+            MediaCondition::Unknown(text) => writer.write(&format!("({})", text)),
+            condition => {
+                writer.write("(");
+                condition.write_css(writer);
+                writer.write(")");
+            }
+        }
+    }
+}
+
+impl ToCss for MediaCondition {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            MediaCondition::Not(condition) => {
+                writer.write("not ");
+                condition.write_in_parens(writer);
+            }
+            MediaCondition::And(conditions) | MediaCondition::Or(conditions) => {
+                let operator = match self {
+                    MediaCondition::And(_) => " and ",
+                    _ => " or ",
+                };
+                for (index, condition) in conditions.iter().enumerate() {
+                    if index > 0 {
+                        writer.write(operator);
+                    }
+                    condition.write_in_parens(writer);
+                }
+            }
+            condition => condition.write_in_parens(writer),
+        }
+    }
+}
+
+impl ToCss for MediaValue {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            MediaValue::Length(value) => {
+                writer.write_number(*value);
+                writer.write("px");
+            }
+            MediaValue::Resolution(value) => {
+                writer.write_number(*value);
+                writer.write("dppx");
+            }
+            MediaValue::Number(value) => writer.write_number(*value),
+            MediaValue::Ratio(width, height) => {
+                writer.write_number(*width);
+                writer.write("/");
+                writer.write_number(*height);
+            }
+            MediaValue::Ident(ident) => writer.write(ident),
+        }
+    }
+}
+
+impl ToCss for MediaFeature {
+    fn write_css(&self, writer: &mut CssWriter) {
+        let (name, comparisons) = match self {
+            MediaFeature::Boolean(name) => return writer.write(&format!("({})", name)),
+            MediaFeature::Range(name, comparisons) => (name, comparisons),
+        };
+        let operator = |comparison: &Comparison| match comparison {
+            Comparison::Equal => "=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+        writer.write("(");
+        match comparisons.as_slice() {
+            // the legacy `min-` and `max-` forms
+            [(comparison, value)] => {
+                let prefix = match comparison {
+                    Comparison::Equal => "",
+                    Comparison::GreaterOrEqual => "min-",
+                    Comparison::LessOrEqual => "max-",
+                    _ => "",
+                };
+                if prefix.is_empty() && *comparison != Comparison::Equal {
+                    writer.write(&format!("{} {} ", name, operator(comparison)));
+                } else {
+                    writer.write(&format!("{}{}:", prefix, name));
+                    writer.space();
+                }
+                value.write_css(writer);
+            }
+            // `400px <= width < 800px`, with the first comparison flipped back
+            comparisons => {
+                for (index, (comparison, value)) in comparisons.iter().enumerate() {
+                    match index {
+                        0 => {
+                            value.write_css(writer);
+                            let flipped = match comparison {
+                                Comparison::Less => Comparison::Greater,
+                                Comparison::LessOrEqual => Comparison::GreaterOrEqual,
+                                Comparison::Greater => Comparison::Less,
+                                Comparison::GreaterOrEqual => Comparison::LessOrEqual,
+                                Comparison::Equal => Comparison::Equal,
+                            };
+                            writer.write(&format!(" {} {}", operator(&flipped), name));
+                        }
+                        _ => {
+                            writer.write(&format!(" {} ", operator(comparison)));
+                            value.write_css(writer);
+                        }
+                    }
+                }
+            }
+        }
+        writer.write(")");
+    }
+}
+
+impl SupportsCondition {
+    fn write_in_parens(&self, writer: &mut CssWriter) {
+        match self {
+            SupportsCondition::Declaration(..)
+            | SupportsCondition::Selector(_)
+            | SupportsCondition::Unknown(_) => self.write_css(writer),
+            condition => {
+                writer.write("(");
+                condition.write_css(writer);
+                writer.write(")");
+            }
+        }
+    }
+}
+
+impl ToCss for SupportsCondition {
+    fn write_css(&self, writer: &mut CssWriter) {
+        match self {
+            SupportsCondition::Not(condition) => {
+                writer.write("not ");
+                condition.write_in_parens(writer);
+            }
+            SupportsCondition::And(conditions) | SupportsCondition::Or(conditions) => {
+                let operator = match self {
+                    SupportsCondition::And(_) => " and ",
+                    _ => " or ",
+                };
+                for (index, condition) in conditions.iter().enumerate() {
+                    if index > 0 {
+                        writer.write(operator);
+                    }
+                    condition.write_in_parens(writer);
+                }
+            }
+            SupportsCondition::Declaration(name, value) => {
+                writer.write(&format!("({}:", name));
+                writer.space();
+                writer.write(value);
+                writer.write(")");
+            }
+            SupportsCondition::Selector(selector) => {
+                writer.write(&format!("selector({})", selector))
+            }
+            SupportsCondition::Unknown(text) => writer.write(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::CSSParser;
+
+    fn parse(css: &str) -> Stylesheet {
+        CSSParser::new(css.to_string()).parse_stylesheet()
+    }
+
+    #[test]
+    fn test_import_layers() {
+        let stylesheet =
+            parse("@import 'a.css' layer( base.theme ) print; @import url(b.css) LAYER;");
+        assert_eq!(
+            stylesheet.to_css(),
+            "@import url(\"a.css\") layer(base.theme) print;\n@import url(\"b.css\") layer;\n"
+        );
+        assert_eq!(
+            stylesheet.to_minified_css(),
+            "@import \"a.css\" layer(base.theme) print;@import \"b.css\" layer;"
+        );
+    }
+
+    #[test]
+    fn test_serialize_stylesheet() {
+        let css = "@import 'theme.css' screen and (min-width: 600px);
+            h1 , p.note>em:nth-child( 2n + 1 ) { color : red; margin: 0 auto !important }
+            a[href^='http' i]:not(.internal)::after { content: \"→\" attr(title) counter(links, upper-roman); }
+            @media (400px <= width < 800px) { ul li + li { width: calc(100% - 20px); } }
+            @supports not (display: nope) { div { font: italic bold 12px/1.5 \"Helvetica Neue\", serif; } }";
+        let stylesheet = parse(css);
+        assert_eq!(
+            stylesheet.to_css(),
+            "@import url(\"theme.css\") screen and (min-width: 600px);
+h1, p.note > em:nth-child(2n+1) {
+  color: rgb(255, 0, 0);
+  margin: 0 auto !important;
+}
+a[href^=\"http\" i]:not(.internal)::after {
+  content: \"→\" attr(title) counter(links, upper-roman);
+}
+@media (400px <= width < 800px) {
+  ul li + li {
+    width: calc(100% - 20px);
+  }
+}
+@supports not (display: nope) {
+  div {
+    font: italic bold 12px / 1.5 \"Helvetica Neue\", serif;
+  }
+}
+"
+        );
+        // serialized css parses back to the same css
+        assert_eq!(parse(&stylesheet.to_css()).to_css(), stylesheet.to_css());
+    }
+
+    #[test]
+    fn test_serialize_at_rules() {
+        let css = "@layer base, theme;
+            @layer theme { p { padding: 1px 2px 1px 2px; } }
+            @keyframes fade { from { opacity: 0 } to { opacity: 1 } }
+            @font-face { font-family: Serif; src: url(serif.woff); }
+            @page :first { margin-top: 1in; @top-center { content: 'title' } }
+            @namespace svg url(http://www.w3.org/2000/svg);";
+        assert_eq!(
+            parse(css).to_css(),
+            "@layer base, theme;
+@layer theme {
+  p {
+    padding: 1px 2px;
+  }
+}
+@keyframes fade {
+  0% {
+    opacity: 0;
+  }
+  100% {
+    opacity: 1;
+  }
+}
+@font-face {
+  font-family: Serif;
+  src: url(serif.woff);
+}
+@page :first {
+  margin-top: 1in;
+  @top-center {
+    content: \"title\";
+  }
+}
+@namespace svg url(http://www.w3.org/2000/svg);
+"
+        );
+    }
+
+    #[test]
+    fn test_minify() {
+        let css = "p { color: #ffffff; margin: 0px 0px; opacity: 0.50; }
+            p { color: #ff0000; border-color: rgb(0, 0, 128) }
+            h1 { padding: 1em; }
+            h2 { padding: 1em; }
+            em { }
+            div > a { width: calc(100% - 10px); font: 12px/1.5 serif; top: 2px !important; top: 1px; }
+            @media screen { b { background-image: url('a b.png'); } }
+            @keyframes spin { from { opacity: 0 } to { opacity: 1 } }
+            i { transition: opacity 0.30s ease-in; transition-duration: 200ms; }";
+        assert_eq!(
+            parse(css).to_minified_css(),
+            "p{margin:0;opacity:.5;color:red;border-color:navy}h1,h2{padding:1em}\
+             div>a{width:calc(100% - 10px);font:12px/1.5 serif;top:2px!important}\
+             @media screen{b{background-image:url(\"a b.png\")}}\
+             @keyframes spin{0%{opacity:0}to{opacity:1}}\
+             i{transition:opacity .3s ease-in;transition-duration:200ms}"
+        );
+    }
+
+    #[test]
+    fn test_minify_shorthands() {
+        let css = "a { border: 0; background: url(a.png); font: 12px serif; }
+            b { border: 1px solid #ff0000; border-top-width: 2px; list-style: none; }
+            i { flex: 1; grid-area: 1 / 2; margin: 0 auto; border-width: 1px 2px; }
+            p { --x: { a }; --y: [b]; color: red; }";
+        assert_eq!(
+            parse(css).to_minified_css(),
+            "a{border:0;background:url(a.png);font:12px serif}\
+             b{border:1px solid red;border-top-width:2px;list-style:none}\
+             i{flex:1;grid-area:1/2;margin:0 auto;border-width:1px 2px}\
+             p{--x:{ a };--y:[b];color:red}"
+        );
+        assert_eq!(
+            parse("p { --x: { a: b; c } }").to_css(),
+            "p {\n  --x: { a: b; c };\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_serialize_values() {
+        let value = |name: &str, css: &str| {
+            Declaration::parse_value(name, css.to_string())
+                .unwrap()
+                .to_css()
+        };
+        assert_eq!(value("width", "min(10px, 5%)"), "min(10px, 5%)");
+        assert_eq!(
+            value("width", "calc(1em + (2px - 3%) / 2)"),
+            "calc(1em + 1px - 1.5%)"
+        );
+        assert_eq!(value("width", "calc(2 * (1em - 10%))"), "calc(2em - 20%)");
+        assert_eq!(value("color", "#0a0b0c"), "rgb(10, 11, 12)");
+        assert_eq!(value("--x", " { a: b } "), "{ a: b }");
+        assert_eq!(value("counter-reset", "a 2 b"), "a 2 b 0");
+        assert_eq!(
+            value(
+                "grid-template-columns",
+                "[a  b] repeat(auto-fit,minmax(1em,1fr)) fit-content(50%)"
+            ),
+            "[a b] repeat(auto-fit, minmax(1em, 1fr)) fit-content(50%)"
+        );
+        let mut writer = CssWriter::new(true);
+        Color::rgb(0, 0, 0).write_css(&mut writer);
+        Color::rgb(17, 34, 51).write_css(&mut writer);
+        assert_eq!(writer.output(), "#000#123");
+
+        assert_eq!(value("color", "rgba(0,0,0,.5)"), "rgba(0, 0, 0, 0.5)");
+        assert_eq!(value("color", "rgb(255 0 0 / 50%)"), "rgba(255, 0, 0, 0.5)");
+        assert_eq!(value("color", "#0008"), "rgba(0, 0, 0, 0.533)");
+        assert_eq!(value("color", "#ff000080"), "rgba(255, 0, 0, 0.5)");
+        assert_eq!(value("color", "rgba(0, 0, 0, 1)"), "rgb(0, 0, 0)");
+        let mut writer = CssWriter::new(true);
+        Color {
+            a: 51,
+            ..Color::rgb(255, 255, 255)
+        }
+        .write_css(&mut writer);
+        assert_eq!(writer.output(), "rgba(255,255,255,.2)");
+    }
+
+    #[test]
+    fn test_serialize_infinite_values() {
+        let value = |name: &str, css: &str| {
+            Declaration::parse_value(name, css.to_string())
+                .unwrap()
+                .to_css()
+        };
+        assert_eq!(value("width", "calc(1px / 0)"), "calc(infinity * 1px)");
+        assert_eq!(value("margin-top", "calc(-1% / 0)"), "calc(-infinity * 1%)");
+        assert_eq!(value("opacity", "calc(0 / 0)"), "calc(NaN)");
+        assert_eq!(
+            value("width", "calc(infinity * 1px)"),
+            "calc(infinity * 1px)"
+        );
+        assert_eq!(
+            value("width", "calc(10% + 1px / 0)"),
+            "calc(10% + infinity * 1px)"
+        );
+    }
+
+    #[test]
+    fn test_minify_unsupported_declarations() {
+        let css = "p { transform: rotate(45deg); width: 100%; width: -webkit-fill-available;
+                cursor: pointer; margin-top: 1px; margin: foo(); }
+            @keyframes spin { to { transform: rotate(1turn) } }";
+        let stylesheet = CSSParser::new(css.to_string())
+            .with_validation(false)
+            .parse_stylesheet();
+        // unsupported declarations are kept, and don't override the
+        // declarations they may fall back to
+        assert_eq!(
+            stylesheet.to_minified_css(),
+            "p{transform:rotate(45deg);width:100%;width:-webkit-fill-available;cursor:pointer;\
+             margin-top:1px;margin:foo()}@keyframes spin{to{transform:rotate(1turn)}}"
+        );
+        assert_eq!(
+            parse(css).to_minified_css(),
+            "p{width:100%;margin-top:1px}@keyframes spin{to{}}"
+        );
+    }
+}
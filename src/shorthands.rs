@@ -0,0 +1,770 @@
+use crate::css::Value;
+
+const BORDER_LONGHANDS: [&str; 12] = [
+    "border-top-width",
+    "border-right-width",
+    "border-bottom-width",
+    "border-left-width",
+    "border-top-style",
+    "border-right-style",
+    "border-bottom-style",
+    "border-left-style",
+    "border-top-color",
+    "border-right-color",
+    "border-bottom-color",
+    "border-left-color",
+];
+
+// the longhands set by a shorthand, in the order `expand` returns them
+pub fn longhands(name: &str) -> &'static [&'static str] {
+    match name {
+        "margin" => &["margin-top", "margin-right", "margin-bottom", "margin-left"],
+        "padding" => &[
+            "padding-top",
+            "padding-right",
+            "padding-bottom",
+            "padding-left",
+        ],
+        "inset" => &["top", "right", "bottom", "left"],
+        "border-width" => &BORDER_LONGHANDS[0..4],
+        "border-style" => &BORDER_LONGHANDS[4..8],
+        "border-color" => &BORDER_LONGHANDS[8..12],
+        "border" => &BORDER_LONGHANDS,
+        "border-top" => &["border-top-width", "border-top-style", "border-top-color"],
+        "border-right" => &[
+            "border-right-width",
+            "border-right-style",
+            "border-right-color",
+        ],
+        "border-bottom" => &[
+            "border-bottom-width",
+            "border-bottom-style",
+            "border-bottom-color",
+        ],
+        "border-left" => &[
+            "border-left-width",
+            "border-left-style",
+            "border-left-color",
+        ],
+        "outline" => &["outline-width", "outline-style", "outline-color"],
+        "list-style" => &["list-style-type", "list-style-position", "list-style-image"],
+        "flex" => &["flex-grow", "flex-shrink", "flex-basis"],
+        "gap" => &["row-gap", "column-gap"],
+        "grid-row" => &["grid-row-start", "grid-row-end"],
+        "grid-column" => &["grid-column-start", "grid-column-end"],
+        "grid-area" => &[
+            "grid-row-start",
+            "grid-column-start",
+            "grid-row-end",
+            "grid-column-end",
+        ],
+        "font" => &[
+            "font-style",
+            "font-variant",
+            "font-weight",
+            "font-stretch",
+            "font-size",
+            "line-height",
+            "font-family",
+        ],
+        "background" => &[
+            "background-color",
+            "background-image",
+            "background-repeat",
+            "background-attachment",
+            "background-position",
+            "background-size",
+            "background-origin",
+            "background-clip",
+        ],
+        _ => &[],
+    }
+}
+
+pub fn is_shorthand(name: &str) -> bool {
+    !longhands(name).is_empty()
+}
+
+fn keyword_in(value: &Value, keywords: &[&str]) -> bool {
+    match value {
+        Value::Keyword(keyword) => keywords.contains(&keyword.to_lowercase().as_str()),
+        _ => false,
+    }
+}
+
+fn is_length(value: &Value) -> bool {
+    match value {
+        Value::Length(..) | Value::Calc(_) => true,
+        Value::Number(number) => *number == 0.0,
+        _ => false,
+    }
+}
+
+fn is_length_percentage(value: &Value) -> bool {
+    is_length(value) || matches!(value, Value::Percentage(_))
+}
+
+fn is_non_negative(value: &Value) -> bool {
+    match value {
+        Value::Length(number, _) | Value::Percentage(number) | Value::Number(number) => {
+            *number >= 0.0
+        }
+        _ => true,
+    }
+}
+
+fn is_margin(value: &Value) -> bool {
+    is_length_percentage(value) || keyword_in(value, &["auto"])
+}
+
+fn is_padding(value: &Value) -> bool {
+    is_length_percentage(value) && is_non_negative(value)
+}
+
+fn is_line_width(value: &Value) -> bool {
+    is_length(value) && is_non_negative(value) || keyword_in(value, &["thin", "medium", "thick"])
+}
+
+fn is_line_style(value: &Value) -> bool {
+    keyword_in(
+        value,
+        &[
+            "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset",
+            "outset",
+        ],
+    )
+}
+
+fn is_outline_style(value: &Value) -> bool {
+    is_line_style(value) || keyword_in(value, &["auto"])
+}
+
+fn is_color(value: &Value) -> bool {
+    match value {
+        Value::ColorValue(_) => true,
+        Value::Keyword(keyword) => {
+            let keyword = keyword.to_lowercase();
+            keyword == "currentcolor"
+                || keyword == "transparent"
+                || keyword.starts_with("hsl(")
+                || keyword.starts_with("hsla(")
+        }
+        _ => false,
+    }
+}
+
+fn is_image(value: &Value) -> bool {
+    match value {
+        Value::Url(_) => true,
+        Value::Keyword(keyword) => keyword.to_lowercase().contains("gradient("),
+        _ => false,
+    }
+}
+
+fn is_slash(value: &Value) -> bool {
+    *value == Value::Keyword(String::from("/"))
+}
+
+fn initial() -> Value {
+    Value::Keyword(String::from("initial"))
+}
+
+fn keyword(keyword: &str) -> Value {
+    Value::Keyword(keyword.to_string())
+}
+
+// a single value, or a list of components
+fn list_or_single(mut values: Vec<Value>) -> Value {
+    match values.len() {
+        1 => values.pop().unwrap(),
+        _ => Value::List(values),
+    }
+}
+
+// the space-separated components of a value
+fn components(value: &Value) -> Option<Vec<Value>> {
+    match value {
+        Value::List(values) => Some(values.clone()),
+        Value::CommaList(_) => None,
+        value => Some(vec![value.clone()]),
+    }
+}
+
+fn assign(name: &str, values: Vec<Value>) -> Vec<(String, Value)> {
+    longhands(name)
+        .iter()
+        .map(|longhand| longhand.to_string())
+        .zip(values)
+        .collect()
+}
+
+// assign each component to the first free slot accepting it, like the `||`
+// combinator of value definitions
+fn any_order(values: &[Value], slots: &[fn(&Value) -> bool]) -> Option<Vec<Option<Value>>> {
+    let mut assigned = vec![None; slots.len()];
+    for value in values {
+        let slot = (0..slots.len()).find(|&slot| assigned[slot].is_none() && slots[slot](value))?;
+        assigned[slot] = Some(value.clone());
+    }
+    Some(assigned)
+}
+
+// one to four values for the top, right, bottom and left sides
+fn expand_sides(value: &Value, accepts: fn(&Value) -> bool) -> Option<Vec<Value>> {
+    let values = components(value)?;
+    if !values.iter().all(accepts) {
+        return None;
+    }
+    let indices = match values.len() {
+        1 => [0, 0, 0, 0],
+        2 => [0, 1, 0, 1],
+        3 => [0, 1, 2, 1],
+        4 => [0, 1, 2, 3],
+        _ => return None,
+    };
+    Some(indices.iter().map(|index| values[*index].clone()).collect())
+}
+
+// `<line-width> || <line-style> || <color>`
+fn expand_line(value: &Value, style: fn(&Value) -> bool) -> Option<Vec<Value>> {
+    let assigned = any_order(&components(value)?, &[is_line_width, style, is_color])?;
+    Some(
+        assigned
+            .into_iter()
+            .map(|value| value.unwrap_or_else(initial))
+            .collect(),
+    )
+}
+
+fn expand_list_style(value: &Value) -> Option<Vec<Value>> {
+    let values = components(value)?;
+    let (nones, others): (Vec<Value>, Vec<Value>) = values
+        .into_iter()
+        .partition(|value| keyword_in(value, &["none"]));
+    let is_type = |value: &Value| matches!(value, Value::Keyword(_) | Value::String(_));
+    let is_position = |value: &Value| keyword_in(value, &["inside", "outside"]);
+    let mut assigned = any_order(&others, &[is_position, is_image, is_type])?;
+
+    // `none` sets the type or the image, whichever is left
+    let free_slots: Vec<usize> = [2, 1]
+        .into_iter()
+        .filter(|slot| assigned[*slot].is_none())
+        .collect();
+    let mut free_slots = free_slots.into_iter();
+    for none in nones {
+        assigned[free_slots.next()?] = Some(none);
+    }
+    let [position, image, list_type] = [0, 1, 2].map(|slot| assigned[slot].take());
+    // a single `none` sets both
+    let (list_type, image) = match (list_type, image) {
+        (Some(list_type), None) if keyword_in(&list_type, &["none"]) => {
+            (Some(list_type.clone()), Some(list_type))
+        }
+        pair => pair,
+    };
+    Some(
+        [list_type, position, image]
+            .into_iter()
+            .map(|value| value.unwrap_or_else(initial))
+            .collect(),
+    )
+}
+
+fn expand_flex(value: &Value) -> Option<Vec<Value>> {
+    let values = components(value)?;
+    let zero = Value::Length(0.0, crate::css::Unit::Px);
+    let number = |value: f32| Value::Number(value);
+    if let [value] = values.as_slice() {
+        if keyword_in(value, &["none"]) {
+            return Some(vec![number(0.0), number(0.0), keyword("auto")]);
+        }
+        if keyword_in(value, &["auto"]) {
+            return Some(vec![number(1.0), number(1.0), keyword("auto")]);
+        }
+    }
+
+    let is_number = |value: &Value| matches!(value, Value::Number(n) if *n >= 0.0);
+    let is_basis = |value: &Value| {
+        is_length_percentage(value) && is_non_negative(value)
+            || keyword_in(value, &["auto", "content"])
+    };
+    // the basis comes before or after the grow and shrink factors; a third
+    // unitless number can only be a zero basis
+    let (factors, basis) = match values.as_slice() {
+        [grow, shrink, basis] if is_number(grow) && is_number(shrink) && is_basis(basis) => {
+            (vec![grow.clone(), shrink.clone()], Some(basis.clone()))
+        }
+        [basis, rest @ ..] if !is_number(basis) && is_basis(basis) => {
+            (rest.to_vec(), Some(basis.clone()))
+        }
+        [rest @ .., basis] if !is_number(basis) && is_basis(basis) => {
+            (rest.to_vec(), Some(basis.clone()))
+        }
+        rest => (rest.to_vec(), None),
+    };
+    if factors.len() > 2 || !factors.iter().all(is_number) || factors.is_empty() && basis.is_none()
+    {
+        return None;
+    }
+    Some(vec![
+        factors.first().cloned().unwrap_or(number(1.0)),
+        factors.get(1).cloned().unwrap_or(number(1.0)),
+        // `flex: 1` means a zero basis, unlike the initial `auto`
+        basis.unwrap_or(zero),
+    ])
+}
+
+// `<row-gap> <column-gap>?`
+fn expand_gap(value: &Value) -> Option<Vec<Value>> {
+    let values = components(value)?;
+    let is_gap = |value: &Value| {
+        is_length_percentage(value) && is_non_negative(value) || keyword_in(value, &["normal"])
+    };
+    match values.as_slice() {
+        [gap] if is_gap(gap) => Some(vec![gap.clone(), gap.clone()]),
+        [row, column] if is_gap(row) && is_gap(column) => Some(values),
+        _ => None,
+    }
+}
+
+// up to `count` grid lines separated by slashes; a missing line repeats the
+// name of the line it pairs with, and is `auto` otherwise
+fn expand_grid_lines(value: &Value, count: usize) -> Option<Vec<Value>> {
+    let values = components(value)?;
+    let mut lines: Vec<Value> = values
+        .split(is_slash)
+        .map(|line| list_or_single(line.to_vec()))
+        .collect();
+    if lines.len() > count || lines.contains(&Value::List(Vec::new())) {
+        return None;
+    }
+    while lines.len() < count {
+        let pair = &lines[lines.len().saturating_sub(2)];
+        let is_name = matches!(pair, Value::Keyword(_)) && !keyword_in(pair, &["auto", "span"]);
+        lines.push(match is_name {
+            true => pair.clone(),
+            false => keyword("auto"),
+        });
+    }
+    Some(lines)
+}
+
+fn expand_font(value: &Value) -> Option<Vec<Value>> {
+    let (first, other_families) = match value {
+        Value::CommaList(groups) => (components(&groups[0])?, &groups[1..]),
+        value => (components(value)?, &[][..]),
+    };
+
+    // optional style, variant, weight and stretch in any order, `normal`
+    // leaving one of them to its initial value
+    let is_style = |value: &Value| keyword_in(value, &["italic", "oblique"]);
+    let is_variant = |value: &Value| keyword_in(value, &["small-caps"]);
+    let is_weight = |value: &Value| match value {
+        Value::Number(weight) => (1.0..=1000.0).contains(weight),
+        value => keyword_in(value, &["bold", "bolder", "lighter"]),
+    };
+    let is_stretch = |value: &Value| {
+        keyword_in(
+            value,
+            &[
+                "ultra-condensed",
+                "extra-condensed",
+                "condensed",
+                "semi-condensed",
+                "semi-expanded",
+                "expanded",
+                "extra-expanded",
+                "ultra-expanded",
+            ],
+        )
+    };
+    let slots: [fn(&Value) -> bool; 4] = [is_style, is_variant, is_weight, is_stretch];
+    let mut index = 0;
+    let mut normals = 0;
+    let mut prefix = Vec::new();
+    while let Some(value) = first.get(index) {
+        if keyword_in(value, &["normal"]) {
+            normals += 1;
+        } else if slots.iter().any(|accepts| accepts(value)) {
+            prefix.push(value.clone());
+        } else {
+            break;
+        }
+        index += 1;
+    }
+    if normals + prefix.len() > 4 {
+        return None;
+    }
+    let assigned = any_order(&prefix, &slots)?;
+
+    let is_size = |value: &Value| {
+        is_length_percentage(value) && is_non_negative(value)
+            || keyword_in(
+                value,
+                &[
+                    "xx-small",
+                    "x-small",
+                    "small",
+                    "medium",
+                    "large",
+                    "x-large",
+                    "xx-large",
+                    "xxx-large",
+                    "larger",
+                    "smaller",
+                ],
+            )
+    };
+    let size = first.get(index).filter(|value| is_size(value))?.clone();
+    index += 1;
+    let mut line_height = initial();
+    if first.get(index).is_some_and(is_slash) {
+        let value = first.get(index + 1)?;
+        if !(is_length_percentage(value) && is_non_negative(value)
+            || matches!(value, Value::Number(n) if *n >= 0.0)
+            || keyword_in(value, &["normal"]))
+        {
+            return None;
+        }
+        line_height = value.clone();
+        index += 2;
+    }
+
+    let is_family = |value: &Value| match value {
+        Value::List(names) => names.iter().all(|name| matches!(name, Value::Keyword(_))),
+        value => matches!(value, Value::Keyword(_) | Value::String(_)) && !is_slash(value),
+    };
+    if index >= first.len() {
+        return None;
+    }
+    let mut families = vec![list_or_single(first[index..].to_vec())];
+    families.extend(other_families.iter().cloned());
+    if !families.iter().all(is_family) {
+        return None;
+    }
+    let family = match families.len() {
+        1 => families.pop().unwrap(),
+        _ => Value::CommaList(families),
+    };
+
+    let mut values: Vec<Value> = assigned
+        .into_iter()
+        .map(|value| value.unwrap_or_else(initial))
+        .collect();
+    values.extend([size, line_height, family]);
+    Some(values)
+}
+
+// the color, image, repeat, attachment, position, size, origin and clip of a
+// background layer; only the final layer has a color
+fn background_layer(values: &[Value], final_layer: bool) -> Option<Vec<Option<Value>>> {
+    let is_repeat = |value: &Value| keyword_in(value, &["repeat", "space", "round", "no-repeat"]);
+    let is_position = |value: &Value| {
+        is_length_percentage(value)
+            || keyword_in(value, &["left", "right", "top", "bottom", "center"])
+    };
+    let is_size = |value: &Value| {
+        is_length_percentage(value) && is_non_negative(value)
+            || keyword_in(value, &["auto", "cover", "contain"])
+    };
+    let is_box = |value: &Value| keyword_in(value, &["border-box", "padding-box", "content-box"]);
+
+    let mut layer: Vec<Option<Value>> = vec![None; 8];
+    let mut index = 0;
+    // the longest run of components accepted by `accepts`, up to `max`
+    let run = |index: usize, max: usize, accepts: &dyn Fn(&Value) -> bool| {
+        values[index..]
+            .iter()
+            .take(max)
+            .take_while(|value| accepts(value))
+            .cloned()
+            .collect::<Vec<Value>>()
+    };
+    while let Some(value) = values.get(index) {
+        let (slot, components) = if final_layer && layer[0].is_none() && is_color(value) {
+            (0, vec![value.clone()])
+        } else if layer[1].is_none() && (is_image(value) || keyword_in(value, &["none"])) {
+            (1, vec![value.clone()])
+        } else if layer[2].is_none() && keyword_in(value, &["repeat-x", "repeat-y"]) {
+            (2, vec![value.clone()])
+        } else if layer[2].is_none() && is_repeat(value) {
+            (2, run(index, 2, &is_repeat))
+        } else if layer[3].is_none() && keyword_in(value, &["scroll", "fixed", "local"]) {
+            (3, vec![value.clone()])
+        } else if layer[4].is_none() && is_position(value) {
+            (4, run(index, 4, &is_position))
+        } else if layer[6].is_none() && is_box(value) {
+            (6, vec![value.clone()])
+        } else if layer[7].is_none() && is_box(value) {
+            (7, vec![value.clone()])
+        } else {
+            return None;
+        };
+        index += components.len();
+        layer[slot] = Some(list_or_single(components));
+
+        // the size follows the position, after a slash
+        if slot == 4 && values.get(index).is_some_and(is_slash) {
+            let size = run(index + 1, 2, &is_size);
+            if size.is_empty() {
+                return None;
+            }
+            index += 1 + size.len();
+            layer[5] = Some(list_or_single(size));
+        }
+    }
+    // a single box keyword sets both the origin and the clip
+    if layer[7].is_none() {
+        layer[7] = layer[6].clone();
+    }
+    Some(layer)
+}
+
+fn expand_background(value: &Value) -> Option<Vec<Value>> {
+    let layers = match value {
+        Value::CommaList(layers) => layers.clone(),
+        value => vec![value.clone()],
+    };
+    let layers = layers
+        .iter()
+        .enumerate()
+        .map(|(index, layer)| background_layer(&components(layer)?, index == layers.len() - 1))
+        .collect::<Option<Vec<Vec<Option<Value>>>>>()?;
+
+    let initial_values = [
+        keyword("transparent"),
+        keyword("none"),
+        keyword("repeat"),
+        keyword("scroll"),
+        Value::List(vec![Value::Percentage(0.0), Value::Percentage(0.0)]),
+        keyword("auto"),
+        keyword("padding-box"),
+        keyword("border-box"),
+    ];
+    let mut values = Vec::new();
+    for (slot, initial_value) in initial_values.into_iter().enumerate() {
+        let mut slot_values: Vec<Value> = layers
+            .iter()
+            .map(|layer| layer[slot].clone().unwrap_or_else(|| initial_value.clone()))
+            .collect();
+        values.push(match (slot, slot_values.len()) {
+            (0, _) | (_, 1) => slot_values.pop().unwrap(),
+            _ => Value::CommaList(slot_values),
+        });
+    }
+    Some(values)
+}
+
+// the longhand declarations of a shorthand, `None` if the value doesn't match
+// the grammar of the shorthand
+pub fn expand(name: &str, value: &Value) -> Option<Vec<(String, Value)>> {
+    let values = match name {
+        "margin" | "inset" => expand_sides(value, is_margin)?,
+        "padding" => expand_sides(value, is_padding)?,
+        "border-width" => expand_sides(value, is_line_width)?,
+        "border-style" => expand_sides(value, is_line_style)?,
+        "border-color" => expand_sides(value, is_color)?,
+        "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            expand_line(value, is_line_style)?
+        }
+        "border" => {
+            // the same width, style and color for the four sides
+            let line = expand_line(value, is_line_style)?;
+            line.into_iter().flat_map(|value| vec![value; 4]).collect()
+        }
+        "outline" => expand_line(value, is_outline_style)?,
+        "list-style" => expand_list_style(value)?,
+        "flex" => expand_flex(value)?,
+        "gap" => expand_gap(value)?,
+        "grid-row" | "grid-column" => expand_grid_lines(value, 2)?,
+        "grid-area" => expand_grid_lines(value, 4)?,
+        "font" => expand_font(value)?,
+        "background" => expand_background(value)?,
+        _ => return None,
+    };
+    Some(assign(name, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::{CSSParser, Color, Unit};
+    use std::collections::HashMap;
+
+    fn expanded(css: &str) -> HashMap<String, Value> {
+        CSSParser::new(css.to_string())
+            .parse_declarations()
+            .into_iter()
+            .map(|declaration| (declaration.name, declaration.value))
+            .collect()
+    }
+
+    fn px(value: f32) -> Value {
+        Value::Length(value, Unit::Px)
+    }
+
+    #[test]
+    fn test_box_sides() {
+        let values = expanded("margin: 10px auto; padding: 1px 2px 3px; inset: 0");
+        assert_eq!(values["margin-top"], px(10.0));
+        assert_eq!(values["margin-right"], keyword("auto"));
+        assert_eq!(values["margin-bottom"], px(10.0));
+        assert_eq!(values["padding-left"], px(2.0));
+        assert_eq!(values["padding-bottom"], px(3.0));
+        assert_eq!(values["left"], Value::Number(0.0));
+
+        // invalid values drop the whole shorthand
+        assert!(expanded("padding: -1px; margin: 1px 2px 3px 4px 5px").is_empty());
+    }
+
+    #[test]
+    fn test_border() {
+        let values = expanded("border: solid 2px #ff0000; border-left: dashed");
+        assert_eq!(values["border-top-width"], px(2.0));
+        assert_eq!(values["border-right-style"], keyword("solid"));
+        assert_eq!(
+            values["border-bottom-color"],
+            Value::ColorValue(Color::rgb(255, 0, 0))
+        );
+        assert_eq!(values["border-left-style"], keyword("dashed"));
+        assert_eq!(values["border-left-width"], initial());
+
+        let values = expanded("border-width: thin 3px; outline: auto red 1px");
+        assert_eq!(values["border-top-width"], keyword("thin"));
+        assert_eq!(values["border-left-width"], px(3.0));
+        assert_eq!(values["outline-style"], keyword("auto"));
+        assert!(expanded("border: solid dashed").is_empty());
+    }
+
+    #[test]
+    fn test_flex_and_list_style() {
+        let flex = |css: &str| {
+            let values = expanded(&format!("flex: {}", css));
+            ["flex-grow", "flex-shrink", "flex-basis"].map(|name| values.get(name).cloned())
+        };
+        let number = |value: f32| Some(Value::Number(value));
+        assert_eq!(
+            flex("none"),
+            [number(0.0), number(0.0), Some(keyword("auto"))]
+        );
+        assert_eq!(flex("2"), [number(2.0), number(1.0), Some(px(0.0))]);
+        assert_eq!(flex("2 3"), [number(2.0), number(3.0), Some(px(0.0))]);
+        assert_eq!(
+            flex("10em"),
+            [
+                number(1.0),
+                number(1.0),
+                Some(Value::Length(10.0, Unit::Em))
+            ]
+        );
+        assert_eq!(
+            flex("30% 2 0"),
+            [number(2.0), number(0.0), Some(Value::Percentage(30.0))]
+        );
+        assert_eq!(flex("1 1 0"), [number(1.0), number(1.0), number(0.0)]);
+        assert_eq!(flex("1 2 3 4"), [None, None, None]);
+
+        let values = expanded("gap: 10px 5%");
+        assert_eq!(values["row-gap"], px(10.0));
+        assert_eq!(values["column-gap"], Value::Percentage(5.0));
+        let values = expanded("gap: normal");
+        assert_eq!(values["column-gap"], keyword("normal"));
+        assert!(expanded("gap: -1px").is_empty());
+    }
+
+    #[test]
+    fn test_grid_lines() {
+        let values = expanded("grid-row: 1 / span 2; grid-column: main");
+        assert_eq!(values["grid-row-start"], Value::Number(1.0));
+        assert_eq!(
+            values["grid-row-end"],
+            Value::List(vec![keyword("span"), Value::Number(2.0)])
+        );
+        assert_eq!(values["grid-column-start"], keyword("main"));
+        assert_eq!(values["grid-column-end"], keyword("main"));
+
+        let values = expanded("grid-area: header / 2");
+        assert_eq!(values["grid-row-start"], keyword("header"));
+        assert_eq!(values["grid-column-start"], Value::Number(2.0));
+        assert_eq!(values["grid-row-end"], keyword("header"));
+        assert_eq!(values["grid-column-end"], keyword("auto"));
+        assert!(expanded("grid-row: 1 / 2 / 3").is_empty());
+
+        let values = expanded("list-style: none");
+        assert_eq!(values["list-style-type"], keyword("none"));
+        assert_eq!(values["list-style-image"], keyword("none"));
+        let values = expanded("list-style: inside url(dot.png) square");
+        assert_eq!(values["list-style-type"], keyword("square"));
+        assert_eq!(values["list-style-position"], keyword("inside"));
+        assert_eq!(
+            values["list-style-image"],
+            Value::Url("dot.png".to_string())
+        );
+    }
+
+    #[test]
+    fn test_font() {
+        let values = expanded("font: italic bold 12px/1.5 \"Helvetica Neue\", Arial, sans-serif");
+        assert_eq!(values["font-style"], keyword("italic"));
+        assert_eq!(values["font-weight"], keyword("bold"));
+        assert_eq!(values["font-variant"], initial());
+        assert_eq!(values["font-size"], px(12.0));
+        assert_eq!(values["line-height"], Value::Number(1.5));
+        assert_eq!(
+            values["font-family"],
+            Value::CommaList(vec![
+                Value::String("Helvetica Neue".to_string()),
+                keyword("Arial"),
+                keyword("sans-serif"),
+            ])
+        );
+
+        let values = expanded("font: 700 2em Times New Roman");
+        assert_eq!(values["font-weight"], Value::Number(700.0));
+        assert_eq!(
+            values["font-family"],
+            Value::List(vec![keyword("Times"), keyword("New"), keyword("Roman")])
+        );
+        // the size and the family are required
+        assert!(expanded("font: bold 12px; font: Arial").is_empty());
+    }
+
+    #[test]
+    fn test_background() {
+        let values = expanded("background: url(\"a.png\") no-repeat center / cover #fff");
+        assert_eq!(values["background-image"], Value::Url("a.png".to_string()));
+        assert_eq!(values["background-repeat"], keyword("no-repeat"));
+        assert_eq!(values["background-position"], keyword("center"));
+        assert_eq!(values["background-size"], keyword("cover"));
+        assert_eq!(
+            values["background-color"],
+            Value::ColorValue(Color::rgb(255, 255, 255))
+        );
+        assert_eq!(values["background-attachment"], keyword("scroll"));
+
+        let values = expanded("background: url(a.png) left top, url(b.png) red");
+        assert_eq!(
+            values["background-image"],
+            Value::CommaList(vec![
+                Value::Url("a.png".to_string()),
+                Value::Url("b.png".to_string())
+            ])
+        );
+        assert_eq!(
+            values["background-color"],
+            Value::ColorValue(Color::rgb(255, 0, 0))
+        );
+        // only the final layer can have a color
+        assert!(expanded("background: red, url(a.png)").is_empty());
+    }
+
+    #[test]
+    fn test_css_wide_keywords_and_variables() {
+        let values = expanded("margin: inherit; padding: var(--gap) 0");
+        assert_eq!(values["margin-left"], keyword("inherit"));
+        assert!(matches!(
+            &values["padding-top"],
+            Value::PendingShorthand(name, _) if name == "padding"
+        ));
+    }
+}
@@ -0,0 +1,1349 @@
+use crate::assign::PropertyMap;
+use crate::calc::{finite, CalcNode};
+use crate::css::{Color, LengthContext, TrackListItem, TrackSize, Unit, Value};
+use crate::properties::initial_value;
+
+const MEDIUM_FONT_SIZE: f32 = 16.0;
+
+// an enum of css keywords
+macro_rules! keyword_enum {
+    ($name:ident { $($variant:ident = $keyword:literal),+ $(,)? }) => {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub enum $name {
+            $($variant),+
+        }
+
+        impl $name {
+            pub fn from_keyword(keyword: &str) -> Option<$name> {
+                match keyword {
+                    $($keyword => Some($name::$variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+keyword_enum!(Display {
+    None = "none",
+    Contents = "contents",
+    Block = "block",
+    Inline = "inline",
+    InlineBlock = "inline-block",
+    FlowRoot = "flow-root",
+    ListItem = "list-item",
+    Flex = "flex",
+    InlineFlex = "inline-flex",
+    Grid = "grid",
+    InlineGrid = "inline-grid",
+    Table = "table",
+    InlineTable = "inline-table",
+    TableRowGroup = "table-row-group",
+    TableHeaderGroup = "table-header-group",
+    TableFooterGroup = "table-footer-group",
+    TableRow = "table-row",
+    TableCell = "table-cell",
+    TableColumnGroup = "table-column-group",
+    TableColumn = "table-column",
+    TableCaption = "table-caption",
+});
+
+keyword_enum!(Position {
+    Static = "static",
+    Relative = "relative",
+    Absolute = "absolute",
+    Fixed = "fixed",
+    Sticky = "sticky",
+});
+
+keyword_enum!(Float {
+    None = "none",
+    Left = "left",
+    Right = "right",
+    InlineStart = "inline-start",
+    InlineEnd = "inline-end",
+});
+
+keyword_enum!(Clear {
+    None = "none",
+    Left = "left",
+    Right = "right",
+    Both = "both",
+    InlineStart = "inline-start",
+    InlineEnd = "inline-end",
+});
+
+keyword_enum!(Visibility {
+    Visible = "visible",
+    Hidden = "hidden",
+    Collapse = "collapse",
+});
+
+keyword_enum!(Overflow {
+    Visible = "visible",
+    Hidden = "hidden",
+    Clip = "clip",
+    Scroll = "scroll",
+    Auto = "auto",
+});
+
+keyword_enum!(BoxSizing {
+    ContentBox = "content-box",
+    BorderBox = "border-box",
+});
+
+keyword_enum!(BorderStyle {
+    None = "none",
+    Hidden = "hidden",
+    Dotted = "dotted",
+    Dashed = "dashed",
+    Solid = "solid",
+    Double = "double",
+    Groove = "groove",
+    Ridge = "ridge",
+    Inset = "inset",
+    Outset = "outset",
+    // only for outlines
+    Auto = "auto",
+});
+
+keyword_enum!(FontStyle {
+    Normal = "normal",
+    Italic = "italic",
+    Oblique = "oblique",
+});
+
+keyword_enum!(FontVariant {
+    Normal = "normal",
+    SmallCaps = "small-caps",
+});
+
+keyword_enum!(TextAlign {
+    Start = "start",
+    End = "end",
+    Left = "left",
+    Right = "right",
+    Center = "center",
+    Justify = "justify",
+});
+
+keyword_enum!(TextTransform {
+    None = "none",
+    Capitalize = "capitalize",
+    Uppercase = "uppercase",
+    Lowercase = "lowercase",
+});
+
+keyword_enum!(WhiteSpace {
+    Normal = "normal",
+    Pre = "pre",
+    Nowrap = "nowrap",
+    PreWrap = "pre-wrap",
+    PreLine = "pre-line",
+    BreakSpaces = "break-spaces",
+});
+
+keyword_enum!(Direction {
+    Ltr = "ltr",
+    Rtl = "rtl",
+});
+
+keyword_enum!(ListStylePosition {
+    Inside = "inside",
+    Outside = "outside",
+});
+
+keyword_enum!(FlexDirection {
+    Row = "row",
+    RowReverse = "row-reverse",
+    Column = "column",
+    ColumnReverse = "column-reverse",
+});
+
+keyword_enum!(FlexWrap {
+    Nowrap = "nowrap",
+    Wrap = "wrap",
+    WrapReverse = "wrap-reverse",
+});
+
+keyword_enum!(Alignment {
+    Auto = "auto",
+    Normal = "normal",
+    Stretch = "stretch",
+    Start = "start",
+    End = "end",
+    Center = "center",
+    FlexStart = "flex-start",
+    FlexEnd = "flex-end",
+    SelfStart = "self-start",
+    SelfEnd = "self-end",
+    Baseline = "baseline",
+    Left = "left",
+    Right = "right",
+    SpaceBetween = "space-between",
+    SpaceAround = "space-around",
+    SpaceEvenly = "space-evenly",
+});
+
+keyword_enum!(GridAutoFlow {
+    Row = "row",
+    Column = "column",
+});
+
+keyword_enum!(BorderCollapse {
+    Separate = "separate",
+    Collapse = "collapse",
+});
+
+keyword_enum!(TableLayout {
+    Auto = "auto",
+    Fixed = "fixed",
+});
+
+keyword_enum!(CaptionSide {
+    Top = "top",
+    Bottom = "bottom",
+});
+
+keyword_enum!(EmptyCells {
+    Show = "show",
+    Hide = "hide",
+});
+
+// a length in px, a percentage, or a math function mixing both
+#[derive(Debug, Clone, PartialEq)]
+pub enum LengthPercentage {
+    Length(f32),
+    Percentage(f32),
+    Calc(CalcNode),
+}
+
+impl LengthPercentage {
+    // the length in px, percentages resolving against `basis`
+    pub fn resolve(&self, basis: f32) -> f32 {
+        match self {
+            LengthPercentage::Length(length) => *length,
+            LengthPercentage::Percentage(percentage) => percentage / 100.0 * basis,
+            LengthPercentage::Calc(node) => node.resolve(&LengthContext {
+                percentage_basis: basis,
+                ..LengthContext::default()
+            }),
+        }
+    }
+}
+
+// the value of sizes, margins, insets and `flex-basis`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Size {
+    Auto,
+    None,
+    MinContent,
+    MaxContent,
+    FitContent,
+    Content,
+    LengthPercentage(LengthPercentage),
+}
+
+impl Size {
+    // the length in px, `None` for keywords and percentages of an
+    // indefinite basis
+    pub fn resolve(&self, basis: Option<f32>) -> Option<f32> {
+        match self {
+            Size::LengthPercentage(LengthPercentage::Length(length)) => Some(*length),
+            Size::LengthPercentage(length) => basis.map(|basis| length.resolve(basis)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineHeight {
+    Normal,
+    Number(f32),
+    Length(f32),
+}
+
+impl LineHeight {
+    // the height of a line in px, `normal` being 1.2 times the font size
+    pub fn resolve(&self, font_size: f32) -> f32 {
+        match self {
+            LineHeight::Normal => font_size * 1.2,
+            LineHeight::Number(number) => number * font_size,
+            LineHeight::Length(length) => *length,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerticalAlign {
+    Baseline,
+    Sub,
+    Super,
+    TextTop,
+    TextBottom,
+    Middle,
+    Top,
+    Bottom,
+    LengthPercentage(LengthPercentage),
+}
+
+// the value of `grid-row-start` and its siblings
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridLine {
+    Auto,
+    // a line number, counting the lines of the name if any
+    Line(i32, Option<String>),
+    Span(u32, Option<String>),
+    // a named area or line
+    Name(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListStyleType {
+    None,
+    // a counter style, like `disc` or `decimal`
+    Named(String),
+    String(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edges<T> {
+    pub top: T,
+    pub right: T,
+    pub bottom: T,
+    pub left: T,
+}
+
+// the computed values of a node; colors are `None` when transparent
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedStyle {
+    pub display: Display,
+    pub position: Position,
+    pub inset: Edges<Size>,
+    pub z_index: Option<i32>,
+    pub float: Float,
+    pub clear: Clear,
+    pub visibility: Visibility,
+    pub overflow_x: Overflow,
+    pub overflow_y: Overflow,
+    pub opacity: f32,
+
+    pub width: Size,
+    pub height: Size,
+    pub min_width: Size,
+    pub min_height: Size,
+    pub max_width: Size,
+    pub max_height: Size,
+    pub box_sizing: BoxSizing,
+    pub margin: Edges<Size>,
+    pub padding: Edges<LengthPercentage>,
+    pub border_width: Edges<f32>,
+    pub border_style: Edges<BorderStyle>,
+    pub border_color: Edges<Option<Color>>,
+    pub outline_width: f32,
+    pub outline_style: BorderStyle,
+    pub outline_color: Option<Color>,
+
+    pub color: Color,
+    pub background_color: Option<Color>,
+    // the url of the image of each layer
+    pub background_image: Vec<Option<String>>,
+
+    pub font_family: Vec<String>,
+    pub font_size: f32,
+    pub font_style: FontStyle,
+    pub font_weight: f32,
+    pub font_variant: FontVariant,
+    // in percent of the normal width
+    pub font_stretch: f32,
+    pub line_height: LineHeight,
+    pub text_align: TextAlign,
+    pub text_indent: LengthPercentage,
+    pub text_transform: TextTransform,
+    pub text_decoration: TextDecoration,
+    pub letter_spacing: f32,
+    pub word_spacing: LengthPercentage,
+    pub white_space: WhiteSpace,
+    pub vertical_align: VerticalAlign,
+    pub direction: Direction,
+
+    pub list_style_type: ListStyleType,
+    pub list_style_position: ListStylePosition,
+    pub list_style_image: Option<String>,
+
+    pub flex_direction: FlexDirection,
+    pub flex_wrap: FlexWrap,
+    pub flex_grow: f32,
+    pub flex_shrink: f32,
+    pub flex_basis: Size,
+    pub order: i32,
+    pub justify_content: Alignment,
+    pub align_items: Alignment,
+    pub align_content: Alignment,
+    pub align_self: Alignment,
+    // `None` for `normal`
+    pub row_gap: Option<LengthPercentage>,
+    pub column_gap: Option<LengthPercentage>,
+
+    // empty for `none`
+    pub grid_template_columns: Vec<TrackListItem>,
+    pub grid_template_rows: Vec<TrackListItem>,
+    // the rows of `grid-template-areas`, empty for `none`
+    pub grid_template_areas: Vec<String>,
+    pub grid_auto_columns: Vec<TrackSize>,
+    pub grid_auto_rows: Vec<TrackSize>,
+    pub grid_auto_flow: GridAutoFlow,
+    pub grid_auto_flow_dense: bool,
+    pub grid_row_start: GridLine,
+    pub grid_row_end: GridLine,
+    pub grid_column_start: GridLine,
+    pub grid_column_end: GridLine,
+    pub justify_items: Alignment,
+    pub justify_self: Alignment,
+
+    pub border_collapse: BorderCollapse,
+    pub border_spacing: (f32, f32),
+    pub table_layout: TableLayout,
+    pub caption_side: CaptionSide,
+    pub empty_cells: EmptyCells,
+}
+
+fn font_size_keyword(keyword: &str) -> Option<f32> {
+    let factor = match keyword {
+        "xx-small" => 3.0 / 5.0,
+        "x-small" => 3.0 / 4.0,
+        "small" => 8.0 / 9.0,
+        "medium" => 1.0,
+        "large" => 6.0 / 5.0,
+        "x-large" => 3.0 / 2.0,
+        "xx-large" => 2.0,
+        "xxx-large" => 3.0,
+        _ => return None,
+    };
+    Some(MEDIUM_FONT_SIZE * factor)
+}
+
+fn font_weight(value: &Value, parent_weight: f32) -> f32 {
+    match value {
+        Value::Number(weight) => *weight,
+        Value::Keyword(keyword) => match keyword.to_lowercase().as_str() {
+            "bold" => 700.0,
+            "bolder" if parent_weight < 350.0 => 400.0,
+            "bolder" if parent_weight < 550.0 => 700.0,
+            "bolder" => parent_weight.max(900.0),
+            "lighter" if parent_weight < 100.0 => parent_weight,
+            "lighter" if parent_weight < 550.0 => 100.0,
+            "lighter" if parent_weight < 750.0 => 400.0,
+            "lighter" => 700.0,
+            _ => 400.0,
+        },
+        _ => 400.0,
+    }
+}
+
+fn font_stretch_keyword(keyword: &str) -> Option<f32> {
+    let percentage = match keyword {
+        "ultra-condensed" => 50.0,
+        "extra-condensed" => 62.5,
+        "condensed" => 75.0,
+        "semi-condensed" => 87.5,
+        "normal" => 100.0,
+        "semi-expanded" => 112.5,
+        "expanded" => 125.0,
+        "extra-expanded" => 150.0,
+        "ultra-expanded" => 200.0,
+        _ => return None,
+    };
+    Some(percentage)
+}
+
+fn is_keyword(value: Option<&Value>, expected: &str) -> bool {
+    matches!(value, Some(Value::Keyword(keyword)) if keyword.eq_ignore_ascii_case(expected))
+}
+
+// the value with its lengths in px and `currentcolor` replaced by `color`;
+// the infinite and NaN results of math functions are clamped
+fn absolute_value(value: &Value, context: &LengthContext, color: &Value) -> Value {
+    match value {
+        Value::Length(length, unit) => {
+            Value::Length(finite(unit.to_px(*length, context)), Unit::Px)
+        }
+        Value::Percentage(percentage) => Value::Percentage(finite(*percentage)),
+        Value::Number(number) => Value::Number(finite(*number)),
+        Value::Calc(node) => match node.to_absolute(context) {
+            CalcNode::Dimension(length, _) => Value::Length(finite(length), Unit::Px),
+            CalcNode::Percentage(percentage) => Value::Percentage(finite(percentage)),
+            CalcNode::Number(number) => Value::Number(finite(number)),
+            node => Value::Calc(node),
+        },
+        Value::Keyword(keyword) if keyword.eq_ignore_ascii_case("currentcolor") => color.clone(),
+        Value::List(values) => Value::List(
+            values
+                .iter()
+                .map(|value| absolute_value(value, context, color))
+                .collect(),
+        ),
+        Value::CommaList(values) => Value::CommaList(
+            values
+                .iter()
+                .map(|value| absolute_value(value, context, color))
+                .collect(),
+        ),
+        Value::TrackList(items) => Value::TrackList(
+            items
+                .iter()
+                .map(|item| item.map_lengths(&|value| absolute_value(value, context, color)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+// turn the specified values of a node into computed values, given the computed
+// values of its parent: lengths are converted to px, `em` being relative to the
+// parent font size in `font-size`, and colors and keywords are resolved
+pub fn compute_values(values: &mut PropertyMap, parent: &PropertyMap, context: &LengthContext) {
+    let parent_font_size = match parent.get("font-size") {
+        Some(Value::Length(size, Unit::Px)) => *size,
+        _ => MEDIUM_FONT_SIZE,
+    };
+    let parent_context = LengthContext {
+        percentage_basis: parent_font_size,
+        font_size: parent_font_size,
+        ..*context
+    };
+    let font_size = match values.get("font-size") {
+        Some(Value::Keyword(keyword)) => match keyword.to_lowercase().as_str() {
+            "larger" => parent_font_size * 1.2,
+            "smaller" => parent_font_size / 1.2,
+            keyword => font_size_keyword(keyword).unwrap_or(parent_font_size),
+        },
+        Some(value) => value.to_px(&parent_context).unwrap_or(parent_font_size),
+        None => parent_font_size,
+    };
+    values.insert("font-size".to_string(), Value::Length(font_size, Unit::Px));
+    let context = LengthContext {
+        font_size,
+        ..*context
+    };
+
+    // `currentcolor` in `color` itself is the color of the parent
+    if is_keyword(values.get("color"), "currentcolor") {
+        let black = Value::ColorValue(Color::rgb(0, 0, 0));
+        let parent_color = parent.get("color").cloned().unwrap_or(black);
+        values.insert("color".to_string(), parent_color);
+    }
+    let color = values
+        .get("color")
+        .cloned()
+        .unwrap_or(Value::ColorValue(Color::rgb(0, 0, 0)));
+    for value in values.values_mut() {
+        *value = absolute_value(value, &context, &color);
+    }
+
+    // percentages of `line-height` are relative to the font size, numbers are
+    // inherited as is
+    if let Some(Value::Percentage(percentage)) = values.get("line-height") {
+        let height = percentage / 100.0 * font_size;
+        values.insert("line-height".to_string(), Value::Length(height, Unit::Px));
+    }
+    if let Some(value) = values.get("font-weight") {
+        let parent_weight = match parent.get("font-weight") {
+            Some(Value::Number(weight)) => *weight,
+            _ => 400.0,
+        };
+        let weight = font_weight(value, parent_weight);
+        values.insert("font-weight".to_string(), Value::Number(weight));
+    }
+    if let Some(Value::Keyword(keyword)) = values.get("font-stretch") {
+        let stretch = font_stretch_keyword(&keyword.to_lowercase()).unwrap_or(100.0);
+        values.insert("font-stretch".to_string(), Value::Percentage(stretch));
+    }
+
+    // border and outline widths are 0 without a style
+    let lines = ["top", "right", "bottom", "left"].map(|side| {
+        (
+            format!("border-{}-width", side),
+            format!("border-{}-style", side),
+        )
+    });
+    let lines = lines
+        .into_iter()
+        .chain([("outline-width".to_string(), "outline-style".to_string())]);
+    for (width_name, style_name) in lines {
+        let width = match values.get(&width_name) {
+            _ if is_keyword(values.get(&style_name), "none")
+                || is_keyword(values.get(&style_name), "hidden") =>
+            {
+                0.0
+            }
+            Some(Value::Keyword(keyword)) => match keyword.to_lowercase().as_str() {
+                "thin" => 1.0,
+                "thick" => 5.0,
+                _ => 3.0,
+            },
+            Some(value) => value.to_px(&context).unwrap_or(0.0),
+            None => 0.0,
+        };
+        values.insert(width_name, Value::Length(width, Unit::Px));
+    }
+}
+
+fn keyword_of(value: &Value) -> Option<String> {
+    match value {
+        Value::Keyword(keyword) => Some(keyword.to_lowercase()),
+        _ => None,
+    }
+}
+
+fn length_percentage(value: &Value) -> Option<LengthPercentage> {
+    match value {
+        Value::Length(length, unit) => Some(LengthPercentage::Length(
+            unit.to_px(*length, &LengthContext::default()),
+        )),
+        Value::Percentage(percentage) => Some(LengthPercentage::Percentage(*percentage)),
+        Value::Number(number) if *number == 0.0 => Some(LengthPercentage::Length(0.0)),
+        Value::Calc(node) => Some(LengthPercentage::Calc(node.clone())),
+        _ => None,
+    }
+}
+
+fn size(value: &Value) -> Option<Size> {
+    let size = match keyword_of(value).as_deref() {
+        Some("auto") => Size::Auto,
+        Some("none") => Size::None,
+        Some("min-content") => Size::MinContent,
+        Some("max-content") => Size::MaxContent,
+        Some("fit-content") => Size::FitContent,
+        Some("content") => Size::Content,
+        _ => Size::LengthPercentage(length_percentage(value)?),
+    };
+    Some(size)
+}
+
+fn number(value: &Value) -> Option<f32> {
+    match value {
+        Value::Number(number) => Some(*number),
+        Value::Percentage(percentage) => Some(percentage / 100.0),
+        _ => None,
+    }
+}
+
+fn length(value: &Value) -> Option<f32> {
+    value.to_px(&LengthContext::default())
+}
+
+fn color(value: &Value) -> Option<Color> {
+    match value {
+        Value::ColorValue(color) => Some(color.clone()),
+        _ => None,
+    }
+}
+
+fn url(value: &Value) -> Option<String> {
+    match value {
+        Value::Url(url) => Some(url.clone()),
+        _ => None,
+    }
+}
+
+fn display(value: &Value) -> Option<Display> {
+    let keywords: Vec<String> = match value {
+        Value::List(values) => values.iter().filter_map(keyword_of).collect(),
+        value => vec![keyword_of(value)?],
+    };
+    if let [keyword] = keywords.as_slice() {
+        return match keyword.as_str() {
+            "flow" => Some(Display::Block),
+            "run-in" => Some(Display::Inline),
+            keyword => Display::from_keyword(keyword),
+        };
+    }
+
+    // the multi-keyword syntax
+    let has = |keyword: &str| keywords.iter().any(|other| other == keyword);
+    let inline = has("inline");
+    let display = if has("list-item") {
+        Display::ListItem
+    } else if has("flow-root") {
+        match inline {
+            true => Display::InlineBlock,
+            false => Display::FlowRoot,
+        }
+    } else if has("flex") {
+        match inline {
+            true => Display::InlineFlex,
+            false => Display::Flex,
+        }
+    } else if has("grid") {
+        match inline {
+            true => Display::InlineGrid,
+            false => Display::Grid,
+        }
+    } else if has("table") {
+        match inline {
+            true => Display::InlineTable,
+            false => Display::Table,
+        }
+    } else {
+        match inline {
+            true => Display::Inline,
+            false => Display::Block,
+        }
+    };
+    Some(display)
+}
+
+fn font_family(value: &Value) -> Option<Vec<String>> {
+    let family = |value: &Value| match value {
+        Value::String(name) => Some(name.clone()),
+        Value::Keyword(name) => Some(name.clone()),
+        Value::List(words) => words
+            .iter()
+            .map(|word| match word {
+                Value::Keyword(word) => Some(word.clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<String>>>()
+            .map(|words| words.join(" ")),
+        _ => None,
+    };
+    match value {
+        Value::CommaList(families) => families.iter().map(family).collect(),
+        value => Some(vec![family(value)?]),
+    }
+}
+
+fn line_height(value: &Value) -> Option<LineHeight> {
+    match value {
+        Value::Number(number) => Some(LineHeight::Number(*number)),
+        Value::Keyword(_) => Some(LineHeight::Normal),
+        value => length(value).map(LineHeight::Length),
+    }
+}
+
+fn vertical_align(value: &Value) -> Option<VerticalAlign> {
+    let vertical_align = match keyword_of(value).as_deref() {
+        Some("baseline") => VerticalAlign::Baseline,
+        Some("sub") => VerticalAlign::Sub,
+        Some("super") => VerticalAlign::Super,
+        Some("text-top") => VerticalAlign::TextTop,
+        Some("text-bottom") => VerticalAlign::TextBottom,
+        Some("middle") => VerticalAlign::Middle,
+        Some("top") => VerticalAlign::Top,
+        Some("bottom") => VerticalAlign::Bottom,
+        _ => VerticalAlign::LengthPercentage(length_percentage(value)?),
+    };
+    Some(vertical_align)
+}
+
+fn text_decoration(value: &Value) -> Option<TextDecoration> {
+    let keywords: Vec<String> = match value {
+        Value::List(values) => values.iter().filter_map(keyword_of).collect(),
+        value => vec![keyword_of(value)?],
+    };
+    let has = |keyword: &str| keywords.iter().any(|other| other == keyword);
+    Some(TextDecoration {
+        underline: has("underline"),
+        overline: has("overline"),
+        line_through: has("line-through"),
+    })
+}
+
+fn list_style_type(value: &Value) -> Option<ListStyleType> {
+    match value {
+        Value::String(text) => Some(ListStyleType::String(text.clone())),
+        Value::Keyword(keyword) if keyword.eq_ignore_ascii_case("none") => {
+            Some(ListStyleType::None)
+        }
+        Value::Keyword(name) => Some(ListStyleType::Named(name.to_lowercase())),
+        _ => None,
+    }
+}
+
+fn track_list(value: &Value) -> Option<Vec<TrackListItem>> {
+    match value {
+        Value::TrackList(items) => Some(items.clone()),
+        Value::Keyword(keyword) if keyword.eq_ignore_ascii_case("none") => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+fn track_sizes(value: &Value) -> Option<Vec<TrackSize>> {
+    match value {
+        Value::TrackList(items) => items
+            .iter()
+            .map(|item| match item {
+                TrackListItem::Track(size) => Some(size.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+fn grid_template_areas(value: &Value) -> Option<Vec<String>> {
+    let values = match value {
+        Value::List(values) => values.as_slice(),
+        value => std::slice::from_ref(value),
+    };
+    values
+        .iter()
+        .filter_map(|value| match value {
+            Value::String(row) => Some(Some(row.clone())),
+            Value::Keyword(keyword) if keyword.eq_ignore_ascii_case("none") => None,
+            _ => Some(None),
+        })
+        .collect()
+}
+
+fn grid_line(value: &Value) -> Option<GridLine> {
+    let values = match value {
+        Value::List(values) => values.as_slice(),
+        value => std::slice::from_ref(value),
+    };
+    let (mut span, mut number, mut name) = (false, None, None);
+    for value in values {
+        match value {
+            Value::Number(value) if number.is_none() && value.fract() == 0.0 => {
+                number = Some(*value as i32)
+            }
+            Value::Keyword(keyword) if keyword.eq_ignore_ascii_case("span") && !span => span = true,
+            Value::Keyword(keyword) if keyword.eq_ignore_ascii_case("auto") => {
+                return Some(GridLine::Auto).filter(|_| values.len() == 1)
+            }
+            Value::Keyword(keyword) if name.is_none() => name = Some(keyword.clone()),
+            _ => return None,
+        }
+    }
+    match (span, number, name) {
+        (true, Some(count), _) if count <= 0 => None,
+        (true, count, name) => Some(GridLine::Span(count.unwrap_or(1) as u32, name)),
+        (false, Some(0), _) => None,
+        (false, Some(line), name) => Some(GridLine::Line(line, name)),
+        (false, None, Some(name)) => Some(GridLine::Name(name)),
+        (false, None, None) => None,
+    }
+}
+
+fn typed<T>(values: &PropertyMap, name: &str, parse: impl Fn(&Value) -> Option<T>) -> T {
+    values
+        .get(name)
+        .and_then(&parse)
+        .or_else(|| initial_value(name).and_then(&parse))
+        .unwrap_or_else(|| panic!("No value for {}", name))
+}
+
+fn comma_list<T>(value: &Value, parse: impl Fn(&Value) -> T) -> Vec<T> {
+    match value {
+        Value::CommaList(values) => values.iter().map(parse).collect(),
+        value => vec![parse(value)],
+    }
+}
+
+impl ComputedStyle {
+    // the typed computed style of computed values, see `compute_values`;
+    // missing or unexpected values fall back to the initial value
+    pub fn from_values(values: &PropertyMap) -> ComputedStyle {
+        let get = |name: &str| values.get(name);
+        let keyword = |name: &str| get(name).and_then(keyword_of);
+        macro_rules! value {
+            ($name:literal, $parse:expr) => {
+                typed(values, $name, $parse)
+            };
+            ($name:literal as $enum:ident) => {
+                typed(values, $name, |value| {
+                    keyword_of(value).and_then(|keyword| $enum::from_keyword(&keyword))
+                })
+            };
+        }
+        let edges = |property: &str| {
+            ["top", "right", "bottom", "left"].map(|side| property.replace("{}", side))
+        };
+        let [top, right, bottom, left] =
+            edges("border-{}-width").map(|name| get(&name).and_then(length).unwrap_or(0.0));
+        let border_width = Edges {
+            top,
+            right,
+            bottom,
+            left,
+        };
+        let [top, right, bottom, left] = edges("border-{}-style").map(|name| {
+            keyword(&name)
+                .and_then(|keyword| BorderStyle::from_keyword(&keyword))
+                .unwrap_or(BorderStyle::None)
+        });
+        let border_style = Edges {
+            top,
+            right,
+            bottom,
+            left,
+        };
+        let [top, right, bottom, left] =
+            edges("border-{}-color").map(|name| get(&name).and_then(color));
+        let border_color = Edges {
+            top,
+            right,
+            bottom,
+            left,
+        };
+        let sizes = |property: &str| {
+            edges(property).map(|name| {
+                get(&name)
+                    .and_then(size)
+                    .unwrap_or(Size::LengthPercentage(LengthPercentage::Length(0.0)))
+            })
+        };
+        let [top, right, bottom, left] = sizes("margin-{}");
+        let margin = Edges {
+            top,
+            right,
+            bottom,
+            left,
+        };
+        let [top, right, bottom, left] = sizes("{}");
+        let inset = Edges {
+            top,
+            right,
+            bottom,
+            left,
+        };
+        let [top, right, bottom, left] = edges("padding-{}").map(|name| {
+            get(&name)
+                .and_then(length_percentage)
+                .unwrap_or(LengthPercentage::Length(0.0))
+        });
+        let padding = Edges {
+            top,
+            right,
+            bottom,
+            left,
+        };
+
+        let overflow = match get("overflow") {
+            Some(Value::List(values)) => values.clone(),
+            Some(value) => vec![value.clone(), value.clone()],
+            None => Vec::new(),
+        };
+        let overflow = |index: usize| {
+            overflow
+                .get(index)
+                .and_then(keyword_of)
+                .and_then(|keyword| Overflow::from_keyword(&keyword))
+                .unwrap_or(Overflow::Visible)
+        };
+        let border_spacing = match get("border-spacing") {
+            Some(Value::List(values)) if values.len() == 2 => (
+                length(&values[0]).unwrap_or(0.0),
+                length(&values[1]).unwrap_or(0.0),
+            ),
+            Some(value) => {
+                let spacing = length(value).unwrap_or(0.0);
+                (spacing, spacing)
+            }
+            None => (0.0, 0.0),
+        };
+        let gap = |name: &str| get(name).and_then(length_percentage);
+        let grid_auto_flow = match get("grid-auto-flow") {
+            Some(Value::List(values)) => values.clone(),
+            Some(value) => vec![value.clone()],
+            None => Vec::new(),
+        };
+        let grid_auto_flow_dense = grid_auto_flow
+            .iter()
+            .any(|value| keyword_of(value).as_deref() == Some("dense"));
+        let grid_auto_flow = grid_auto_flow
+            .iter()
+            .filter_map(keyword_of)
+            .find_map(|keyword| GridAutoFlow::from_keyword(&keyword))
+            .unwrap_or(GridAutoFlow::Row);
+
+        ComputedStyle {
+            display: value!("display", display),
+            position: value!("position" as Position),
+            inset,
+            z_index: get("z-index").and_then(number).map(|index| index as i32),
+            float: value!("float" as Float),
+            clear: value!("clear" as Clear),
+            visibility: value!("visibility" as Visibility),
+            overflow_x: overflow(0),
+            overflow_y: overflow(1),
+            opacity: value!("opacity", number).clamp(0.0, 1.0),
+
+            width: value!("width", size),
+            height: value!("height", size),
+            min_width: value!("min-width", size),
+            min_height: value!("min-height", size),
+            max_width: value!("max-width", size),
+            max_height: value!("max-height", size),
+            box_sizing: value!("box-sizing" as BoxSizing),
+            margin,
+            padding,
+            border_width,
+            border_style,
+            border_color,
+            outline_width: get("outline-width").and_then(length).unwrap_or(0.0),
+            outline_style: value!("outline-style" as BorderStyle),
+            outline_color: get("outline-color").and_then(color),
+
+            color: get("color").and_then(color).unwrap_or(Color::rgb(0, 0, 0)),
+            background_color: get("background-color").and_then(color),
+            background_image: get("background-image")
+                .map(|value| comma_list(value, url))
+                .unwrap_or_default(),
+
+            font_family: value!("font-family", font_family),
+            font_size: get("font-size")
+                .and_then(length)
+                .unwrap_or(MEDIUM_FONT_SIZE),
+            font_style: value!("font-style" as FontStyle),
+            font_weight: get("font-weight").and_then(number).unwrap_or(400.0),
+            font_variant: value!("font-variant" as FontVariant),
+            font_stretch: match get("font-stretch") {
+                Some(Value::Percentage(stretch)) => *stretch,
+                _ => 100.0,
+            },
+            line_height: value!("line-height", line_height),
+            text_align: value!("text-align" as TextAlign),
+            text_indent: value!("text-indent", length_percentage),
+            text_transform: value!("text-transform" as TextTransform),
+            text_decoration: value!("text-decoration-line", text_decoration),
+            letter_spacing: get("letter-spacing").and_then(length).unwrap_or(0.0),
+            word_spacing: get("word-spacing")
+                .and_then(length_percentage)
+                .unwrap_or(LengthPercentage::Length(0.0)),
+            white_space: value!("white-space" as WhiteSpace),
+            vertical_align: value!("vertical-align", vertical_align),
+            direction: value!("direction" as Direction),
+
+            list_style_type: value!("list-style-type", list_style_type),
+            list_style_position: value!("list-style-position" as ListStylePosition),
+            list_style_image: get("list-style-image").and_then(url),
+
+            flex_direction: value!("flex-direction" as FlexDirection),
+            flex_wrap: value!("flex-wrap" as FlexWrap),
+            flex_grow: value!("flex-grow", number),
+            flex_shrink: value!("flex-shrink", number),
+            flex_basis: value!("flex-basis", size),
+            order: value!("order", number) as i32,
+            justify_content: value!("justify-content" as Alignment),
+            align_items: value!("align-items" as Alignment),
+            align_content: value!("align-content" as Alignment),
+            align_self: value!("align-self" as Alignment),
+            row_gap: gap("row-gap"),
+            column_gap: gap("column-gap"),
+
+            grid_template_columns: value!("grid-template-columns", track_list),
+            grid_template_rows: value!("grid-template-rows", track_list),
+            grid_template_areas: value!("grid-template-areas", grid_template_areas),
+            grid_auto_columns: value!("grid-auto-columns", track_sizes),
+            grid_auto_rows: value!("grid-auto-rows", track_sizes),
+            grid_auto_flow,
+            grid_auto_flow_dense,
+            grid_row_start: value!("grid-row-start", grid_line),
+            grid_row_end: value!("grid-row-end", grid_line),
+            grid_column_start: value!("grid-column-start", grid_line),
+            grid_column_end: value!("grid-column-end", grid_line),
+            justify_items: value!("justify-items" as Alignment),
+            justify_self: value!("justify-self" as Alignment),
+
+            border_collapse: value!("border-collapse" as BorderCollapse),
+            border_spacing,
+            table_layout: value!("table-layout" as TableLayout),
+            caption_side: value!("caption-side" as CaptionSide),
+            empty_cells: value!("empty-cells" as EmptyCells),
+        }
+    }
+}
+
+impl Default for ComputedStyle {
+    fn default() -> Self {
+        ComputedStyle::from_values(&PropertyMap::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assign::{style_tree, StyledNode};
+    use crate::css::{CSSParser, RepeatCount, TrackBreadth};
+    use crate::html::HTMLParser;
+
+    fn styled(html: &str, css: &str, f: impl Fn(&StyledNode)) {
+        let document = HTMLParser::new(html.to_string()).parse_document();
+        let stylesheet = CSSParser::new(css.to_string()).parse_stylesheet();
+        f(&style_tree(document.root(), &stylesheet));
+    }
+
+    #[test]
+    fn test_relative_lengths() {
+        styled(
+            "<html><div><p><span>text</span></p></div></html>",
+            "html { font-size: 20px; }
+            div { font-size: 2em; margin-top: 1em; padding-left: 50%; line-height: 150%; }
+            p { font-size: larger; margin-top: 1rem; width: calc(100% - 1em); }
+            span { font-size: 50%; line-height: inherit; }",
+            |root| {
+                let div = &root.children[0];
+                assert_eq!(div.style.font_size, 40.0);
+                assert_eq!(
+                    div.style.margin.top,
+                    Size::LengthPercentage(LengthPercentage::Length(40.0))
+                );
+                assert_eq!(div.style.padding.left, LengthPercentage::Percentage(50.0));
+                assert_eq!(div.style.line_height, LineHeight::Length(60.0));
+
+                let paragraph = &div.children[0];
+                assert_eq!(paragraph.style.font_size, 48.0);
+                assert_eq!(
+                    paragraph.style.margin.top,
+                    Size::LengthPercentage(LengthPercentage::Length(20.0))
+                );
+                let width = match &paragraph.style.width {
+                    Size::LengthPercentage(width) => width.resolve(200.0),
+                    width => panic!("unexpected width {:?}", width),
+                };
+                assert_eq!(width, 152.0);
+
+                // the computed line height is inherited, not the percentage
+                let span = &paragraph.children[0];
+                assert_eq!(span.style.font_size, 24.0);
+                assert_eq!(span.style.line_height, LineHeight::Length(60.0));
+                // so is the computed font size, by the text
+                assert_eq!(span.children[0].style.font_size, 24.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_translucent_colors() {
+        styled(
+            "<html><div><p>text</p></div></html>",
+            "html { color: rgba(204, 0, 0, 50%); }
+            div { border-top: 1px solid; background-color: #00000080; }",
+            |root| {
+                let div = &root.children[0];
+                let red = Color {
+                    a: 128,
+                    ..Color::rgb(204, 0, 0)
+                };
+                // the alpha is inherited and used by `currentcolor`
+                assert_eq!(div.style.color, red);
+                assert_eq!(div.style.border_color.top, Some(red.clone()));
+                assert_eq!(div.children[0].style.color, red);
+                assert_eq!(
+                    div.style.background_color,
+                    Some(Color {
+                        a: 128,
+                        ..Color::rgb(0, 0, 0)
+                    })
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_inheritance_and_keywords() {
+        styled(
+            "<html><div><p>text</p></div></html>",
+            "html { color: #cc0000; margin-top: 4px; font-weight: bold; }
+            div { border-top: thick solid; border-bottom-width: 2px; margin-top: inherit; }
+            p { color: initial; font-weight: bolder; visibility: hidden; margin-top: unset; }",
+            |root| {
+                let div = &root.children[0];
+                let red = Color::rgb(204, 0, 0);
+                assert_eq!(div.style.color, red);
+                assert_eq!(div.style.font_weight, 700.0);
+                assert_eq!(
+                    div.style.margin.top,
+                    Size::LengthPercentage(LengthPercentage::Length(4.0))
+                );
+                assert_eq!(div.style.border_width.top, 5.0);
+                assert_eq!(div.style.border_style.top, BorderStyle::Solid);
+                assert_eq!(div.style.border_color.top, Some(red));
+                // widths are 0 without a border style
+                assert_eq!(div.style.border_width.bottom, 0.0);
+                assert_eq!(div.style.display, Display::Block);
+                assert_eq!(root.style.margin.top, div.style.margin.top);
+
+                let paragraph = &div.children[0];
+                assert_eq!(paragraph.style.color, Color::rgb(0, 0, 0));
+                assert_eq!(paragraph.style.font_weight, 900.0);
+                assert_eq!(paragraph.style.visibility, Visibility::Hidden);
+                assert_eq!(
+                    paragraph.style.margin.top,
+                    Size::LengthPercentage(LengthPercentage::Length(0.0))
+                );
+                assert_eq!(paragraph.children[0].style.visibility, Visibility::Hidden);
+            },
+        );
+    }
+
+    #[test]
+    fn test_typed_values() {
+        styled(
+            "<html><ul><li>item</li></ul></html>",
+            "ul { display: block flex; flex-direction: column; row-gap: 1em; overflow: hidden auto;
+              font-family: \"Fira Sans\", Open Sans, sans-serif; background-color: transparent; }
+            li { display: list-item; list-style-type: \"-\"; flex: 2; z-index: 3;
+              text-decoration-line: underline overline; max-width: none; }",
+            |root| {
+                let list = &root.children[0];
+                assert_eq!(list.style.display, Display::Flex);
+                assert_eq!(list.style.flex_direction, FlexDirection::Column);
+                assert_eq!(list.style.row_gap, Some(LengthPercentage::Length(16.0)));
+                assert_eq!(list.style.column_gap, None);
+                assert_eq!(list.style.overflow_x, Overflow::Hidden);
+                assert_eq!(list.style.overflow_y, Overflow::Auto);
+                assert_eq!(
+                    list.style.font_family,
+                    vec!["Fira Sans", "Open Sans", "sans-serif"]
+                );
+                assert_eq!(list.style.background_color, None);
+
+                let item = &list.children[0];
+                assert_eq!(item.style.display, Display::ListItem);
+                assert_eq!(
+                    item.style.list_style_type,
+                    ListStyleType::String("-".to_string())
+                );
+                assert_eq!(item.style.flex_grow, 2.0);
+                assert_eq!(
+                    item.style.flex_basis,
+                    Size::LengthPercentage(LengthPercentage::Length(0.0))
+                );
+                assert_eq!(item.style.z_index, Some(3));
+                assert!(item.style.text_decoration.underline);
+                assert!(!item.style.text_decoration.line_through);
+                assert_eq!(item.style.max_width, Size::None);
+            },
+        );
+    }
+
+    #[test]
+    fn test_math_function_limits() {
+        styled(
+            "<html><div><p>a</p></div></html>",
+            "div { display: flex; z-index: calc(1.5); width: calc(1px / 0);
+              margin-left: calc(0px / 0); flex-grow: calc(infinity); }
+            p { order: calc(-infinity); z-index: 1.5; }",
+            |root| {
+                // math functions are rounded where integers are expected
+                let div = &root.children[0];
+                assert_eq!(div.style.z_index, Some(2));
+                assert_eq!(div.children[0].style.order, i32::MIN);
+                // but numbers aren't
+                assert_eq!(div.children[0].style.z_index, None);
+                // and their infinite and NaN results are clamped
+                assert_eq!(
+                    div.style.width,
+                    Size::LengthPercentage(LengthPercentage::Length(16_777_216.0))
+                );
+                assert_eq!(
+                    div.style.margin.left,
+                    Size::LengthPercentage(LengthPercentage::Length(0.0))
+                );
+                assert_eq!(div.style.flex_grow, 16_777_216.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_inapplicable_values() {
+        // properties that don't apply keep their computed value, ignored by
+        // layout but inherited by the children they apply to
+        styled(
+            "<html><div><p>a</p><span>b</span></div></html>",
+            "div { top: 5px; flex-grow: 2; z-index: 1; table-layout: fixed; row-gap: 4px; }
+            p { position: relative; top: inherit; z-index: inherit; flex-grow: inherit; }
+            span { overflow: hidden; table-layout: inherit; row-gap: inherit; }",
+            |root| {
+                let div = &root.children[0];
+                assert_eq!(
+                    div.style.inset.top,
+                    Size::LengthPercentage(LengthPercentage::Length(5.0))
+                );
+                assert_eq!(div.style.flex_grow, 2.0);
+                assert_eq!(div.style.z_index, Some(1));
+                assert_eq!(div.style.row_gap, Some(LengthPercentage::Length(4.0)));
+
+                let (p, span) = (&div.children[0], &div.children[1]);
+                assert_eq!(
+                    p.style.inset.top,
+                    Size::LengthPercentage(LengthPercentage::Length(5.0))
+                );
+                assert_eq!(p.style.z_index, Some(1));
+                assert_eq!(p.style.flex_grow, 2.0);
+                assert_eq!(span.style.overflow_x, Overflow::Hidden);
+                assert_eq!(
+                    span.value("table-layout"),
+                    Some(&Value::Keyword("fixed".to_string()))
+                );
+                assert_eq!(span.style.row_gap, Some(LengthPercentage::Length(4.0)));
+            },
+        );
+    }
+
+    #[test]
+    fn test_grid_values() {
+        styled(
+            "<html><div><p>item</p></div></html>",
+            "div { display: grid; grid-template-columns: repeat(2, 2em); grid-auto-rows: 1fr;
+              grid-template-areas: \"a b\" \"a c\"; grid-auto-flow: dense column; font-size: 10px; }
+            p { grid-row: span 2 / -1; grid-column: main 2; justify-self: end; }",
+            |root| {
+                let grid = &root.children[0];
+                assert_eq!(
+                    grid.style.grid_template_columns,
+                    vec![TrackListItem::Repeat(
+                        RepeatCount::Count(2),
+                        vec![TrackListItem::Track(TrackSize::Breadth(
+                            TrackBreadth::Length(Value::Length(20.0, Unit::Px))
+                        ))]
+                    )]
+                );
+                assert_eq!(grid.style.grid_template_rows, Vec::new());
+                assert_eq!(
+                    grid.style.grid_auto_rows,
+                    vec![TrackSize::Breadth(TrackBreadth::Flex(1.0))]
+                );
+                assert_eq!(
+                    grid.style.grid_auto_columns,
+                    vec![TrackSize::Breadth(TrackBreadth::Auto)]
+                );
+                assert_eq!(grid.style.grid_template_areas, vec!["a b", "a c"]);
+                assert_eq!(grid.style.grid_auto_flow, GridAutoFlow::Column);
+                assert!(grid.style.grid_auto_flow_dense);
+
+                let item = &grid.children[0];
+                assert_eq!(item.style.grid_row_start, GridLine::Span(2, None));
+                assert_eq!(item.style.grid_row_end, GridLine::Line(-1, None));
+                assert_eq!(
+                    item.style.grid_column_start,
+                    GridLine::Line(2, Some("main".to_string()))
+                );
+                assert_eq!(item.style.grid_column_end, GridLine::Auto);
+                assert_eq!(item.style.justify_self, Alignment::End);
+            },
+        );
+    }
+
+    #[test]
+    fn test_default_style() {
+        let style = ComputedStyle::default();
+        assert_eq!(style.display, Display::Inline);
+        assert_eq!(style.font_size, 16.0);
+        assert_eq!(style.width, Size::Auto);
+        assert_eq!(
+            style.list_style_type,
+            ListStyleType::Named("disc".to_string())
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::css::{is_supported_selector, valid_identifier_char, Declaration};
+use crate::parser::TextParser;
+use crate::properties::lookup;
+use crate::shorthands::is_shorthand;
+use crate::variables::is_custom_property;
+
+// the condition of a `@supports` rule
+#[derive(Debug, Clone, PartialEq)]
+pub enum SupportsCondition {
+    Not(Box<SupportsCondition>),
+    And(Vec<SupportsCondition>),
+    Or(Vec<SupportsCondition>),
+    // `(display: grid)`
+    Declaration(String, String),
+    // `selector(a > b)`
+    Selector(String),
+    // syntactically valid but unknown conditions are false, kept as written
+    Unknown(String),
+}
+
+impl SupportsCondition {
+    pub fn parse(input: &str) -> SupportsCondition {
+        let mut parser = SupportsParser {
+            text_parser: TextParser::new(input.to_string()),
+        };
+        let condition = parser.parse_condition();
+        parser.text_parser.remove_whitespaces();
+        match parser.text_parser.eol() {
+            true => condition,
+            false => SupportsCondition::Unknown(input.to_string()),
+        }
+    }
+
+    // declarations are supported when the property is known and the value
+    // valid for it
+    pub fn matches(&self) -> bool {
+        match self {
+            SupportsCondition::Not(condition) => !condition.matches(),
+            SupportsCondition::And(conditions) => {
+                conditions.iter().all(|condition| condition.matches())
+            }
+            SupportsCondition::Or(conditions) => {
+                conditions.iter().any(|condition| condition.matches())
+            }
+            SupportsCondition::Declaration(name, value) => {
+                let known =
+                    is_custom_property(name) || is_shorthand(name) || lookup(name).is_some();
+                known && Declaration::parse_longhands(name, value.clone()).is_some()
+            }
+            SupportsCondition::Selector(selector) => is_supported_selector(selector),
+            SupportsCondition::Unknown(_) => false,
+        }
+    }
+}
+
+struct SupportsParser {
+    text_parser: TextParser,
+}
+
+impl SupportsParser {
+    fn current_char(&self) -> Option<char> {
+        if self.text_parser.eol() {
+            None
+        } else {
+            Some(self.text_parser.get_current_char())
+        }
+    }
+
+    // consume the keyword if it comes next, as a whole word
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        let state = self.text_parser._state();
+        let matches = state.len() >= keyword.len()
+            && state.is_char_boundary(keyword.len())
+            && state[..keyword.len()].eq_ignore_ascii_case(keyword)
+            && !state[keyword.len()..]
+                .chars()
+                .next()
+                .is_some_and(valid_identifier_char);
+        if matches {
+            self.text_parser
+                .consume_pattern(state[..keyword.len()].to_string());
+            self.text_parser.remove_whitespaces();
+        }
+        matches
+    }
+
+    // the text up to the closing parenthesis of a block, which is consumed
+    fn consume_block(&mut self) -> String {
+        let mut depth = 1;
+        let mut block = String::new();
+        while let Some(c) = self.current_char() {
+            self.text_parser.consume_char();
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 1 => break,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            block.push(c);
+        }
+        block
+    }
+
+    fn parse_condition(&mut self) -> SupportsCondition {
+        self.text_parser.remove_whitespaces();
+        if self.consume_keyword("not") {
+            return SupportsCondition::Not(Box::new(self.parse_in_parens()));
+        }
+
+        let mut conditions = vec![self.parse_in_parens()];
+        let mut is_or = false;
+        loop {
+            if self.consume_keyword("and") {
+                conditions.push(self.parse_in_parens());
+            } else if self.consume_keyword("or") {
+                is_or = true;
+                conditions.push(self.parse_in_parens());
+            } else {
+                break;
+            }
+        }
+
+        match (conditions.len(), is_or) {
+            (1, _) => conditions.remove(0),
+            (_, true) => SupportsCondition::Or(conditions),
+            (_, false) => SupportsCondition::And(conditions),
+        }
+    }
+
+    fn parse_in_parens(&mut self) -> SupportsCondition {
+        self.text_parser.remove_whitespaces();
+        let function = self.text_parser.consume_chars_while(valid_identifier_char);
+        if self.current_char() != Some('(') {
+            return SupportsCondition::Unknown(function);
+        }
+        self.text_parser.consume_char();
+
+        let condition = if function.eq_ignore_ascii_case("selector") {
+            SupportsCondition::Selector(self.consume_block().trim().to_string())
+        } else if !function.is_empty() {
+            SupportsCondition::Unknown(format!("{}({})", function, self.consume_block()))
+        } else {
+            self.text_parser.remove_whitespaces();
+            let state = self.text_parser._state().to_lowercase();
+            if state.starts_with('(') || state.starts_with("not ") || state.starts_with("not(") {
+                let condition = self.parse_condition();
+                self.text_parser.remove_whitespaces();
+                if self.current_char() == Some(')') {
+                    self.text_parser.consume_char();
+                }
+                condition
+            } else {
+                let block = self.consume_block();
+                match block.split_once(':') {
+                    Some((name, value)) => SupportsCondition::Declaration(
+                        name.trim().to_lowercase(),
+                        value.trim().to_string(),
+                    ),
+                    None => SupportsCondition::Unknown(format!("({})", block)),
+                }
+            }
+        };
+        self.text_parser.remove_whitespaces();
+        condition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supports(condition: &str) -> bool {
+        SupportsCondition::parse(condition).matches()
+    }
+
+    #[test]
+    fn test_parse_conditions() {
+        assert_eq!(
+            SupportsCondition::parse("not (display: grid) and (color: red)"),
+            SupportsCondition::Unknown("not (display: grid) and (color: red)".to_string())
+        );
+        assert_eq!(
+            SupportsCondition::parse("((display: flex) or (float: left)) and selector(a > b)"),
+            SupportsCondition::And(vec![
+                SupportsCondition::Or(vec![
+                    SupportsCondition::Declaration("display".to_string(), "flex".to_string()),
+                    SupportsCondition::Declaration("float".to_string(), "left".to_string()),
+                ]),
+                SupportsCondition::Selector("a > b".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_evaluate_conditions() {
+        assert!(supports("(display: flex)"));
+        assert!(supports("(margin: 1px auto)"));
+        assert!(supports("(--anything: { weird })"));
+        assert!(!supports("(display: 12px)"));
+        assert!(!supports("(unknown-property: 1px)"));
+        assert!(supports("not (display: 12px)"));
+        assert!(supports("(display: nope) or (color: #fff)"));
+        assert!(!supports("(display: block) and (color: 1px)"));
+        assert!(supports("selector(ul > li:first-child::marker)"));
+        assert!(!supports("selector(:unknown-pseudo)"));
+        assert!(!supports("font-tech(color-colrv1)"));
+        assert!(!supports("display: flex"));
+    }
+}
@@ -0,0 +1,1058 @@
+use crate::block::MarginStrut;
+use crate::dom::NodeType;
+use crate::floats::FloatContext;
+use crate::layout::{
+    is_row_group, BoxType, ContainingBlock, Dimensions, EdgeSizes, LayoutBox, LayoutContext, Rect,
+};
+use crate::style::{
+    BorderCollapse, BorderStyle, BoxSizing, CaptionSide, ComputedStyle, Display, LengthPercentage,
+    Size, TableLayout, VerticalAlign,
+};
+use std::ops::Range;
+
+// where a row or a column box is in the children of its table: the index of
+// its group, unless it is a child of the table, and its index there
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Path {
+    group: Option<usize>,
+    index: usize,
+}
+
+// a cell, the child `index` of the row at `row`, and the slots of the grid
+// it spans
+#[derive(Debug)]
+struct Cell {
+    row: Path,
+    index: usize,
+    rows: Range<usize>,
+    columns: Range<usize>,
+}
+
+// the rows of a table in the order they are displayed, the column box of
+// each column, and the cells placed in the slots of the grid
+#[derive(Debug, Default)]
+struct TableGrid {
+    rows: Vec<Path>,
+    columns: Vec<Option<Path>>,
+    cells: Vec<Cell>,
+    slots: Vec<Vec<Option<usize>>>,
+}
+
+impl TableGrid {
+    fn slot(&self, row: usize, column: usize) -> Option<usize> {
+        self.slots.get(row)?.get(column).copied().flatten()
+    }
+
+    fn set_slot(&mut self, row: usize, column: usize, cell: usize) {
+        if self.slots.len() <= row {
+            self.slots.resize(row + 1, Vec::new());
+        }
+        if self.slots[row].len() <= column {
+            self.slots[row].resize(column + 1, None);
+        }
+        self.slots[row][column] = Some(cell);
+    }
+}
+
+// the widths a column needs: the widest min-content and max-content widths
+// of its cells, the fixed width and the percentage of the table width they
+// ask for
+#[derive(Debug, Clone, Copy, Default)]
+struct Column {
+    min: f32,
+    max: f32,
+    fixed: Option<f32>,
+    percentage: Option<f32>,
+}
+
+// a positive integer attribute of the element of a box, like `colspan`
+fn attribute(layout_box: &LayoutBox, name: &str) -> Option<usize> {
+    match &layout_box.styled_node()?.node.node_type {
+        NodeType::Element(elem) => elem.attributes.get(name)?.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// the borders of a box, as `[top, right, bottom, left]`
+fn borders(style: &ComputedStyle) -> [(BorderStyle, f32); 4] {
+    let (styles, widths) = (&style.border_style, &style.border_width);
+    [
+        (styles.top, widths.top),
+        (styles.right, widths.right),
+        (styles.bottom, widths.bottom),
+        (styles.left, widths.left),
+    ]
+}
+
+// CSS 2.1 §17.6.2.1: the width of a collapsed border, from the borders
+// meeting there: a hidden border suppresses it, and the widest of the others
+// wins
+fn collapsed_width(borders: &[(BorderStyle, f32)]) -> f32 {
+    let mut width: f32 = 0.0;
+    for &(style, border_width) in borders {
+        match style {
+            BorderStyle::Hidden => return 0.0,
+            BorderStyle::None => {}
+            _ => width = width.max(border_width),
+        }
+    }
+    width
+}
+
+// adds `extra` to the values, in proportion to their weights, or evenly when
+// they weigh nothing
+fn spread(targets: Vec<(&mut f32, f32)>, extra: f32) {
+    let total: f32 = targets.iter().map(|(_, weight)| weight).sum();
+    let count = targets.len() as f32;
+    for (value, weight) in targets {
+        *value += match total > 0.0 {
+            true => extra * weight / total,
+            false => extra / count,
+        };
+    }
+}
+
+// CSS 2.1 §17.5.2.2: the widths of the columns sharing the `available` width.
+// The columns with a percentage take it first; the others get their
+// min-content widths, growing towards their max-content widths, and past
+// them for the columns without a fixed width
+fn auto_column_widths(columns: &[Column], available: f32) -> Vec<f32> {
+    let mut widths = vec![0.0; columns.len()];
+    let mut remaining = available;
+    let mut others = Vec::new();
+    for (index, column) in columns.iter().enumerate() {
+        match column.percentage {
+            Some(percentage) => {
+                widths[index] = (percentage / 100.0 * available).max(column.min);
+                remaining -= widths[index];
+            }
+            None => others.push(index),
+        }
+    }
+    let min: f32 = others.iter().map(|&index| columns[index].min).sum();
+    let max: f32 = others.iter().map(|&index| columns[index].max).sum();
+    if remaining >= max {
+        for &index in &others {
+            widths[index] = columns[index].max;
+        }
+        let flexible: Vec<usize> = others
+            .iter()
+            .copied()
+            .filter(|&index| columns[index].fixed.is_none())
+            .collect();
+        let growing = match (flexible.is_empty(), others.is_empty()) {
+            (false, _) => flexible,
+            (true, false) => others,
+            (true, true) => (0..columns.len()).collect(),
+        };
+        let weights = widths.clone();
+        let targets = widths
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| growing.contains(index))
+            .map(|(index, width)| (width, weights[index]))
+            .collect();
+        spread(targets, remaining - max);
+    } else if remaining > min {
+        let ratio = (remaining - min) / (max - min);
+        for &index in &others {
+            let column = columns[index];
+            widths[index] = column.min + (column.max - column.min) * ratio;
+        }
+    } else {
+        for &index in &others {
+            widths[index] = columns[index].min;
+        }
+    }
+    widths
+}
+
+// the horizontal padding and borders of a cell
+fn cell_edges(cell: &LayoutBox, border: &EdgeSizes, table_width: f32) -> f32 {
+    let (padding, _) = cell.padding_and_border(table_width);
+    padding.left + padding.right + border.left + border.right
+}
+
+impl<'a> LayoutBox<'a> {
+    pub(crate) fn is_table(&self) -> bool {
+        !matches!(self.box_type, BoxType::Inline(_) | BoxType::Text(..))
+            && matches!(self.style().display, Display::Table | Display::InlineTable)
+    }
+
+    fn collapses_borders(&self) -> bool {
+        self.style().border_collapse == BorderCollapse::Collapse
+    }
+
+    // the horizontal and vertical spacing between the cells, and around them
+    fn border_spacing(&self) -> (f32, f32) {
+        match self.collapses_borders() {
+            true => (0.0, 0.0),
+            false => self.style().border_spacing,
+        }
+    }
+
+    // the fixed layout applies to tables with a width
+    fn has_fixed_layout(&self) -> bool {
+        let style = self.style();
+        style.table_layout == TableLayout::Fixed && style.width != Size::Auto
+    }
+
+    // the borders of a table collapsing its borders with its cells, which
+    // has no padding: half of its borders, the other half being outside of it
+    pub(crate) fn collapsed_table_borders(&self) -> Option<EdgeSizes> {
+        if !self.is_table() || !self.collapses_borders() {
+            return None;
+        }
+        let [top, right, bottom, left] =
+            borders(self.style()).map(|border| collapsed_width(&[border]) / 2.0);
+        Some(EdgeSizes {
+            left,
+            right,
+            top,
+            bottom,
+        })
+    }
+
+    fn child_at(&self, path: Path) -> &LayoutBox<'a> {
+        match path.group {
+            Some(group) => &self.children[group].children[path.index],
+            None => &self.children[path.index],
+        }
+    }
+
+    fn child_at_mut(&mut self, path: Path) -> &mut LayoutBox<'a> {
+        match path.group {
+            Some(group) => &mut self.children[group].children[path.index],
+            None => &mut self.children[path.index],
+        }
+    }
+
+    fn cell_box(&self, cell: &Cell) -> &LayoutBox<'a> {
+        &self.child_at(cell.row).children[cell.index]
+    }
+
+    // the rows of the table and the cells in the slots of its grid, after
+    // the HTML table processing model: each cell takes the first free slot
+    // of its row, and spans the columns of its `colspan` and the rows of its
+    // `rowspan` in the row group
+    fn table_grid(&self) -> TableGrid {
+        // the first header group is displayed at the top, and the first
+        // footer group at the bottom
+        let display = |index: usize| self.children[index].style().display;
+        let mut order: Vec<usize> = (0..self.children.len()).collect();
+        if let Some(position) = order
+            .iter()
+            .position(|&index| display(index) == Display::TableHeaderGroup)
+        {
+            let header = order.remove(position);
+            order.insert(0, header);
+        }
+        if let Some(position) = order
+            .iter()
+            .position(|&index| display(index) == Display::TableFooterGroup)
+        {
+            let footer = order.remove(position);
+            order.push(footer);
+        }
+
+        // the row groups, and the runs of rows outside of them
+        let mut grid = TableGrid::default();
+        let mut sections: Vec<Vec<Path>> = Vec::new();
+        let mut in_run = false;
+        for index in order {
+            let child = &self.children[index];
+            let span = || attribute(child, "span").unwrap_or(1).clamp(1, 1000);
+            match child.style().display {
+                Display::TableRow => {
+                    if !in_run {
+                        sections.push(Vec::new());
+                        in_run = true;
+                    }
+                    sections
+                        .last_mut()
+                        .unwrap()
+                        .push(Path { group: None, index });
+                }
+                display if is_row_group(display) => {
+                    in_run = false;
+                    sections.push(
+                        (0..child.children.len())
+                            .map(|row| Path {
+                                group: Some(index),
+                                index: row,
+                            })
+                            .collect(),
+                    );
+                }
+                // columns, and column groups without them, span the columns
+                // of their `span`
+                Display::TableColumn | Display::TableColumnGroup if child.children.is_empty() => {
+                    let path = Path { group: None, index };
+                    grid.columns.extend(std::iter::repeat_n(Some(path), span()));
+                }
+                Display::TableColumnGroup => {
+                    for (column, col) in child.children.iter().enumerate() {
+                        let path = Path {
+                            group: Some(index),
+                            index: column,
+                        };
+                        let span = attribute(col, "span").unwrap_or(1).clamp(1, 1000);
+                        grid.columns.extend(std::iter::repeat_n(Some(path), span));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for section in sections {
+            let end = grid.rows.len() + section.len();
+            for path in section {
+                let row = grid.rows.len();
+                grid.rows.push(path);
+                let mut column = 0;
+                for (index, cell) in self.child_at(path).children.iter().enumerate() {
+                    while grid.slot(row, column).is_some() {
+                        column += 1;
+                    }
+                    let colspan = attribute(cell, "colspan").unwrap_or(1).clamp(1, 1000);
+                    let rowspan = match attribute(cell, "rowspan") {
+                        Some(0) => end - row,
+                        Some(rowspan) => rowspan.clamp(1, end - row),
+                        None => 1,
+                    };
+                    let id = grid.cells.len();
+                    for slot_row in row..row + rowspan {
+                        for slot_column in column..column + colspan {
+                            grid.set_slot(slot_row, slot_column, id);
+                        }
+                    }
+                    grid.cells.push(Cell {
+                        row: path,
+                        index,
+                        rows: row..row + rowspan,
+                        columns: column..column + colspan,
+                    });
+                    column += colspan;
+                }
+            }
+        }
+        let columns = grid
+            .cells
+            .iter()
+            .map(|cell| cell.columns.end)
+            .fold(grid.columns.len(), usize::max);
+        grid.columns.resize(columns, None);
+        grid
+    }
+
+    // the borders of the cells: their own in the separated borders model, and
+    // in the collapsing borders model half of the borders they share with the
+    // cells, rows and table around them
+    fn cell_borders(&self, grid: &TableGrid) -> Vec<EdgeSizes> {
+        if !self.collapses_borders() {
+            return grid
+                .cells
+                .iter()
+                .map(|cell| {
+                    let [top, right, bottom, left] =
+                        borders(self.cell_box(cell).style()).map(|border| border.1);
+                    EdgeSizes {
+                        left,
+                        right,
+                        top,
+                        bottom,
+                    }
+                })
+                .collect();
+        }
+
+        let table = borders(self.style());
+        let row = |row: usize| borders(self.child_at(grid.rows[row]).style());
+        let cell = |id: usize| borders(self.cell_box(&grid.cells[id]).style());
+        let (last_row, last_column) = (grid.rows.len(), grid.columns.len());
+        grid.cells
+            .iter()
+            .enumerate()
+            .map(|(id, placed)| {
+                let (rows, columns) = (placed.rows.clone(), placed.columns.clone());
+                let own = cell(id);
+                let mut edges: [Vec<(BorderStyle, f32)>; 4] = own.map(|border| vec![border]);
+                edges[0].push(row(rows.start)[0]);
+                edges[2].push(row(rows.end - 1)[2]);
+                match rows.start {
+                    0 => edges[0].push(table[0]),
+                    start => {
+                        edges[0].push(row(start - 1)[2]);
+                        let above = columns
+                            .clone()
+                            .filter_map(|column| grid.slot(start - 1, column));
+                        edges[0].extend(above.map(|id| cell(id)[2]));
+                    }
+                }
+                match rows.end {
+                    end if end == last_row => edges[2].push(table[2]),
+                    end => {
+                        edges[2].push(row(end)[0]);
+                        let below = columns.clone().filter_map(|column| grid.slot(end, column));
+                        edges[2].extend(below.map(|id| cell(id)[0]));
+                    }
+                }
+                match columns.start {
+                    0 => {
+                        edges[3].push(table[3]);
+                        edges[3].extend(rows.clone().map(|index| row(index)[3]));
+                    }
+                    start => {
+                        let before = rows.clone().filter_map(|index| grid.slot(index, start - 1));
+                        edges[3].extend(before.map(|id| cell(id)[1]));
+                    }
+                }
+                match columns.end {
+                    end if end == last_column => {
+                        edges[1].push(table[1]);
+                        edges[1].extend(rows.clone().map(|index| row(index)[1]));
+                    }
+                    end => {
+                        let after = rows.clone().filter_map(|index| grid.slot(index, end));
+                        edges[1].extend(after.map(|id| cell(id)[3]));
+                    }
+                }
+                let [top, right, bottom, left] = edges.map(|edge| collapsed_width(&edge) / 2.0);
+                EdgeSizes {
+                    left,
+                    right,
+                    top,
+                    bottom,
+                }
+            })
+            .collect()
+    }
+
+    // the width of a cell or column box, as the border box width of a cell
+    fn specified_width(&self, edges: f32, table_width: Option<f32>) -> Option<f32> {
+        let width = self.style().width.resolve(table_width)?;
+        Some(match self.style().box_sizing {
+            BoxSizing::ContentBox => width + edges,
+            BoxSizing::BorderBox => width.max(edges),
+        })
+    }
+
+    // the widths the columns need for the automatic table layout: those of
+    // their column boxes and of the cells spanning one column, then the
+    // cells spanning more columns widen the columns they span
+    fn table_columns(
+        &self,
+        context: &LayoutContext,
+        grid: &TableGrid,
+        borders: &[EdgeSizes],
+    ) -> Vec<Column> {
+        let (spacing, _) = self.border_spacing();
+        let mut columns = vec![Column::default(); grid.columns.len()];
+        for (column, path) in columns.iter_mut().zip(&grid.columns) {
+            let Some(path) = path else { continue };
+            match &self.child_at(*path).style().width {
+                Size::LengthPercentage(LengthPercentage::Percentage(percentage)) => {
+                    column.percentage = Some(*percentage);
+                }
+                _ => column.fixed = self.child_at(*path).specified_width(0.0, None),
+            }
+        }
+
+        let mut cells: Vec<usize> = (0..grid.cells.len()).collect();
+        cells.sort_by_key(|&id| grid.cells[id].columns.len());
+        for id in cells {
+            let cell = &grid.cells[id];
+            let cell_box = self.cell_box(cell);
+            let edges = cell_edges(cell_box, &borders[id], 0.0);
+            let (min, max) = cell_box.intrinsic_widths(context);
+            let (min, max) = (min + edges, max + edges);
+            if cell.columns.len() == 1 {
+                let column = &mut columns[cell.columns.start];
+                column.min = column.min.max(min);
+                column.max = column.max.max(max);
+                match &cell_box.style().width {
+                    Size::LengthPercentage(LengthPercentage::Percentage(percentage)) => {
+                        column.percentage = Some(column.percentage.unwrap_or(0.0).max(*percentage));
+                    }
+                    _ => {
+                        if let Some(width) = cell_box.specified_width(edges, None) {
+                            column.fixed = Some(column.fixed.unwrap_or(0.0).max(width));
+                        }
+                    }
+                }
+                continue;
+            }
+
+            // the spacing between the spanned columns counts towards the width
+            let between = spacing * (cell.columns.len() - 1) as f32;
+            let spanned = &mut columns[cell.columns.clone()];
+            let excess = min - between - spanned.iter().map(|column| column.min).sum::<f32>();
+            if excess > 0.0 {
+                let targets = spanned
+                    .iter_mut()
+                    .map(|column| (column.max, &mut column.min))
+                    .map(|(weight, min)| (min, weight))
+                    .collect();
+                spread(targets, excess);
+            }
+            let excess = max - between - spanned.iter().map(|column| column.max).sum::<f32>();
+            if excess > 0.0 {
+                let targets = spanned
+                    .iter_mut()
+                    .map(|column| (column.max, &mut column.max))
+                    .map(|(weight, max)| (max, weight))
+                    .collect();
+                spread(targets, excess);
+            }
+        }
+
+        // a fixed width replaces the max-content width of a column
+        for column in &mut columns {
+            if let Some(fixed) = column.fixed {
+                column.max = fixed;
+            }
+            column.max = column.max.max(column.min);
+        }
+        columns
+    }
+
+    // CSS 2.1 §17.5.2.1: the widths of the columns of a table with the fixed
+    // layout, sharing the `available` width: the widths of the column boxes,
+    // else of the cells in the first row, the other columns sharing the rest
+    fn fixed_column_widths(
+        &self,
+        grid: &TableGrid,
+        borders: &[EdgeSizes],
+        available: f32,
+    ) -> Vec<f32> {
+        let (spacing, _) = self.border_spacing();
+        let mut widths: Vec<Option<f32>> = grid
+            .columns
+            .iter()
+            .map(|path| {
+                path.and_then(|path| self.child_at(path).specified_width(0.0, Some(available)))
+            })
+            .collect();
+        for (id, cell) in grid.cells.iter().enumerate() {
+            if cell.rows.start != 0 {
+                continue;
+            }
+            let cell_box = self.cell_box(cell);
+            let edges = cell_edges(cell_box, &borders[id], available);
+            let Some(width) = cell_box.specified_width(edges, Some(available)) else {
+                continue;
+            };
+            let span = cell.columns.len() as f32;
+            let width = (width - spacing * (span - 1.0)) / span;
+            for column in cell.columns.clone() {
+                widths[column] = widths[column].or(Some(width));
+            }
+        }
+
+        let used: f32 = widths.iter().flatten().sum();
+        let auto = widths.iter().filter(|width| width.is_none()).count();
+        let rest = (available - used).max(0.0);
+        let mut widths: Vec<f32> = widths
+            .into_iter()
+            .map(|width| width.unwrap_or(rest / auto.max(1) as f32))
+            .collect();
+        if auto == 0 && rest > 0.0 {
+            let weights = widths.clone();
+            spread(widths.iter_mut().zip(weights).collect(), rest);
+        }
+        widths
+    }
+
+    // the min-content and max-content widths of the content of a table: its
+    // columns and the spacing around them, and its captions
+    pub(crate) fn table_intrinsic_widths(&self, context: &LayoutContext) -> (f32, f32) {
+        let grid = self.table_grid();
+        let borders = self.cell_borders(&grid);
+        let (spacing, _) = self.border_spacing();
+        let spacing = match grid.columns.len() {
+            0 => 0.0,
+            columns => spacing * (columns + 1) as f32,
+        };
+        let (min, max) = match self.has_fixed_layout() {
+            true => {
+                let width: f32 = self.fixed_column_widths(&grid, &borders, 0.0).iter().sum();
+                (width, width)
+            }
+            false => self
+                .table_columns(context, &grid, &borders)
+                .iter()
+                .fold((0.0, 0.0), |(min, max), column| {
+                    (min + column.min, max + column.max)
+                }),
+        };
+        let caption = self
+            .children
+            .iter()
+            .filter(|child| child.style().display == Display::TableCaption)
+            .map(|caption| caption.outer_intrinsic_widths(context).0)
+            .fold(0.0, f32::max);
+        ((min + spacing).max(caption), (max + spacing).max(caption))
+    }
+
+    // lays out the captions, rows and cells of a table from the top of its
+    // content; returns the height of its content
+    pub(crate) fn layout_table(
+        &mut self,
+        context: &LayoutContext,
+        containing: &ContainingBlock,
+    ) -> f32 {
+        let grid = self.table_grid();
+        let borders = self.cell_borders(&grid);
+        let (spacing_x, spacing_y) = self.border_spacing();
+        let table_width = containing.width;
+
+        // the columns share the width of the table
+        let count = grid.columns.len();
+        let available = (table_width - spacing_x * (count + 1) as f32).max(0.0);
+        let widths = match self.has_fixed_layout() {
+            true => self.fixed_column_widths(&grid, &borders, available),
+            false => auto_column_widths(&self.table_columns(context, &grid, &borders), available),
+        };
+        let mut column_x = Vec::new();
+        let mut x = containing.x + spacing_x;
+        for width in &widths {
+            column_x.push(x);
+            x += width + spacing_x;
+        }
+        let extent = |sizes: &[f32], spacing: f32, span: &Range<usize>| {
+            sizes[span.clone()].iter().sum::<f32>() + spacing * span.len().saturating_sub(1) as f32
+        };
+
+        // the cells laid out in their columns give their heights to the rows
+        let mut heights: Vec<f32> = grid
+            .rows
+            .iter()
+            .map(|&path| {
+                let row = self.child_at(path);
+                row.style().height.resolve(None).unwrap_or(0.0)
+            })
+            .collect();
+        let mut cell_heights = Vec::new();
+        let mut cell_baselines = Vec::new();
+        for (id, cell) in grid.cells.iter().enumerate() {
+            let cell_box = &mut self.child_at_mut(cell.row).children[cell.index];
+            let (padding, _) = cell_box.padding_and_border(table_width);
+            let mut dimensions = Dimensions {
+                padding,
+                border: borders[id],
+                ..Dimensions::default()
+            };
+            let edges = cell_edges(cell_box, &borders[id], table_width);
+            dimensions.content.width = (extent(&widths, spacing_x, &cell.columns) - edges).max(0.0);
+            cell_box.layout_sized(context, dimensions, None, None);
+            let border_box = cell_box.dimensions.border_box();
+            let content = cell_box.dimensions.content;
+            let baseline = cell_box
+                .first_baseline()
+                .unwrap_or(content.y + content.height)
+                - border_box.y;
+            cell_heights.push(border_box.height);
+            cell_baselines.push(baseline);
+        }
+
+        // the baseline aligned cells of a row share a baseline, the others
+        // take the height of the rows they span
+        let aligned: Vec<bool> = grid
+            .cells
+            .iter()
+            .map(|cell| {
+                cell.rows.len() == 1
+                    && !matches!(
+                        self.cell_box(cell).style().vertical_align,
+                        VerticalAlign::Top | VerticalAlign::Middle | VerticalAlign::Bottom
+                    )
+            })
+            .collect();
+        let mut baselines = vec![0.0; grid.rows.len()];
+        for (id, cell) in grid.cells.iter().enumerate() {
+            if aligned[id] {
+                let baseline = &mut baselines[cell.rows.start];
+                *baseline = f32::max(*baseline, cell_baselines[id]);
+            }
+        }
+        for (id, cell) in grid.cells.iter().enumerate() {
+            if cell.rows.len() != 1 {
+                continue;
+            }
+            let row = cell.rows.start;
+            let height = match aligned[id] {
+                true => baselines[row] - cell_baselines[id] + cell_heights[id],
+                false => cell_heights[id],
+            };
+            heights[row] = heights[row].max(height);
+        }
+        let mut spanning: Vec<usize> = (0..grid.cells.len())
+            .filter(|&id| grid.cells[id].rows.len() > 1)
+            .collect();
+        spanning.sort_by_key(|&id| grid.cells[id].rows.len());
+        for id in spanning {
+            let rows = grid.cells[id].rows.clone();
+            let excess = cell_heights[id] - extent(&heights, spacing_y, &rows);
+            if excess > 0.0 {
+                let weights = heights.clone();
+                let targets = heights[rows.clone()]
+                    .iter_mut()
+                    .zip(&weights[rows])
+                    .map(|(height, weight)| (height, *weight))
+                    .collect();
+                spread(targets, excess);
+            }
+        }
+
+        // the captions go above and below the grid
+        let mut caption_heights = Vec::new();
+        for child in &mut self.children {
+            if child.style().display != Display::TableCaption {
+                continue;
+            }
+            let caption_containing = ContainingBlock {
+                height: None,
+                ..*containing
+            };
+            child.layout_block(
+                context,
+                &caption_containing,
+                containing.y,
+                MarginStrut::default(),
+                true,
+                &mut FloatContext::new(),
+            );
+            caption_heights.push((
+                child.style().caption_side,
+                child.dimensions.margin_box().height,
+            ));
+        }
+        let captions_height = |side: CaptionSide| -> f32 {
+            caption_heights
+                .iter()
+                .filter(|caption| caption.0 == side)
+                .map(|caption| caption.1)
+                .sum()
+        };
+        let (top, bottom) = (
+            captions_height(CaptionSide::Top),
+            captions_height(CaptionSide::Bottom),
+        );
+
+        // a table taller than its rows shares the extra height between them
+        let rows_height = |heights: &[f32]| match heights.len() {
+            0 => 0.0,
+            rows => heights.iter().sum::<f32>() + spacing_y * (rows + 1) as f32,
+        };
+        if let Some(height) = containing.height {
+            let extra = height - top - bottom - rows_height(&heights);
+            if extra > 0.0 && !heights.is_empty() {
+                let weights = heights.clone();
+                spread(heights.iter_mut().zip(weights).collect(), extra);
+            }
+        }
+        let grid_height = rows_height(&heights);
+        let grid_top = containing.y + top;
+        let mut row_y = Vec::new();
+        let mut y = grid_top + spacing_y;
+        for height in &heights {
+            row_y.push(y);
+            y += height + spacing_y;
+        }
+
+        let (mut above, mut below) = (0.0, grid_top + grid_height - containing.y);
+        for child in &mut self.children {
+            if child.style().display != Display::TableCaption {
+                continue;
+            }
+            let height = child.dimensions.margin_box().height;
+            let offset = match child.style().caption_side {
+                CaptionSide::Top => &mut above,
+                CaptionSide::Bottom => &mut below,
+            };
+            child.translate(0.0, *offset);
+            *offset += height;
+        }
+
+        // the cells stretch to the height of their rows, their content
+        // aligned in them
+        for (id, cell) in grid.cells.iter().enumerate() {
+            let x = column_x
+                .get(cell.columns.start)
+                .copied()
+                .unwrap_or(containing.x);
+            let y = row_y[cell.rows.start];
+            let height = extent(&heights, spacing_y, &cell.rows);
+            let baseline = baselines[cell.rows.start] - cell_baselines[id];
+            let cell_box = &mut self.child_at_mut(cell.row).children[cell.index];
+            let border_box = cell_box.dimensions.border_box();
+            let free = height - border_box.height;
+            let offset = match cell_box.style().vertical_align {
+                VerticalAlign::Middle => free / 2.0,
+                VerticalAlign::Bottom => free,
+                _ if aligned[id] => baseline,
+                _ => 0.0,
+            };
+            cell_box.translate(x - border_box.x, y - border_box.y + offset);
+            cell_box.dimensions.content.y -= offset;
+            cell_box.dimensions.content.height += free;
+        }
+
+        // the rows, row groups and columns cover the slots of the grid
+        let grid_x = containing.x + spacing_x;
+        let grid_width = extent(&widths, spacing_x, &(0..count));
+        let rows_top = grid_top + spacing_y;
+        let rows_extent = extent(&heights, spacing_y, &(0..heights.len()));
+        for (row, &path) in grid.rows.iter().enumerate() {
+            self.child_at_mut(path).dimensions = Dimensions {
+                content: Rect {
+                    x: grid_x,
+                    y: row_y[row],
+                    width: grid_width,
+                    height: heights[row],
+                },
+                ..Dimensions::default()
+            };
+        }
+        for (column, path) in grid.columns.iter().enumerate() {
+            let Some(path) = *path else { continue };
+            let column_box = self.child_at_mut(path);
+            if column > 0 && grid.columns[column - 1] == Some(path) {
+                column_box.dimensions.content.width =
+                    column_x[column] + widths[column] - column_box.dimensions.content.x;
+                continue;
+            }
+            column_box.dimensions = Dimensions {
+                content: Rect {
+                    x: column_x[column],
+                    y: rows_top,
+                    width: widths[column],
+                    height: rows_extent,
+                },
+                ..Dimensions::default()
+            };
+        }
+        for child in &mut self.children {
+            // a column group without column boxes is sized as a column above
+            let display = child.style().display;
+            let is_group = match display {
+                Display::TableColumnGroup => !child.children.is_empty(),
+                display => is_row_group(display),
+            };
+            if !is_group {
+                continue;
+            }
+            let mut rect = Rect {
+                x: grid_x,
+                y: rows_top,
+                width: grid_width,
+                height: 0.0,
+            };
+            if let (Some(first), Some(last)) = (child.children.first(), child.children.last()) {
+                let (first, last) = (first.dimensions.content, last.dimensions.content);
+                rect = match is_row_group(display) {
+                    true => Rect {
+                        y: first.y,
+                        height: last.y + last.height - first.y,
+                        ..rect
+                    },
+                    false => Rect {
+                        x: first.x,
+                        width: last.x + last.width - first.x,
+                        height: rows_extent,
+                        ..rect
+                    },
+                };
+            }
+            child.dimensions = Dimensions {
+                content: rect,
+                ..Dimensions::default()
+            };
+        }
+
+        top + grid_height + bottom
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::test_util::{border_box, find, with_layout};
+
+    const ROW: &str = "<table id='t'><tr><td id='a'>aaaa aa</td><td id='b'>b</td></tr></table>";
+
+    #[test]
+    fn test_automatic_layout() {
+        // the table shrinks to the max-content widths of its columns, with the
+        // spacing around them
+        with_layout(ROW, "", |root| {
+            assert_eq!(border_box(root, "t"), (0.0, 0.0, 54.0, 14.0));
+            assert_eq!(border_box(root, "a"), (2.0, 2.0, 42.0, 10.0));
+            assert_eq!(border_box(root, "b"), (46.0, 2.0, 6.0, 10.0));
+        });
+        // a narrower table takes the columns from their min-content widths
+        // towards their max-content widths
+        with_layout(ROW, "#t { width: 40px; }", |root| {
+            assert_eq!(border_box(root, "t"), (0.0, 0.0, 40.0, 24.0));
+            assert_eq!(border_box(root, "a"), (2.0, 2.0, 28.0, 20.0));
+            assert_eq!(border_box(root, "b"), (32.0, 2.0, 6.0, 20.0));
+        });
+        // but not below the min-content widths
+        with_layout(ROW, "#t { width: 10px; }", |root| {
+            assert_eq!(border_box(root, "t").2, 36.0);
+        });
+        // a wider table widens the columns without a fixed width
+        with_layout(ROW, "#t { width: 100px; } #b { width: 20px; }", |root| {
+            assert_eq!(border_box(root, "a"), (2.0, 2.0, 74.0, 10.0));
+            assert_eq!(border_box(root, "b"), (78.0, 2.0, 20.0, 10.0));
+        });
+        // and percentages are of the width of the table
+        with_layout(
+            ROW,
+            "#t { width: 206px; border-spacing: 2px 0; } #a { width: 25%; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (2.0, 0.0, 50.0, 10.0));
+                assert_eq!(border_box(root, "b"), (54.0, 0.0, 150.0, 10.0));
+            },
+        );
+        // tables with auto margins are centered
+        with_layout(ROW, "#t { margin: 0 auto; }", |root| {
+            assert_eq!(border_box(root, "t"), (373.0, 0.0, 54.0, 14.0));
+        });
+    }
+
+    #[test]
+    fn test_fixed_layout() {
+        // the first row sets the widths of the columns, the others share the
+        // rest of the width
+        with_layout(
+            "<table id='t'><tr><td id='a'></td><td id='b'></td><td id='c'></td></tr>\
+             <tr><td>aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa</td></tr></table>",
+            "#t { table-layout: fixed; width: 206px; } #a { width: 50px; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (2.0, 2.0, 50.0, 0.0));
+                assert_eq!(border_box(root, "b"), (54.0, 2.0, 74.0, 0.0));
+                assert_eq!(border_box(root, "c"), (130.0, 2.0, 74.0, 0.0));
+            },
+        );
+        // column boxes come first
+        with_layout(
+            "<table id='t'><col id='c1' span='2'><col id='c2'>\
+             <tr><td id='a'></td><td id='b'></td><td id='c'></td></tr></table>",
+            "#t { table-layout: fixed; width: 100px; border-spacing: 0; }
+            #c1 { width: 20px; } #a { width: 50px; }",
+            |root| {
+                assert_eq!(border_box(root, "a").2, 20.0);
+                assert_eq!(border_box(root, "b").2, 20.0);
+                assert_eq!(border_box(root, "c").2, 60.0);
+                assert_eq!(border_box(root, "c1"), (0.0, 0.0, 40.0, 0.0));
+                assert_eq!(border_box(root, "c2"), (40.0, 0.0, 60.0, 0.0));
+            },
+        );
+    }
+
+    const SPANS: &str = "<table id='t'><tr id='r1'><td id='a' rowspan='2'>a</td><td id='b'>b</td>\
+        <td id='c'>c</td></tr><tr id='r2'><td id='d' colspan='2'>dddddddddd</td></tr></table>";
+
+    #[test]
+    fn test_spanning_cells() {
+        with_layout(SPANS, "#t { border-spacing: 0; }", |root| {
+            // the spanning cell widens the columns it spans
+            assert_eq!(border_box(root, "a"), (0.0, 0.0, 6.0, 20.0));
+            assert_eq!(border_box(root, "b"), (6.0, 0.0, 30.0, 10.0));
+            assert_eq!(border_box(root, "c"), (36.0, 0.0, 30.0, 10.0));
+            assert_eq!(border_box(root, "d"), (6.0, 10.0, 60.0, 10.0));
+            assert_eq!(border_box(root, "r2"), (0.0, 10.0, 66.0, 10.0));
+        });
+        // and the rows it spans
+        with_layout(
+            SPANS,
+            "#t { border-spacing: 0; } #a { height: 40px; }",
+            |root| {
+                assert_eq!(border_box(root, "a"), (0.0, 0.0, 6.0, 40.0));
+                assert_eq!(border_box(root, "d"), (6.0, 20.0, 60.0, 20.0));
+            },
+        );
+        // a table taller than its rows shares the extra height between them
+        with_layout(SPANS, "#t { border-spacing: 0; height: 60px; }", |root| {
+            assert_eq!(border_box(root, "t"), (0.0, 0.0, 66.0, 60.0));
+            assert_eq!(border_box(root, "r1"), (0.0, 0.0, 66.0, 30.0));
+            assert_eq!(border_box(root, "d"), (6.0, 30.0, 60.0, 30.0));
+        });
+    }
+
+    #[test]
+    fn test_vertical_align() {
+        with_layout(
+            "<table><tr><td id='a'>a</td><td id='b'>b</td><td id='c'>c</td><td id='d'>d</td>\
+             <td id='e'><span>e</span></td></tr></table>",
+            "table { border-spacing: 0; } #a { height: 30px; }
+            #c { vertical-align: bottom; } #d { vertical-align: top; }
+            #e { vertical-align: baseline; } span { font-size: 20px; line-height: 20px; }",
+            |root| {
+                let line_y = |id: &str| find(root, id).lines[0].rect.y;
+                // cells in rows directly inside a table are centered
+                assert_eq!(border_box(root, "b"), (6.0, 0.0, 6.0, 30.0));
+                assert_eq!(line_y("b"), 10.0);
+                assert_eq!(line_y("c"), 20.0);
+                assert_eq!(line_y("d"), 0.0);
+                assert_eq!(line_y("e"), 0.0);
+            },
+        );
+    }
+
+    #[test]
+    fn test_row_groups_and_captions() {
+        with_layout(
+            "<table id='t'><caption id='top'>t</caption><caption id='bottom'>b</caption>\
+             <tfoot id='f'><tr><td>f</td></tr></tfoot><tbody id='b'><tr><td>b</td></tr>\
+             <tr><td>b</td></tr></tbody><thead id='h'><tr><td>h</td></tr></thead></table>",
+            "#t { border-spacing: 0; } #bottom { caption-side: bottom; }
+            caption { width: 100px; }",
+            |root| {
+                // the captions widen the table
+                assert_eq!(border_box(root, "t"), (0.0, 0.0, 100.0, 60.0));
+                assert_eq!(border_box(root, "top"), (0.0, 0.0, 100.0, 10.0));
+                // the header goes above the body, the footer below
+                assert_eq!(border_box(root, "h"), (0.0, 10.0, 100.0, 10.0));
+                assert_eq!(border_box(root, "b"), (0.0, 20.0, 100.0, 20.0));
+                assert_eq!(border_box(root, "f"), (0.0, 40.0, 100.0, 10.0));
+                assert_eq!(border_box(root, "bottom"), (0.0, 50.0, 100.0, 10.0));
+            },
+        );
+    }
+
+    #[test]
+    fn test_border_collapse() {
+        let html = "<table id='t'><tr><td id='a'>a</td><td id='b'>b</td></tr></table>";
+        let css = "#t { border-collapse: collapse; border: 4px solid; padding: 5px; }
+            td { border: 2px solid; }";
+        with_layout(html, css, |root| {
+            // the table and the cells each take half of the borders they
+            // share, the widest of them
+            assert_eq!(border_box(root, "t"), (0.0, 0.0, 22.0, 18.0));
+            assert_eq!(border_box(root, "a"), (2.0, 2.0, 9.0, 14.0));
+            assert_eq!(border_box(root, "b"), (11.0, 2.0, 9.0, 14.0));
+            let border = find(root, "a").dimensions.border;
+            assert_eq!((border.left, border.right), (2.0, 1.0));
+        });
+        // hidden borders suppress the others
+        with_layout(
+            html,
+            &format!("{} #b {{ border-left-style: hidden; }}", css),
+            |root| {
+                assert_eq!(border_box(root, "a"), (2.0, 2.0, 8.0, 14.0));
+                assert_eq!(border_box(root, "b"), (10.0, 2.0, 8.0, 14.0));
+            },
+        );
+        // without collapsing, the borders are separated by the spacing
+        with_layout(
+            html,
+            "#t { border: 4px solid; border-spacing: 3px; } td { border: 2px solid; }",
+            |root| {
+                assert_eq!(border_box(root, "t"), (0.0, 0.0, 37.0, 28.0));
+                assert_eq!(border_box(root, "a"), (7.0, 7.0, 10.0, 14.0));
+                assert_eq!(border_box(root, "b"), (20.0, 7.0, 10.0, 14.0));
+            },
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use crate::parser::TextParser;
+
+// a css token, as defined in css syntax 3
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Ident(String),
+    // the name of a function, its arguments follow up to the matching `CloseParen`
+    Function(String),
+    AtKeyword(String),
+    Hash(String),
+    String(String),
+    // a string interrupted by a newline, which makes its declaration invalid
+    BadString,
+    // numbers, percentages and dimensions, as written
+    Number(String),
+    Whitespace,
+    Comma,
+    Colon,
+    Semicolon,
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    OpenBrace,
+    CloseBrace,
+    Delim(char),
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+pub(crate) fn starts_number(rest: &str) -> bool {
+    let mut chars = rest.chars().peekable();
+    if let Some('+' | '-') = chars.peek() {
+        chars.next();
+    }
+    match chars.next() {
+        Some('.') => chars.next().is_some_and(|c| c.is_ascii_digit()),
+        Some(c) => c.is_ascii_digit(),
+        None => false,
+    }
+}
+
+fn starts_identifier(rest: &str) -> bool {
+    let mut chars = rest.chars();
+    match chars.next() {
+        Some('-') => chars.next().is_some_and(|c| c == '-' || is_name_start(c)),
+        Some(c) => is_name_start(c),
+        None => false,
+    }
+}
+
+// the number and unit of a numeric token, `%` being the unit of percentages
+pub(crate) fn consume_numeric(text_parser: &mut TextParser) -> (String, String) {
+    let mut number = String::from("");
+    if let '+' | '-' = text_parser.get_current_char() {
+        number.push(text_parser.consume_char());
+    }
+    number.push_str(&text_parser.consume_chars_while(|c| c.is_ascii_digit()));
+    if text_parser.starts_with(".") && starts_number(&text_parser._state()[1..]) {
+        number.push(text_parser.consume_char());
+        number.push_str(&text_parser.consume_chars_while(|c| c.is_ascii_digit()));
+    }
+
+    // the exponent, only if digits follow
+    let rest = text_parser._state();
+    if rest.starts_with(['e', 'E']) && starts_number(&rest[1..]) && !rest[1..].starts_with('.') {
+        number.push(text_parser.consume_char());
+        if let '+' | '-' = text_parser.get_current_char() {
+            number.push(text_parser.consume_char());
+        }
+        number.push_str(&text_parser.consume_chars_while(|c| c.is_ascii_digit()));
+    }
+
+    let unit = if text_parser.starts_with("%") {
+        text_parser.consume_char().to_string()
+    } else if starts_identifier(text_parser._state()) {
+        text_parser.consume_chars_while(is_name_char)
+    } else {
+        String::from("")
+    };
+    (number, unit)
+}
+
+// the character of an escape, after its `\`: up to 6 hex digits and an
+// optional whitespace, or any other character as is
+fn consume_escape(text_parser: &mut TextParser) -> char {
+    let rest = text_parser._state();
+    let digits = rest.len()
+        - rest
+            .trim_start_matches(|c: char| c.is_ascii_hexdigit())
+            .len();
+    if digits == 0 {
+        return text_parser.consume_char();
+    }
+    let hex: String = (0..digits.min(6))
+        .map(|_| text_parser.consume_char())
+        .collect();
+    if text_parser._state().starts_with(char::is_whitespace) {
+        text_parser.consume_char();
+    }
+    // null, surrogates and out of range code points are replaced
+    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+        Some('\0') | None => '\u{fffd}',
+        Some(c) => c,
+    }
+}
+
+// a quoted string, without its quotes and with its escapes decoded; the end of
+// the input ends it, and `None` is a bad string, ended by a newline left in
+// the input
+pub(crate) fn consume_string(text_parser: &mut TextParser) -> Option<String> {
+    let quote = text_parser.consume_char();
+    let mut result = String::from("");
+    while !text_parser.eol() {
+        match text_parser.get_current_char() {
+            '\n' | '\r' | '\x0c' => return None,
+            '\\' => {
+                text_parser.consume_char();
+                match text_parser._state().chars().next() {
+                    None => {}
+                    // an escaped newline continues the string
+                    Some('\n' | '\r' | '\x0c') => {
+                        let newline = text_parser.consume_char();
+                        if newline == '\r' && text_parser.starts_with("\n") {
+                            text_parser.consume_char();
+                        }
+                    }
+                    Some(_) => result.push(consume_escape(text_parser)),
+                }
+            }
+            c => {
+                text_parser.consume_char();
+                if c == quote {
+                    break;
+                }
+                result.push(c);
+            }
+        }
+    }
+    Some(result)
+}
+
+// a double-quoted string of the text, to be parsed again
+pub(crate) fn quote_string(text: &str) -> String {
+    let mut result = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                result.push('\\');
+                result.push(c);
+            }
+            '\n' => result.push_str("\\a "),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+pub fn tokenize(input: &str) -> Vec<Token> {
+    let mut text_parser = TextParser::new(input.to_string());
+    let mut tokens = Vec::new();
+
+    while !text_parser.eol() {
+        let rest = text_parser._state();
+        let token = if starts_number(rest) {
+            let (number, unit) = consume_numeric(&mut text_parser);
+            Token::Number(number + &unit)
+        } else if starts_identifier(rest) {
+            let name = text_parser.consume_chars_while(is_name_char);
+            if text_parser.starts_with("(") {
+                text_parser.consume_char();
+                Token::Function(name)
+            } else {
+                Token::Ident(name)
+            }
+        } else {
+            match text_parser.get_current_char() {
+                c if c.is_whitespace() => {
+                    text_parser.remove_whitespaces();
+                    Token::Whitespace
+                }
+                '"' | '\'' => match consume_string(&mut text_parser) {
+                    Some(string) => Token::String(string),
+                    None => Token::BadString,
+                },
+                '#' if rest[1..].starts_with(is_name_char) => {
+                    text_parser.consume_char();
+                    Token::Hash(text_parser.consume_chars_while(is_name_char))
+                }
+                '@' if starts_identifier(&rest[1..]) => {
+                    text_parser.consume_char();
+                    Token::AtKeyword(text_parser.consume_chars_while(is_name_char))
+                }
+                c => {
+                    text_parser.consume_char();
+                    match c {
+                        ',' => Token::Comma,
+                        ':' => Token::Colon,
+                        ';' => Token::Semicolon,
+                        '(' => Token::OpenParen,
+                        ')' => Token::CloseParen,
+                        '[' => Token::OpenBracket,
+                        ']' => Token::CloseBracket,
+                        '{' => Token::OpenBrace,
+                        '}' => Token::CloseBrace,
+                        c => Token::Delim(c),
+                    }
+                }
+            }
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+// the css text of a list of tokens
+pub fn serialize(tokens: &[Token]) -> String {
+    let mut result = String::from("");
+    for token in tokens {
+        match token {
+            Token::Ident(name) => result.push_str(name),
+            Token::Function(name) => {
+                result.push_str(name);
+                result.push('(');
+            }
+            Token::AtKeyword(name) => {
+                result.push('@');
+                result.push_str(name);
+            }
+            Token::Hash(name) => {
+                result.push('#');
+                result.push_str(name);
+            }
+            Token::String(text) => result.push_str(&quote_string(text)),
+            Token::BadString => result.push_str("\"\n"),
+            Token::Number(number) => result.push_str(number),
+            Token::Whitespace => result.push(' '),
+            Token::Comma => result.push(','),
+            Token::Colon => result.push(':'),
+            Token::Semicolon => result.push(';'),
+            Token::OpenParen => result.push('('),
+            Token::CloseParen => result.push(')'),
+            Token::OpenBracket => result.push('['),
+            Token::CloseBracket => result.push(']'),
+            Token::OpenBrace => result.push('{'),
+            Token::CloseBrace => result.push('}'),
+            Token::Delim(c) => result.push(*c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("var(--gap, 1.5em) -2px 50% #fff 'a\\'b'"),
+            vec![
+                Token::Function("var".to_string()),
+                Token::Ident("--gap".to_string()),
+                Token::Comma,
+                Token::Whitespace,
+                Token::Number("1.5em".to_string()),
+                Token::CloseParen,
+                Token::Whitespace,
+                Token::Number("-2px".to_string()),
+                Token::Whitespace,
+                Token::Number("50%".to_string()),
+                Token::Whitespace,
+                Token::Hash("fff".to_string()),
+                Token::Whitespace,
+                Token::String("a'b".to_string()),
+            ]
+        );
+        assert_eq!(
+            tokenize("a-b - 1e3 {x}"),
+            vec![
+                Token::Ident("a-b".to_string()),
+                Token::Whitespace,
+                Token::Delim('-'),
+                Token::Whitespace,
+                Token::Number("1e3".to_string()),
+                Token::Whitespace,
+                Token::OpenBrace,
+                Token::Ident("x".to_string()),
+                Token::CloseBrace,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_serialize() {
+        let css = "calc(100% - 2 * var(--gap)) \"q\\\"uote\" @media #id";
+        assert_eq!(serialize(&tokenize(css)), css);
+        assert_eq!(serialize(&tokenize("'\\201C\\\\'")), "\"\u{201c}\\\\\"");
+        assert_eq!(quote_string("a\nb"), "\"a\\a b\"");
+    }
+
+    #[test]
+    fn test_bad_strings() {
+        assert_eq!(
+            tokenize("'a\nb"),
+            vec![
+                Token::BadString,
+                Token::Whitespace,
+                Token::Ident("b".to_string()),
+            ]
+        );
+        assert_eq!(tokenize("'a"), vec![Token::String("a".to_string())]);
+    }
+}
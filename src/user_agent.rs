@@ -0,0 +1,58 @@
+use crate::css::{CSSParser, Origin, Stylesheet};
+use std::sync::OnceLock;
+
+const USER_AGENT_CSS: &str = include_str!("user_agent.css");
+
+// the default styles of html elements, parsed once
+pub fn user_agent_stylesheet() -> &'static Stylesheet {
+    static STYLESHEET: OnceLock<Stylesheet> = OnceLock::new();
+    STYLESHEET.get_or_init(|| {
+        CSSParser::new(USER_AGENT_CSS.to_string())
+            .parse_stylesheet()
+            .with_origin(Origin::UserAgent)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::{CssRule, Unit, Value};
+
+    // the value a rule whose selectors include the selector declares for the property
+    fn declared(selector: &str, name: &str) -> Option<Value> {
+        let selectors = CSSParser::new(selector.to_string())
+            .parse_selectors()
+            .unwrap();
+        user_agent_stylesheet()
+            .rules
+            .iter()
+            .find_map(|rule| match rule {
+                CssRule::Style(rule) if rule.selectors.contains(&selectors[0]) => rule
+                    .declarations
+                    .iter()
+                    .find(|declaration| declaration.name == name)
+                    .map(|declaration| declaration.value.clone()),
+                _ => None,
+            })
+    }
+
+    fn keyword(keyword: &str) -> Option<Value> {
+        Some(Value::Keyword(keyword.to_string()))
+    }
+
+    #[test]
+    fn test_user_agent_stylesheet() {
+        let stylesheet = user_agent_stylesheet();
+        assert_eq!(stylesheet.origin(), Origin::UserAgent);
+        assert_eq!(declared("head", "display"), keyword("none"));
+        assert_eq!(declared("[hidden]", "display"), keyword("none"));
+        assert_eq!(declared("div", "display"), keyword("block"));
+        assert_eq!(declared("li", "display"), keyword("list-item"));
+        assert_eq!(declared("ol", "list-style-type"), keyword("decimal"));
+        assert_eq!(
+            declared("body", "margin-top"),
+            Some(Value::Length(8.0, Unit::Px))
+        );
+        assert_eq!(declared("span", "display"), None);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::assign::PropertyMap;
+use crate::css::{Declaration, Value};
+use crate::tokenizer::{serialize, Token};
+use std::collections::HashMap;
+
+pub fn is_custom_property(name: &str) -> bool {
+    name.starts_with("--")
+}
+
+pub fn contains_var(tokens: &[Token]) -> bool {
+    tokens
+        .iter()
+        .any(|token| matches!(token, Token::Function(name) if name.eq_ignore_ascii_case("var")))
+}
+
+// the tokens up to the `CloseParen` matching an already consumed function or
+// parenthesis, which is consumed too
+fn take_block(tokens: &mut std::slice::Iter<Token>) -> Vec<Token> {
+    let mut block = Vec::new();
+    let mut depth = 0;
+    for token in tokens.by_ref() {
+        match token {
+            Token::Function(_) | Token::OpenParen => depth += 1,
+            Token::CloseParen if depth == 0 => break,
+            Token::CloseParen => depth -= 1,
+            _ => {}
+        }
+        block.push(token.clone());
+    }
+    block
+}
+
+// replace the `var()` functions of `tokens` with the value of the custom
+// properties they reference, or their fallback; `None` when a reference is
+// invalid and has no fallback
+fn substitute(
+    tokens: &[Token],
+    lookup: &mut dyn FnMut(&str) -> Option<Vec<Token>>,
+) -> Option<Vec<Token>> {
+    let mut result = Vec::new();
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Function(function) if function.eq_ignore_ascii_case("var") => {
+                let arguments = take_block(&mut tokens);
+                let mut arguments = arguments
+                    .iter()
+                    .skip_while(|token| **token == Token::Whitespace);
+                let name = match arguments.next() {
+                    Some(Token::Ident(name)) if is_custom_property(name) => name,
+                    _ => return None,
+                };
+                let rest: Vec<Token> = arguments
+                    .skip_while(|token| **token == Token::Whitespace)
+                    .cloned()
+                    .collect();
+                let fallback = match rest.split_first() {
+                    None => None,
+                    Some((Token::Comma, fallback)) => Some(fallback),
+                    Some(_) => return None,
+                };
+
+                match (lookup(name), fallback) {
+                    (Some(value), _) => result.extend(value),
+                    (None, Some(fallback)) => result.extend(substitute(fallback, lookup)?),
+                    (None, None) => return None,
+                }
+            }
+            _ => result.push(token.clone()),
+        }
+    }
+    Some(result)
+}
+
+fn trim_whitespace(tokens: &[Token]) -> &[Token] {
+    let start = tokens
+        .iter()
+        .position(|token| *token != Token::Whitespace)
+        .unwrap_or(tokens.len());
+    let end = tokens
+        .iter()
+        .rposition(|token| *token != Token::Whitespace)
+        .map_or(start, |end| end + 1);
+    &tokens[start..end]
+}
+
+fn keyword_value(tokens: &[Token]) -> Option<String> {
+    match trim_whitespace(tokens) {
+        [Token::Ident(keyword)] => Some(keyword.to_lowercase()),
+        _ => None,
+    }
+}
+
+struct CustomProperties<'v> {
+    declared: &'v PropertyMap,
+    inherited: &'v PropertyMap,
+    // `None` marks the properties invalid at computed-value time
+    computed: HashMap<String, Option<Vec<Token>>>,
+    // the properties being resolved, to detect cycles
+    resolving: Vec<String>,
+}
+
+impl CustomProperties<'_> {
+    fn inherited_value(&self, name: &str) -> Option<Vec<Token>> {
+        match self.inherited.get(name) {
+            Some(Value::Tokens(tokens)) => Some(tokens.clone()),
+            _ => None,
+        }
+    }
+
+    fn resolve(&mut self, name: &str) -> Option<Vec<Token>> {
+        if let Some(value) = self.computed.get(name) {
+            return value.clone();
+        }
+        let tokens = match self.declared.get(name) {
+            Some(Value::Tokens(tokens)) => tokens,
+            _ => {
+                let value = self.inherited_value(name);
+                self.computed.insert(name.to_string(), value.clone());
+                return value;
+            }
+        };
+
+        // every property of a cycle is invalid
+        if let Some(start) = self.resolving.iter().position(|other| other == name) {
+            for other in &self.resolving[start..] {
+                self.computed.insert(other.clone(), None);
+            }
+            return None;
+        }
+
+        let value = match keyword_value(tokens).as_deref() {
+            Some("initial") => None,
+            Some("inherit" | "unset" | "revert") => self.inherited_value(name),
+            _ => {
+                self.resolving.push(name.to_string());
+                let value = substitute(tokens, &mut |reference| self.resolve(reference));
+                self.resolving.pop();
+                value
+            }
+        };
+
+        // the property may have been found to be part of a cycle meanwhile
+        let value = match self.computed.get(name) {
+            Some(None) => None,
+            _ => value,
+        };
+        self.computed.insert(name.to_string(), value.clone());
+        value
+    }
+}
+
+// the computed values of an element: custom properties are inherited from the
+// parent values unless declared, and `var()` references are substituted.
+// properties referencing invalid custom properties without fallback are invalid
+// at computed-value time, and dropped
+pub fn resolve_variables(values: PropertyMap, inherited: &PropertyMap) -> PropertyMap {
+    let mut custom = CustomProperties {
+        declared: &values,
+        inherited,
+        computed: HashMap::new(),
+        resolving: Vec::new(),
+    };
+    let names = values
+        .keys()
+        .chain(inherited.keys())
+        .filter(|name| is_custom_property(name));
+    for name in names {
+        custom.resolve(name);
+    }
+    let custom_values = custom.computed;
+
+    let mut computed = PropertyMap::new();
+    for (name, value) in custom_values {
+        if let Some(tokens) = value {
+            computed.insert(name, Value::Tokens(tokens));
+        }
+    }
+    for (name, value) in &values {
+        if is_custom_property(name) {
+            continue;
+        }
+        let substituted_css = |tokens: &[Token]| {
+            let substituted = substitute(tokens, &mut |reference| match computed.get(reference) {
+                Some(Value::Tokens(tokens)) => Some(tokens.clone()),
+                _ => None,
+            });
+            substituted.map(|tokens| serialize(trim_whitespace(&tokens)))
+        };
+        let value = match value {
+            Value::Tokens(tokens) => {
+                substituted_css(tokens).and_then(|css| Declaration::parse_value(name, css))
+            }
+            // expand the shorthand again, and keep the value of this longhand
+            Value::PendingShorthand(shorthand, tokens) => substituted_css(tokens)
+                .and_then(|css| Declaration::parse_longhands(shorthand, css))
+                .and_then(|longhands| {
+                    longhands
+                        .into_iter()
+                        .find(|(longhand, _)| longhand == name)
+                        .map(|(_, value)| value)
+                }),
+            value => Some(value.clone()),
+        };
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
+        computed.insert(name.clone(), value);
+    }
+    computed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::{CSSParser, Color, ContentItem, Unit};
+
+    fn declared(css: &str) -> PropertyMap {
+        CSSParser::new(css.to_string())
+            .parse_declarations()
+            .into_iter()
+            .map(|declaration| (declaration.name, declaration.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_substitution_and_fallbacks() {
+        let values = resolve_variables(
+            declared(
+                "--brand: #cc0000; --size: var(--missing, var(--gap, 12px));
+                color: var(--brand, black); margin-top: var(--size); display: var(--none, block)",
+            ),
+            &PropertyMap::new(),
+        );
+        assert_eq!(values["color"], Value::ColorValue(Color::rgb(204, 0, 0)));
+        assert_eq!(values["margin-top"], Value::Length(12.0, Unit::Px));
+        assert_eq!(values["display"], Value::Keyword("block".to_string()));
+        assert!(values.contains_key("--size"));
+    }
+
+    #[test]
+    fn test_inheritance() {
+        let parent = resolve_variables(
+            declared("--brand: #cc0000; --gap: 4px"),
+            &PropertyMap::new(),
+        );
+        let child = resolve_variables(
+            declared(
+                "--gap: inherit; --brand: initial; color: var(--brand); margin-top: var(--gap)",
+            ),
+            &parent,
+        );
+        assert!(!child.contains_key("--brand"));
+        assert!(!child.contains_key("color"));
+        assert_eq!(child["margin-top"], Value::Length(4.0, Unit::Px));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let values = resolve_variables(
+            declared(
+                "--a: var(--b); --b: var(--a, 1px); --c: var(--a, 2px); --d: var(--d);
+                margin-top: var(--c); padding-top: var(--b)",
+            ),
+            &PropertyMap::new(),
+        );
+        assert!(!values.contains_key("--a"));
+        assert!(!values.contains_key("--b"));
+        assert!(!values.contains_key("--d"));
+        assert_eq!(values["margin-top"], Value::Length(2.0, Unit::Px));
+        assert!(!values.contains_key("padding-top"));
+    }
+
+    #[test]
+    fn test_shorthand_substitution() {
+        let values = resolve_variables(
+            declared("--x: 1px 2px; --bad: solid; margin: var(--x) 3px; padding: var(--bad)"),
+            &PropertyMap::new(),
+        );
+        assert_eq!(values["margin-top"], Value::Length(1.0, Unit::Px));
+        assert_eq!(values["margin-right"], Value::Length(2.0, Unit::Px));
+        assert_eq!(values["margin-left"], Value::Length(2.0, Unit::Px));
+        assert_eq!(values["margin-bottom"], Value::Length(3.0, Unit::Px));
+        assert!(!values.contains_key("padding-top"));
+    }
+
+    #[test]
+    fn test_invalid_content_substitution() {
+        let values = resolve_variables(
+            declared("--c: counter(); --n: item 1.5; content: var(--c); counter-reset: var(--n)"),
+            &PropertyMap::new(),
+        );
+        assert!(!values.contains_key("content"));
+        assert!(!values.contains_key("counter-reset"));
+        let values = resolve_variables(declared("--q: open-quote; content: var(--q)"), &values);
+        assert_eq!(
+            values["content"],
+            Value::Content(vec![ContentItem::OpenQuote])
+        );
+    }
+}